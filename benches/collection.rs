@@ -0,0 +1,162 @@
+// Benchmarks for ignore matching, the file walk, and Markdown rendering on synthetic trees
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::HashSet;
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+use digest::format::{render_with, MarkdownFormatter};
+use digest::ignores::IgnoreSources;
+use digest::matcher::CompiledIgnore;
+use digest::schema::SCHEMA_VERSION;
+use digest::summary::{GENERATED_BY, TOOL_VERSION};
+use digest::{collect_files, should_ignore, CollectOptions, Digest, FileInfo};
+
+const EXTENSIONS: &[&str] = &["rs", "py", "js", "md", "log", "json"];
+
+/// Relative paths of a tree with `count` files spread over nested directories
+fn synthetic_paths(count: usize) -> Vec<PathBuf> {
+    (0..count)
+        .map(|i| {
+            let top = ["src", "tests", "build", "node_modules", "docs", "lib"][i % 6];
+            Path::new(top)
+                .join(format!("mod{}", i / 100 % 50))
+                .join(format!("sub{}", i / 10 % 10))
+                .join(format!("file{}.{}", i, EXTENSIONS[i % EXTENSIONS.len()]))
+        })
+        .collect()
+}
+
+/// A pattern set the size of a large monorepo's combined ignore files
+fn large_pattern_set() -> HashSet<String> {
+    let mut patterns: HashSet<String> = [
+        "node_modules/",
+        "build/",
+        "tests/",
+        "**/*.log",
+        "*.min.js",
+        "**/test*/**",
+        "dist/**",
+        "target",
+        "coverage/",
+        "*.py[co]",
+        "tmp?/",
+    ]
+    .iter()
+    .map(|p| p.to_string())
+    .collect();
+    for i in 0..200 {
+        patterns.insert(format!("generated{}/", i));
+        patterns.insert(format!("*.gen{}", i));
+        patterns.insert(format!("vendor/pkg{}/**", i));
+    }
+    patterns
+}
+
+fn bench_matching(c: &mut Criterion) {
+    let patterns = large_pattern_set();
+    let mut group = c.benchmark_group("matching");
+    group.sample_size(10);
+
+    for count in [10_000, 100_000] {
+        let paths = synthetic_paths(count);
+        group.throughput(Throughput::Elements(count as u64));
+
+        // The compiled matcher is what the walk uses
+        group.bench_with_input(BenchmarkId::new("compiled", count), &paths, |b, paths| {
+            b.iter(|| {
+                let compiled = CompiledIgnore::new(&patterns);
+                paths.iter().filter(|p| compiled.is_ignored(p)).count()
+            })
+        });
+
+        // `should_ignore` recompiles on every call; benchmark a slice to keep runs short
+        let sample = &paths[..1_000];
+        group.bench_with_input(
+            BenchmarkId::new("should_ignore_1k", count),
+            sample,
+            |b, paths| b.iter(|| paths.iter().filter(|p| should_ignore(p, &patterns)).count()),
+        );
+    }
+    group.finish();
+}
+
+/// Write a synthetic tree to a temporary directory
+fn synthetic_tree(count: usize) -> TempDir {
+    let dir = TempDir::new().expect("create temp dir");
+    for path in synthetic_paths(count) {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).expect("create dir");
+        fs::write(&path, "fn main() {}\n").expect("write file");
+    }
+    dir
+}
+
+fn bench_walk(c: &mut Criterion) {
+    let ignores: IgnoreSources = large_pattern_set().into_iter().collect();
+    let mut group = c.benchmark_group("walk");
+    group.sample_size(10);
+
+    for count in [10_000, 100_000] {
+        let tree = synthetic_tree(count);
+        let options = CollectOptions {
+            max_files: usize::MAX,
+            ..CollectOptions::default()
+        };
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| collect_files(tree.path(), &ignores, &options).expect("walk"))
+        });
+    }
+    group.finish();
+}
+
+fn bench_format(c: &mut Criterion) {
+    let mut group = c.benchmark_group("format");
+
+    for count in [1_000, 10_000] {
+        let files = synthetic_paths(count)
+            .into_iter()
+            .map(|path| {
+                FileInfo::new(
+                    path.to_string_lossy().to_string(),
+                    Some("Rust".to_string()),
+                    "fn main() {\n    println!(\"hello\");\n}\n".repeat(20),
+                )
+            })
+            .collect();
+        let digest = Digest {
+            schema_version: SCHEMA_VERSION,
+            generated_by: GENERATED_BY.to_string(),
+            digest_version: TOOL_VERSION.to_string(),
+            generated_at: None,
+            project_name: "bench".to_string(),
+            part: None,
+            instructions: None,
+            git: None,
+            submodules: Vec::new(),
+            entry_points: Vec::new(),
+            godot: None,
+            main_language: Some("Rust".to_string()),
+            language_breakdown: Vec::new(),
+            dependencies: Vec::new(),
+            excluded: Vec::new(),
+            skipped: Vec::new(),
+            images: Vec::new(),
+            lockfiles: Vec::new(),
+            duplicates: Vec::new(),
+            pii: Vec::new(),
+            files,
+            summary: None,
+        };
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_function(BenchmarkId::new("markdown", count), |b| {
+            b.iter(|| render_with(&MarkdownFormatter::default(), black_box(&digest)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_matching, bench_walk, bench_format);
+criterion_main!(benches);
@@ -1,9 +1,6 @@
 use anyhow::Result;
 use std::collections::HashSet;
-use std::fs::{self, File};
-use std::io::Write;
 use std::path::Path;
-use tempfile::TempDir;
 
 use digest::should_ignore;
 
@@ -45,7 +42,7 @@ fn run_pattern_test(test: &PatternTest) -> Result<()> {
         }
     }
 
-    println!("");
+    println!();
     Ok(())
 }
 
@@ -50,7 +50,7 @@ fn test_gitignore_patterns() -> Result<()> {
     create_gitignore(temp_dir.path(), gitignore_patterns)?;
 
     // Load the gitignore patterns
-    let patterns = check_for_gitignore(temp_dir.path())?;
+    let patterns = check_for_gitignore(temp_dir.path())?.unwrap_or_default();
 
     // Test paths that should be ignored
     let should_be_ignored = vec![
@@ -96,11 +96,11 @@ fn test_both_ignore_files() -> Result<()> {
     // Load both ignore patterns
     let mut ignore_patterns = HashSet::new();
 
-    if let Ok(git_patterns) = check_for_gitignore(temp_dir.path()) {
+    if let Some(git_patterns) = check_for_gitignore(temp_dir.path())? {
         ignore_patterns.extend(git_patterns);
     }
 
-    if let Ok(digest_patterns) = check_for_digestignore(temp_dir.path()) {
+    if let Some(digest_patterns) = check_for_digestignore(temp_dir.path())? {
         ignore_patterns.extend(digest_patterns);
     }
 
@@ -0,0 +1,322 @@
+// Public API extraction for --public-api: the `pub` items of a Rust file as signatures, with
+// their doc comments and attributes, cut out of the original source
+use log::warn;
+use proc_macro2::{LineColumn, Span};
+use syn::spanned::Spanned;
+use syn::{Fields, ImplItem, Item, TraitItem, Visibility};
+
+use crate::FileInfo;
+
+/// The public API of the Rust `source`, or None when it has no public items: module docs,
+/// functions and methods as signatures, structs without their private fields, constants and
+/// statics without their values, and public modules, traits, enums, types, and re-exports.
+/// Inherent impls keep their public items, trait impls just their header.
+pub fn public_api(source: &str) -> syn::Result<Option<String>> {
+    let file = syn::parse_file(source)?;
+    let source = Source::new(source);
+
+    let mut blocks = Vec::new();
+    let docs: Vec<String> = file
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .map(|attr| source.text(attr.span()))
+        .collect();
+    if !docs.is_empty() {
+        blocks.push(docs.join("\n"));
+    }
+    blocks.extend(file.items.iter().filter_map(|item| source.item(item)));
+    let has_items = blocks.len() > docs.len().min(1);
+    Ok(has_items.then(|| blocks.join("\n\n") + "\n"))
+}
+
+/// A collected file reduced to its public API, for `CollectOptions::transform`: Rust files
+/// without public items and all other files are left out, and a Rust file that doesn't parse
+/// is kept whole
+pub fn public_api_file(file: FileInfo) -> Option<FileInfo> {
+    if !file.path.ends_with(".rs") {
+        return None;
+    }
+    match public_api(&file.content) {
+        Ok(api) => Some(file.with_content(api?)),
+        Err(err) => {
+            warn!(
+                path:% = file.path, reason = "parse_error";
+                "Keeping {} whole, it doesn't parse as Rust: {}", file.path, err
+            );
+            Some(file)
+        }
+    }
+}
+
+fn is_pub(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+/// The file being cut, with the byte offset of each line for turning spans into slices
+pub(crate) struct Source<'a> {
+    text: &'a str,
+    lines: Vec<usize>,
+}
+
+impl<'a> Source<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let lines = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Source { text, lines }
+    }
+
+    /// Byte offset of a span position; columns count characters
+    fn offset(&self, at: LineColumn) -> usize {
+        let start = self.lines[at.line - 1];
+        self.text[start..]
+            .char_indices()
+            .nth(at.column)
+            .map_or(self.text.len(), |(i, _)| start + i)
+    }
+
+    /// Source from `start` to `end`, led by the indentation of the first line
+    pub(crate) fn slice(&self, start: LineColumn, end: LineColumn) -> String {
+        let line_start = self.lines[start.line - 1];
+        let from = self.offset(start);
+        let indent = &self.text[line_start..from];
+        let indent = if indent.trim().is_empty() { indent } else { "" };
+        format!("{}{}", indent, &self.text[from..self.offset(end)])
+    }
+
+    pub(crate) fn text(&self, span: Span) -> String {
+        self.slice(span.start(), span.end())
+    }
+
+    /// Source of `item` up to `before`, as a declaration ending in `;`
+    fn signature(&self, item: Span, before: Span) -> String {
+        format!("{};", self.slice(item.start(), before.start()).trim_end())
+    }
+
+    /// Indentation of the line `span` starts on
+    pub(crate) fn indent(&self, span: Span) -> &str {
+        let start = self.lines[span.start().line - 1];
+        let line = &self.text[start..];
+        &line[..line.len() - line.trim_start().len()]
+    }
+
+    /// A braced item as its header, the given members, and its closing brace
+    fn braced(&self, item: Span, open: Span, members: Vec<String>) -> String {
+        let mut text = self.slice(item.start(), open.end());
+        for member in members {
+            text.push('\n');
+            text.push_str(&member);
+        }
+        text.push('\n');
+        text.push_str(self.indent(item));
+        text.push('}');
+        text
+    }
+
+    fn item(&self, item: &Item) -> Option<String> {
+        let span = item.span();
+        match item {
+            Item::Fn(f) if is_pub(&f.vis) => Some(self.signature(span, f.block.span())),
+            Item::Const(c) if is_pub(&c.vis) => Some(self.signature(span, c.eq_token.span())),
+            Item::Static(s) if is_pub(&s.vis) => Some(self.signature(span, s.eq_token.span())),
+            Item::Struct(s) if is_pub(&s.vis) => Some(self.strukt(span, &s.fields)),
+            Item::Enum(e) if is_pub(&e.vis) => Some(self.text(span)),
+            Item::Union(u) if is_pub(&u.vis) => Some(self.text(span)),
+            Item::Type(t) if is_pub(&t.vis) => Some(self.text(span)),
+            Item::Use(u) if is_pub(&u.vis) => Some(self.text(span)),
+            Item::Trait(t) if is_pub(&t.vis) => {
+                let members = t
+                    .items
+                    .iter()
+                    .map(|member| match member {
+                        TraitItem::Fn(f) => match &f.default {
+                            Some(body) => self.signature(member.span(), body.span()),
+                            None => self.text(member.span()),
+                        },
+                        _ => self.text(member.span()),
+                    })
+                    .collect();
+                Some(self.braced(span, t.brace_token.span.open(), members))
+            }
+            Item::Mod(m) if is_pub(&m.vis) => match &m.content {
+                Some((brace, items)) => {
+                    let members = items.iter().filter_map(|item| self.item(item)).collect();
+                    Some(self.braced(span, brace.span.open(), members))
+                }
+                None => Some(self.text(span)),
+            },
+            Item::Impl(i) if i.trait_.is_some() => Some(format!(
+                "{} {{ ... }}",
+                self.slice(span.start(), i.brace_token.span.open().start())
+                    .trim_end()
+            )),
+            Item::Impl(i) => {
+                let members: Vec<String> = i
+                    .items
+                    .iter()
+                    .filter_map(|member| match member {
+                        ImplItem::Fn(f) if is_pub(&f.vis) => {
+                            Some(self.signature(member.span(), f.block.span()))
+                        }
+                        ImplItem::Const(c) if is_pub(&c.vis) => {
+                            Some(self.signature(member.span(), c.eq_token.span()))
+                        }
+                        ImplItem::Type(t) if is_pub(&t.vis) => Some(self.text(member.span())),
+                        _ => None,
+                    })
+                    .collect();
+                (!members.is_empty()).then(|| self.braced(span, i.brace_token.span.open(), members))
+            }
+            Item::Macro(m) if m.attrs.iter().any(|a| a.path().is_ident("macro_export")) => {
+                Some(self.text(span))
+            }
+            _ => None,
+        }
+    }
+
+    /// A struct with the lines of its private fields replaced by one comment
+    fn strukt(&self, span: Span, fields: &Fields) -> String {
+        let Fields::Named(named) = fields else {
+            return self.text(span);
+        };
+        let private: Vec<(usize, usize)> = named
+            .named
+            .iter()
+            .filter(|field| !is_pub(&field.vis))
+            .map(|field| (field.span().start().line, field.span().end().line))
+            .collect();
+        if private.is_empty() {
+            return self.text(span);
+        }
+
+        let text = self.text(span);
+        let first_line = span.start().line;
+        let mut kept = Vec::new();
+        let mut noted = false;
+        for (i, line) in text.lines().enumerate() {
+            let number = first_line + i;
+            if private
+                .iter()
+                .any(|&(start, end)| (start..=end).contains(&number))
+            {
+                if !noted {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    kept.push(format!("{}// private fields omitted", indent));
+                    noted = true;
+                }
+            } else {
+                kept.push(line.to_string());
+            }
+        }
+        kept.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_api() {
+        let source = r#"//! Parsing helpers
+
+use std::fmt;
+pub use crate::error::Error;
+
+/// Largest accepted input
+pub const LIMIT: usize = 1024 * 1024;
+
+/// A parsed value
+#[derive(Debug)]
+pub struct Value {
+    /// The text it came from
+    pub raw: String,
+    cached: Option<u32>,
+}
+
+pub enum Kind {
+    Number,
+    Text,
+}
+
+/// Parse `text` into a value
+pub fn parse(text: &str) -> Value {
+    Value { raw: text.to_string(), cached: None }
+}
+
+fn helper() {}
+
+impl Value {
+    /// The length of the raw text
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    fn reset(&mut self) {}
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+pub trait Visit {
+    fn visit(&mut self, value: &Value);
+    fn done(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {}
+}
+"#;
+        let expected = r#"//! Parsing helpers
+
+pub use crate::error::Error;
+
+/// Largest accepted input
+pub const LIMIT: usize;
+
+/// A parsed value
+#[derive(Debug)]
+pub struct Value {
+    /// The text it came from
+    pub raw: String,
+    // private fields omitted
+}
+
+pub enum Kind {
+    Number,
+    Text,
+}
+
+/// Parse `text` into a value
+pub fn parse(text: &str) -> Value;
+
+impl Value {
+    /// The length of the raw text
+    pub fn len(&self) -> usize;
+}
+
+impl fmt::Display for Value { ... }
+
+pub trait Visit {
+    fn visit(&mut self, value: &Value);
+    fn done(&self) -> bool;
+}
+"#;
+        assert_eq!(public_api(source).unwrap().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_public_api_without_public_items() {
+        assert_eq!(public_api("fn main() {}\n").unwrap(), None);
+        assert_eq!(public_api("//! Docs only\nfn main() {}\n").unwrap(), None);
+        assert!(public_api("pub fn broken(").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+// Extraction of .zip, .tar, .tar.gz and .tgz archives so they can be digested like a directory
+use flate2::read::GzDecoder;
+use log::warn;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+use crate::error::{DigestError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// How much an archive may unpack to before extraction stops, so a decompression bomb fails
+/// cleanly instead of filling the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractLimits {
+    /// Total bytes of file content
+    pub max_bytes: u64,
+    /// Entries of any kind, directories and skipped entries included
+    pub max_entries: usize,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        ExtractLimits {
+            max_bytes: 1024 * 1024 * 1024,
+            max_entries: 100_000,
+        }
+    }
+}
+
+/// An archive unpacked into a temporary directory, removed when this value is dropped
+pub struct ExtractedArchive {
+    _dir: TempDir,
+    /// Directory to digest: the single top-level directory of the archive if there is
+    /// one (as in most release tarballs), otherwise the extraction directory itself
+    pub root: PathBuf,
+    /// Project name derived from the archive file name, e.g. `source-1.0` for `source-1.0.tar.gz`
+    pub name: String,
+}
+
+/// Detect the archive type from the file name, or None if `path` isn't a supported archive
+pub fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    if !path.is_file() {
+        return None;
+    }
+    let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else {
+        None
+    }
+}
+
+/// Archive file name without its archive extensions
+pub fn archive_stem(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let lower = name.to_ascii_lowercase();
+    for ext in [".tar.gz", ".tgz", ".tar", ".zip"] {
+        if lower.ends_with(ext) {
+            return name[..name.len() - ext.len()].to_string();
+        }
+    }
+    name
+}
+
+/// Unpack `path` into a temporary directory, within the default `ExtractLimits`.
+///
+/// Entries with absolute paths or `..` components are skipped, and so are symlinks, hardlinks,
+/// and special files, so a malicious archive can't write or point outside the temporary
+/// directory.
+pub fn extract_archive(path: &Path, kind: ArchiveKind) -> Result<ExtractedArchive> {
+    extract_archive_with_limits(path, kind, ExtractLimits::default())
+}
+
+/// `extract_archive` with explicit limits
+pub fn extract_archive_with_limits(
+    path: &Path,
+    kind: ArchiveKind,
+    limits: ExtractLimits,
+) -> Result<ExtractedArchive> {
+    let dir = TempDir::new().map_err(DigestError::io("create a temporary directory for", path))?;
+    let file = File::open(path).map_err(DigestError::io("open", path))?;
+
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive =
+                zip::ZipArchive::new(file).map_err(|source| DigestError::Archive {
+                    path: path.to_path_buf(),
+                    source,
+                })?;
+            extract_zip_entries(&mut archive, path, dir.path(), limits)?;
+        }
+        ArchiveKind::Tar => {
+            extract_tar_entries(tar::Archive::new(file), path, dir.path(), limits)?;
+        }
+        ArchiveKind::TarGz => {
+            extract_tar_entries(
+                tar::Archive::new(GzDecoder::new(file)),
+                path,
+                dir.path(),
+                limits,
+            )?;
+        }
+    }
+
+    let root = single_top_level_dir(dir.path()).unwrap_or_else(|| dir.path().to_path_buf());
+
+    Ok(ExtractedArchive {
+        _dir: dir,
+        root,
+        name: archive_stem(path),
+    })
+}
+
+/// Running totals of an extraction, checked against its `ExtractLimits`
+struct Extraction<'a> {
+    path: &'a Path,
+    limits: ExtractLimits,
+    bytes: u64,
+    entries: usize,
+}
+
+impl<'a> Extraction<'a> {
+    fn new(path: &'a Path, limits: ExtractLimits) -> Self {
+        Extraction {
+            path,
+            limits,
+            bytes: 0,
+            entries: 0,
+        }
+    }
+
+    fn too_large(&self, message: String) -> DigestError {
+        DigestError::ArchiveTooLarge {
+            path: self.path.to_path_buf(),
+            message,
+        }
+    }
+
+    /// Count one more entry
+    fn entry(&mut self) -> Result<()> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(self.too_large(format!("more than {} entries", self.limits.max_entries)));
+        }
+        Ok(())
+    }
+
+    /// Copy `reader` into a new file at `target`, failing once the total passes the limit
+    fn write(&mut self, reader: impl Read, target: &Path) -> Result<()> {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(DigestError::io("extract", self.path))?;
+        }
+        let remaining = self.limits.max_bytes - self.bytes;
+        let mut out = File::create(target).map_err(DigestError::io("extract", self.path))?;
+        // One byte past the limit is enough to know it was passed
+        let copied = io::copy(&mut reader.take(remaining + 1), &mut out)
+            .map_err(DigestError::io("extract", self.path))?;
+        if copied > remaining {
+            return Err(self.too_large(format!(
+                "more than {} bytes unpacked",
+                self.limits.max_bytes
+            )));
+        }
+        self.bytes += copied;
+        Ok(())
+    }
+}
+
+/// Unpack the zip `archive`, read from `path`, into `dest`
+fn extract_zip_entries(
+    archive: &mut zip::ZipArchive<File>,
+    path: &Path,
+    dest: &Path,
+    limits: ExtractLimits,
+) -> Result<()> {
+    let mut extraction = Extraction::new(path, limits);
+    for index in 0..archive.len() {
+        extraction.entry()?;
+        let entry = archive
+            .by_index(index)
+            .map_err(|source| DigestError::Archive {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        let Some(relative) = entry.enclosed_name() else {
+            warn!("Skipping unsafe archive entry: {}", entry.name());
+            continue;
+        };
+        if entry.is_symlink() {
+            warn!("Skipping symlink in archive: {}", entry.name());
+            continue;
+        }
+        let target = dest.join(relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target).map_err(DigestError::io("extract", path))?;
+            continue;
+        }
+        extraction.write(entry, &target)?;
+    }
+    Ok(())
+}
+
+/// Unpack the tar `archive`, read from `path`, into `dest`, keeping only directories and
+/// regular files
+fn extract_tar_entries<R: Read>(
+    mut archive: tar::Archive<R>,
+    path: &Path,
+    dest: &Path,
+    limits: ExtractLimits,
+) -> Result<()> {
+    let mut extraction = Extraction::new(path, limits);
+    let entries = archive
+        .entries()
+        .map_err(DigestError::io("extract", path))?;
+    for entry in entries {
+        extraction.entry()?;
+        let entry = entry.map_err(DigestError::io("extract", path))?;
+        let name = entry
+            .path()
+            .map_err(DigestError::io("extract", path))?
+            .into_owned();
+        let Some(target) = enclosed_path(dest, &name) else {
+            warn!("Skipping unsafe archive entry: {}", name.display());
+            continue;
+        };
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => {
+                fs::create_dir_all(&target).map_err(DigestError::io("extract", path))?;
+            }
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                extraction.write(entry, &target)?;
+            }
+            other => warn!("Skipping {:?} entry in archive: {}", other, name.display()),
+        }
+    }
+    Ok(())
+}
+
+/// `name` joined onto `dest`, or None when it's absolute or climbs out with `..`
+fn enclosed_path(dest: &Path, name: &Path) -> Option<PathBuf> {
+    let mut target = dest.to_path_buf();
+    for component in name.components() {
+        match component {
+            std::path::Component::Normal(part) => target.push(part),
+            std::path::Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(target)
+}
+
+fn single_top_level_dir(dir: &Path) -> Option<PathBuf> {
+    let mut entries = fs::read_dir(dir).ok()?.flatten();
+    let first = entries.next()?;
+    if entries.next().is_some() || !first.path().is_dir() {
+        return None;
+    }
+    Some(first.path())
+}
@@ -0,0 +1,168 @@
+// Non-text assets: recognizing images and binaries the digest leaves out (--mention-skipped),
+// and embedding small images for multimodal models (--embed-images)
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+
+use crate::vfs::{self, Vfs};
+use crate::SkippedKind;
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "webp", "ico", "icns", "tif", "tiff", "psd", "avif", "heic",
+];
+
+const BINARY_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "so", "dylib", "a", "lib", "o", "obj", "bin", "wasm", "class", "jar", "pyc",
+    "zip", "gz", "tgz", "bz2", "xz", "7z", "rar", "tar", "pdf", "ttf", "otf", "woff", "woff2",
+    "mp3", "wav", "ogg", "flac", "mp4", "mov", "avi", "webm", "sqlite", "db", "pck",
+];
+
+/// How much of an unrecognized file is checked for NUL bytes
+const SNIFF_BYTES: u64 = 8 * 1024;
+
+/// Whether the start of the file has a NUL byte, which text files don't
+fn looks_binary(vfs: &dyn Vfs, path: &Path) -> bool {
+    let mut buffer = Vec::new();
+    vfs.open(path)
+        .and_then(|file| file.take(SNIFF_BYTES).read_to_end(&mut buffer))
+        .is_ok_and(|_| buffer.contains(&0))
+}
+
+/// Classify a file the digest doesn't include: images and binaries by extension, other files
+/// by sniffing their first bytes. Text files that just aren't code return `None`.
+pub fn classify(vfs: &dyn Vfs, path: &Path) -> Option<SkippedKind> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some(ext) if IMAGE_EXTENSIONS.contains(&ext) => Some(SkippedKind::Image),
+        Some(ext) if BINARY_EXTENSIONS.contains(&ext) => Some(SkippedKind::Binary),
+        _ if looks_binary(vfs, path) => Some(SkippedKind::Binary),
+        _ => None,
+    }
+}
+
+/// An image carried in the digest, for models that accept images
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedImage {
+    pub path: String,
+    pub media_type: &'static str,
+    pub bytes: u64,
+    /// The image bytes, base64-encoded
+    pub data: String,
+}
+
+impl EmbeddedImage {
+    /// A `data:` URI for the image, as Markdown and HTML accept
+    pub fn data_uri(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+}
+
+/// Media type of the images `--embed-images` can carry. SVG is collected as source instead.
+fn embeddable_media_type(extension: &str) -> Option<&'static str> {
+    match extension {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Read an image no larger than `max_bytes` for embedding, or `None` if it's larger, not a
+/// supported image, or unreadable
+pub fn embed_image(
+    vfs: &dyn Vfs,
+    path: &Path,
+    relative_path: String,
+    max_bytes: u64,
+) -> Option<EmbeddedImage> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let media_type = embeddable_media_type(&extension)?;
+    if vfs.metadata(path).ok()?.len > max_bytes {
+        return None;
+    }
+    let raw = vfs::read(vfs, path).ok()?;
+    Some(EmbeddedImage {
+        path: relative_path,
+        media_type,
+        bytes: raw.len() as u64,
+        data: STANDARD.encode(&raw),
+    })
+}
+
+/// A byte count for people: `512 B`, `12.3 KB`, `4.0 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::OsFs;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_classify() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("logo.PNG"), "not really a png").unwrap();
+        fs::write(root.join("tool.exe"), "MZ").unwrap();
+        fs::write(root.join("blob"), b"\x7fELF\x00\x01").unwrap();
+        fs::write(root.join("notes.txt"), "plain text").unwrap();
+
+        assert_eq!(
+            classify(&OsFs, &root.join("logo.PNG")),
+            Some(SkippedKind::Image)
+        );
+        assert_eq!(
+            classify(&OsFs, &root.join("tool.exe")),
+            Some(SkippedKind::Binary)
+        );
+        assert_eq!(
+            classify(&OsFs, &root.join("blob")),
+            Some(SkippedKind::Binary)
+        );
+        assert_eq!(classify(&OsFs, &root.join("notes.txt")), None);
+    }
+
+    #[test]
+    fn test_embed_image() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("icon.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        fs::write(root.join("flow.svg"), "<svg/>").unwrap();
+        fs::write(root.join("big.png"), [0u8; 64]).unwrap();
+
+        let png = embed_image(&OsFs, &root.join("icon.png"), "icon.png".to_string(), 32).unwrap();
+        assert_eq!(png.media_type, "image/png");
+        assert_eq!(png.data, "iVBORw==");
+        assert_eq!(png.data_uri(), "data:image/png;base64,iVBORw==");
+
+        assert!(embed_image(&OsFs, &root.join("flow.svg"), "flow.svg".to_string(), 32).is_none());
+
+        assert!(embed_image(&OsFs, &root.join("big.png"), "big.png".to_string(), 32).is_none());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(12_600), "12.3 KB");
+        assert_eq!(format_size(4 * 1024 * 1024), "4.0 MB");
+    }
+}
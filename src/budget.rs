@@ -0,0 +1,128 @@
+// Size limits expressed in tokens or bytes, e.g. "100000", "100k" or "2MB"
+use std::fmt;
+use std::str::FromStr;
+
+use crate::tokens::estimate_tokens;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Estimated LLM tokens
+    Tokens(usize),
+    /// Bytes of rendered output
+    Bytes(u64),
+}
+
+impl Budget {
+    /// Measure `content` in this budget's unit
+    pub fn measure(&self, content: &str) -> u64 {
+        match self {
+            Budget::Tokens(_) => estimate_tokens(content) as u64,
+            Budget::Bytes(_) => content.len() as u64,
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        match self {
+            Budget::Tokens(limit) => *limit as u64,
+            Budget::Bytes(limit) => *limit,
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Budget::Tokens(_) => "tokens",
+            Budget::Bytes(_) => "bytes",
+        }
+    }
+
+    /// Whether `content` is larger than the budget allows
+    pub fn is_exceeded_by(&self, content: &str) -> bool {
+        self.measure(content) > self.limit()
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.limit(), self.unit())
+    }
+}
+
+impl FromStr for Budget {
+    type Err = String;
+
+    /// Plain numbers and `k`/`m` suffixes are tokens; `B`, `KB` and `MB` suffixes are bytes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let lower = trimmed.to_ascii_lowercase();
+        let split_at = lower
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(lower.len());
+        let (number, suffix) = lower.split_at(split_at);
+
+        let value: u64 = number
+            .parse()
+            .map_err(|_| format!("invalid budget '{}': expected a number", trimmed))?;
+
+        let too_large = || format!("invalid budget '{}': too large", trimmed);
+        let tokens = |multiplier: u64| {
+            value
+                .checked_mul(multiplier)
+                .and_then(|tokens| usize::try_from(tokens).ok())
+                .map(Budget::Tokens)
+                .ok_or_else(too_large)
+        };
+        let bytes = |multiplier: u64| {
+            value
+                .checked_mul(multiplier)
+                .map(Budget::Bytes)
+                .ok_or_else(too_large)
+        };
+        match suffix.trim() {
+            "" | "t" | "tokens" => tokens(1),
+            "k" => tokens(1_000),
+            "m" => tokens(1_000_000),
+            "b" | "bytes" => bytes(1),
+            "kb" => bytes(1024),
+            "mb" => bytes(1024 * 1024),
+            other => Err(format!(
+                "invalid budget unit '{}': use tokens (100000, 100k) or bytes (500KB, 2MB)",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_budget() {
+        assert_eq!("100000".parse(), Ok(Budget::Tokens(100_000)));
+        assert_eq!("100k".parse(), Ok(Budget::Tokens(100_000)));
+        assert_eq!("2m".parse(), Ok(Budget::Tokens(2_000_000)));
+        assert_eq!("500KB".parse(), Ok(Budget::Bytes(512_000)));
+        assert_eq!("2MB".parse(), Ok(Budget::Bytes(2 * 1024 * 1024)));
+        assert_eq!("10 bytes".parse(), Ok(Budget::Bytes(10)));
+        assert!("lots".parse::<Budget>().is_err());
+        assert!("10GB".parse::<Budget>().is_err());
+    }
+
+    #[test]
+    fn test_parse_budget_overflow() {
+        assert_eq!(
+            "99999999999999999m".parse::<Budget>(),
+            Err("invalid budget '99999999999999999m': too large".to_string())
+        );
+        assert!("99999999999999MB".parse::<Budget>().is_err());
+        assert!("99999999999999999999".parse::<Budget>().is_err());
+    }
+
+    #[test]
+    fn test_budget_exceeded() {
+        assert!(Budget::Bytes(3).is_exceeded_by("abcd"));
+        assert!(!Budget::Bytes(4).is_exceeded_by("abcd"));
+        assert!(!Budget::Tokens(1).is_exceeded_by("abcd"));
+        assert!(Budget::Tokens(1).is_exceeded_by("abcde"));
+    }
+}
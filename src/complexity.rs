@@ -0,0 +1,219 @@
+// Rough complexity metrics for --complexity and --prioritize complexity: decision points
+// counted by keyword, and nesting measured by braces or indentation
+use serde::Serialize;
+
+use crate::FileInfo;
+
+/// Cyclomatic complexity and nesting depth of a file, estimated from its text
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Complexity {
+    /// One plus the branches and boolean operators in the file's code
+    pub cyclomatic: usize,
+    /// Deepest block nesting: braces, or indentation levels in languages without them
+    pub max_nesting: usize,
+}
+
+/// Words that open a branch, in any language that has them
+const DECISIONS: &[&str] = &[
+    "if", "elif", "elsif", "for", "foreach", "while", "until", "unless", "case", "when", "catch",
+    "except", "guard",
+];
+
+/// How a language marks comments and blocks
+struct Syntax {
+    line_comment: &'static str,
+    block_comments: bool,
+    /// Nesting by braces rather than indentation
+    braces: bool,
+    /// `and` and `or` are operators
+    word_operators: bool,
+    /// Single quotes delimit strings, rather than Rust's lifetimes
+    single_quotes: bool,
+}
+
+fn syntax(path: &str) -> Option<Syntax> {
+    let extension = path.rsplit_once('.')?.1.to_lowercase();
+    let braced = |single_quotes| Syntax {
+        line_comment: "//",
+        block_comments: true,
+        braces: true,
+        word_operators: false,
+        single_quotes,
+    };
+    let indented = |line_comment| Syntax {
+        line_comment,
+        block_comments: false,
+        braces: false,
+        word_operators: true,
+        single_quotes: true,
+    };
+    Some(match extension.as_str() {
+        "rs" => braced(false),
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" | "m" | "mm" | "cs" | "java"
+        | "kt" | "kts" | "scala" | "groovy" | "go" | "swift" | "dart" | "js" | "mjs" | "cjs"
+        | "jsx" | "ts" | "tsx" | "php" => braced(true),
+        "py" | "pyi" | "gd" | "rb" => indented("#"),
+        "lua" => indented("--"),
+        _ => return None,
+    })
+}
+
+/// Estimated complexity of the source at `path`; None for languages it doesn't know
+pub fn complexity(path: &str, content: &str) -> Option<Complexity> {
+    let syntax = syntax(path)?;
+    let rust = path.ends_with(".rs");
+    let mut decisions = 0;
+    let mut depth = 0usize;
+    let mut max_nesting = 0;
+    let mut indent_unit = 0;
+    let mut in_block_comment = false;
+
+    for line in content.lines() {
+        let code = strip_line(line, &syntax, &mut in_block_comment);
+        if code.trim().is_empty() {
+            continue;
+        }
+
+        for word in code.split(|c: char| !c.is_alphanumeric() && c != '_') {
+            decisions += DECISIONS.contains(&word) as usize
+                + (syntax.word_operators && (word == "and" || word == "or")) as usize;
+        }
+        decisions += code.matches("&&").count() + code.matches("||").count();
+        // Each arm of a Rust match is a branch
+        if rust {
+            decisions += code.matches("=>").count();
+        }
+
+        if syntax.braces {
+            for c in code.chars() {
+                match c {
+                    '{' => {
+                        depth += 1;
+                        max_nesting = max_nesting.max(depth);
+                    }
+                    '}' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+        } else {
+            let indent = code.len() - code.trim_start().len();
+            if indent > 0 {
+                // The narrowest indentation seen is one level
+                if indent_unit == 0 || indent < indent_unit {
+                    indent_unit = indent;
+                }
+                max_nesting = max_nesting.max(indent / indent_unit);
+            }
+        }
+    }
+
+    Some(Complexity {
+        cyclomatic: 1 + decisions,
+        max_nesting,
+    })
+}
+
+/// A line with its comments and the contents of its string literals removed
+fn strip_line(line: &str, syntax: &Syntax, in_block_comment: &mut bool) -> String {
+    let mut code = String::with_capacity(line.len());
+    let mut chars = line.char_indices().peekable();
+    let mut quote = None;
+    while let Some((i, c)) = chars.next() {
+        if *in_block_comment {
+            if line[i..].starts_with("*/") {
+                chars.next();
+                *in_block_comment = false;
+            }
+            continue;
+        }
+        if let Some(open) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == open {
+                quote = None;
+                code.push(c);
+            }
+            continue;
+        }
+        let rest = &line[i..];
+        if rest.starts_with(syntax.line_comment) {
+            break;
+        }
+        if syntax.block_comments && rest.starts_with("/*") {
+            chars.next();
+            *in_block_comment = true;
+            continue;
+        }
+        if c == '"' || c == '`' || (c == '\'' && syntax.single_quotes) {
+            quote = Some(c);
+        }
+        code.push(c);
+    }
+    code
+}
+
+/// Fill in `FileInfo::complexity` for the files in a language `complexity` knows
+pub fn annotate_complexity(files: &mut [FileInfo]) {
+    for file in files {
+        file.complexity = complexity(&file.path, &file.content);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_braced_complexity() {
+        let source = "// if this were code: while (x) {\n\
+                      fn check(items: &[u32]) -> bool {\n\
+                      \x20   for item in items {\n\
+                      \x20       if *item > 3 && *item < 9 || *item == 0 {\n\
+                      \x20           return true;\n\
+                      \x20       }\n\
+                      \x20   }\n\
+                      \x20   let label = \"if { while\";\n\
+                      \x20   /* else if {\n\
+                      \x20      for */\n\
+                      \x20   match label { \"a\" => true, _ => false }\n\
+                      }\n";
+        assert_eq!(
+            complexity("src/check.rs", source),
+            Some(Complexity {
+                // for, if, &&, ||, and two match arms
+                cyclomatic: 7,
+                max_nesting: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_indented_complexity() {
+        let source = "def pick(rows):\n\
+                      \x20 # if commented: while\n\
+                      \x20 for row in rows:\n\
+                      \x20   if row.ok and not row.skip:\n\
+                      \x20     return row\n\
+                      \x20 return 'for if'\n";
+        assert_eq!(
+            complexity("pick.py", source),
+            Some(Complexity {
+                cyclomatic: 4,
+                max_nesting: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_languages_have_no_complexity() {
+        assert_eq!(complexity("README.md", "if you can\n"), None);
+        assert_eq!(complexity("Makefile", "all:\n"), None);
+        assert_eq!(
+            complexity("empty.go", ""),
+            Some(Complexity {
+                cyclomatic: 1,
+                max_nesting: 0,
+            })
+        );
+    }
+}
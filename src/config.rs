@@ -0,0 +1,125 @@
+// Project configuration: digest.toml sets default options for a project, using the long
+// command-line flag names as keys
+use std::path::Path;
+
+use crate::error::{DigestError, Result};
+use crate::reader::read_utf8;
+
+/// Name of the configuration file read from the project root
+pub const CONFIG_FILE: &str = "digest.toml";
+
+/// Options whose values are `KEY=VALUE` pairs, which digest.toml may give as a table
+const TABLE_OPTIONS: &[&str] = &["priority"];
+
+/// Turn the top-level keys of digest.toml into command-line arguments: `max-files = 20`
+/// becomes `--max-files=20`, `link-files = true` becomes `--link-files` (false leaves the flag
+/// off), and arrays repeat the flag once per value. A `[priority]` table repeats the flag once
+/// per entry, so `"src/**" = 10` becomes `--priority=src/**=10`. Errors describe what's wrong
+/// with the content.
+pub fn config_args(content: &str) -> Result<Vec<String>, String> {
+    let table = match content.parse::<toml::Value>() {
+        Ok(toml::Value::Table(table)) => table,
+        Ok(_) => return Err("expected a table of options".to_string()),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let mut args = Vec::new();
+    for (key, value) in &table {
+        let flag = format!("--{}", key.replace('_', "-"));
+        match value {
+            toml::Value::Boolean(true) => args.push(flag),
+            toml::Value::Boolean(false) => {}
+            toml::Value::Array(values) => {
+                for value in values {
+                    args.push(format!("{}={}", flag, scalar(key, value)?));
+                }
+            }
+            toml::Value::Table(entries) if TABLE_OPTIONS.contains(&key.as_str()) => {
+                for (entry, value) in entries {
+                    args.push(format!("{}={}={}", flag, entry, scalar(key, value)?));
+                }
+            }
+            value => args.push(format!("{}={}", flag, scalar(key, value)?)),
+        }
+    }
+    Ok(args)
+}
+
+/// A string, number, or datetime value as flag text
+fn scalar(key: &str, value: &toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(text) => Ok(text.clone()),
+        toml::Value::Integer(number) => Ok(number.to_string()),
+        toml::Value::Float(number) => Ok(number.to_string()),
+        toml::Value::Datetime(datetime) => Ok(datetime.to_string()),
+        _ => Err(format!(
+            "'{}' must be a string, number, boolean, or array of those",
+            key
+        )),
+    }
+}
+
+/// Read digest.toml at the project root as command-line arguments, or `None` without one
+pub fn load_config_args(project_path: &Path) -> Result<Option<Vec<String>>> {
+    let path = project_path.join(CONFIG_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = read_utf8(&path)?;
+    let args = config_args(&content).map_err(|message| DigestError::Config {
+        path: path.clone(),
+        message,
+    })?;
+    Ok(Some(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_args() {
+        let config = r#"
+max-files = 20
+format = "json"
+link-files = true
+git-file-info = false
+ignore-pattern = ["*.snap", "fixtures/"]
+"#;
+        assert_eq!(
+            config_args(config).unwrap(),
+            vec![
+                "--format=json",
+                "--ignore-pattern=*.snap",
+                "--ignore-pattern=fixtures/",
+                "--link-files",
+                "--max-files=20",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_args_priority_table() {
+        let config = r#"
+max-files = 20
+
+[priority]
+"src/core/**" = 10
+"examples/**" = -5
+"#;
+        assert_eq!(
+            config_args(config).unwrap(),
+            vec![
+                "--max-files=20",
+                "--priority=examples/**=-5",
+                "--priority=src/core/**=10",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_args_rejects_tables() {
+        assert!(config_args("[digest]\nmax-files = 20\n").is_err());
+        assert!(config_args("max-files = ").is_err());
+    }
+}
@@ -0,0 +1,150 @@
+// The files that contribute the most tokens to a digest, with ignore patterns that would drop them
+use serde::Serialize;
+
+use crate::tokens::estimate_tokens;
+use crate::FileInfo;
+
+/// One of the largest files in a digest
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Contributor {
+    pub path: String,
+    /// Estimated tokens of the file's content
+    pub tokens: usize,
+    /// Share of the tokens of all files' content, in percent
+    pub share: f64,
+    /// Ignore patterns that would leave the file out, narrowest first
+    pub suggestions: Vec<String>,
+}
+
+/// The `n` files with the most estimated tokens, largest first; ties go by path
+pub fn top_contributors(files: &[FileInfo], n: usize) -> Vec<Contributor> {
+    let sized: Vec<(&FileInfo, usize)> = files
+        .iter()
+        .map(|file| (file, estimate_tokens(&file.content)))
+        .collect();
+    let total: usize = sized.iter().map(|(_, tokens)| tokens).sum();
+
+    let mut top: Vec<Contributor> = sized
+        .into_iter()
+        .map(|(file, tokens)| Contributor {
+            path: file.path.clone(),
+            tokens,
+            share: if total == 0 {
+                0.0
+            } else {
+                tokens as f64 * 100.0 / total as f64
+            },
+            suggestions: suggest_patterns(&file.path),
+        })
+        .collect();
+    top.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.path.cmp(&b.path)));
+    top.truncate(n);
+    top
+}
+
+/// Ignore patterns that would leave `path` out: the file itself, files sharing its compound
+/// extension (`*.min.js`, `*.pb.go`), and its directory. A plain extension such as `*.rs` is
+/// never suggested, since it would drop most of a project along with the file.
+pub fn suggest_patterns(path: &str) -> Vec<String> {
+    let path = path.replace('\\', "/");
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, path.as_str()),
+    };
+
+    let mut suggestions = vec![path.clone()];
+    // Dot files like .eslintrc.json keep their leading dot in the stem
+    let extensions: Vec<&str> = name.trim_start_matches('.').split('.').skip(1).collect();
+    if extensions.len() >= 2 {
+        suggestions.push(format!("*.{}", extensions.join(".")));
+    }
+    if let Some(dir) = dir {
+        suggestions.push(format!("{}/", dir));
+    }
+    suggestions
+}
+
+/// Table of the largest contributors for --top-files, with the suggested patterns of each
+pub fn format_top_files(contributors: &[Contributor]) -> String {
+    let width = contributors
+        .iter()
+        .map(|c| c.path.len())
+        .max()
+        .unwrap_or(0)
+        .max("PATH".len());
+    let mut output = format!(
+        "{:>8}  {:>6}  {:<width$}  SUGGESTED IGNORE PATTERNS\n",
+        "TOKENS", "SHARE", "PATH"
+    );
+    for contributor in contributors {
+        output.push_str(&format!(
+            "{:>8}  {:>5.1}%  {:<width$}  {}\n",
+            contributor.tokens,
+            contributor.share,
+            contributor.path,
+            contributor.suggestions.join(", ")
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::CompiledIgnore;
+    use std::path::Path;
+
+    #[test]
+    fn test_suggest_patterns() {
+        assert_eq!(suggest_patterns("README.md"), vec!["README.md"]);
+        assert_eq!(suggest_patterns("src/main.rs"), vec!["src/main.rs", "src/"]);
+        assert_eq!(
+            suggest_patterns("web/static/app.min.js"),
+            vec!["web/static/app.min.js", "*.min.js", "web/static/"]
+        );
+        assert_eq!(
+            suggest_patterns("config/.eslintrc.json"),
+            vec!["config/.eslintrc.json", "config/"]
+        );
+
+        // Each suggestion really leaves the file out
+        for path in [
+            "src/main.rs",
+            "web/static/app.min.js",
+            "api/gen/types.pb.go",
+        ] {
+            for pattern in suggest_patterns(path) {
+                let matcher = CompiledIgnore::new([&pattern]);
+                assert!(
+                    matcher.is_ignored(Path::new(path)),
+                    "{} doesn't ignore {}",
+                    pattern,
+                    path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_top_contributors() {
+        let file =
+            |path: &str, words: usize| FileInfo::new(path.to_string(), None, "word ".repeat(words));
+        let files = [
+            file("src/small.rs", 10),
+            file("src/big.rs", 300),
+            file("docs/guide.md", 100),
+        ];
+
+        let top = top_contributors(&files, 2);
+        let paths: Vec<&str> = top.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/big.rs", "docs/guide.md"]);
+        assert!(top[0].tokens > top[1].tokens);
+        assert!(top[0].share > 50.0 && top[0].share < 100.0);
+        assert_eq!(top[1].suggestions, vec!["docs/guide.md", "docs/"]);
+
+        let table = format_top_files(&top);
+        assert!(table.starts_with("  TOKENS   SHARE  PATH"));
+        assert!(table.contains("src/big.rs, src/\n"));
+        assert!(top_contributors(&[], 5).is_empty());
+    }
+}
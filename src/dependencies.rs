@@ -0,0 +1,362 @@
+// Dependency summaries extracted from ecosystem manifests and lockfiles
+use log::warn;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::lockfiles::count_locked_packages;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+impl DependencyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyKind::Normal => "normal",
+            DependencyKind::Dev => "dev",
+            DependencyKind::Build => "build",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Dependency {
+    pub name: String,
+    pub version: Option<String>,
+    pub kind: DependencyKind,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DependencySummary {
+    /// Ecosystem name, e.g. "Cargo", "npm", "Python" or "Go"
+    pub ecosystem: String,
+    /// Manifest file the direct dependencies were read from
+    pub manifest: String,
+    /// Direct dependencies declared in the manifest
+    pub direct: Vec<Dependency>,
+    /// Lockfile used to count transitive dependencies, if one was found
+    pub lockfile: Option<String>,
+    /// Number of locked packages, excluding the project's own packages
+    pub transitive_count: Option<usize>,
+}
+
+/// Collect dependency summaries for every ecosystem manifest found at the project root.
+///
+/// Manifests that fail to parse are skipped with a warning rather than failing the digest.
+pub fn summarize_dependencies(project_path: &Path) -> Vec<DependencySummary> {
+    let mut summaries = Vec::new();
+
+    let extractors: [fn(&Path) -> Option<DependencySummary>; 4] =
+        [cargo_summary, npm_summary, python_summary, go_summary];
+    for extractor in extractors {
+        if let Some(summary) = extractor(project_path) {
+            summaries.push(summary);
+        }
+    }
+
+    summaries
+}
+
+fn read_manifest(path: &Path) -> Option<String> {
+    if !path.is_file() {
+        return None;
+    }
+    match fs::read_to_string(path) {
+        Ok(content) => Some(content),
+        Err(err) => {
+            warn!("Error reading {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+fn parse_toml(path: &Path) -> Option<toml::Value> {
+    let content = read_manifest(path)?;
+    match content.parse::<toml::Value>() {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Error parsing {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+fn parse_json(path: &Path) -> Option<serde_json::Value> {
+    let content = read_manifest(path)?;
+    match serde_json::from_str(&content) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Error parsing {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// Locked packages in the project's lockfile `name`, counted as its --lockfiles summary is
+fn locked_packages(project_path: &Path, name: &str) -> Option<usize> {
+    count_locked_packages(name, &read_manifest(&project_path.join(name))?)
+}
+
+fn cargo_summary(project_path: &Path) -> Option<DependencySummary> {
+    let manifest = parse_toml(&project_path.join("Cargo.toml"))?;
+
+    let mut direct = Vec::new();
+    let sections = [
+        ("dependencies", DependencyKind::Normal),
+        ("dev-dependencies", DependencyKind::Dev),
+        ("build-dependencies", DependencyKind::Build),
+    ];
+    for (section, kind) in sections {
+        if let Some(table) = manifest.get(section).and_then(|v| v.as_table()) {
+            for (name, spec) in table {
+                direct.push(Dependency {
+                    name: name.clone(),
+                    version: cargo_version(spec),
+                    kind,
+                });
+            }
+        }
+    }
+
+    // Virtual workspace manifests declare shared dependencies instead
+    if let Some(table) = manifest
+        .get("workspace")
+        .and_then(|w| w.get("dependencies"))
+        .and_then(|v| v.as_table())
+    {
+        for (name, spec) in table {
+            if !direct.iter().any(|d| &d.name == name) {
+                direct.push(Dependency {
+                    name: name.clone(),
+                    version: cargo_version(spec),
+                    kind: DependencyKind::Normal,
+                });
+            }
+        }
+    }
+
+    let transitive_count = locked_packages(project_path, "Cargo.lock");
+
+    Some(DependencySummary {
+        ecosystem: "Cargo".to_string(),
+        manifest: "Cargo.toml".to_string(),
+        direct,
+        lockfile: transitive_count.map(|_| "Cargo.lock".to_string()),
+        transitive_count,
+    })
+}
+
+fn cargo_version(spec: &toml::Value) -> Option<String> {
+    match spec {
+        toml::Value::String(version) => Some(version.clone()),
+        toml::Value::Table(table) => {
+            if let Some(version) = table.get("version").and_then(|v| v.as_str()) {
+                Some(version.to_string())
+            } else if let Some(path) = table.get("path").and_then(|v| v.as_str()) {
+                Some(format!("path:{}", path))
+            } else if let Some(git) = table.get("git").and_then(|v| v.as_str()) {
+                Some(format!("git:{}", git))
+            } else if table.get("workspace").and_then(|v| v.as_bool()) == Some(true) {
+                Some("workspace".to_string())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn npm_summary(project_path: &Path) -> Option<DependencySummary> {
+    let manifest = parse_json(&project_path.join("package.json"))?;
+
+    let mut direct = Vec::new();
+    let sections = [
+        ("dependencies", DependencyKind::Normal),
+        ("devDependencies", DependencyKind::Dev),
+    ];
+    for (section, kind) in sections {
+        if let Some(object) = manifest.get(section).and_then(|v| v.as_object()) {
+            for (name, version) in object {
+                direct.push(Dependency {
+                    name: name.clone(),
+                    version: version.as_str().map(|v| v.to_string()),
+                    kind,
+                });
+            }
+        }
+    }
+
+    let (lockfile, transitive_count) = ["package-lock.json", "yarn.lock"]
+        .into_iter()
+        .find_map(|name| Some((Some(name), Some(locked_packages(project_path, name)?))))
+        .unwrap_or((None, None));
+
+    Some(DependencySummary {
+        ecosystem: "npm".to_string(),
+        manifest: "package.json".to_string(),
+        direct,
+        lockfile: lockfile.map(|l| l.to_string()),
+        transitive_count,
+    })
+}
+
+fn python_summary(project_path: &Path) -> Option<DependencySummary> {
+    let (manifest, direct) =
+        if let Some(pyproject) = parse_toml(&project_path.join("pyproject.toml")) {
+            let mut direct = Vec::new();
+
+            // PEP 621 metadata
+            if let Some(deps) = pyproject
+                .get("project")
+                .and_then(|p| p.get("dependencies"))
+                .and_then(|d| d.as_array())
+            {
+                for requirement in deps.iter().filter_map(|d| d.as_str()) {
+                    direct.push(parse_python_requirement(
+                        requirement,
+                        DependencyKind::Normal,
+                    ));
+                }
+            }
+
+            // Poetry metadata
+            let poetry = pyproject.get("tool").and_then(|t| t.get("poetry"));
+            let poetry_sections = [
+                ("dependencies", DependencyKind::Normal),
+                ("dev-dependencies", DependencyKind::Dev),
+            ];
+            for (section, kind) in poetry_sections {
+                if let Some(table) = poetry
+                    .and_then(|p| p.get(section))
+                    .and_then(|d| d.as_table())
+                {
+                    for (name, spec) in table {
+                        if name == "python" {
+                            continue;
+                        }
+                        let version = match spec {
+                            toml::Value::String(v) => Some(v.clone()),
+                            toml::Value::Table(t) => {
+                                t.get("version").and_then(|v| v.as_str()).map(String::from)
+                            }
+                            _ => None,
+                        };
+                        direct.push(Dependency {
+                            name: name.clone(),
+                            version,
+                            kind,
+                        });
+                    }
+                }
+            }
+
+            ("pyproject.toml", direct)
+        } else if let Some(requirements) = read_manifest(&project_path.join("requirements.txt")) {
+            let direct = requirements
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or("").trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('-'))
+                .map(|line| parse_python_requirement(line, DependencyKind::Normal))
+                .collect();
+            ("requirements.txt", direct)
+        } else {
+            return None;
+        };
+
+    let mut lockfile = None;
+    let mut transitive_count = None;
+    for name in ["poetry.lock", "uv.lock"] {
+        if let Some(count) = locked_packages(project_path, name) {
+            lockfile = Some(name.to_string());
+            transitive_count = Some(count);
+            break;
+        }
+    }
+
+    Some(DependencySummary {
+        ecosystem: "Python".to_string(),
+        manifest: manifest.to_string(),
+        direct,
+        lockfile,
+        transitive_count,
+    })
+}
+
+/// Split a PEP 508 requirement such as `requests>=2.0; python_version > "3.8"`
+/// into its name and version specifier
+fn parse_python_requirement(requirement: &str, kind: DependencyKind) -> Dependency {
+    let requirement = requirement.split(';').next().unwrap_or("").trim();
+    let split_at = requirement
+        .find(['=', '<', '>', '~', '!', ' ', '['])
+        .unwrap_or(requirement.len());
+    let (name, rest) = requirement.split_at(split_at);
+
+    // Drop extras like `[security]` from the version part
+    let rest = match rest.find(']') {
+        Some(end) if rest.starts_with('[') => &rest[end + 1..],
+        _ => rest,
+    };
+    let version = rest.trim();
+
+    Dependency {
+        name: name.to_string(),
+        version: if version.is_empty() {
+            None
+        } else {
+            Some(version.to_string())
+        },
+        kind,
+    }
+}
+
+fn go_summary(project_path: &Path) -> Option<DependencySummary> {
+    let manifest = read_manifest(&project_path.join("go.mod"))?;
+
+    let mut direct = Vec::new();
+    let mut in_require_block = false;
+    for line in manifest.lines() {
+        let line = line.trim();
+        let requirement = if in_require_block {
+            if line == ")" {
+                in_require_block = false;
+                continue;
+            }
+            line
+        } else if line == "require (" {
+            in_require_block = true;
+            continue;
+        } else if let Some(rest) = line.strip_prefix("require ") {
+            rest
+        } else {
+            continue;
+        };
+
+        // Indirect requirements are transitive, not direct
+        if requirement.is_empty() || requirement.contains("// indirect") {
+            continue;
+        }
+        let mut parts = requirement.split_whitespace();
+        if let Some(name) = parts.next() {
+            direct.push(Dependency {
+                name: name.to_string(),
+                version: parts.next().map(|v| v.to_string()),
+                kind: DependencyKind::Normal,
+            });
+        }
+    }
+
+    let transitive_count = locked_packages(project_path, "go.sum");
+
+    Some(DependencySummary {
+        ecosystem: "Go".to_string(),
+        manifest: "go.mod".to_string(),
+        direct,
+        lockfile: transitive_count.map(|_| "go.sum".to_string()),
+        transitive_count,
+    })
+}
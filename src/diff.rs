@@ -0,0 +1,145 @@
+// Comparison of two previously generated JSON digests
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeMap;
+
+use crate::error::{DigestError, Result};
+use crate::tokens::estimate_tokens;
+
+/// The subset of a JSON digest needed to compare file contents
+#[derive(Deserialize, Debug)]
+struct DigestFiles {
+    files: Vec<DigestFile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DigestFile {
+    path: String,
+    content: String,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileDelta {
+    pub path: String,
+    pub change: FileChange,
+    pub old_tokens: usize,
+    pub new_tokens: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+impl FileDelta {
+    pub fn token_delta(&self) -> i64 {
+        self.new_tokens as i64 - self.old_tokens as i64
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DigestDiff {
+    /// Added, removed and changed files, sorted by path
+    pub files: Vec<FileDelta>,
+    pub unchanged: usize,
+    pub old_total_tokens: usize,
+    pub new_total_tokens: usize,
+}
+
+impl DigestDiff {
+    pub fn token_delta(&self) -> i64 {
+        self.new_total_tokens as i64 - self.old_total_tokens as i64
+    }
+}
+
+/// Compare two JSON digests and report added, removed and changed files with token deltas
+pub fn diff_digests(old_json: &str, new_json: &str) -> Result<DigestDiff> {
+    let old: DigestFiles = serde_json::from_str(old_json).map_err(|source| DigestError::Json {
+        which: "old",
+        source,
+    })?;
+    let new: DigestFiles = serde_json::from_str(new_json).map_err(|source| DigestError::Json {
+        which: "new",
+        source,
+    })?;
+
+    let old_files: BTreeMap<&str, &str> = old
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.content.as_str()))
+        .collect();
+    let new_files: BTreeMap<&str, &str> = new
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.content.as_str()))
+        .collect();
+
+    let mut files = Vec::new();
+    let mut unchanged = 0;
+
+    for (path, old_content) in &old_files {
+        match new_files.get(path) {
+            Some(new_content) if new_content == old_content => unchanged += 1,
+            Some(new_content) => {
+                let (lines_added, lines_removed) = count_line_changes(old_content, new_content);
+                files.push(FileDelta {
+                    path: path.to_string(),
+                    change: FileChange::Changed,
+                    old_tokens: estimate_tokens(old_content),
+                    new_tokens: estimate_tokens(new_content),
+                    lines_added,
+                    lines_removed,
+                });
+            }
+            None => files.push(FileDelta {
+                path: path.to_string(),
+                change: FileChange::Removed,
+                old_tokens: estimate_tokens(old_content),
+                new_tokens: 0,
+                lines_added: 0,
+                lines_removed: old_content.lines().count(),
+            }),
+        }
+    }
+
+    for (path, new_content) in &new_files {
+        if !old_files.contains_key(path) {
+            files.push(FileDelta {
+                path: path.to_string(),
+                change: FileChange::Added,
+                old_tokens: 0,
+                new_tokens: estimate_tokens(new_content),
+                lines_added: new_content.lines().count(),
+                lines_removed: 0,
+            });
+        }
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(DigestDiff {
+        files,
+        unchanged,
+        old_total_tokens: old_files.values().map(|c| estimate_tokens(c)).sum(),
+        new_total_tokens: new_files.values().map(|c| estimate_tokens(c)).sum(),
+    })
+}
+
+fn count_line_changes(old: &str, new: &str) -> (usize, usize) {
+    let diff = TextDiff::from_lines(old, new);
+    let mut added = 0;
+    let mut removed = 0;
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => added += 1,
+            ChangeTag::Delete => removed += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+    (added, removed)
+}
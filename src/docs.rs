@@ -0,0 +1,334 @@
+// Documentation harvesting for --docs-only: READMEs, documentation directories and
+// architecture decision records, and the doc comments of source files
+use std::ops::RangeInclusive;
+
+/// Directories whose files are all documentation, matched anywhere in the project
+pub const DOC_DIRS: &[&str] = &["docs", "doc", "adr", "adrs", "decisions"];
+
+/// Prose formats collected from documentation directories though they aren't code
+pub const PROSE_EXTENSIONS: &[&str] = &["md", "markdown", "mdx", "rst", "adoc", "txt", "org"];
+
+/// Whether the file at `path`, relative to the project root, is documentation: a README, or
+/// a file under one of `DOC_DIRS`
+pub fn is_doc_file(path: &str) -> bool {
+    let path = path.replace('\\', "/").to_lowercase();
+    let mut segments: Vec<&str> = path.split('/').collect();
+    let name = segments.pop().unwrap_or_default();
+    name.starts_with("readme") || segments.iter().any(|dir| DOC_DIRS.contains(dir))
+}
+
+/// Whether a documentation file at `path` is prose the walk wouldn't otherwise collect, such
+/// as `docs/guide.rst` or an extensionless `README`
+pub fn is_prose(path: &str) -> bool {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((_, extension)) => PROSE_EXTENSIONS.contains(&extension.to_lowercase().as_str()),
+        None => true,
+    }
+}
+
+/// How a language marks its doc comments
+#[derive(Clone, Copy)]
+enum Style {
+    /// Runs of lines starting with one of these, e.g. Rust's `///` and `//!`
+    Lines(&'static [&'static str]),
+    /// `/** ... */` blocks
+    Block,
+    /// Module, class, and function docstrings
+    Docstring,
+    /// `//` comments directly above a declaration, as godoc reads them
+    Godoc,
+}
+
+fn styles(extension: &str) -> &'static [Style] {
+    const RUST: &[&str] = &["///", "//!"];
+    const TRIPLE_SLASH: &[&str] = &["///"];
+    const LUA: &[&str] = &["---"];
+    match extension {
+        "rs" => &[Style::Lines(RUST), Style::Block],
+        "cs" | "swift" | "c" | "h" | "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => {
+            &[Style::Lines(TRIPLE_SLASH), Style::Block]
+        }
+        "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx" | "java" | "kt" | "kts" | "scala" | "php"
+        | "m" | "mm" | "dart" | "groovy" => &[Style::Block],
+        "py" | "pyi" => &[Style::Docstring],
+        "go" => &[Style::Godoc],
+        "lua" => &[Style::Lines(LUA)],
+        _ => &[],
+    }
+}
+
+/// The doc comments of the source file at `path`, each followed by the item it documents,
+/// with a blank line between blocks; None when the language isn't known or the file has none
+pub fn doc_comments(path: &str, content: &str) -> Option<String> {
+    let extension = path.rsplit_once('.')?.1.to_lowercase();
+    let lines: Vec<&str> = content.lines().collect();
+    let mut blocks: Vec<RangeInclusive<usize>> = Vec::new();
+    for style in styles(&extension) {
+        blocks.extend(match *style {
+            Style::Lines(prefixes) => line_blocks(&lines, prefixes),
+            Style::Block => star_blocks(&lines),
+            Style::Docstring => docstring_blocks(&lines),
+            Style::Godoc => godoc_blocks(&lines),
+        });
+    }
+    if blocks.is_empty() {
+        return None;
+    }
+
+    // Overlapping or touching blocks print as one
+    blocks.sort_by_key(|block| *block.start());
+    let mut merged: Vec<RangeInclusive<usize>> = Vec::new();
+    for block in blocks {
+        match merged.last_mut() {
+            Some(last) if *block.start() <= last.end() + 1 => {
+                *last = *last.start()..=*last.end().max(block.end());
+            }
+            _ => merged.push(block),
+        }
+    }
+    let text: Vec<String> = merged
+        .into_iter()
+        .map(|block| lines[block].join("\n"))
+        .collect();
+    Some(text.join("\n\n") + "\n")
+}
+
+/// Extend a comment ending at `end` to the item below it, past attributes and decorators
+fn with_item(lines: &[&str], start: usize, end: usize) -> RangeInclusive<usize> {
+    let mut last = end;
+    for (i, line) in lines.iter().enumerate().skip(end + 1) {
+        let line = line.trim_start();
+        if line.is_empty() {
+            break;
+        }
+        last = i;
+        if !line.starts_with("#[") && !line.starts_with('@') {
+            break;
+        }
+    }
+    start..=last
+}
+
+fn line_blocks(lines: &[&str], prefixes: &[&str]) -> Vec<RangeInclusive<usize>> {
+    // Four slashes or dashes are a plain comment, not a doc comment
+    let is_doc = |line: &str| {
+        let line = line.trim_start();
+        prefixes.iter().any(|prefix| {
+            line.strip_prefix(prefix)
+                .is_some_and(|rest| !rest.starts_with(&prefix[prefix.len() - 1..]))
+        })
+    };
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !is_doc(lines[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i + 1 < lines.len() && is_doc(lines[i + 1]) {
+            i += 1;
+        }
+        blocks.push(with_item(lines, start, i));
+        i += 1;
+    }
+    blocks
+}
+
+fn star_blocks(lines: &[&str]) -> Vec<RangeInclusive<usize>> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_start();
+        if !line.starts_with("/**") || line.starts_with("/**/") {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        // The closing `*/` may be on the opening line
+        if !line[3..].contains("*/") {
+            while i + 1 < lines.len() && !lines[i].contains("*/") {
+                i += 1;
+            }
+        }
+        blocks.push(with_item(lines, start, i));
+        i += 1;
+    }
+    blocks
+}
+
+fn docstring_blocks(lines: &[&str]) -> Vec<RangeInclusive<usize>> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_start();
+        let body = line.trim_start_matches(['r', 'R', 'u', 'U']);
+        let Some(quote) = ["\"\"\"", "'''"].into_iter().find(|q| body.starts_with(q)) else {
+            i += 1;
+            continue;
+        };
+        let Some(start) = docstring_owner(lines, i) else {
+            i += 1;
+            continue;
+        };
+        if !body[3..].contains(quote) {
+            i += 1;
+            while i < lines.len() && !lines[i].contains(quote) {
+                i += 1;
+            }
+        }
+        blocks.push(start..=i.min(lines.len() - 1));
+        i += 1;
+    }
+    blocks
+}
+
+/// Where the docstring opening on line `i` starts its block: the header of the function or
+/// class it's the first statement of, or `i` itself for a module docstring. None for a string
+/// that isn't a docstring.
+fn docstring_owner(lines: &[&str], i: usize) -> Option<usize> {
+    let code_above = (0..i).rev().find(|&j| {
+        let line = lines[j].trim();
+        !line.is_empty() && !line.starts_with('#')
+    });
+    let Some(previous) = code_above else {
+        return Some(i);
+    };
+    if !lines[previous].trim_end().ends_with(':') {
+        return None;
+    }
+    // A signature may span lines; its header is the nearest def or class above
+    (0..=previous).rev().take(20).find(|&j| {
+        let line = lines[j].trim_start();
+        ["def ", "async def ", "class "]
+            .iter()
+            .any(|keyword| line.starts_with(keyword))
+    })
+}
+
+fn godoc_blocks(lines: &[&str]) -> Vec<RangeInclusive<usize>> {
+    const DECLARATIONS: &[&str] = &["package ", "func ", "type ", "var ", "const "];
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !lines[i].starts_with("//") {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i + 1 < lines.len() && lines[i + 1].starts_with("//") {
+            i += 1;
+        }
+        let documents = lines
+            .get(i + 1)
+            .is_some_and(|next| DECLARATIONS.iter().any(|d| next.starts_with(d)));
+        if documents {
+            blocks.push(start..=i + 1);
+        }
+        i += 1;
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_doc_file() {
+        assert!(is_doc_file("README.md"));
+        assert!(is_doc_file("crates/core/readme"));
+        assert!(is_doc_file("docs/guide/install.md"));
+        assert!(is_doc_file("doc/adr/0001-record-decisions.md"));
+        assert!(is_doc_file("docs\\api.rst"));
+        assert!(!is_doc_file("src/docs.rs"));
+        assert!(!is_doc_file("CHANGELOG.md"));
+
+        assert!(is_prose("docs/guide.rst"));
+        assert!(is_prose("README"));
+        assert!(!is_prose("docs/diagram.png"));
+    }
+
+    #[test]
+    fn test_rust_doc_comments() {
+        let source = "//! Parsing\n\
+                      \n\
+                      use std::fmt;\n\
+                      \n\
+                      /// A parsed value\n\
+                      #[derive(Debug)]\n\
+                      pub struct Value {\n\
+                      \x20   // internal\n\
+                      \x20   inner: u32,\n\
+                      }\n\
+                      \n\
+                      //// Not a doc comment\n\
+                      fn helper() {}\n";
+        assert_eq!(
+            doc_comments("src/parse.rs", source).unwrap(),
+            "//! Parsing\n\n/// A parsed value\n#[derive(Debug)]\npub struct Value {\n"
+        );
+        assert_eq!(doc_comments("src/empty.rs", "fn main() {}\n"), None);
+        assert_eq!(doc_comments("config.json", "{}\n"), None);
+    }
+
+    #[test]
+    fn test_block_doc_comments() {
+        let source = "import x from 'x';\n\
+                      /**\n\
+                      \x20* Adds two numbers.\n\
+                      \x20*/\n\
+                      export function add(a, b) {\n\
+                      \x20 return a + b;\n\
+                      }\n\
+                      /* plain */\n\
+                      /** Inline. */\n\
+                      const y = 1;\n";
+        assert_eq!(
+            doc_comments("src/add.ts", source).unwrap(),
+            "/**\n * Adds two numbers.\n */\nexport function add(a, b) {\n\n\
+             /** Inline. */\nconst y = 1;\n"
+        );
+    }
+
+    #[test]
+    fn test_python_docstrings() {
+        let source = "#!/usr/bin/env python\n\
+                      \"\"\"Command-line entry point.\"\"\"\n\
+                      import sys\n\
+                      \n\
+                      def run(\n\
+                      \x20   args,\n\
+                      ):\n\
+                      \x20   \"\"\"Run the tool.\n\
+                      \n\
+                      \x20   Returns the exit code.\n\
+                      \x20   \"\"\"\n\
+                      \x20   x = \"\"\"not a docstring\"\"\"\n\
+                      \x20   return 0\n";
+        assert_eq!(
+            doc_comments("cli.py", source).unwrap(),
+            "\"\"\"Command-line entry point.\"\"\"\n\n\
+             def run(\n    args,\n):\n    \"\"\"Run the tool.\n\n    Returns the exit code.\n    \"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_godoc_comments() {
+        let source = "// Package cache keeps recent results.\n\
+                      package cache\n\
+                      \n\
+                      // Get looks up a key.\n\
+                      func Get(key string) {}\n\
+                      \n\
+                      // stray note\n\
+                      \n\
+                      func other() {}\n";
+        assert_eq!(
+            doc_comments("cache/cache.go", source).unwrap(),
+            "// Package cache keeps recent results.\npackage cache\n\n\
+             // Get looks up a key.\nfunc Get(key string) {}\n"
+        );
+    }
+}
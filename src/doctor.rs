@@ -0,0 +1,225 @@
+// `digest doctor`: checks a project's digest.toml and ignore files for mistakes that would
+// otherwise only show up as a surprising digest
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{load_config_args, CONFIG_FILE};
+use crate::ignores::{IgnoreSource, IgnoreSources};
+use crate::{
+    collect_files, count_all_files, invalid_ignore_lines, parse_ignore_line, CollectOptions,
+};
+
+/// Ignore files `digest doctor` reads, in the order digest applies them
+pub const IGNORE_FILES: &[&str] = &[".digestignore", ".gitignore", ".ignore"];
+
+/// Patterns that match every path
+const CATCH_ALL_PATTERNS: &[&str] = &["*", "**", "/*", "/**", "**/*", "*/"];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The setup is broken: a file can't be parsed or a pattern can't be compiled
+    Error,
+    /// The setup works, but probably not as intended
+    Warning,
+}
+
+/// One problem `digest doctor` found
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    pub fn error(message: impl Into<String>) -> Self {
+        Finding {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Finding {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+/// A pattern and where it came from
+struct Located {
+    file: &'static str,
+    line: usize,
+    pattern: String,
+}
+
+/// Check the project's digest.toml and ignore files. Options in digest.toml are only checked
+/// for TOML syntax here; whether they name real flags is up to the command-line parser.
+pub fn diagnose(project_path: &Path) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if let Err(err) = load_config_args(project_path) {
+        findings.push(Finding::error(format!("{:#}", err)));
+    }
+
+    let mut patterns = Vec::new();
+    for &file in IGNORE_FILES {
+        let Ok(content) = fs::read_to_string(project_path.join(file)) else {
+            continue;
+        };
+        for (line, pattern, err) in invalid_ignore_lines(&content) {
+            findings.push(Finding::error(format!(
+                "{}:{}:{}: invalid pattern `{}`: {}",
+                file, line, err.position, pattern, err.message
+            )));
+        }
+        for (index, line) in content.lines().enumerate() {
+            let Some(pattern) = parse_ignore_line(line) else {
+                continue;
+            };
+            patterns.push(Located {
+                file,
+                line: index + 1,
+                pattern,
+            });
+        }
+    }
+
+    findings.extend(conflicts(&patterns));
+
+    for located in &patterns {
+        if CATCH_ALL_PATTERNS.contains(&located.pattern.as_str()) {
+            findings.push(Finding::warning(format!(
+                "{}:{}: `{}` ignores every file",
+                located.file, located.line, located.pattern
+            )));
+        }
+    }
+
+    if let Some(finding) = everything_ignored(project_path, &patterns) {
+        findings.push(finding);
+    }
+    findings
+}
+
+/// Re-includes (`!pattern`) of a pattern another ignore file ignores. The file walk applies
+/// .gitignore and .ignore with negations, .ignore taking precedence, while .digestignore is
+/// matched by digest on its own: its negations only re-include digest's defaults, so the file
+/// stays out.
+fn conflicts(patterns: &[Located]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for negation in patterns {
+        let Some(reincluded) = negation.pattern.strip_prefix('!') else {
+            continue;
+        };
+        for ignored in patterns {
+            let overrides = negation.file == ".ignore" && ignored.file == ".gitignore";
+            if ignored.file != negation.file && ignored.pattern == reincluded && !overrides {
+                findings.push(Finding::warning(format!(
+                    "{}:{}: `{}` conflicts with `{}` in {}:{}; digest leaves matching files out",
+                    negation.file,
+                    negation.line,
+                    negation.pattern,
+                    ignored.pattern,
+                    ignored.file,
+                    ignored.line
+                )));
+            }
+        }
+    }
+    findings
+}
+
+/// A warning when the ignore files leave nothing to digest in a project that has files
+fn everything_ignored(project_path: &Path, patterns: &[Located]) -> Option<Finding> {
+    if count_all_files(project_path) == 0 {
+        return None;
+    }
+
+    let mut ignores = IgnoreSources::new().with(IgnoreSource::Builtin, [".git"]);
+    for located in patterns {
+        ignores.add(
+            IgnoreSource::File(located.file.to_string()),
+            [located.pattern.as_str()],
+        );
+    }
+    let options = CollectOptions {
+        max_files: 0,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(project_path, &ignores, &options).ok()?;
+    if collection.overflow > 0 {
+        return None;
+    }
+    let config = if project_path.join(CONFIG_FILE).is_file() {
+        format!(", {}", CONFIG_FILE)
+    } else {
+        String::new()
+    };
+    Some(Finding::warning(format!(
+        "no file would be digested: the ignore files leave out every code file (check {}{})",
+        IGNORE_FILES.join(", "),
+        config
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_healthy_project_has_no_findings() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(temp_dir.path().join(".gitignore"), "target/\n").unwrap();
+        // .ignore outranks .gitignore in the file walk, so this re-include works
+        fs::write(temp_dir.path().join(".ignore"), "!target/\n").unwrap();
+        fs::write(temp_dir.path().join(CONFIG_FILE), "max-files = 10\n").unwrap();
+
+        assert_eq!(diagnose(temp_dir.path()), vec![]);
+    }
+
+    #[test]
+    fn test_reports_broken_files_and_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join(".digestignore"), "# generated\nbuild/\nsrc/[a-\n").unwrap();
+        fs::write(root.join(".gitignore"), "!build/\n").unwrap();
+        fs::write(root.join(CONFIG_FILE), "max-files = \n").unwrap();
+
+        let findings = diagnose(root);
+        assert_eq!(findings.len(), 3, "{:?}", findings);
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(findings[0].message.contains("Invalid"));
+        assert_eq!(findings[1].severity, Severity::Error);
+        assert!(findings[1]
+            .message
+            .starts_with(".digestignore:3:5: invalid pattern `src/[a-`"));
+        assert_eq!(
+            findings[2],
+            Finding::warning(
+                ".gitignore:1: `!build/` conflicts with `build/` in .digestignore:2; \
+                 digest leaves matching files out"
+            )
+        );
+    }
+
+    #[test]
+    fn test_warns_when_everything_is_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(temp_dir.path().join(".digestignore"), "*\n").unwrap();
+
+        let findings = diagnose(temp_dir.path());
+        assert_eq!(findings.len(), 2, "{:?}", findings);
+        assert_eq!(
+            findings[0],
+            Finding::warning(".digestignore:1: `*` ignores every file")
+        );
+        assert!(findings[1].message.starts_with("no file would be digested"));
+    }
+}
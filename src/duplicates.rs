@@ -0,0 +1,264 @@
+// Near-duplicate code across included files for --duplicates: winnowed fingerprints of
+// normalized lines, extended into the longest blocks the copies share
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+use crate::FileInfo;
+
+/// Fingerprints are the smallest k-gram hash in each window of this many k-grams. K-grams are
+/// sized so that every shared run of the minimum length contains a whole window, which
+/// guarantees the copies a common fingerprint.
+const WINDOW: usize = 4;
+
+/// A block of code that appears in more than one place
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateBlock {
+    /// Significant lines the copies share, not counting blank lines and lone braces
+    pub lines: usize,
+    /// Where the block appears, by path and line range
+    pub occurrences: Vec<Occurrence>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Occurrence {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Occurrence {
+    fn contains(&self, other: &Occurrence) -> bool {
+        self.path == other.path
+            && self.start_line <= other.start_line
+            && other.end_line <= self.end_line
+    }
+}
+
+/// A file's significant lines: trimmed, with whitespace runs collapsed, and their line numbers
+struct Lines {
+    path: String,
+    numbers: Vec<usize>,
+    text: Vec<String>,
+}
+
+impl Lines {
+    /// Lines without a letter or digit (`}`, `});`, `*/`) carry no code of their own and are
+    /// left out, so they neither make nor break a match
+    fn new(file: &FileInfo) -> Self {
+        let mut numbers = Vec::new();
+        let mut text = Vec::new();
+        for (i, line) in file.content.lines().enumerate() {
+            if !line.chars().any(char::is_alphanumeric) {
+                continue;
+            }
+            numbers.push(i + 1);
+            text.push(line.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        Lines {
+            path: file.path.clone(),
+            numbers,
+            text,
+        }
+    }
+
+    fn occurrence(&self, start: usize, end: usize) -> Occurrence {
+        Occurrence {
+            path: self.path.clone(),
+            start_line: self.numbers[start],
+            end_line: self.numbers[end],
+        }
+    }
+}
+
+fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Winnowing: hashes of every `k` consecutive lines, keeping the rightmost minimum of each
+/// window. Returns (hash, index of the k-gram's first line).
+fn fingerprints(lines: &Lines, k: usize, window: usize) -> Vec<(u64, usize)> {
+    if lines.text.len() < k {
+        return Vec::new();
+    }
+    let hashes: Vec<u64> = lines.text.windows(k).map(hash_of).collect();
+    let mut picked: Vec<(u64, usize)> = Vec::new();
+    for start in 0..hashes.len().saturating_sub(window - 1).max(1) {
+        let end = (start + window).min(hashes.len());
+        let (offset, &hash) = hashes[start..end]
+            .iter()
+            .enumerate()
+            .rev()
+            .min_by_key(|&(_, hash)| *hash)
+            .expect("non-empty window");
+        let position = start + offset;
+        if picked.last() != Some(&(hash, position)) {
+            picked.push((hash, position));
+        }
+    }
+    picked
+}
+
+/// Blocks of at least `min_lines` significant lines that appear in two or more places among
+/// `files`, longest first. A copy within one file counts when the copies don't overlap.
+pub fn find_duplicates(files: &[FileInfo], min_lines: usize) -> Vec<DuplicateBlock> {
+    let min_lines = min_lines.max(1);
+    let window = WINDOW.min(min_lines);
+    let k = min_lines - window + 1;
+    let lines: Vec<Lines> = files.iter().map(Lines::new).collect();
+
+    let mut locations: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+    for (file, file_lines) in lines.iter().enumerate() {
+        for (hash, position) in fingerprints(file_lines, k, window) {
+            locations.entry(hash).or_default().push((file, position));
+        }
+    }
+
+    // Each later location is extended against the first, both ways, as far as lines agree;
+    // copies of the same text are gathered under its hash
+    let mut blocks: BTreeMap<u64, (usize, Vec<Occurrence>)> = BTreeMap::new();
+    for places in locations.values().filter(|places| places.len() > 1) {
+        let (anchor_file, anchor_at) = places[0];
+        let anchor = &lines[anchor_file];
+        for &(file, at) in &places[1..] {
+            let other = &lines[file];
+            let mut before = 0;
+            while before < anchor_at.min(at)
+                && anchor.text[anchor_at - before - 1] == other.text[at - before - 1]
+            {
+                before += 1;
+            }
+            let mut length = 0;
+            while anchor_at + length < anchor.text.len()
+                && at + length < other.text.len()
+                && anchor.text[anchor_at + length] == other.text[at + length]
+            {
+                length += 1;
+            }
+            let (start, other_start) = (anchor_at - before, at - before);
+            let length = before + length;
+            // A hash collision leaves a short run; a run overlapping itself is repetition, not a copy
+            let overlaps = file == anchor_file && start.abs_diff(other_start) < length;
+            if length < min_lines || overlaps {
+                continue;
+            }
+
+            let key = hash_of(&anchor.text[start..start + length]);
+            let (_, occurrences) = blocks.entry(key).or_insert_with(|| (length, Vec::new()));
+            for occurrence in [
+                anchor.occurrence(start, start + length - 1),
+                other.occurrence(other_start, other_start + length - 1),
+            ] {
+                if !occurrences.contains(&occurrence) {
+                    occurrences.push(occurrence);
+                }
+            }
+        }
+    }
+
+    let mut blocks: Vec<DuplicateBlock> = blocks
+        .into_values()
+        .map(|(lines, mut occurrences)| {
+            occurrences.sort();
+            DuplicateBlock { lines, occurrences }
+        })
+        .collect();
+    blocks.sort_by(|a, b| {
+        b.lines
+            .cmp(&a.lines)
+            .then_with(|| a.occurrences.cmp(&b.occurrences))
+    });
+
+    // A block found inside every copy of a longer one adds nothing
+    let mut kept: Vec<DuplicateBlock> = Vec::new();
+    for block in blocks {
+        let covered = block.occurrences.iter().all(|occurrence| {
+            kept.iter()
+                .flat_map(|kept| &kept.occurrences)
+                .any(|bigger| bigger.contains(occurrence))
+        });
+        if !covered {
+            kept.push(block);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> FileInfo {
+        FileInfo::new(path.to_string(), None, content.to_string())
+    }
+
+    const SHARED: &str = "def total(items):\n\
+                          \x20   result = 0\n\
+                          \x20   for item in items:\n\
+                          \x20       if item.active:\n\
+                          \x20           result += item.price\n\
+                          \x20           result -= item.discount\n\
+                          \x20   return result\n";
+
+    #[test]
+    fn test_finds_copies_across_files() {
+        let a = format!("import os\n\n{}\nprint('a')\n", SHARED);
+        // Reindented, with an extra blank line: still the same block
+        let b = format!(
+            "class Cart:\n{}\n",
+            SHARED
+                .lines()
+                .map(|line| format!("  {}", line))
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        );
+        let blocks = find_duplicates(&[file("a.py", &a), file("b.py", &b)], 5);
+        assert_eq!(
+            blocks,
+            vec![DuplicateBlock {
+                lines: 7,
+                occurrences: vec![
+                    Occurrence {
+                        path: "a.py".to_string(),
+                        start_line: 3,
+                        end_line: 9,
+                    },
+                    Occurrence {
+                        path: "b.py".to_string(),
+                        start_line: 2,
+                        end_line: 14,
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_copies_within_one_file_and_in_three_files() {
+        let twice = format!("{}\nx = 1\n\n{}", SHARED, SHARED);
+        let blocks = find_duplicates(&[file("twice.py", &twice)], 5);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].occurrences.len(), 2);
+
+        let files = [
+            file("a.py", SHARED),
+            file("b.py", SHARED),
+            file("c.py", SHARED),
+        ];
+        let blocks = find_duplicates(&files, 5);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].occurrences.len(), 3);
+    }
+
+    #[test]
+    fn test_ignores_short_and_repetitive_matches() {
+        let short = "a = 1\nb = 2\nc = 3\n";
+        assert!(find_duplicates(&[file("a.py", short), file("b.py", short)], 5).is_empty());
+
+        let repeated = "x += 1\n".repeat(8);
+        assert!(find_duplicates(&[file("loop.py", &repeated)], 5).is_empty());
+    }
+}
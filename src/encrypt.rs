@@ -0,0 +1,111 @@
+// Output encryption for --encrypt: digests are piped through age (or rage) so they can sit on
+// shared storage without being readable
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::{DigestError, Result};
+
+/// Programs tried in order; both take the same `-r` and `--armor` flags
+const AGE_PROGRAMS: &[&str] = &["age", "rage"];
+
+/// Who a digest is encrypted to, from `--encrypt age:<recipient>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipient(String);
+
+impl Recipient {
+    /// Parse `age:<recipient>`, where the recipient is an age public key (`age1...`) or an
+    /// SSH public key (`ssh-ed25519 ...`, `ssh-rsa ...`)
+    pub fn parse(value: &str) -> std::result::Result<Recipient, String> {
+        let Some((scheme, recipient)) = value.split_once(':') else {
+            return Err(format!("expected age:<recipient>, got '{}'", value));
+        };
+        if scheme != "age" {
+            return Err(format!(
+                "unsupported encryption '{}', only age is supported",
+                scheme
+            ));
+        }
+        let recipient = recipient.trim();
+        if !recipient.starts_with("age1") && !recipient.starts_with("ssh-") {
+            return Err(format!(
+                "'{}' isn't an age recipient (age1...) or an SSH public key (ssh-...)",
+                recipient
+            ));
+        }
+        Ok(Recipient(recipient.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Encrypt `content` to every one of `recipients` with the first of age or rage installed;
+/// `armor` gives PEM-style text instead of binary
+pub fn encrypt(content: &[u8], recipients: &[Recipient], armor: bool) -> Result<Vec<u8>> {
+    for program in AGE_PROGRAMS {
+        let mut command = Command::new(program);
+        for recipient in recipients {
+            command.arg("-r").arg(recipient.as_str());
+        }
+        if armor {
+            command.arg("--armor");
+        }
+        let spawned = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(DigestError::Encryption(format!(
+                    "Failed to run {}: {}",
+                    program, err
+                )))
+            }
+        };
+
+        // Feed stdin from a separate thread so a large digest can't deadlock against stdout
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = content.to_vec();
+        let writer = std::thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output().map_err(|err| {
+            DigestError::Encryption(format!("Failed to run {}: {}", program, err))
+        })?;
+        let _ = writer.join();
+
+        if !output.status.success() {
+            return Err(DigestError::Encryption(format!(
+                "{} failed ({}): {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        return Ok(output.stdout);
+    }
+    Err(DigestError::Encryption(format!(
+        "--encrypt needs {} on the PATH; neither was found",
+        AGE_PROGRAMS.join(" or ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recipient() {
+        let key = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+        assert_eq!(
+            Recipient::parse(&format!("age:{}", key)).unwrap().as_str(),
+            key
+        );
+        assert!(Recipient::parse("age:ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAI").is_ok());
+        assert!(Recipient::parse(key).is_err());
+        assert!(Recipient::parse("gpg:ABCDEF").is_err());
+        assert!(Recipient::parse("age:alice@example.com").is_err());
+    }
+}
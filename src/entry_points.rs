@@ -0,0 +1,135 @@
+// Entry-point detection: where execution starts in each ecosystem
+#[cfg(feature = "native")]
+use ignore::WalkBuilder;
+use serde::Serialize;
+#[cfg(feature = "native")]
+use std::fs;
+#[cfg(feature = "native")]
+use std::path::Path;
+
+#[cfg(feature = "native")]
+use crate::paths;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EntryPoint {
+    /// Path relative to the project root, with forward slashes
+    pub path: String,
+    /// Ecosystem the entry point belongs to (Rust, Node, Python, Go, ...)
+    pub ecosystem: &'static str,
+}
+
+/// Directories that never hold a project's own entry points
+#[cfg(feature = "native")]
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "vendor",
+    "venv",
+    ".venv",
+    "dist",
+    "build",
+    "__pycache__",
+];
+
+/// Find entry points in the project, sorted by path.
+///
+/// Looks a few directories deep so workspace members and `cmd/<name>/main.go` layouts are found.
+#[cfg(feature = "native")]
+pub fn detect_entry_points(project_path: &Path) -> Vec<EntryPoint> {
+    let mut entry_points = Vec::new();
+
+    let mut builder = WalkBuilder::new(project_path);
+    builder.max_depth(Some(4)).filter_entry(|entry| {
+        entry
+            .file_name()
+            .to_str()
+            .map(|name| !SKIPPED_DIRS.contains(&name))
+            .unwrap_or(true)
+    });
+
+    for entry in builder.build().flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Some(relative) = paths::strip_root(entry.path(), project_path) else {
+            continue;
+        };
+        let relative = paths::slash(&relative);
+        if let Some(ecosystem) = entry_point_ecosystem(&relative) {
+            entry_points.push(EntryPoint {
+                path: relative,
+                ecosystem,
+            });
+        }
+    }
+
+    // package.json names its entry points explicitly
+    for path in package_json_entry_points(project_path) {
+        if !entry_points.iter().any(|e| e.path == path) && project_path.join(&path).is_file() {
+            entry_points.push(EntryPoint {
+                path,
+                ecosystem: "Node",
+            });
+        }
+    }
+
+    entry_points.sort_by(|a, b| a.path.cmp(&b.path));
+    entry_points
+}
+
+/// Classify a relative path as an entry point by its name and location
+pub fn entry_point_ecosystem(path: &str) -> Option<&'static str> {
+    let segments: Vec<&str> = path.split('/').collect();
+    let file_name = *segments.last()?;
+    let parent = segments.len().checked_sub(2).map(|i| segments[i]);
+    let grandparent = segments.len().checked_sub(3).map(|i| segments[i]);
+
+    match file_name {
+        "main.rs" if parent == Some("src") => Some("Rust"),
+        name if name.ends_with(".rs") && parent == Some("bin") => Some("Rust"),
+        "main.rs" if grandparent == Some("bin") => Some("Rust"),
+        "main.go" => Some("Go"),
+        "manage.py" | "app.py" | "main.py" | "__main__.py" | "wsgi.py" | "asgi.py" => {
+            Some("Python")
+        }
+        "index.js" | "index.ts" | "index.mjs" | "index.tsx" | "main.js" | "main.ts"
+        | "server.js" | "server.ts"
+            if parent.is_none() || parent == Some("src") =>
+        {
+            Some("Node")
+        }
+        "project.godot" => Some("Godot"),
+        "main.lua" => Some("Lua"),
+        "main.c" => Some("C"),
+        "main.cpp" | "main.cc" => Some("C++"),
+        _ => None,
+    }
+}
+
+/// `main` and `bin` targets declared in the root package.json
+#[cfg(feature = "native")]
+fn package_json_entry_points(project_path: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(project_path.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return Vec::new();
+    };
+
+    let mut paths = Vec::new();
+    if let Some(main) = json.get("main").and_then(|v| v.as_str()) {
+        paths.push(main.to_string());
+    }
+    match json.get("bin") {
+        Some(serde_json::Value::String(bin)) => paths.push(bin.to_string()),
+        Some(serde_json::Value::Object(bins)) => {
+            paths.extend(bins.values().filter_map(|v| v.as_str()).map(String::from))
+        }
+        _ => {}
+    }
+
+    paths
+        .into_iter()
+        .map(|p| p.trim_start_matches("./").to_string())
+        .collect()
+}
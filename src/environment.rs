@@ -0,0 +1,67 @@
+// Defaults from the environment: CI detection, and DIGEST_* variables standing in for
+// command-line flags
+use std::env;
+
+/// Prefix of the variables read as flag defaults
+pub const ENV_PREFIX: &str = "DIGEST_";
+
+/// Whether a `CI` value means "running in CI": `true` or `1`, as GitHub Actions, GitLab CI,
+/// and most other providers set it
+pub fn is_ci_value(value: &str) -> bool {
+    value.eq_ignore_ascii_case("true") || value == "1"
+}
+
+/// Whether digest is running in CI
+pub fn is_ci() -> bool {
+    env::var("CI").is_ok_and(|value| is_ci_value(&value))
+}
+
+/// Whether a boolean flag's variable turns it on: `1`, `true`, or `yes`
+pub fn is_truthy(value: &str) -> bool {
+    ["1", "true", "yes"]
+        .iter()
+        .any(|truthy| value.eq_ignore_ascii_case(truthy))
+}
+
+/// The long flag a variable stands for: `DIGEST_MAX_FILES` is `max-files`
+pub fn flag_name(variable: &str) -> Option<String> {
+    let name = variable.strip_prefix(ENV_PREFIX)?;
+    if name.is_empty() {
+        return None;
+    }
+    Some(name.to_ascii_lowercase().replace('_', "-"))
+}
+
+/// Every `DIGEST_*` variable as a long flag name and its value, sorted by name
+pub fn env_options() -> Vec<(String, String)> {
+    let mut options: Vec<(String, String)> = env::vars()
+        .filter_map(|(variable, value)| Some((flag_name(&variable)?, value)))
+        .collect();
+    options.sort();
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag_name() {
+        assert_eq!(flag_name("DIGEST_MAX_FILES").as_deref(), Some("max-files"));
+        assert_eq!(flag_name("DIGEST_FORMAT").as_deref(), Some("format"));
+        assert_eq!(flag_name("DIGEST_"), None);
+        assert_eq!(flag_name("CI"), None);
+    }
+
+    #[test]
+    fn test_ci_and_truthy_values() {
+        assert!(is_ci_value("true"));
+        assert!(is_ci_value("TRUE"));
+        assert!(is_ci_value("1"));
+        assert!(!is_ci_value("false"));
+        assert!(!is_ci_value(""));
+
+        assert!(is_truthy("yes"));
+        assert!(!is_truthy("0"));
+    }
+}
@@ -0,0 +1,135 @@
+// The library's error type, so consumers can match on what went wrong instead of parsing
+// messages
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::exit::ErrorKind;
+
+/// Everything that can go wrong while building a digest
+#[derive(Debug, Error)]
+pub enum DigestError {
+    /// An ignore, grep, or symbol pattern couldn't be compiled; `flag` says where it came from
+    #[error("Invalid {flag} pattern '{pattern}': {message}")]
+    PatternInvalid {
+        flag: String,
+        pattern: String,
+        message: String,
+    },
+    /// `--check-patterns` found invalid lines in the project's ignore files
+    #[error("{invalid} of {checked} patterns are invalid")]
+    PatternsInvalid { invalid: usize, checked: usize },
+    /// Reading or writing `path` failed; `action` says what was being done
+    #[error("Failed to {action} {}", path.display())]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// A file that has to be text isn't valid UTF-8
+    #[error("{} isn't valid UTF-8", path.display())]
+    NotUtf8 { path: PathBuf },
+    /// The directory walk failed at an entry
+    #[error("Error accessing entry")]
+    #[cfg(feature = "native")]
+    Walk(#[source] ignore::Error),
+    /// A config file such as digest.toml couldn't be used; `message` says why
+    #[error("Invalid {}: {message}", path.display())]
+    Config { path: PathBuf, message: String },
+    /// An archive couldn't be read
+    #[error("Failed to read archive {}", path.display())]
+    #[cfg(feature = "native")]
+    Archive {
+        path: PathBuf,
+        #[source]
+        source: zip::result::ZipError,
+    },
+    /// An archive unpacks to more than `ExtractLimits` allows
+    #[error("Archive {} is too large to extract: {message}", path.display())]
+    #[cfg(feature = "native")]
+    ArchiveTooLarge { path: PathBuf, message: String },
+    /// A digest given as input isn't valid JSON; `which` is `old` or `new`
+    #[error("Failed to parse {which} digest as JSON")]
+    Json {
+        which: &'static str,
+        #[source]
+        source: serde_json::Error,
+    },
+    /// A prompt template failed to parse or render
+    #[error("Failed to {action} template: {message}")]
+    Template {
+        action: &'static str,
+        message: String,
+    },
+    /// The digest went over a size, token, or file-count limit
+    #[error("{0}")]
+    BudgetExceeded(String),
+    /// No files were left to digest
+    #[error("{0}")]
+    NoFiles(String),
+    /// `--check` found the output file out of date
+    #[error("{} is out of date: a fresh digest differs from it", path.display())]
+    Stale { path: PathBuf },
+    /// Invalid command-line arguments
+    #[error("{0}")]
+    Usage(String),
+    /// A `digest extract` selector names a file that isn't Rust or Python, doesn't parse, or has
+    /// no such item
+    #[error("{0}")]
+    Extract(String),
+    /// `--encrypt` couldn't run age or rage, or it failed
+    #[error("{0}")]
+    Encryption(String),
+    /// An output format failed to render the digest, e.g. an `exec:` command that failed
+    #[error("{0}")]
+    Formatter(String),
+    /// The digest would break a rule of digest.policy.toml
+    #[error("{0}")]
+    PolicyViolation(String),
+    /// The digest is too large to print to a terminal without --force
+    #[error("output too large for a terminal ({size}); use --output, pipe it, or pass --force")]
+    TooLargeForTerminal { size: String },
+}
+
+impl DigestError {
+    /// A `DigestError::Io` for `action` on `path`, for use with `map_err`
+    pub fn io(action: &'static str, path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |source| DigestError::Io {
+            action,
+            path,
+            source,
+        }
+    }
+
+    /// The kind of failure, which determines the process exit code
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            DigestError::PatternInvalid { .. } | DigestError::PatternsInvalid { .. } => {
+                ErrorKind::InvalidPattern
+            }
+            DigestError::Io { .. } | DigestError::NotUtf8 { .. } => ErrorKind::Io,
+            #[cfg(feature = "native")]
+            DigestError::Archive { .. } | DigestError::ArchiveTooLarge { .. } => ErrorKind::Io,
+            #[cfg(feature = "native")]
+            DigestError::Walk(err) if err.io_error().is_some() => ErrorKind::Io,
+            DigestError::BudgetExceeded(_) => ErrorKind::OverBudget,
+            DigestError::NoFiles(_) => ErrorKind::NoFiles,
+            DigestError::Stale { .. } => ErrorKind::Stale,
+            DigestError::Usage(_) | DigestError::Extract(_) => ErrorKind::Usage,
+            DigestError::PolicyViolation(_) => ErrorKind::PolicyViolation,
+            DigestError::TooLargeForTerminal { .. } => ErrorKind::TooLargeForTerminal,
+            #[cfg(feature = "native")]
+            DigestError::Walk(_) => ErrorKind::Other,
+            DigestError::Config { .. }
+            | DigestError::Json { .. }
+            | DigestError::Template { .. }
+            | DigestError::Formatter(_)
+            | DigestError::Encryption(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// `Result` with `DigestError`, as the library's functions return it
+pub type Result<T, E = DigestError> = std::result::Result<T, E>;
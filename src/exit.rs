@@ -0,0 +1,131 @@
+// Exit codes and machine-readable error reports for scripts wrapping digest
+use serde::Serialize;
+use std::error::Error;
+
+use crate::error::DigestError;
+
+/// What went wrong, which determines the process exit code
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Anything not covered below
+    Other,
+    /// Invalid command-line arguments
+    Usage,
+    /// No files were left to digest
+    NoFiles,
+    /// The digest exceeded a size, token, or file-count limit
+    OverBudget,
+    /// Reading or writing a file failed
+    Io,
+    /// An ignore, grep, or symbol pattern couldn't be compiled
+    InvalidPattern,
+    /// `--check` found the output file out of date
+    Stale,
+    /// The digest would break the data-egress policy
+    PolicyViolation,
+    /// The digest is too large to print to a terminal without --force
+    TooLargeForTerminal,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::NoFiles => 3,
+            ErrorKind::OverBudget => 4,
+            ErrorKind::Io => 5,
+            ErrorKind::InvalidPattern => 6,
+            ErrorKind::Stale => 7,
+            ErrorKind::PolicyViolation => 8,
+            ErrorKind::TooLargeForTerminal => 9,
+        }
+    }
+}
+
+/// The errors behind `err`, outermost first
+fn chain<'a>(err: &'a (dyn Error + 'static)) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+    std::iter::successors(Some(err), |&err| err.source())
+}
+
+/// Determine the kind of an error: a `DigestError` in the chain wins, then any IO error
+pub fn classify(err: &(dyn Error + 'static)) -> ErrorKind {
+    if let Some(err) = chain(err).find_map(|cause| cause.downcast_ref::<DigestError>()) {
+        return err.kind();
+    }
+    if chain(err).any(|cause| cause.is::<std::io::Error>()) {
+        return ErrorKind::Io;
+    }
+    ErrorKind::Other
+}
+
+/// Structured form of an error printed by `--error-format json`
+#[derive(Serialize, Debug)]
+pub struct ErrorReport {
+    pub kind: ErrorKind,
+    pub code: i32,
+    pub message: String,
+    /// Underlying causes, outermost first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<String>,
+}
+
+impl ErrorReport {
+    pub fn new(err: &(dyn Error + 'static)) -> Self {
+        let kind = classify(err);
+        ErrorReport {
+            kind,
+            code: kind.exit_code(),
+            message: err.to_string(),
+            causes: chain(err).skip(1).map(|cause| cause.to_string()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    #[test]
+    fn test_classify() {
+        let err = DigestError::BudgetExceeded("too big".to_string());
+        assert_eq!(classify(&err), ErrorKind::OverBudget);
+        let err = DigestError::TooLargeForTerminal {
+            size: "2.0 MB".to_string(),
+        };
+        assert_eq!(classify(&err).exit_code(), 9);
+        // A selector that names nothing is a usage error, not an empty digest
+        let err = DigestError::Extract("no item 'tick' in src/engine.rs".to_string());
+        assert_eq!(classify(&err).exit_code(), 2);
+
+        let err = std::fs::read_to_string("/nonexistent/digest/file")
+            .map_err(DigestError::io("read", "/nonexistent/digest/file"))
+            .unwrap_err();
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.kind, ErrorKind::Io);
+        assert_eq!(report.code, 5);
+        assert_eq!(report.message, "Failed to read /nonexistent/digest/file");
+        assert_eq!(report.causes.len(), 1);
+
+        // A DigestError wrapped in another error still decides the kind
+        #[derive(Debug)]
+        struct Wrapper(DigestError);
+        impl fmt::Display for Wrapper {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("while digesting")
+            }
+        }
+        impl Error for Wrapper {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+        let err = Wrapper(DigestError::NoFiles("nothing left".to_string()));
+        assert_eq!(classify(&err), ErrorKind::NoFiles);
+
+        let err = std::fmt::Error;
+        assert_eq!(classify(&err), ErrorKind::Other);
+    }
+}
@@ -0,0 +1,511 @@
+// `digest extract`: single functions, types, and impl blocks cut out of a file by a
+// `path::Type::method` selector, with the imports they use
+use proc_macro2::Span;
+use serde::Serialize;
+use std::collections::HashSet;
+use syn::spanned::Spanned;
+use syn::{ImplItem, Item, TraitItem, UseTree};
+
+use crate::api::Source;
+
+/// A file and the path of an item in it, as in `src/engine.rs::Engine::tick`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    pub path: String,
+    /// Item names from the outermost in: a module, type, or trait, then a member
+    pub item: Vec<String>,
+}
+
+impl Selector {
+    /// Parse `path::Item::member`; the file path ends at the first `::`
+    pub fn parse(selector: &str) -> Result<Selector, String> {
+        let (path, item) = selector.split_once("::").ok_or_else(|| {
+            format!(
+                "expected FILE::ITEM, e.g. src/engine.rs::Engine::tick, got '{}'",
+                selector
+            )
+        })?;
+        let item: Vec<String> = item.split("::").map(str::to_string).collect();
+        if path.is_empty() || item.iter().any(|name| name.is_empty()) {
+            return Err(format!("expected FILE::ITEM, got '{}'", selector));
+        }
+        Ok(Selector {
+            path: path.to_string(),
+            item,
+        })
+    }
+}
+
+/// The code a selector picked out of its file
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Extract {
+    pub path: String,
+    /// The item path, e.g. `Engine::tick`
+    pub item: String,
+    /// Code fence language, e.g. "rust"
+    pub language: String,
+    /// The file's imports that the excerpts refer to
+    pub imports: Vec<String>,
+    pub excerpts: Vec<Excerpt>,
+}
+
+/// One matching item, inside the header of the impl, trait, or class that holds it
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Excerpt {
+    /// First and last line of the item itself, 1-based
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// Cut the item `selector.item` out of `content`, the file at `selector.path`. Rust and Python
+/// files are supported; errors say what couldn't be found.
+pub fn extract(selector: &Selector, content: &str) -> Result<Extract, String> {
+    let extension = selector.path.rsplit_once('.').map(|(_, ext)| ext);
+    let (language, excerpts, imports) = match extension {
+        Some("rs") => {
+            let file = syn::parse_file(content)
+                .map_err(|err| format!("{} doesn't parse as Rust: {}", selector.path, err))?;
+            let source = Source::new(content);
+            let excerpts = rust_items(&source, &file.items, &selector.item);
+            let imports = rust_imports(&source, &file.items);
+            ("rust", excerpts, imports)
+        }
+        Some("py") => {
+            let lines: Vec<&str> = content.lines().collect();
+            let excerpts = python_items(&lines, 0, lines.len(), &selector.item);
+            ("python", excerpts, python_imports(&lines))
+        }
+        _ => {
+            return Err(format!(
+                "can't extract from {}: only Rust and Python files are supported",
+                selector.path
+            ))
+        }
+    };
+    if excerpts.is_empty() {
+        return Err(format!(
+            "no item '{}' in {}",
+            selector.item.join("::"),
+            selector.path
+        ));
+    }
+
+    // Only the imports whose names the excerpts use; glob imports always
+    let used: HashSet<&str> = excerpts
+        .iter()
+        .flat_map(|excerpt| {
+            excerpt
+                .text
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+        })
+        .collect();
+    let imports = imports
+        .into_iter()
+        .filter(|(names, _)| {
+            names
+                .iter()
+                .any(|name| name == "*" || used.contains(name.as_str()))
+        })
+        .map(|(_, text)| text)
+        .collect();
+    Ok(Extract {
+        path: selector.path.clone(),
+        item: selector.item.join("::"),
+        language: language.to_string(),
+        imports,
+        excerpts,
+    })
+}
+
+/// An extract as a Markdown section: a heading with the selector and lines, then the imports
+/// and excerpts in one code block
+pub fn format_extract(extract: &Extract) -> String {
+    let lines: Vec<String> = extract
+        .excerpts
+        .iter()
+        .map(|e| format!("{}-{}", e.start_line, e.end_line))
+        .collect();
+    let mut output = format!(
+        "### {}::{} (lines {})\n\n```{}\n",
+        extract.path,
+        extract.item,
+        lines.join(", "),
+        extract.language
+    );
+    if !extract.imports.is_empty() {
+        output.push_str(&extract.imports.join("\n"));
+        output.push_str("\n\n");
+    }
+    let excerpts: Vec<&str> = extract.excerpts.iter().map(|e| e.text.as_str()).collect();
+    output.push_str(&excerpts.join("\n\n"));
+    output.push_str("\n```\n");
+    output
+}
+
+fn excerpt(source: &Source, span: Span) -> Excerpt {
+    Excerpt {
+        start_line: span.start().line,
+        end_line: span.end().line,
+        text: source.text(span),
+    }
+}
+
+/// `member` inside the braces of `holder`, e.g. a method inside its impl block
+fn wrapped(source: &Source, holder: Span, open: Span, member: Span) -> Excerpt {
+    Excerpt {
+        text: format!(
+            "{}\n{}\n{}}}",
+            source.slice(holder.start(), open.end()),
+            source.text(member),
+            source.indent(holder)
+        ),
+        ..excerpt(source, member)
+    }
+}
+
+fn type_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn rust_items(source: &Source, items: &[Item], path: &[String]) -> Vec<Excerpt> {
+    let Some((name, rest)) = path.split_first() else {
+        return Vec::new();
+    };
+    let mut excerpts = Vec::new();
+    for item in items {
+        let span = item.span();
+        let named = |ident: &syn::Ident| ident == name && rest.is_empty();
+        match item {
+            Item::Mod(m) if m.ident == name => match (&m.content, rest.is_empty()) {
+                (_, true) => excerpts.push(excerpt(source, span)),
+                (Some((_, items)), false) => excerpts.extend(rust_items(source, items, rest)),
+                (None, false) => {}
+            },
+            // All of a type's impl blocks, or the members they define
+            Item::Impl(i) if type_name(&i.self_ty).as_deref() == Some(name.as_str()) => {
+                let [member] = rest else {
+                    if rest.is_empty() {
+                        excerpts.push(excerpt(source, span));
+                    }
+                    continue;
+                };
+                for item in &i.items {
+                    let ident = match item {
+                        ImplItem::Fn(f) => &f.sig.ident,
+                        ImplItem::Const(c) => &c.ident,
+                        ImplItem::Type(t) => &t.ident,
+                        _ => continue,
+                    };
+                    if ident == member {
+                        let open = i.brace_token.span.open();
+                        excerpts.push(wrapped(source, span, open, item.span()));
+                    }
+                }
+            }
+            Item::Trait(t) if t.ident == name => {
+                let [member] = rest else {
+                    if rest.is_empty() {
+                        excerpts.push(excerpt(source, span));
+                    }
+                    continue;
+                };
+                for item in &t.items {
+                    let ident = match item {
+                        TraitItem::Fn(f) => &f.sig.ident,
+                        TraitItem::Const(c) => &c.ident,
+                        TraitItem::Type(t) => &t.ident,
+                        _ => continue,
+                    };
+                    if ident == member {
+                        let open = t.brace_token.span.open();
+                        excerpts.push(wrapped(source, span, open, item.span()));
+                    }
+                }
+            }
+            Item::Fn(f) if named(&f.sig.ident) => excerpts.push(excerpt(source, span)),
+            Item::Struct(s) if named(&s.ident) => excerpts.push(excerpt(source, span)),
+            Item::Enum(e) if named(&e.ident) => excerpts.push(excerpt(source, span)),
+            Item::Union(u) if named(&u.ident) => excerpts.push(excerpt(source, span)),
+            Item::Type(t) if named(&t.ident) => excerpts.push(excerpt(source, span)),
+            Item::Const(c) if named(&c.ident) => excerpts.push(excerpt(source, span)),
+            Item::Static(s) if named(&s.ident) => excerpts.push(excerpt(source, span)),
+            Item::Macro(m) if m.ident.as_ref().is_some_and(named) => {
+                excerpts.push(excerpt(source, span))
+            }
+            _ => {}
+        }
+    }
+    excerpts
+}
+
+/// The file's top-level `use` items, each with the names it brings in
+fn rust_imports(source: &Source, items: &[Item]) -> Vec<(Vec<String>, String)> {
+    fn names(tree: &UseTree, out: &mut Vec<String>) {
+        match tree {
+            UseTree::Path(path) => names(&path.tree, out),
+            UseTree::Name(name) => out.push(name.ident.to_string()),
+            UseTree::Rename(rename) => out.push(rename.rename.to_string()),
+            UseTree::Glob(_) => out.push("*".to_string()),
+            UseTree::Group(group) => group.items.iter().for_each(|tree| names(tree, out)),
+        }
+    }
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Use(u) => {
+                let mut found = Vec::new();
+                names(&u.tree, &mut found);
+                Some((found, source.text(item.span())))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Python definitions named by `path` among lines `from..to`, which share one indentation
+fn python_items(lines: &[&str], from: usize, to: usize, path: &[String]) -> Vec<Excerpt> {
+    let Some((name, rest)) = path.split_first() else {
+        return Vec::new();
+    };
+    let Some(level) = lines[from..to]
+        .iter()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+    else {
+        return Vec::new();
+    };
+
+    let mut excerpts = Vec::new();
+    for i in from..to {
+        let line = lines[i];
+        if indentation(line) != level {
+            continue;
+        }
+        let header = line.trim_start();
+        let defines = ["def ", "async def ", "class "].iter().any(|keyword| {
+            header.strip_prefix(keyword).is_some_and(|rest| {
+                rest.strip_prefix(name.as_str())
+                    .is_some_and(|after| after.starts_with(['(', ':', '[']))
+            })
+        });
+        if !defines {
+            continue;
+        }
+        // The block runs to the next line at its own indentation or less
+        let end = (i + 1..to)
+            .find(|&j| !lines[j].trim().is_empty() && indentation(lines[j]) <= level)
+            .unwrap_or(to);
+        let last = (i..end)
+            .rev()
+            .find(|&j| !lines[j].trim().is_empty())
+            .unwrap_or(i);
+        if !rest.is_empty() {
+            for mut inner in python_items(lines, i + 1, last + 1, rest) {
+                if header.starts_with("class ") {
+                    inner.text = format!("{}\n{}", line, inner.text);
+                }
+                excerpts.push(inner);
+            }
+            continue;
+        }
+        let start = (from..i)
+            .rev()
+            .take_while(|&j| {
+                indentation(lines[j]) == level && lines[j].trim_start().starts_with('@')
+            })
+            .last()
+            .unwrap_or(i);
+        excerpts.push(Excerpt {
+            start_line: start + 1,
+            end_line: last + 1,
+            text: lines[start..=last].join("\n"),
+        });
+    }
+    excerpts
+}
+
+/// The module's top-level imports, a parenthesized list spanning lines as one, each with the
+/// names it binds
+fn python_imports(lines: &[&str]) -> Vec<(Vec<String>, String)> {
+    let mut imports = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if !line.starts_with("import ") && !line.starts_with("from ") {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        if line.contains('(') {
+            while i + 1 < lines.len() && !lines[i].contains(')') {
+                i += 1;
+            }
+        }
+        let text = lines[start..=i].join("\n");
+        let bound = match text.split_once(" import ") {
+            Some((_, names)) if text.starts_with("from ") => names,
+            _ => text.trim_start_matches("import "),
+        };
+        let names = bound
+            .split(',')
+            .map(|name| name.trim_matches(|c: char| c.is_whitespace() || c == '(' || c == ')'))
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                // `import a.b` binds `a`; `x as y` binds `y`
+                let name = name.rsplit(" as ").next().unwrap_or(name).trim();
+                name.split('.').next().unwrap_or(name).to_string()
+            })
+            .collect();
+        imports.push((names, text));
+        i += 1;
+    }
+    imports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGINE: &str = r#"use std::collections::HashMap;
+use std::fmt;
+
+use crate::world::{Entity, World};
+
+/// Runs the simulation
+pub struct Engine {
+    world: World,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine { world: World::default() }
+    }
+
+    /// Advance one frame
+    pub fn tick(&mut self) {
+        for entity in self.world.entities() {
+            Entity::update(entity);
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "engine")
+    }
+}
+"#;
+
+    fn run(selector: &str, content: &str) -> Result<Extract, String> {
+        extract(&Selector::parse(selector).unwrap(), content)
+    }
+
+    #[test]
+    fn test_selector_parse() {
+        assert_eq!(
+            Selector::parse("src/engine.rs::Engine::tick").unwrap(),
+            Selector {
+                path: "src/engine.rs".to_string(),
+                item: vec!["Engine".to_string(), "tick".to_string()],
+            }
+        );
+        assert!(Selector::parse("src/engine.rs").is_err());
+        assert!(Selector::parse("src/engine.rs::Engine::").is_err());
+    }
+
+    #[test]
+    fn test_extract_rust_method() {
+        let extract = run("src/engine.rs::Engine::tick", ENGINE).unwrap();
+        assert_eq!(extract.imports, vec!["use crate::world::{Entity, World};"]);
+        assert_eq!(extract.excerpts.len(), 1);
+        assert_eq!(extract.excerpts[0].start_line, 16);
+        assert_eq!(extract.excerpts[0].end_line, 21);
+        assert_eq!(
+            extract.excerpts[0].text,
+            "impl Engine {\n    /// Advance one frame\n    pub fn tick(&mut self) {\n        \
+             for entity in self.world.entities() {\n            Entity::update(entity);\n        \
+             }\n    }\n}"
+        );
+
+        let formatted = format_extract(&extract);
+        assert!(formatted.starts_with(
+            "### src/engine.rs::Engine::tick (lines 16-21)\n\n```rust\nuse crate::world::"
+        ));
+        assert!(formatted.ends_with("}\n```\n"));
+    }
+
+    #[test]
+    fn test_extract_rust_type_with_impls() {
+        let extract = run("src/engine.rs::Engine", ENGINE).unwrap();
+        let starts: Vec<usize> = extract.excerpts.iter().map(|e| e.start_line).collect();
+        assert_eq!(starts, vec![6, 11, 24]);
+        assert_eq!(extract.imports.len(), 2);
+
+        let fmt = run("src/engine.rs::Engine::fmt", ENGINE).unwrap();
+        assert!(fmt.excerpts[0]
+            .text
+            .starts_with("impl fmt::Display for Engine {\n"));
+
+        assert_eq!(
+            run("src/engine.rs::Engine::stop", ENGINE).unwrap_err(),
+            "no item 'Engine::stop' in src/engine.rs"
+        );
+        assert!(run("web/app.ts::App", "class App {}").is_err());
+    }
+
+    #[test]
+    fn test_extract_python() {
+        let source = "import os\n\
+                      from typing import (\n\
+                      \x20   Dict,\n\
+                      \x20   List,\n\
+                      )\n\
+                      import numpy as np\n\
+                      \n\
+                      class Engine:\n\
+                      \x20   def __init__(self):\n\
+                      \x20       self.items: List[int] = []\n\
+                      \n\
+                      \x20   @property\n\
+                      \x20   def size(self):\n\
+                      \x20       return len(self.items)\n\
+                      \n\
+                      \x20   def tick(self):\n\
+                      \x20       return np.sum(self.items)\n\
+                      \n\
+                      def helper():\n\
+                      \x20   pass\n";
+        let extract = run("engine.py::Engine::size", source).unwrap();
+        assert_eq!(extract.excerpts[0].start_line, 12);
+        assert_eq!(
+            extract.excerpts[0].text,
+            "class Engine:\n    @property\n    def size(self):\n        return len(self.items)"
+        );
+        assert!(extract.imports.is_empty());
+
+        let extract = run("engine.py::Engine", source).unwrap();
+        assert_eq!(
+            (extract.excerpts[0].start_line, extract.excerpts[0].end_line),
+            (8, 17)
+        );
+        assert_eq!(
+            extract.imports,
+            vec![
+                "from typing import (\n    Dict,\n    List,\n)",
+                "import numpy as np"
+            ]
+        );
+        assert_eq!(
+            run("engine.py::helper", source).unwrap().excerpts[0].text,
+            "def helper():\n    pass"
+        );
+    }
+}
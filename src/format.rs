@@ -0,0 +1,967 @@
+// Pluggable output formats: Markdown built from a header, one section per file, and a footer,
+// and JSON and external commands that render the whole digest at once
+#[cfg(feature = "native")]
+use std::io::Write;
+#[cfg(feature = "native")]
+use std::process::{Command, Stdio};
+
+use crate::assets::format_size;
+use crate::error::{DigestError, Result};
+use crate::update::mark_section;
+use crate::{Digest, ExclusionReason, FileInfo, SkippedKind};
+
+/// A text output format for digests.
+///
+/// By default `render` concatenates the header, every file section in order, and the footer
+/// with `render_with`, so a sectioned format only has to describe those pieces. Formats that
+/// produce the whole document at once, like JSON, override `render` instead.
+pub trait DigestFormatter {
+    /// File extension for outputs digest names itself (e.g. --per-directory)
+    fn extension(&self) -> &'static str {
+        "txt"
+    }
+
+    /// The whole rendered digest
+    fn render(&self, digest: &Digest) -> Result<String> {
+        Ok(render_with(self, digest))
+    }
+
+    /// Everything before the first file: project name, git state, summaries
+    fn header(&self, _digest: &Digest) -> String {
+        String::new()
+    }
+
+    /// The section for one included file
+    fn file(&self, _digest: &Digest, _file: &FileInfo) -> String {
+        String::new()
+    }
+
+    /// Everything after the last file
+    fn footer(&self, _digest: &Digest) -> String {
+        String::new()
+    }
+
+    /// The section `file` is listed under, for formats that organize files; `None` keeps
+    /// one flat list
+    fn group(&self, _file: &FileInfo) -> Option<String> {
+        None
+    }
+
+    /// Emitted before the first file of each group
+    fn group_heading(&self, _group: &str) -> String {
+        String::new()
+    }
+}
+
+/// Render a whole digest with `formatter`. Files are gathered under their group, ungrouped
+/// ones included, with groups in order of their first file so the ranking still shows.
+pub fn render_with<F: DigestFormatter + ?Sized>(formatter: &F, digest: &Digest) -> String {
+    let mut output = formatter.header(digest);
+
+    let mut groups: Vec<(Option<String>, Vec<&FileInfo>)> = Vec::new();
+    for file in &digest.files {
+        let group = formatter.group(file);
+        match groups.iter_mut().find(|(name, _)| *name == group) {
+            Some((_, files)) => files.push(file),
+            None => groups.push((group, vec![file])),
+        }
+    }
+    for (group, files) in groups {
+        if let Some(group) = group {
+            output.push_str(&formatter.group_heading(&group));
+        }
+        for file in files {
+            output.push_str(&formatter.file(digest, file));
+        }
+    }
+
+    output.push_str(&formatter.footer(digest));
+    output
+}
+
+/// How the Markdown Files section is organized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupBy {
+    /// One flat list
+    #[default]
+    None,
+    /// Under a subheading per directory
+    Dir,
+    /// Under a subheading per language
+    Language,
+}
+
+/// The default Markdown format: headings, tables, and fenced code blocks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownFormatter {
+    pub group_by: GroupBy,
+    /// List the SHA-256 of each file's digested content after the summary
+    pub checksums: bool,
+    /// Wrap each file section in the hash markers `--update` reads back
+    pub markers: bool,
+}
+
+impl MarkdownFormatter {
+    /// The Checksums section, one `<sha256>  <path>` line per file. The hashes are of the
+    /// content as digested, so a redacted, PII-scrubbed, summarized, or stripped file won't
+    /// match its checkout
+    fn checksums(&self, digest: &Digest) -> String {
+        if !self.checksums || digest.files.is_empty() {
+            return String::new();
+        }
+        let mut output = String::from("\n## Checksums\n\n```text\n");
+        for file in &digest.files {
+            output.push_str(&format!("{}  {}\n", file.sha256, file.path));
+        }
+        output.push_str("```\n");
+        output
+    }
+}
+
+impl DigestFormatter for MarkdownFormatter {
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn header(&self, digest: &Digest) -> String {
+        let mut output = String::new();
+
+        // Project header
+        output.push_str(&format!("# Project Digest: {}\n\n", digest.project_name));
+
+        match &digest.generated_at {
+            Some(at) => output.push_str(&format!(
+                "_Generated by {} {} at {}_\n\n",
+                digest.generated_by, digest.digest_version, at
+            )),
+            None => output.push_str(&format!(
+                "_Generated by {} {}_\n\n",
+                digest.generated_by, digest.digest_version
+            )),
+        }
+
+        if let Some(part) = &digest.part {
+            output.push_str(&format!("_Part {} of {}_\n\n", part.index, part.total));
+        }
+
+        if let Some(instructions) = &digest.instructions {
+            output.push_str(&format!("## Instructions\n\n{}\n\n", instructions));
+        }
+
+        // Repository state the digest was generated from
+        if let Some(git) = &digest.git {
+            output.push_str(&format!(
+                "- Branch: {}\n",
+                git.branch.as_deref().unwrap_or("(detached HEAD)")
+            ));
+            output.push_str(&format!("- Commit: `{}`\n", git.commit));
+            output.push_str(&format!(
+                "- Worktree: {}\n",
+                if git.dirty { "dirty" } else { "clean" }
+            ));
+            if let Some(remote) = &git.remote_url {
+                output.push_str(&format!("- Remote: {}\n", remote));
+            }
+            output.push('\n');
+        }
+
+        if !digest.submodules.is_empty() {
+            output.push_str("## Submodules\n\n");
+            for submodule in &digest.submodules {
+                match &submodule.url {
+                    Some(url) => output.push_str(&format!("- `{}` ({})\n", submodule.path, url)),
+                    None => output.push_str(&format!("- `{}`\n", submodule.path)),
+                }
+            }
+            output.push('\n');
+        }
+
+        if !digest.entry_points.is_empty() {
+            output.push_str("## Entry Points\n\n");
+            for entry in &digest.entry_points {
+                output.push_str(&format!("- `{}` ({})\n", entry.path, entry.ecosystem));
+            }
+            output.push('\n');
+        }
+
+        if let Some(godot) = &digest.godot {
+            output.push_str("## Godot Project\n\n");
+            if let Some(name) = &godot.name {
+                output.push_str(&format!("- Name: {}\n", name));
+            }
+            if let Some(scene) = &godot.main_scene {
+                output.push_str(&format!("- Main scene: `{}`\n", scene));
+            }
+            for autoload in &godot.autoloads {
+                output.push_str(&format!(
+                    "- Autoload `{}`: `{}`\n",
+                    autoload.name, autoload.path
+                ));
+            }
+            for script in &godot.scripts {
+                output.push_str(&format!("- Script: `{}`\n", script));
+            }
+            output.push('\n');
+        }
+
+        // Language summary
+        output.push_str("## Language Breakdown\n\n");
+        if let Some(main) = &digest.main_language {
+            output.push_str(&format!("Main language: **{}**\n\n", main));
+        }
+
+        output.push_str("| Language | Files | Lines | Bytes | Share |\n");
+        output.push_str("|----------|-------|-------|-------|-------|\n");
+
+        for lang in &digest.language_breakdown {
+            output.push_str(&format!(
+                "| {} | {} | {} | {} | {:.1}% |\n",
+                lang.language, lang.files, lang.lines, lang.bytes, lang.percentage
+            ));
+        }
+        output.push('\n');
+
+        // Dependencies
+        if !digest.dependencies.is_empty() {
+            output.push_str("## Dependencies\n\n");
+            for summary in &digest.dependencies {
+                output.push_str(&format!(
+                    "### {} ({})\n\n",
+                    summary.ecosystem, summary.manifest
+                ));
+
+                if !summary.direct.is_empty() {
+                    output.push_str("| Dependency | Version | Kind |\n");
+                    output.push_str("|------------|---------|------|\n");
+                    for dep in &summary.direct {
+                        output.push_str(&format!(
+                            "| {} | {} | {} |\n",
+                            dep.name,
+                            dep.version.as_deref().unwrap_or("*"),
+                            dep.kind.as_str()
+                        ));
+                    }
+                    output.push('\n');
+                }
+
+                if let (Some(lockfile), Some(count)) = (&summary.lockfile, summary.transitive_count)
+                {
+                    output.push_str(&format!("Locked packages ({}): {}\n\n", lockfile, count));
+                }
+            }
+        }
+
+        if !digest.lockfiles.is_empty() {
+            output.push_str("## Lockfiles\n\n");
+            output.push_str("Listed in place of their content:\n\n");
+            output.push_str("| Lockfile | Ecosystem | Packages | Lines | SHA-256 |\n");
+            output.push_str("|----------|-----------|----------|-------|---------|\n");
+            for lockfile in &digest.lockfiles {
+                output.push_str(&format!(
+                    "| {} | {} | {} | {} | `{}` |\n",
+                    lockfile.path,
+                    lockfile.ecosystem,
+                    lockfile
+                        .packages
+                        .map_or_else(|| "-".to_string(), |count| count.to_string()),
+                    lockfile.lines,
+                    lockfile.sha256
+                ));
+            }
+            output.push('\n');
+        }
+
+        if !digest.excluded.is_empty() {
+            output.push_str("## Excluded Files\n\n");
+            for excluded in &digest.excluded {
+                let note = match excluded.reason {
+                    ExclusionReason::Symlink => "symlink, not followed",
+                    ExclusionReason::SymlinkLoop => "symlink loop",
+                    ExclusionReason::Duplicate => "duplicate",
+                };
+                match &excluded.target {
+                    Some(target) => output.push_str(&format!(
+                        "- `{}` -> `{}` ({})\n",
+                        excluded.path, target, note
+                    )),
+                    None => output.push_str(&format!("- `{}` ({})\n", excluded.path, note)),
+                }
+            }
+            output.push('\n');
+        }
+
+        if !digest.skipped.is_empty() {
+            output.push_str("## Skipped Files\n\n");
+            output.push_str("Present in the project but left out of this digest:\n\n");
+            for skipped in &digest.skipped {
+                let kind = match skipped.kind {
+                    SkippedKind::Image => "image",
+                    SkippedKind::Binary => "binary",
+                    SkippedKind::Oversized => "over the size limit",
+                    SkippedKind::Minified => "minified",
+                };
+                output.push_str(&format!(
+                    "- `{}` ({}, {})\n",
+                    skipped.path,
+                    kind,
+                    format_size(skipped.bytes)
+                ));
+            }
+            output.push('\n');
+        }
+
+        if !digest.images.is_empty() {
+            output.push_str("## Images\n\n");
+            for image in &digest.images {
+                output.push_str(&format!(
+                    "### {}\n\n![{}]({})\n\n",
+                    image.path,
+                    image.path,
+                    image.data_uri()
+                ));
+            }
+        }
+
+        if !digest.pii.is_empty() {
+            output.push_str("## Personal Data\n\n");
+            if digest.pii.iter().all(|finding| finding.redacted) {
+                output.push_str("Found by `--scan pii` and redacted from the files below:\n\n");
+            } else {
+                output.push_str(
+                    "Found by `--scan pii` in the files below; check before sharing:\n\n",
+                );
+            }
+            output.push_str("| File | Line | Kind |\n");
+            output.push_str("|------|------|------|\n");
+            for finding in &digest.pii {
+                output.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    finding.path,
+                    finding.line,
+                    finding.kind.label()
+                ));
+            }
+            output.push('\n');
+        }
+
+        if !digest.duplicates.is_empty() {
+            output.push_str("## Duplicate Code\n\n");
+            output.push_str("Blocks repeated across the included files, longest first:\n\n");
+            for block in &digest.duplicates {
+                let places: Vec<String> = block
+                    .occurrences
+                    .iter()
+                    .map(|o| format!("`{}` lines {}-{}", o.path, o.start_line, o.end_line))
+                    .collect();
+                output.push_str(&format!("- {} lines: {}\n", block.lines, places.join(", ")));
+            }
+            output.push('\n');
+        }
+
+        // Files
+        output.push_str("## Files\n\n");
+
+        // Index of included files with their line counts, and complexity when measured
+        let complexity = digest.files.iter().any(|file| file.complexity.is_some());
+        if complexity {
+            output.push_str(
+                "| File | Language | Code | Comments | Blanks | Complexity | Nesting |\n",
+            );
+            output.push_str(
+                "|------|----------|------|----------|--------|------------|---------|\n",
+            );
+        } else {
+            output.push_str("| File | Language | Code | Comments | Blanks |\n");
+            output.push_str("|------|----------|------|----------|--------|\n");
+        }
+        for file in &digest.files {
+            let stats = file.stats.unwrap_or_default();
+            output.push_str(&format!(
+                "| {} | {} | {} | {} | {} |",
+                file.path,
+                file.language.as_deref().unwrap_or("-"),
+                stats.code,
+                stats.comments,
+                stats.blanks
+            ));
+            if complexity {
+                match file.complexity {
+                    Some(c) => output.push_str(&format!(" {} | {} |", c.cyclomatic, c.max_nesting)),
+                    None => output.push_str(" - | - |"),
+                }
+            }
+            output.push('\n');
+        }
+        output.push('\n');
+
+        output
+    }
+
+    fn footer(&self, digest: &Digest) -> String {
+        let Some(summary) = &digest.summary else {
+            return self.checksums(digest);
+        };
+        let mut output = String::from("## Summary\n\n");
+        output.push_str(&format!("- Files included: {}\n", summary.files_included));
+        let reasons: Vec<String> = summary
+            .files_excluded
+            .iter()
+            .map(|(reason, count)| format!("{} {}", reason.replace('_', " "), count))
+            .collect();
+        if reasons.is_empty() {
+            output.push_str("- Files excluded: 0\n");
+        } else {
+            output.push_str(&format!(
+                "- Files excluded: {} ({})\n",
+                summary.files_excluded_total(),
+                reasons.join(", ")
+            ));
+        }
+        output.push_str(&format!("- Total lines: {}\n", summary.total_lines));
+        output.push_str(&format!("- Total tokens: ~{}\n", summary.total_tokens));
+        match summary.generation_ms {
+            Some(ms) => output.push_str(&format!(
+                "- Generated in {} ms by digest {}\n",
+                ms, summary.tool_version
+            )),
+            None => output.push_str(&format!("- Generated by digest {}\n", summary.tool_version)),
+        }
+        output.push_str(&self.checksums(digest));
+        output
+    }
+
+    fn group(&self, file: &FileInfo) -> Option<String> {
+        let group = match self.group_by {
+            GroupBy::None => None,
+            GroupBy::Dir => Some(match file.path.replace('\\', "/").rsplit_once('/') {
+                Some((dir, _)) => format!("{}/", dir),
+                None => "(root)".to_string(),
+            }),
+            GroupBy::Language => Some(file.language.clone().unwrap_or_else(|| "Other".into())),
+        };
+        // A submodule's files get sections of their own
+        match (&file.submodule, group) {
+            (Some(submodule), Some(group)) => Some(format!("{} (submodule {})", group, submodule)),
+            (Some(submodule), None) => Some(format!("Submodule {}", submodule)),
+            (None, group) => group,
+        }
+    }
+
+    fn group_heading(&self, group: &str) -> String {
+        format!("### {}\n\n", group)
+    }
+
+    fn file(&self, _digest: &Digest, file: &FileInfo) -> String {
+        let mut output = String::new();
+        // File headings nest under group headings
+        let level = if self.group_by == GroupBy::None && file.submodule.is_none() {
+            "###"
+        } else {
+            "####"
+        };
+        match &file.url {
+            Some(url) => output.push_str(&format!("{} [{}]({})\n\n", level, file.path, url)),
+            None => output.push_str(&format!("{} {}\n\n", level, file.path)),
+        }
+
+        if let Some(git) = &file.git {
+            output.push_str(&format!(
+                "_Last changed in `{}` by {} on {} ({} days ago)_\n\n",
+                &git.commit[..git.commit.len().min(12)],
+                git.author,
+                git.date,
+                git.age_days
+            ));
+        }
+
+        if let Some(complexity) = &file.complexity {
+            output.push_str(&format!(
+                "_Complexity: cyclomatic {}, nesting depth {}_\n\n",
+                complexity.cyclomatic, complexity.max_nesting
+            ));
+        }
+
+        output.push_str("```");
+        if let Some(lang) = &file.language {
+            let lang_tag = match lang.as_str() {
+                "JavaScript" => "js",
+                "TypeScript" => "ts",
+                "Python" => "python",
+                "Rust" => "rust",
+                "Java" => "java",
+                "Go" => "go",
+                "C" => "c",
+                "C++" => "cpp",
+                "Objective-C" | "Objective-C++" => "objectivec",
+                "MATLAB" => "matlab",
+                "Ruby" => "ruby",
+                "PHP" => "php",
+                "Lua" => "lua",
+                "Vim Script" => "vim",
+                "SVG" => "svg",
+                "Mermaid" => "mermaid",
+                "PlantUML" => "plantuml",
+                "Graphviz" => "dot",
+                "C#" => "csharp",
+                "GDScript C#" => "csharp",
+                "HTML" => "html",
+                "CSS" => "css",
+                "JSON" => "json",
+                "Markdown" => "md",
+                "YAML" => "yaml",
+                "TOML" => "toml",
+                "GDScript" => "gdscript",
+                "Godot Scene" => "gdscript",
+                "Godot Shader" => "glsl",
+                _ => "",
+            };
+            if !lang_tag.is_empty() {
+                output.push_str(lang_tag);
+            }
+        }
+        output.push('\n');
+        output.push_str(&file.content);
+        output.push_str("\n```\n");
+        if self.markers {
+            output = mark_section(&file.path, &file.sha256, &output);
+        }
+        output.push('\n');
+        output
+    }
+}
+
+/// The digest structure as pretty-printed JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormatter;
+
+impl DigestFormatter for JsonFormatter {
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn render(&self, digest: &Digest) -> Result<String> {
+        serde_json::to_string_pretty(digest).map_err(|err| DigestError::Formatter(err.to_string()))
+    }
+}
+
+/// Streams the JSON digest to an external command's stdin and returns its stdout
+#[cfg(feature = "native")]
+#[derive(Debug, Clone)]
+pub struct ExecFormatter {
+    pub command: String,
+}
+
+#[cfg(feature = "native")]
+impl DigestFormatter for ExecFormatter {
+    fn render(&self, digest: &Digest) -> Result<String> {
+        let failed = |message: String| DigestError::Formatter(message);
+        let json = JsonFormatter.render(digest)?;
+        // Split like a shell would, so quoted arguments and paths with spaces stay whole
+        let words = shlex::split(&self.command).ok_or_else(|| {
+            failed(format!(
+                "Formatter '{}' has an unbalanced quote",
+                self.command
+            ))
+        })?;
+        let (program, args) = words
+            .split_first()
+            .ok_or_else(|| failed("The exec: formatter needs a command".to_string()))?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| {
+                failed(format!(
+                    "Failed to run formatter '{}': {}",
+                    self.command, err
+                ))
+            })?;
+
+        // Feed stdin from a separate thread so a formatter that writes before reading
+        // everything can't deadlock against us
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = std::thread::spawn(move || stdin.write_all(json.as_bytes()));
+        let output = child.wait_with_output().map_err(|err| {
+            failed(format!(
+                "Failed to run formatter '{}': {}",
+                self.command, err
+            ))
+        })?;
+        // A formatter may exit without reading all of its input; its status decides success
+        let _ = writer.join();
+
+        if !output.status.success() {
+            return Err(failed(format!(
+                "Formatter '{}' exited with {}",
+                self.command, output.status
+            )));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| failed(format!("Formatter '{}' wrote invalid UTF-8", self.command)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::complexity::annotate_complexity;
+    use crate::duplicates::find_duplicates;
+    use crate::lockfiles::summarize_lockfile;
+    use crate::pii::scan_pii;
+    use crate::summary::{DigestSummary, GENERATED_BY, TOOL_VERSION};
+    use std::collections::BTreeMap;
+
+    struct OrgFormatter;
+
+    impl DigestFormatter for OrgFormatter {
+        fn header(&self, digest: &Digest) -> String {
+            format!("* {}\n", digest.project_name)
+        }
+
+        fn file(&self, _digest: &Digest, file: &FileInfo) -> String {
+            format!(
+                "** {}\n#+begin_src\n{}\n#+end_src\n",
+                file.path, file.content
+            )
+        }
+
+        fn footer(&self, digest: &Digest) -> String {
+            format!("{} files\n", digest.files.len())
+        }
+    }
+
+    fn digest_of(files: Vec<FileInfo>) -> Digest {
+        Digest {
+            schema_version: crate::schema::SCHEMA_VERSION,
+            generated_by: GENERATED_BY.to_string(),
+            digest_version: TOOL_VERSION.to_string(),
+            generated_at: None,
+            project_name: "demo".to_string(),
+            part: None,
+            instructions: None,
+            git: None,
+            submodules: Vec::new(),
+            entry_points: Vec::new(),
+            godot: None,
+            main_language: None,
+            language_breakdown: Vec::new(),
+            dependencies: Vec::new(),
+            excluded: Vec::new(),
+            skipped: Vec::new(),
+            images: Vec::new(),
+            lockfiles: Vec::new(),
+            duplicates: Vec::new(),
+            pii: Vec::new(),
+            files,
+            summary: None,
+        }
+    }
+
+    fn file(path: &str, language: &str) -> FileInfo {
+        FileInfo::new(path.to_string(), Some(language.to_string()), String::new())
+    }
+
+    #[test]
+    fn test_render_with_custom_formatter() {
+        let digest = digest_of(vec![FileInfo::new(
+            "main.py".to_string(),
+            Some("Python".to_string()),
+            "print(1)".to_string(),
+        )]);
+
+        assert_eq!(
+            render_with(&OrgFormatter, &digest),
+            "* demo\n** main.py\n#+begin_src\nprint(1)\n#+end_src\n1 files\n"
+        );
+        assert!(render_with(&MarkdownFormatter::default(), &digest)
+            .contains("```python\nprint(1)\n```"));
+    }
+
+    #[test]
+    fn test_every_format_renders_through_the_trait() {
+        let digest = digest_of(vec![FileInfo::new(
+            "main.py".to_string(),
+            Some("Python".to_string()),
+            "print(1)".to_string(),
+        )]);
+        let formats: Vec<Box<dyn DigestFormatter>> = vec![
+            Box::new(OrgFormatter),
+            Box::new(MarkdownFormatter::default()),
+            Box::new(JsonFormatter),
+        ];
+        let extensions: Vec<&str> = formats.iter().map(|f| f.extension()).collect();
+        assert_eq!(extensions, vec!["txt", "md", "json"]);
+
+        assert_eq!(
+            formats[0].render(&digest).unwrap(),
+            render_with(&OrgFormatter, &digest)
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&formats[2].render(&digest).unwrap()).unwrap();
+        assert_eq!(json["files"][0]["path"], "main.py");
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_exec_formatter_pipes_json() {
+        let digest = digest_of(Vec::new());
+        let formatter = ExecFormatter {
+            command: "head -c 1".to_string(),
+        };
+        assert_eq!(formatter.render(&digest).unwrap(), "{");
+
+        // Quoted arguments reach the command whole
+        let quoted = ExecFormatter {
+            command: r#"sh -c 'printf "%s|" "$@"' sh "a b" '.files[] | .path' c"#.to_string(),
+        };
+        assert_eq!(quoted.render(&digest).unwrap(), "a b|.files[] | .path|c|");
+
+        for command in ["false", "jq -r '.files[]"] {
+            let failing = ExecFormatter {
+                command: command.to_string(),
+            };
+            assert!(matches!(
+                failing.render(&digest),
+                Err(DigestError::Formatter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_markdown_groups_files() {
+        let digest = digest_of(vec![
+            file("src/main.rs", "Rust"),
+            file("build.py", "Python"),
+            file("src/lib.rs", "Rust"),
+        ]);
+        let headings = |group_by| -> Vec<String> {
+            let formatter = MarkdownFormatter {
+                group_by,
+                ..MarkdownFormatter::default()
+            };
+            render_with(&formatter, &digest)
+                .lines()
+                .skip_while(|line| !line.starts_with("## Files"))
+                .filter(|line| line.starts_with("###"))
+                .map(str::to_string)
+                .collect()
+        };
+
+        assert_eq!(
+            headings(GroupBy::Dir),
+            vec![
+                "### src/",
+                "#### src/main.rs",
+                "#### src/lib.rs",
+                "### (root)",
+                "#### build.py"
+            ]
+        );
+        assert_eq!(
+            headings(GroupBy::Language),
+            vec![
+                "### Rust",
+                "#### src/main.rs",
+                "#### src/lib.rs",
+                "### Python",
+                "#### build.py"
+            ]
+        );
+        assert_eq!(
+            headings(GroupBy::None),
+            vec!["### src/main.rs", "### build.py", "### src/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn test_markdown_gives_submodules_sections() {
+        let vendored = |path| FileInfo {
+            submodule: Some("libs/core".to_string()),
+            ..file(path, "Rust")
+        };
+        let digest = digest_of(vec![
+            vendored("libs/core/src/lib.rs"),
+            file("src/main.rs", "Rust"),
+            vendored("libs/core/build.rs"),
+        ]);
+        let headings: Vec<String> = render_with(&MarkdownFormatter::default(), &digest)
+            .lines()
+            .filter(|line| line.starts_with("###"))
+            .map(str::to_string)
+            .collect();
+
+        // Sections come in ranking order; the project's own files sit at the level of the
+        // submodule heading, outside its section
+        assert_eq!(
+            headings,
+            vec![
+                "### Submodule libs/core",
+                "#### libs/core/src/lib.rs",
+                "#### libs/core/build.rs",
+                "### src/main.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_lists_lockfile_summaries() {
+        let digest = Digest {
+            lockfiles: vec![
+                summarize_lockfile(
+                    "Cargo.lock",
+                    "[[package]]\nname = \"a\"\nsource = \"git\"\n",
+                )
+                .unwrap(),
+                summarize_lockfile("flake.lock", "{}\n").unwrap(),
+            ],
+            ..digest_of(vec![file("src/main.rs", "Rust")])
+        };
+        let output = render_with(&MarkdownFormatter::default(), &digest);
+        assert!(output.contains("## Lockfiles\n"));
+        assert!(output.contains(&format!(
+            "| Cargo.lock | Cargo | 1 | 3 | `{}` |\n",
+            digest.lockfiles[0].sha256
+        )));
+        assert!(output.contains("| flake.lock | Nix | - | 1 |"));
+        assert!(
+            !render_with(&MarkdownFormatter::default(), &digest_of(Vec::new()))
+                .contains("## Lockfiles")
+        );
+    }
+
+    #[test]
+    fn test_markdown_lists_pii_findings() {
+        let mut files = vec![file("fixtures/users.csv", "CSV")];
+        files[0].content = "id,email\n1,jane@corp.io\n".to_string();
+        let digest = Digest {
+            pii: scan_pii(&mut files, false),
+            ..digest_of(files)
+        };
+        let output = render_with(&MarkdownFormatter::default(), &digest);
+        assert!(output.contains(
+            "## Personal Data\n\nFound by `--scan pii` in the files below; check before sharing:\n\n\
+             | File | Line | Kind |\n|------|------|------|\n| fixtures/users.csv | 2 | Email address |\n"
+        ));
+        assert!(output.contains("1,jane@corp.io"));
+    }
+
+    #[test]
+    fn test_markdown_shows_complexity() {
+        let mut files = vec![file("src/main.rs", "Rust"), file("notes.txt", "Text")];
+        files[0].content = "fn main() {\n    if true {}\n}\n".to_string();
+        annotate_complexity(&mut files);
+        let output = render_with(&MarkdownFormatter::default(), &digest_of(files));
+        assert!(output
+            .contains("| File | Language | Code | Comments | Blanks | Complexity | Nesting |\n"));
+        assert!(output.contains("| src/main.rs | Rust | 0 | 0 | 0 | 2 | 2 |\n"));
+        assert!(output.contains("| notes.txt | Text | 0 | 0 | 0 | - | - |\n"));
+        assert!(
+            output.contains("### src/main.rs\n\n_Complexity: cyclomatic 2, nesting depth 2_\n\n")
+        );
+
+        let plain = render_with(&MarkdownFormatter::default(), &digest_of(Vec::new()));
+        assert!(plain.contains("| File | Language | Code | Comments | Blanks |\n"));
+    }
+
+    #[test]
+    fn test_markdown_lists_duplicate_code() {
+        let shared = "a = load()\nb = a.parse()\nc = b.check()\nd = c.save()\n";
+        let mut files = vec![file("one.py", "Python"), file("two.py", "Python")];
+        for file in &mut files {
+            file.content = shared.to_string();
+        }
+        let digest = Digest {
+            duplicates: find_duplicates(&files, 4),
+            ..digest_of(files)
+        };
+        let output = render_with(&MarkdownFormatter::default(), &digest);
+        assert!(output.contains(
+            "## Duplicate Code\n\nBlocks repeated across the included files, longest first:\n\n\
+             - 4 lines: `one.py` lines 1-4, `two.py` lines 1-4\n"
+        ));
+    }
+
+    #[test]
+    fn test_markdown_footer_summarizes_digest() {
+        let files = vec![file("src/main.rs", "Rust")];
+        let mut digest = digest_of(files.clone());
+        assert!(!render_with(&MarkdownFormatter::default(), &digest).contains("## Summary"));
+
+        digest.summary = Some(DigestSummary::new(
+            &files,
+            BTreeMap::from([("max_files", 2), ("not_code", 1)]),
+            Some(5),
+        ));
+        let output = render_with(&MarkdownFormatter::default(), &digest);
+        let footer = &output[output.find("## Summary").unwrap()..];
+        assert_eq!(
+            footer,
+            format!(
+                "## Summary\n\n\
+                 - Files included: 1\n\
+                 - Files excluded: 3 (max files 2, not code 1)\n\
+                 - Total lines: 0\n\
+                 - Total tokens: ~0\n\
+                 - Generated in 5 ms by digest {}\n",
+                TOOL_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn test_markdown_header_names_tool_and_time() {
+        let mut digest = digest_of(Vec::new());
+        let render = |digest: &Digest| render_with(&MarkdownFormatter::default(), digest);
+        assert!(render(&digest).starts_with(&format!(
+            "# Project Digest: demo\n\n_Generated by digest {}_\n\n",
+            TOOL_VERSION
+        )));
+
+        digest.generated_at = Some("2024-05-01T12:30:00Z".to_string());
+        assert!(render(&digest).contains(&format!(
+            "_Generated by digest {} at 2024-05-01T12:30:00Z_\n",
+            TOOL_VERSION
+        )));
+    }
+
+    #[test]
+    fn test_markdown_checksums_section() {
+        let digest = digest_of(vec![FileInfo::new(
+            "a.rs".to_string(),
+            Some("Rust".to_string()),
+            "abc".to_string(),
+        )]);
+        assert!(!render_with(&MarkdownFormatter::default(), &digest).contains("## Checksums"));
+
+        let formatter = MarkdownFormatter {
+            checksums: true,
+            ..MarkdownFormatter::default()
+        };
+        assert!(render_with(&formatter, &digest).ends_with(
+            "## Checksums\n\n```text\n\
+             ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  a.rs\n```\n"
+        ));
+    }
+
+    #[test]
+    fn test_markdown_marks_sections_for_update() {
+        let digest = digest_of(vec![FileInfo::new(
+            "a.rs".to_string(),
+            Some("Rust".to_string()),
+            "abc".to_string(),
+        )]);
+        let formatter = MarkdownFormatter {
+            markers: true,
+            ..MarkdownFormatter::default()
+        };
+        let hash = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(render_with(&formatter, &digest).contains(&format!(
+            "<!-- digest:file sha256={} path=a.rs -->\n### a.rs\n\n```rust\nabc\n```\n\
+             <!-- digest:end sha256={} -->\n\n",
+            hash, hash
+        )));
+        assert!(!render_with(&MarkdownFormatter::default(), &digest).contains("<!--"));
+    }
+}
@@ -0,0 +1,142 @@
+// Python web framework detection: which files explain the app and which directories are noise
+use std::path::Path;
+
+use crate::dependencies::summarize_dependencies;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameworkSpec {
+    pub name: &'static str,
+    /// Distribution name in pyproject.toml or requirements.txt
+    pub package: &'static str,
+    /// File names (or directories, with a trailing slash) ranked ahead of other files
+    pub key_files: &'static [&'static str],
+    /// Ignore patterns added whenever the framework is detected
+    pub ignore: &'static [&'static str],
+}
+
+/// Tool caches every Python framework project tends to have lying around
+const PYTHON_CACHES: &[&str] = &[".tox/", ".mypy_cache/", ".pytest_cache/", "__pycache__/"];
+
+pub const PYTHON_FRAMEWORKS: &[FrameworkSpec] = &[
+    FrameworkSpec {
+        name: "Django",
+        package: "django",
+        key_files: &[
+            "settings.py",
+            "settings/",
+            "urls.py",
+            "models.py",
+            "models/",
+            "views.py",
+            "views/",
+            "admin.py",
+            "forms.py",
+            "serializers.py",
+        ],
+        ignore: &[
+            "migrations/",
+            ".tox/",
+            ".mypy_cache/",
+            ".pytest_cache/",
+            "__pycache__/",
+        ],
+    },
+    FrameworkSpec {
+        name: "Flask",
+        package: "flask",
+        key_files: &[
+            "app.py",
+            "wsgi.py",
+            "config.py",
+            "models.py",
+            "views.py",
+            "routes.py",
+            "forms.py",
+        ],
+        ignore: PYTHON_CACHES,
+    },
+    FrameworkSpec {
+        name: "FastAPI",
+        package: "fastapi",
+        key_files: &[
+            "main.py",
+            "app.py",
+            "config.py",
+            "settings.py",
+            "models.py",
+            "schemas.py",
+            "routers/",
+            "dependencies.py",
+            "crud.py",
+        ],
+        ignore: PYTHON_CACHES,
+    },
+];
+
+/// Frameworks the project depends on; a root `manage.py` also marks a Django project
+pub fn detect_python_frameworks(project_path: &Path) -> Vec<&'static FrameworkSpec> {
+    let packages: Vec<String> = summarize_dependencies(project_path)
+        .into_iter()
+        .filter(|summary| summary.ecosystem == "Python")
+        .flat_map(|summary| summary.direct)
+        .map(|dependency| dependency.name.to_lowercase())
+        .collect();
+
+    PYTHON_FRAMEWORKS
+        .iter()
+        .filter(|framework| {
+            packages.iter().any(|name| name == framework.package)
+                || (framework.name == "Django" && project_path.join("manage.py").is_file())
+        })
+        .collect()
+}
+
+impl FrameworkSpec {
+    /// Ranking score for a file path: 1 for the framework's key files, 0 otherwise
+    pub fn score(&self, path: &str) -> i64 {
+        let path = path.replace('\\', "/");
+        let file_name = path.rsplit('/').next().unwrap_or(&path);
+        let key = self
+            .key_files
+            .iter()
+            .any(|key| match key.strip_suffix('/') {
+                Some(dir) => path.split('/').rev().skip(1).any(|segment| segment == dir),
+                None => file_name == *key,
+            });
+        key as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detects_django_from_manage_py_and_flask_from_requirements() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert!(detect_python_frameworks(temp_dir.path()).is_empty());
+
+        fs::write(temp_dir.path().join("manage.py"), "import django").unwrap();
+        fs::write(
+            temp_dir.path().join("requirements.txt"),
+            "Flask==3.0\nrequests\n",
+        )
+        .unwrap();
+        let names: Vec<&str> = detect_python_frameworks(temp_dir.path())
+            .iter()
+            .map(|f| f.name)
+            .collect();
+        assert_eq!(names, vec!["Django", "Flask"]);
+    }
+
+    #[test]
+    fn test_django_key_files() {
+        let django = &PYTHON_FRAMEWORKS[0];
+        assert_eq!(django.score("shop/settings.py"), 1);
+        assert_eq!(django.score("shop/views/cart.py"), 1);
+        assert_eq!(django.score("shop/urls.py"), 1);
+        assert_eq!(django.score("shop/utils.py"), 0);
+        assert_eq!(django.score("views.py.bak/readme.md"), 0);
+    }
+}
@@ -0,0 +1,89 @@
+// GitHub Actions integration: workflow-command annotations and step outputs
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{DigestError, Result};
+
+/// Environment variable naming the file a step writes its outputs to
+pub const GITHUB_OUTPUT: &str = "GITHUB_OUTPUT";
+
+/// Escape an annotation message so `%` and line breaks survive the workflow command
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape an annotation property, which also can't contain the `:` and `,` separators
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// A workflow command annotation such as `::warning file=src/lib.rs,title=...::message`.
+/// `level` is `notice`, `warning`, or `error`; a `file` attaches it to that file in the PR.
+pub fn annotation(level: &str, file: Option<&str>, title: &str, message: &str) -> String {
+    let mut properties = Vec::new();
+    if let Some(file) = file {
+        properties.push(format!("file={}", escape_property(file)));
+    }
+    properties.push(format!("title={}", escape_property(title)));
+    format!(
+        "::{} {}::{}",
+        level,
+        properties.join(","),
+        escape_data(message)
+    )
+}
+
+/// Step outputs in the `name=value` lines GITHUB_OUTPUT expects
+pub fn output_lines(outputs: &[(&str, String)]) -> String {
+    outputs
+        .iter()
+        .map(|(name, value)| format!("{}={}\n", name, value))
+        .collect()
+}
+
+/// Append step outputs to the GITHUB_OUTPUT file at `path`
+pub fn write_outputs(path: &Path, outputs: &[(&str, String)]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(DigestError::io("open", path))?;
+    file.write_all(output_lines(outputs).as_bytes())
+        .map_err(DigestError::io("write", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotation_escapes_properties_and_message() {
+        assert_eq!(
+            annotation(
+                "warning",
+                Some("src/a,b.rs"),
+                "Left out: max-files",
+                "100% over\nbudget"
+            ),
+            "::warning file=src/a%2Cb.rs,title=Left out%3A max-files::100%25 over%0Abudget"
+        );
+        assert_eq!(
+            annotation("error", None, "digest", "too big"),
+            "::error title=digest::too big"
+        );
+    }
+
+    #[test]
+    fn test_output_lines() {
+        assert_eq!(
+            output_lines(&[
+                ("files_included", "3".to_string()),
+                ("over_budget", "false".to_string())
+            ]),
+            "files_included=3\nover_budget=false\n"
+        );
+    }
+}
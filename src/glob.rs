@@ -0,0 +1,252 @@
+// Gitignore-style glob matching: `*`, `?`, `[abc]`, `[a-z]`, `[!a-z]`, `**` segments, and
+// backslash escapes
+
+/// Whether one path segment matches one pattern segment. `*` and `?` never match `/`.
+pub fn segment_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_chars(&pattern, &text)
+}
+
+fn match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| match_chars(&pattern[1..], &text[i..])),
+        Some('?') => match text.first() {
+            Some(&c) if c != '/' => match_chars(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some('[') => match (parse_class(&pattern[1..]), text.first()) {
+            (Some((class, len)), Some(&c)) => {
+                c != '/' && class.matches(c) && match_chars(&pattern[1 + len..], &text[1..])
+            }
+            // An unterminated class is a literal `[`
+            (None, Some('[')) => match_chars(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        // `\x` matches `x` literally; a trailing lone backslash matches nothing
+        Some('\\') => match pattern.get(1) {
+            Some(&escaped) => {
+                text.first() == Some(&escaped) && match_chars(&pattern[2..], &text[1..])
+            }
+            None => false,
+        },
+        Some(&literal) => text.first() == Some(&literal) && match_chars(&pattern[1..], &text[1..]),
+    }
+}
+
+/// A bracket expression such as `[abc]`, `[a-z]`, or `[!0-9]`
+struct CharClass {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != self.negated
+    }
+}
+
+/// Parse the class after an opening `[`, returning it and the number of chars consumed
+/// (including the closing `]`), or `None` when there is no closing bracket
+fn parse_class(chars: &[char]) -> Option<(CharClass, usize)> {
+    let mut i = 0;
+    let negated = matches!(chars.first(), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+    while i < chars.len() {
+        let c = chars[i];
+        // A `]` right after the opening bracket is a literal
+        if c == ']' && !first {
+            return Some((CharClass { negated, ranges }, i + 1));
+        }
+        first = false;
+        if i + 2 < chars.len() && chars[i + 1] == '-' && chars[i + 2] != ']' {
+            ranges.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Why a pattern can't be used, and where: `position` is the 1-based character column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Check a pattern for syntax the matcher would otherwise read as something that never
+/// matches: unclosed or reversed character classes, a trailing lone backslash, or no path
+/// segments at all. A leading `!` is skipped; whether negation is allowed is up to the caller.
+pub fn validate(pattern: &str) -> Result<(), PatternError> {
+    let error = |position: usize, message: String| Err(PatternError { position, message });
+    let body = pattern.strip_prefix('!').unwrap_or(pattern);
+    let offset = pattern.len() - body.len();
+    if body.trim().is_empty() {
+        return error(offset + 1, "empty pattern".to_string());
+    }
+    if body.split('/').all(str::is_empty) {
+        return error(offset + 1, "pattern has no path segments".to_string());
+    }
+
+    let chars: Vec<char> = body.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 == chars.len() => {
+                return error(
+                    offset + i + 1,
+                    "trailing backslash escapes nothing".to_string(),
+                );
+            }
+            '\\' => i += 2,
+            '[' => {
+                let Some((class, len)) = parse_class(&chars[i + 1..]) else {
+                    return error(offset + i + 1, "unclosed character class".to_string());
+                };
+                if let Some(&(lo, hi)) = class.ranges.iter().find(|(lo, hi)| lo > hi) {
+                    return error(offset + i + 1, format!("invalid range {}-{}", lo, hi));
+                }
+                i += 1 + len;
+            }
+            _ => i += 1,
+        }
+    }
+    Ok(())
+}
+
+/// Drop trailing spaces that aren't escaped with a backslash
+pub fn trim_trailing_spaces(pattern: &str) -> &str {
+    let mut end = pattern.len();
+    while pattern[..end].ends_with(' ') {
+        let backslashes = pattern[..end - 1]
+            .chars()
+            .rev()
+            .take_while(|&c| c == '\\')
+            .count();
+        if backslashes % 2 == 1 {
+            break;
+        }
+        end -= 1;
+    }
+    &pattern[..end]
+}
+
+/// Whether `pattern` matches `path` or one of its parent directories, at any depth.
+///
+/// A trailing `/` restricts the pattern to directories, i.e. to a parent of `path`.
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let dir_only = pattern.ends_with('/');
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if pattern.is_empty() {
+        return false;
+    }
+    (0..segments.len()).any(|start| match_segments(&pattern, &segments[start..], dir_only))
+}
+
+/// Whether `pattern` matches `path` or one of its parent directories, starting from the first
+/// segment of `path` as a gitignore pattern with a slash in it does
+pub fn anchored_path_matches(pattern: &str, path: &str) -> bool {
+    let dir_only = pattern.ends_with('/');
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    !pattern.is_empty() && match_segments(&pattern, &segments, dir_only)
+}
+
+fn match_segments(pattern: &[&str], segments: &[&str], dir_only: bool) -> bool {
+    match pattern.split_first() {
+        // Leftover segments mean the pattern matched a parent directory
+        None => !(dir_only && segments.is_empty()),
+        Some((&"**", rest)) => {
+            (0..=segments.len()).any(|skip| match_segments(rest, &segments[skip..], dir_only))
+        }
+        Some((first, rest)) => match segments.split_first() {
+            Some((segment, remaining)) => {
+                segment_matches(first, segment) && match_segments(rest, remaining, dir_only)
+            }
+            None => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("src/**/*.rs").is_ok());
+        assert!(validate("!keep.log").is_ok());
+        assert!(validate("log[0-9]/").is_ok());
+        assert!(validate("literal\\[").is_ok());
+
+        let err = validate("src/[a-").unwrap_err();
+        assert_eq!(err.position, 5);
+        assert_eq!(err.to_string(), "unclosed character class at position 5");
+        assert_eq!(validate("!log[z-a]").unwrap_err().position, 5);
+        assert_eq!(
+            validate("dir\\").unwrap_err().message,
+            "trailing backslash escapes nothing"
+        );
+        assert_eq!(validate("  ").unwrap_err().message, "empty pattern");
+        assert_eq!(
+            validate("//").unwrap_err().message,
+            "pattern has no path segments"
+        );
+    }
+
+    #[test]
+    fn test_segment_wildcards_and_classes() {
+        assert!(segment_matches("file?.rs", "file1.rs"));
+        assert!(!segment_matches("file?.rs", "file10.rs"));
+        assert!(segment_matches("[abc].txt", "b.txt"));
+        assert!(!segment_matches("[abc].txt", "d.txt"));
+        assert!(segment_matches("log[0-9]", "log7"));
+        assert!(!segment_matches("log[!0-9]", "log7"));
+        assert!(segment_matches("log[!0-9]", "logx"));
+        assert!(segment_matches("*.[ch]", "main.h"));
+        assert!(segment_matches("[]]x", "]x"));
+        assert!(segment_matches("a[b", "a[b"));
+        assert!(segment_matches("\\*.rs", "*.rs"));
+        assert!(!segment_matches("\\*.rs", "main.rs"));
+        assert!(segment_matches("foo\\ ", "foo "));
+    }
+
+    #[test]
+    fn test_trim_trailing_spaces() {
+        assert_eq!(trim_trailing_spaces("foo  "), "foo");
+        assert_eq!(trim_trailing_spaces("foo\\ "), "foo\\ ");
+        assert_eq!(trim_trailing_spaces("foo\\  "), "foo\\ ");
+        assert_eq!(trim_trailing_spaces("foo\\\\ "), "foo\\\\");
+        assert_eq!(trim_trailing_spaces("  "), "");
+    }
+
+    #[test]
+    fn test_path_matches_files_and_directories() {
+        assert!(path_matches("*.py[co]", "/repo/src/mod.pyc"));
+        assert!(path_matches("build?/", "/repo/build2/out.js"));
+        assert!(!path_matches("build?/", "/repo/build2"));
+        assert!(path_matches("src/v[0-9]/*.rs", "/repo/src/v1/lib.rs"));
+        assert!(!path_matches("src/v[0-9]/*.rs", "/repo/src/vx/lib.rs"));
+        assert!(path_matches("**/gen?/**", "/repo/a/gen1/b/c.rs"));
+    }
+}
@@ -0,0 +1,329 @@
+// Godot projects: the main scene and autoloads declared in project.godot, the scripts those
+// scenes attach, and structural summaries of scenes
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::FileInfo;
+
+/// A singleton loaded at startup, from the `[autoload]` section of project.godot
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Autoload {
+    pub name: String,
+    /// Path relative to the project root, without the `res://` prefix
+    pub path: String,
+}
+
+/// What project.godot says runs first
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct GodotProject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_scene: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub autoloads: Vec<Autoload>,
+    /// Scripts attached by the main scene and autoloaded scenes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<String>,
+}
+
+impl GodotProject {
+    /// Rank of a file for `--max-files`: the main scene first, then autoloads, then the
+    /// scripts they attach
+    pub fn score(&self, path: &str) -> i64 {
+        let path = path.replace('\\', "/");
+        if self.main_scene.as_deref() == Some(path.as_str()) {
+            3
+        } else if self.autoloads.iter().any(|autoload| autoload.path == path) {
+            2
+        } else if self.scripts.contains(&path) {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// A quoted `res://` value as a project-relative path. Autoload values start with `*` when
+/// the singleton is enabled as a global; `uid://` references can't be resolved and are skipped.
+fn res_path(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches('"').trim_start_matches('*');
+    value.strip_prefix("res://").map(str::to_string)
+}
+
+/// Read the main scene, autoloads, and project name from project.godot's INI-like content
+pub fn parse_project_godot(content: &str) -> GodotProject {
+    let mut project = GodotProject::default();
+    let mut section = "";
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with(';') || line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match (section, key.trim()) {
+            ("application", "config/name") => {
+                project.name = Some(value.trim().trim_matches('"').to_string());
+            }
+            ("application", "run/main_scene") => project.main_scene = res_path(value),
+            ("autoload", name) => {
+                if let Some(path) = res_path(value) {
+                    project.autoloads.push(Autoload {
+                        name: name.to_string(),
+                        path,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    project
+}
+
+/// Scripts a scene references through `[ext_resource type="Script" path="res://..."]`
+pub fn scene_scripts(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| line.starts_with("[ext_resource") && line.contains("type=\"Script\""))
+        .filter_map(|line| {
+            let start = line.find("path=\"")? + "path=\"".len();
+            let end = start + line[start..].find('"')?;
+            res_path(&line[start..end])
+        })
+        .collect()
+}
+
+/// Parse project.godot at the project root and follow its scenes to their scripts
+pub fn read_godot_project(project_path: &Path) -> Option<GodotProject> {
+    let content = fs::read_to_string(project_path.join("project.godot")).ok()?;
+    let mut project = parse_project_godot(&content);
+
+    let scenes = project
+        .main_scene
+        .iter()
+        .chain(project.autoloads.iter().map(|autoload| &autoload.path))
+        .filter(|path| path.ends_with(".tscn"));
+    let mut scripts = Vec::new();
+    for scene in scenes {
+        let Ok(content) = fs::read_to_string(project_path.join(scene)) else {
+            continue;
+        };
+        for script in scene_scripts(&content) {
+            if !scripts.contains(&script) {
+                scripts.push(script);
+            }
+        }
+    }
+    project.scripts = scripts;
+    Some(project)
+}
+
+/// The value of `key="..."` or `key=ExtResource("...")` in a section header or property line
+fn attribute<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let needle = format!(" {}=", key);
+    let start = line.find(&needle)? + needle.len();
+    let rest = &line[start..];
+    match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next(),
+        None => rest.split([' ', ']']).next(),
+    }
+}
+
+/// The id inside `ExtResource("id")`
+fn ext_resource_id(value: &str) -> Option<&str> {
+    value
+        .trim()
+        .strip_prefix("ExtResource(")?
+        .strip_suffix(')')
+        .map(|id| id.trim_matches('"'))
+}
+
+/// Describe a scene's node tree, attached scripts, instanced scenes, and signal connections,
+/// leaving out the serialized resources and property values that make up most of a `.tscn`.
+/// Returns `None` if `content` isn't a text scene.
+pub fn summarize_scene(content: &str) -> Option<String> {
+    if !content.trim_start().starts_with("[gd_scene") {
+        return None;
+    }
+
+    let mut resources: HashMap<&str, &str> = HashMap::new();
+    let mut nodes: Vec<String> = Vec::new();
+    let mut connections: Vec<String> = Vec::new();
+    let mut in_node = false;
+
+    for line in content.lines() {
+        if line.starts_with("[ext_resource") {
+            in_node = false;
+            if let (Some(id), Some(path)) = (attribute(line, "id"), attribute(line, "path")) {
+                resources.insert(id, path);
+            }
+        } else if line.starts_with("[node") {
+            in_node = true;
+            let name = attribute(line, "name").unwrap_or("?");
+            // Nodes are listed parents first, so the parent path gives the depth
+            let depth = match attribute(line, "parent") {
+                None => 0,
+                Some(".") => 1,
+                Some(parent) => parent.matches('/').count() + 2,
+            };
+            let kind = match (attribute(line, "type"), attribute(line, "instance")) {
+                (Some(kind), _) => kind.to_string(),
+                (None, Some(instance)) => {
+                    let id = ext_resource_id(instance).unwrap_or(instance);
+                    format!("instance of {}", resources.get(id).copied().unwrap_or(id))
+                }
+                (None, None) => "inherited".to_string(),
+            };
+            nodes.push(format!("{}{} ({})", "  ".repeat(depth), name, kind));
+        } else if line.starts_with("[connection") {
+            in_node = false;
+            connections.push(format!(
+                "{}.{} -> {}() on {}",
+                attribute(line, "from").unwrap_or("?"),
+                attribute(line, "signal").unwrap_or("?"),
+                attribute(line, "method").unwrap_or("?"),
+                attribute(line, "to").unwrap_or("?"),
+            ));
+        } else if line.starts_with('[') {
+            in_node = false;
+        } else if in_node {
+            let script = line
+                .strip_prefix("script = ")
+                .and_then(ext_resource_id)
+                .and_then(|id| resources.get(id));
+            if let (Some(script), Some(node)) = (script, nodes.last_mut()) {
+                node.push_str(&format!(" script={}", script));
+            }
+        }
+    }
+
+    let mut summary = String::from("; Scene summary: node tree, scripts, and signals\n");
+    for node in &nodes {
+        summary.push_str(node);
+        summary.push('\n');
+    }
+    if !connections.is_empty() {
+        summary.push_str("\n; Signals\n");
+        summary.push_str(&connections.join("\n"));
+        summary.push('\n');
+    }
+    Some(summary)
+}
+
+/// Replace the content of `.tscn` files with their structural summary
+pub fn summarize_scenes(files: &mut [FileInfo]) {
+    for file in files.iter_mut() {
+        if !file.path.ends_with(".tscn") {
+            continue;
+        }
+        if let Some(summary) = summarize_scene(&file.content) {
+            file.set_content(summary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = r#"; Engine configuration file.
+config_version=5
+
+[application]
+
+config/name="Dodge"
+run/main_scene="res://scenes/main.tscn"
+
+[autoload]
+
+Globals="*res://globals.gd"
+Music="*res://audio/music.tscn"
+Lazy="uid://c3k2"
+"#;
+
+    #[test]
+    fn test_parses_main_scene_and_autoloads() {
+        let project = parse_project_godot(PROJECT);
+        assert_eq!(project.name.as_deref(), Some("Dodge"));
+        assert_eq!(project.main_scene.as_deref(), Some("scenes/main.tscn"));
+        assert_eq!(
+            project.autoloads,
+            vec![
+                Autoload {
+                    name: "Globals".to_string(),
+                    path: "globals.gd".to_string()
+                },
+                Autoload {
+                    name: "Music".to_string(),
+                    path: "audio/music.tscn".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scene_scripts() {
+        let scene = r#"[gd_scene load_steps=3 format=3]
+
+[ext_resource type="Script" path="res://player.gd" id="1_abc"]
+[ext_resource type="Texture2D" path="res://icon.svg" id="2_def"]
+
+[node name="Player" type="Area2D"]
+script = ExtResource("1_abc")
+"#;
+        assert_eq!(scene_scripts(scene), vec!["player.gd".to_string()]);
+    }
+
+    #[test]
+    fn test_score_orders_main_scene_autoloads_scripts() {
+        let project = GodotProject {
+            scripts: vec!["player.gd".to_string()],
+            ..parse_project_godot(PROJECT)
+        };
+        assert_eq!(project.score("scenes/main.tscn"), 3);
+        assert_eq!(project.score("globals.gd"), 2);
+        assert_eq!(project.score("player.gd"), 1);
+        assert_eq!(project.score("enemy.gd"), 0);
+    }
+
+    #[test]
+    fn test_summarize_scene() {
+        let scene = r#"[gd_scene load_steps=4 format=3 uid="uid://b1"]
+
+[ext_resource type="Script" path="res://main.gd" id="1_main"]
+[ext_resource type="PackedScene" path="res://player.tscn" id="2_player"]
+
+[sub_resource type="RectangleShape2D" id="RectangleShape2D_x"]
+size = Vector2(1920, 1080)
+
+[node name="Main" type="Node2D"]
+script = ExtResource("1_main")
+
+[node name="Player" parent="." instance=ExtResource("2_player")]
+position = Vector2(240, 450)
+
+[node name="Timer" type="Timer" parent="Player"]
+wait_time = 0.5
+
+[connection signal="hit" from="Player" to="." method="game_over"]
+"#;
+        assert_eq!(
+            summarize_scene(scene).unwrap(),
+            "; Scene summary: node tree, scripts, and signals\n\
+             Main (Node2D) script=res://main.gd\n\
+             \x20 Player (instance of res://player.tscn)\n\
+             \x20   Timer (Timer)\n\
+             \n; Signals\n\
+             Player.hit -> game_over() on .\n"
+        );
+        assert!(summarize_scene("[gd_resource]").is_none());
+    }
+}
@@ -0,0 +1,37 @@
+// Content-based file filtering for --grep / --grep-exclude
+use crate::FileInfo;
+use regex::Regex;
+
+/// Keep files whose content matches any `include` pattern (all files if there are none)
+/// and matches none of the `exclude` patterns
+pub fn filter_by_content(files: &mut Vec<FileInfo>, include: &[Regex], exclude: &[Regex]) {
+    files.retain(|file| {
+        (include.is_empty() || include.iter().any(|re| re.is_match(&file.content)))
+            && !exclude.iter().any(|re| re.is_match(&file.content))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> FileInfo {
+        FileInfo::new(path.to_string(), None, content.to_string())
+    }
+
+    #[test]
+    fn test_filter_by_content() {
+        let mut files = vec![
+            file("a.rs", "struct PaymentProcessor;"),
+            file("b.rs", "fn main() {}"),
+            file("c.rs", "// TODO PaymentProcessor mock"),
+        ];
+        let include = [Regex::new("PaymentProcessor").unwrap()];
+        let exclude = [Regex::new("(?i)mock").unwrap()];
+
+        filter_by_content(&mut files, &include, &exclude);
+
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.rs"]);
+    }
+}
@@ -0,0 +1,48 @@
+// Content hashing for integrity checks and cache keys
+use sha2::{Digest as _, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Lowercase hex SHA-256 of `bytes`
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A line of `sha256sum` output for `bytes` saved as `name`, which `sha256sum -c` verifies
+pub fn sha256sum_line(bytes: &[u8], name: &str) -> String {
+    format!("{}  {}\n", sha256_hex(bytes), name)
+}
+
+/// The sidecar checksum file for `path`: `digest.md` gets `digest.md.sha256`
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".sha256");
+    PathBuf::from(sidecar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_sidecar() {
+        assert_eq!(
+            sha256sum_line(b"abc", "digest.md"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  digest.md\n"
+        );
+        assert_eq!(
+            sidecar_path(Path::new("out/digest.md")),
+            PathBuf::from("out/digest.md.sha256")
+        );
+    }
+}
@@ -0,0 +1,101 @@
+// `digest hook`: git hooks that regenerate or verify a digest committed alongside the code
+use std::path::Path;
+
+/// Second line of every hook digest installs, so reinstalling can tell its own hooks apart
+pub const HOOK_MARKER: &str = "# Installed by `digest hook install`";
+
+/// Options that keep a committed digest stable from one commit to the next: no timestamp,
+/// timing, or git state
+pub const STABLE_ARGS: &[&str] = &["--no-timestamp", "--no-git-info"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    PreCommit,
+    PrePush,
+}
+
+impl HookKind {
+    /// File name of the hook in the hooks directory
+    pub fn file_name(self) -> &'static str {
+        match self {
+            HookKind::PreCommit => "pre-commit",
+            HookKind::PrePush => "pre-push",
+        }
+    }
+}
+
+/// What an installed hook does with the digest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookAction {
+    /// Write the digest and stage it, so it's part of the commit
+    Regenerate,
+    /// Fail when the committed digest doesn't match a fresh one
+    Check,
+}
+
+/// `text` quoted for a POSIX shell
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// The hook script for the project at `project`, relative to the top of the worktree where
+/// hooks run (`.` at the repository root), and the digest at `output` within the project
+pub fn hook_script(action: HookAction, project: &Path, output: &Path) -> String {
+    match action {
+        HookAction::Regenerate => {
+            // A plain digest run resolves --output against the worktree, not the project
+            let output = if project == Path::new(".") {
+                output.to_path_buf()
+            } else {
+                project.join(output)
+            };
+            let output = shell_quote(&output.to_string_lossy());
+            format!(
+                "#!/bin/sh\n{}: regenerate the digest and commit it with the code\n\
+                 digest {} --output {} {} || exit 1\n\
+                 git add {}\n",
+                HOOK_MARKER,
+                STABLE_ARGS.join(" "),
+                output,
+                shell_quote(&project.to_string_lossy()),
+                output
+            )
+        }
+        HookAction::Check => format!(
+            "#!/bin/sh\n{}: fail when the committed digest is out of date\n\
+             exec digest hook check --output {} {}\n",
+            HOOK_MARKER,
+            shell_quote(&output.to_string_lossy()),
+            shell_quote(&project.to_string_lossy())
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_script() {
+        assert_eq!(
+            hook_script(
+                HookAction::Regenerate,
+                Path::new("."),
+                Path::new("digest.md")
+            ),
+            "#!/bin/sh\n\
+             # Installed by `digest hook install`: regenerate the digest and commit it with the code\n\
+             digest --no-timestamp --no-git-info --output 'digest.md' '.' || exit 1\n\
+             git add 'digest.md'\n"
+        );
+        let regenerate = hook_script(
+            HookAction::Regenerate,
+            Path::new("app"),
+            Path::new("digest.md"),
+        );
+        assert!(regenerate
+            .contains("--output 'app/digest.md' 'app' || exit 1\ngit add 'app/digest.md'\n"));
+        let check = hook_script(HookAction::Check, Path::new("it's"), Path::new("digest.md"));
+        assert!(check.ends_with("exec digest hook check --output 'digest.md' 'it'\\''s'\n"));
+    }
+}
@@ -0,0 +1,326 @@
+// Ignore patterns from every source in one layered set, each remembering where it came from
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::error::Result;
+#[cfg(feature = "native")]
+use crate::git;
+use crate::matcher::CompiledIgnore;
+use crate::{parse_ignore_line, reader};
+
+/// Test-data sinks left out by default: snapshot and fixture directories, golden files, and
+/// recorded HTTP cassettes
+pub const TEST_DATA_PATTERNS: &[&str] = &[
+    "__snapshots__/",
+    "*.snap",
+    "fixtures/",
+    "__fixtures__/",
+    "testdata/",
+    "test_data/",
+    "*.golden",
+    "cassettes/",
+    "vcr_cassettes/",
+];
+
+/// Where an ignore pattern came from
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "name")]
+pub enum IgnoreSource {
+    /// Always applied: the `.git` directory
+    Builtin,
+    /// Defaults for the languages in the project, layered under the ignore files
+    Defaults,
+    /// An ignore file, named relative to the project root
+    File(String),
+    /// The repository's `.git/info/exclude`
+    GitExclude,
+    /// The user's global gitignore (`core.excludesFile`), by path
+    GlobalGitignore(String),
+    /// A detected project type or framework, such as Unreal build output or Django migrations
+    ProjectType(String),
+    /// Given directly: `--ignore-pattern`, or a library caller's own patterns
+    CommandLine,
+}
+
+impl fmt::Display for IgnoreSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IgnoreSource::Builtin => f.write_str("built-in"),
+            IgnoreSource::Defaults => f.write_str("defaults"),
+            IgnoreSource::File(name) => f.write_str(name),
+            IgnoreSource::GitExclude => f.write_str(".git/info/exclude"),
+            IgnoreSource::GlobalGitignore(path) => f.write_str(path),
+            IgnoreSource::ProjectType(name) => write!(f, "{} defaults", name),
+            IgnoreSource::CommandLine => f.write_str("--ignore-pattern"),
+        }
+    }
+}
+
+impl IgnoreSource {
+    /// Whether digest supplies the pattern itself, rather than the project or the user
+    pub fn is_default(&self) -> bool {
+        matches!(
+            self,
+            IgnoreSource::Builtin | IgnoreSource::Defaults | IgnoreSource::ProjectType(_)
+        )
+    }
+}
+
+/// One ignore pattern and its origin
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct IgnorePattern {
+    pub pattern: String,
+    pub source: IgnoreSource,
+    /// Line number in the ignore file, for patterns read from one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+impl IgnorePattern {
+    /// Where the pattern came from, as `file:line` for an ignore file
+    pub fn location(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.source, line),
+            None => self.source.to_string(),
+        }
+    }
+}
+
+/// The ignore patterns of a run, layered in the order their sources were added. A path
+/// matching several patterns is reported against the earliest, and a `!pattern` re-includes
+/// what the layers before it ignore.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreSources {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreSources {
+    pub fn new() -> Self {
+        IgnoreSources::default()
+    }
+
+    /// Add `patterns` from `source`, skipping any that source already gave
+    pub fn add<S: Into<String>>(
+        &mut self,
+        source: IgnoreSource,
+        patterns: impl IntoIterator<Item = S>,
+    ) {
+        for pattern in patterns {
+            self.push(pattern.into(), source.clone(), None);
+        }
+    }
+
+    /// The set with `patterns` from `source` added
+    pub fn with<S: Into<String>>(
+        mut self,
+        source: IgnoreSource,
+        patterns: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.add(source, patterns);
+        self
+    }
+
+    /// Add the patterns of the ignore file `name` in `project_path`, with their line numbers.
+    /// Returns false when there is no such file; failing to read one is an error.
+    pub fn load_file(&mut self, project_path: &Path, name: &str) -> Result<bool> {
+        self.load_path(
+            IgnoreSource::File(name.to_string()),
+            &project_path.join(name),
+        )
+    }
+
+    /// Add the repository's `.git/info/exclude`, found with git; false outside a repository or
+    /// when the file doesn't exist
+    #[cfg(feature = "native")]
+    pub fn load_git_exclude(&mut self, project_path: &Path) -> Result<bool> {
+        match git::info_exclude(project_path) {
+            Some(path) => self.load_path(IgnoreSource::GitExclude, &path),
+            None => Ok(false),
+        }
+    }
+
+    /// Add the user's global gitignore: `core.excludesFile`, or git's default
+    /// `$XDG_CONFIG_HOME/git/ignore`
+    #[cfg(feature = "native")]
+    pub fn load_global_gitignore(&mut self, project_path: &Path) -> Result<bool> {
+        match git::global_excludes_file(project_path) {
+            Some(path) => {
+                let source = IgnoreSource::GlobalGitignore(path.display().to_string());
+                self.load_path(source, &path)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Add the patterns of the ignore file at `path` from `source`, with their line numbers
+    fn load_path(&mut self, source: IgnoreSource, path: &Path) -> Result<bool> {
+        if !path.exists() {
+            return Ok(false);
+        }
+        let content = reader::read_utf8(path)?;
+        for (index, line) in content.lines().enumerate() {
+            if let Some(pattern) = parse_ignore_line(line) {
+                self.push(pattern, source.clone(), Some(index + 1));
+            }
+        }
+        Ok(true)
+    }
+
+    fn push(&mut self, pattern: String, source: IgnoreSource, line: Option<usize>) {
+        let known = self
+            .patterns
+            .iter()
+            .any(|existing| existing.pattern == pattern && existing.source == source);
+        if !known {
+            self.patterns.push(IgnorePattern {
+                pattern,
+                source,
+                line,
+            });
+        }
+    }
+
+    /// Every pattern with its origin, in layer order; a pattern given by several sources
+    /// appears once for each
+    pub fn iter(&self) -> impl Iterator<Item = &IgnorePattern> {
+        self.patterns.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether any pattern came from `source`
+    pub fn has_source(&self, source: &IgnoreSource) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| &pattern.source == source)
+    }
+
+    /// The distinct patterns, for APIs that take a plain set
+    pub fn to_set(&self) -> HashSet<String> {
+        self.patterns.iter().map(|p| p.pattern.clone()).collect()
+    }
+
+    /// The patterns compiled for matching many paths, each distinct pattern once
+    pub fn matcher(&self) -> CompiledIgnore<'_> {
+        self.matcher_without(|_| false)
+    }
+
+    /// The patterns compiled for matching, leaving out the sources `skip` picks, such as the
+    /// ignore files a directory walker applies itself
+    pub fn matcher_without(&self, skip: impl Fn(&IgnoreSource) -> bool) -> CompiledIgnore<'_> {
+        let mut seen = HashSet::new();
+        CompiledIgnore::new(
+            self.patterns
+                .iter()
+                .filter(|p| !skip(&p.source))
+                .map(|p| p.pattern.as_str())
+                .filter(|pattern| seen.insert(*pattern)),
+        )
+    }
+
+    /// Where `pattern` came from: the earliest source that gave it
+    pub fn origin(&self, pattern: &str) -> Option<&IgnorePattern> {
+        self.patterns.iter().find(|p| p.pattern == pattern)
+    }
+
+    /// The pattern that excludes `path`, with its origin, if any
+    pub fn explain(&self, path: &Path) -> Option<&IgnorePattern> {
+        let pattern = self.matcher().matching(path)?;
+        self.origin(pattern)
+    }
+
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.matcher().is_ignored(path)
+    }
+}
+
+/// One line per pattern, in layer order, with its location in an aligned column
+pub fn format_patterns<'a>(patterns: impl IntoIterator<Item = &'a IgnorePattern>) -> String {
+    let patterns: Vec<&IgnorePattern> = patterns.into_iter().collect();
+    let width = patterns
+        .iter()
+        .map(|p| p.pattern.chars().count())
+        .max()
+        .unwrap_or(0);
+    patterns
+        .iter()
+        .map(|p| format!("{:<width$}  {}\n", p.pattern, p.location(), width = width))
+        .collect()
+}
+
+/// Patterns given directly, as `--ignore-pattern` values are
+impl<S: Into<String>> FromIterator<S> for IgnoreSources {
+    fn from_iter<I: IntoIterator<Item = S>>(patterns: I) -> Self {
+        IgnoreSources::new().with(IgnoreSource::CommandLine, patterns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_layers_keep_their_origin() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join(".gitignore"),
+            "# build output\ntarget/\n\n*.log\n",
+        )
+        .unwrap();
+
+        let mut ignores = IgnoreSources::new().with(IgnoreSource::Builtin, [".git"]);
+        assert!(ignores.load_file(root, ".gitignore").unwrap());
+        assert!(!ignores.load_file(root, ".digestignore").unwrap());
+        ignores.add(IgnoreSource::CommandLine, ["*.log", "fixtures/"]);
+
+        let target = ignores.explain(Path::new("target/debug/app.rs")).unwrap();
+        assert_eq!(target.source, IgnoreSource::File(".gitignore".to_string()));
+        assert_eq!(target.line, Some(2));
+        // Given twice, reported against the earlier layer
+        let log = ignores.explain(Path::new("logs/run.log")).unwrap();
+        assert_eq!(log.line, Some(4));
+        assert_eq!(
+            ignores.explain(Path::new("fixtures/a.rs")).unwrap().source,
+            IgnoreSource::CommandLine
+        );
+        assert_eq!(ignores.explain(Path::new("src/main.rs")), None);
+        assert_eq!(ignores.iter().count(), 5);
+        assert_eq!(ignores.to_set().len(), 4);
+        assert_eq!(
+            format_patterns(ignores.iter()),
+            ".git       built-in\n\
+             target/    .gitignore:2\n\
+             *.log      .gitignore:4\n\
+             *.log      --ignore-pattern\n\
+             fixtures/  --ignore-pattern\n"
+        );
+        assert_eq!(ignores.iter().filter(|p| p.source.is_default()).count(), 1);
+    }
+
+    #[test]
+    fn test_test_data_patterns() {
+        let ignores =
+            IgnoreSources::new().with(IgnoreSource::Defaults, TEST_DATA_PATTERNS.iter().copied());
+        for path in [
+            "src/__snapshots__/app.test.js.snap",
+            "tests/snapshots/parse.snap",
+            "tests/fixtures/input.json",
+            "pkg/parser/testdata/case1.txt",
+            "render/output.golden",
+            "spec/cassettes/login.yml",
+            "test/vcr_cassettes/api.yaml",
+        ] {
+            assert!(ignores.is_ignored(Path::new(path)), "{}", path);
+        }
+        for path in ["src/fixtures.rs", "src/snapshot.rs", "docs/testing.md"] {
+            assert!(!ignores.is_ignored(Path::new(path)), "{}", path);
+        }
+    }
+}
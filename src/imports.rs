@@ -0,0 +1,513 @@
+// The import graph between collected files, for --expand-context: which files a file imports,
+// resolved against the other files of the digest
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::sync::OnceLock;
+
+use crate::FileInfo;
+
+/// Extensions tried for a relative JavaScript or TypeScript import without one
+const SCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+/// `import a.b`, and `from .a import b, c`
+fn python_import() -> &'static Regex {
+    static PYTHON_IMPORT: OnceLock<Regex> = OnceLock::new();
+    PYTHON_IMPORT.get_or_init(|| {
+        Regex::new(
+            r"(?m)^\s*(?:from\s+(\.*[\w.]*)\s+import\s+(\([^)]*\)|[\w \t,*]+)|import\s+([\w.]+))",
+        )
+        .expect("valid regex")
+    })
+}
+
+/// Relative `import ... from './x'`, `import('./x')`, `require('../x')`, and `export ... from`
+fn script_import() -> &'static Regex {
+    static SCRIPT_IMPORT: OnceLock<Regex> = OnceLock::new();
+    SCRIPT_IMPORT.get_or_init(|| {
+        Regex::new(r#"(?:\bfrom\s+|\bimport\s*\(?\s*|\brequire\s*\(\s*)['"](\.{1,2}/[^'"]*)['"]"#)
+            .expect("valid regex")
+    })
+}
+
+/// `mod name;`, a module in its own file
+fn rust_mod() -> &'static Regex {
+    static RUST_MOD: OnceLock<Regex> = OnceLock::new();
+    RUST_MOD.get_or_init(|| {
+        Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+)\s*;").expect("valid regex")
+    })
+}
+
+/// The root and leading path of a `use` declaration
+fn rust_use() -> &'static Regex {
+    static RUST_USE: OnceLock<Regex> = OnceLock::new();
+    RUST_USE.get_or_init(|| {
+        Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?use\s+(\w+)((?:::\w+)*)")
+            .expect("valid regex")
+    })
+}
+
+/// An import path, alone in an `import (...)` group or after `import`
+fn go_import() -> &'static Regex {
+    static GO_IMPORT: OnceLock<Regex> = OnceLock::new();
+    GO_IMPORT.get_or_init(|| {
+        Regex::new(r#"(?m)^\s*(?:import\s+)?(?:\w+\s+)?"([\w./-]+)"\s*$"#).expect("valid regex")
+    })
+}
+
+/// `#include "header.h"`; angle-bracket includes are system headers
+fn c_include() -> &'static Regex {
+    static C_INCLUDE: OnceLock<Regex> = OnceLock::new();
+    C_INCLUDE.get_or_init(|| Regex::new(r#"(?m)^\s*#\s*include\s+"([^"]+)""#).expect("valid regex"))
+}
+
+/// Which collected files import which, by path with forward slashes
+#[derive(Debug, Default)]
+pub struct ImportGraph {
+    /// Each file's imports among the collected files
+    imports: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ImportGraph {
+    /// Resolve the imports of every file in `files` against the others. Relative imports are
+    /// resolved for Python, JavaScript, TypeScript, Rust (`mod`, and `use` of `crate`,
+    /// `super`, `self`, or the crate's own name), Go packages, and C and C++ includes.
+    pub fn build(files: &[FileInfo]) -> ImportGraph {
+        let paths: Vec<String> = files.iter().map(|f| f.path.replace('\\', "/")).collect();
+        let known: BTreeSet<&str> = paths.iter().map(String::as_str).collect();
+        let crates = crate_roots(files);
+
+        let mut imports = BTreeMap::new();
+        for (file, path) in files.iter().zip(&paths) {
+            let resolver = Resolver {
+                known: &known,
+                crates: &crates,
+            };
+            let found: BTreeSet<String> = resolver
+                .imports(path, &file.content)
+                .into_iter()
+                .filter(|target| target != path)
+                .collect();
+            imports.insert(path.clone(), found);
+        }
+        ImportGraph { imports }
+    }
+
+    /// The files `path` imports
+    pub fn imports_of(&self, path: &str) -> impl Iterator<Item = &str> {
+        self.imports
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// Files within `hops` imports of the `seeds`, in either direction, with their distance;
+    /// nearest first, then by path. The seeds themselves aren't listed.
+    pub fn neighborhood(&self, seeds: &[&str], hops: usize) -> Vec<(String, usize)> {
+        let mut neighbors: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+        for (from, targets) in &self.imports {
+            for to in targets {
+                neighbors.entry(from).or_default().insert(to);
+                neighbors.entry(to).or_default().insert(from);
+            }
+        }
+
+        let mut distance: HashMap<&str, usize> = seeds.iter().map(|&seed| (seed, 0)).collect();
+        let mut queue: VecDeque<&str> = seeds.iter().copied().collect();
+        while let Some(path) = queue.pop_front() {
+            let next = distance[path] + 1;
+            if next > hops {
+                continue;
+            }
+            for &neighbor in neighbors.get(path).into_iter().flatten() {
+                if !distance.contains_key(neighbor) {
+                    distance.insert(neighbor, next);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        let mut found: Vec<(String, usize)> = distance
+            .into_iter()
+            .filter(|&(_, hops)| hops > 0)
+            .map(|(path, hops)| (path.to_string(), hops))
+            .collect();
+        found.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        found
+    }
+}
+
+/// Rust crate names, as `use` spells them, and the `src` directory of each, from the
+/// collected Cargo.toml files
+fn crate_roots(files: &[FileInfo]) -> HashMap<String, String> {
+    let mut roots = HashMap::new();
+    for file in files {
+        let path = file.path.replace('\\', "/");
+        let Some(dir) = path.strip_suffix("Cargo.toml") else {
+            continue;
+        };
+        let Ok(manifest) = file.content.parse::<toml::Value>() else {
+            continue;
+        };
+        let name = manifest
+            .get("lib")
+            .and_then(|lib| lib.get("name"))
+            .or_else(|| manifest.get("package").and_then(|p| p.get("name")))
+            .and_then(|name| name.as_str());
+        if let Some(name) = name {
+            roots.insert(name.replace('-', "_"), format!("{}src", dir));
+        }
+    }
+    roots
+}
+
+struct Resolver<'a> {
+    known: &'a BTreeSet<&'a str>,
+    crates: &'a HashMap<String, String>,
+}
+
+impl Resolver<'_> {
+    fn imports(&self, path: &str, content: &str) -> Vec<String> {
+        let (dir, _) = path.rsplit_once('/').unwrap_or(("", path));
+        let extension = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+        match extension {
+            "py" => self.python(dir, content),
+            "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "vue" | "svelte" => {
+                self.scripts(dir, content)
+            }
+            "rs" => self.rust(path, content),
+            "go" => self.go(content),
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" | "m" | "mm" => {
+                self.includes(dir, content)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The first of `candidates` that is a collected file
+    fn first(&self, candidates: impl IntoIterator<Item = String>) -> Option<String> {
+        candidates
+            .into_iter()
+            .map(|candidate| normalize(&candidate))
+            .find(|candidate| self.known.contains(candidate.as_str()))
+    }
+
+    /// Collected files whose path is `suffix` or ends with `/suffix`
+    fn ending_with(&self, suffix: &str) -> Vec<String> {
+        let nested = format!("/{}", suffix);
+        self.known
+            .iter()
+            .filter(|path| **path == suffix || path.ends_with(&nested))
+            .map(|path| path.to_string())
+            .collect()
+    }
+
+    fn python(&self, dir: &str, content: &str) -> Vec<String> {
+        let mut found = Vec::new();
+        for captures in python_import().captures_iter(content) {
+            if let Some(module) = captures.get(3) {
+                found.extend(self.python_module(dir, module.as_str()));
+                continue;
+            }
+            let module = &captures[1];
+            // `from pkg import mod` may name submodules as well as attributes
+            let names = captures[2].split(',').map(|name| {
+                let name = name.trim_matches(|c: char| c.is_whitespace() || c == '(' || c == ')');
+                name.split_whitespace().next().unwrap_or("")
+            });
+            for name in names.filter(|name| !name.is_empty() && *name != "*") {
+                let joined = if module.ends_with('.') {
+                    format!("{}{}", module, name)
+                } else {
+                    format!("{}.{}", module, name)
+                };
+                found.extend(self.python_module(dir, &joined));
+            }
+            found.extend(self.python_module(dir, module));
+        }
+        found
+    }
+
+    fn python_module(&self, dir: &str, module: &str) -> Vec<String> {
+        let dots = module.len() - module.trim_start_matches('.').len();
+        let module_path = module.trim_start_matches('.').replace('.', "/");
+        if dots == 0 {
+            // Absolute: matched at any depth, for src/ layouts
+            if module_path.is_empty() {
+                return Vec::new();
+            }
+            let mut found = self.ending_with(&format!("{}.py", module_path));
+            found.extend(self.ending_with(&format!("{}/__init__.py", module_path)));
+            return found;
+        }
+        let mut base = dir.to_string();
+        for _ in 1..dots {
+            base = base
+                .rsplit_once('/')
+                .map_or(String::new(), |(parent, _)| parent.to_string());
+        }
+        let stem = join(&base, &module_path);
+        self.first([format!("{}.py", stem), format!("{}/__init__.py", stem)])
+            .into_iter()
+            .collect()
+    }
+
+    fn scripts(&self, dir: &str, content: &str) -> Vec<String> {
+        script_import()
+            .captures_iter(content)
+            .filter_map(|captures| {
+                let target = join(dir, &captures[1]);
+                let mut candidates = vec![target.clone()];
+                for extension in SCRIPT_EXTENSIONS {
+                    candidates.push(format!("{}.{}", target, extension));
+                }
+                for extension in SCRIPT_EXTENSIONS {
+                    candidates.push(format!("{}/index.{}", target, extension));
+                }
+                self.first(candidates)
+            })
+            .collect()
+    }
+
+    fn rust(&self, path: &str, content: &str) -> Vec<String> {
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        // Submodules of main.rs, lib.rs, and mod.rs live beside them; of foo.rs, in foo/
+        let module_dir = match name {
+            "main.rs" | "lib.rs" | "mod.rs" => dir.to_string(),
+            _ => join(dir, name.trim_end_matches(".rs")),
+        };
+
+        let mut found = Vec::new();
+        for captures in rust_mod().captures_iter(content) {
+            let module = &captures[1];
+            found.extend(self.first([
+                join(&module_dir, &format!("{}.rs", module)),
+                join(&module_dir, &format!("{}/mod.rs", module)),
+            ]));
+        }
+
+        let src_root = path
+            .match_indices("src/")
+            .filter(|(i, _)| *i == 0 || path.as_bytes()[i - 1] == b'/')
+            .last()
+            .map(|(i, _)| path[..i + 3].to_string());
+        for captures in rust_use().captures_iter(content) {
+            let root = match &captures[1] {
+                "crate" => src_root.clone(),
+                "self" => Some(module_dir.clone()),
+                "super" => Some(parent(&module_dir)),
+                name => self.crates.get(name).cloned(),
+            };
+            let Some(root) = root else {
+                continue;
+            };
+            // The longest prefix of the path that is a module file
+            let segments: Vec<&str> = captures[2].split("::").filter(|s| !s.is_empty()).collect();
+            let target = (1..=segments.len()).rev().find_map(|n| {
+                let module = join(&root, &segments[..n].join("/"));
+                self.first([format!("{}.rs", module), format!("{}/mod.rs", module)])
+            });
+            let target = target.or_else(|| {
+                // The root module itself, for `use crate::Item` or `use super::Item`
+                let root = normalize(&root);
+                self.first([
+                    join(&root, "lib.rs"),
+                    join(&root, "main.rs"),
+                    join(&root, "mod.rs"),
+                    format!("{}.rs", root),
+                ])
+                .filter(|_| captures[1] != *"self")
+            });
+            found.extend(target);
+        }
+        found
+    }
+
+    fn go(&self, content: &str) -> Vec<String> {
+        let mut found = Vec::new();
+        for captures in go_import().captures_iter(content) {
+            let package = &captures[1];
+            // A package is its directory; match the directory by the import path's suffix
+            for path in self.known.iter().filter(|path| path.ends_with(".go")) {
+                let Some((dir, _)) = path.rsplit_once('/') else {
+                    continue;
+                };
+                if package == dir || package.ends_with(&format!("/{}", dir)) {
+                    found.push(path.to_string());
+                }
+            }
+        }
+        found
+    }
+
+    fn includes(&self, dir: &str, content: &str) -> Vec<String> {
+        c_include()
+            .captures_iter(content)
+            .flat_map(|captures| {
+                let header = &captures[1];
+                match self.first([join(dir, header)]) {
+                    Some(local) => vec![local],
+                    None => self.ending_with(header.trim_start_matches("./")),
+                }
+            })
+            .collect()
+    }
+}
+
+fn join(dir: &str, path: &str) -> String {
+    if dir.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", dir, path)
+    }
+}
+
+/// The directory holding `path`, or the empty root
+fn parent(path: &str) -> String {
+    path.rsplit_once('/')
+        .map_or(String::new(), |(parent, _)| parent.to_string())
+}
+
+/// `a/./b/../c` as `a/c`; a `..` above the root is dropped
+fn normalize(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(files: &[(&str, &str)]) -> ImportGraph {
+        let files: Vec<FileInfo> = files
+            .iter()
+            .map(|(path, content)| FileInfo::new(path.to_string(), None, content.to_string()))
+            .collect();
+        ImportGraph::build(&files)
+    }
+
+    fn imports<'a>(graph: &'a ImportGraph, path: &str) -> Vec<&'a str> {
+        graph.imports_of(path).collect()
+    }
+
+    #[test]
+    fn test_python_imports() {
+        let graph = graph(&[
+            (
+                "app/main.py",
+                "from app.models import User\nimport app.db\nfrom . import views\n",
+            ),
+            ("app/models.py", "from .db import session\n"),
+            ("app/db.py", "import os\n"),
+            ("app/views.py", "from ..shared.util import x\n"),
+            ("shared/util.py", ""),
+        ]);
+        assert_eq!(
+            imports(&graph, "app/main.py"),
+            vec!["app/db.py", "app/models.py", "app/views.py"]
+        );
+        assert_eq!(imports(&graph, "app/models.py"), vec!["app/db.py"]);
+        assert_eq!(imports(&graph, "app/views.py"), vec!["shared/util.py"]);
+    }
+
+    #[test]
+    fn test_script_imports() {
+        let graph = graph(&[
+            ("web/src/app.ts", "import { api } from './api';\nimport React from 'react';\nconst c = require('../lib/c');\n"),
+            ("web/src/api/index.ts", "export * from \"./client.js\";\n"),
+            ("web/src/api/client.js", ""),
+            ("web/lib/c.js", ""),
+        ]);
+        assert_eq!(
+            imports(&graph, "web/src/app.ts"),
+            vec!["web/lib/c.js", "web/src/api/index.ts"]
+        );
+        assert_eq!(
+            imports(&graph, "web/src/api/index.ts"),
+            vec!["web/src/api/client.js"]
+        );
+    }
+
+    #[test]
+    fn test_rust_imports() {
+        let graph = graph(&[
+            ("Cargo.toml", "[package]\nname = \"my-tool\"\n"),
+            ("src/lib.rs", "pub mod engine;\nmod util;\n"),
+            ("src/main.rs", "use my_tool::engine::Engine;\n"),
+            (
+                "src/engine.rs",
+                "mod physics;\nuse crate::util::clamp;\nuse std::fmt;\n",
+            ),
+            ("src/engine/physics.rs", "use super::Engine;\n"),
+            ("src/util/mod.rs", ""),
+        ]);
+        assert_eq!(
+            imports(&graph, "src/lib.rs"),
+            vec!["src/engine.rs", "src/util/mod.rs"]
+        );
+        assert_eq!(imports(&graph, "src/main.rs"), vec!["src/engine.rs"]);
+        assert_eq!(
+            imports(&graph, "src/engine.rs"),
+            vec!["src/engine/physics.rs", "src/util/mod.rs"]
+        );
+        assert_eq!(
+            imports(&graph, "src/engine/physics.rs"),
+            vec!["src/engine.rs"]
+        );
+    }
+
+    #[test]
+    fn test_go_and_c_imports() {
+        let graph = graph(&[
+            (
+                "cmd/server/main.go",
+                "import (\n\t\"fmt\"\n\t\"example.com/app/internal/store\"\n)\n",
+            ),
+            ("internal/store/store.go", ""),
+            ("src/main.c", "#include \"parser.h\"\n#include <stdio.h>\n"),
+            ("src/parser.h", ""),
+        ]);
+        assert_eq!(
+            imports(&graph, "cmd/server/main.go"),
+            vec!["internal/store/store.go"]
+        );
+        assert_eq!(imports(&graph, "src/main.c"), vec!["src/parser.h"]);
+    }
+
+    #[test]
+    fn test_neighborhood() {
+        let graph = graph(&[
+            ("a.py", "import b\n"),
+            ("b.py", "import c\n"),
+            ("c.py", "import d\n"),
+            ("d.py", ""),
+            ("e.py", "import b\n"),
+            ("lonely.py", ""),
+        ]);
+        assert_eq!(
+            graph.neighborhood(&["b.py"], 1),
+            vec![
+                ("a.py".to_string(), 1),
+                ("c.py".to_string(), 1),
+                ("e.py".to_string(), 1)
+            ]
+        );
+        let two: Vec<String> = graph
+            .neighborhood(&["a.py"], 2)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+        assert_eq!(two, vec!["b.py", "c.py", "e.py"]);
+        assert!(graph.neighborhood(&["lonely.py"], 3).is_empty());
+        assert!(graph.neighborhood(&["a.py"], 0).is_empty());
+    }
+}
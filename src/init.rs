@@ -0,0 +1,175 @@
+// `digest init`: a starter .digestignore and digest.toml for the detected ecosystems and
+// project types
+use std::path::Path;
+
+use crate::projects::{detect_project_types, ProjectType, GODOT};
+
+/// An ecosystem recognized by its manifest, and the build output and lockfiles it leaves behind
+struct Ecosystem {
+    name: &'static str,
+    manifests: &'static [&'static str],
+    ignore: &'static [&'static str],
+}
+
+const ECOSYSTEMS: &[Ecosystem] = &[
+    Ecosystem {
+        name: "Rust",
+        manifests: &["Cargo.toml"],
+        ignore: &["target/", "Cargo.lock"],
+    },
+    Ecosystem {
+        name: "Node",
+        manifests: &["package.json"],
+        ignore: &[
+            "node_modules/",
+            "dist/",
+            "build/",
+            "coverage/",
+            "package-lock.json",
+            "yarn.lock",
+            "pnpm-lock.yaml",
+            "*.min.js",
+        ],
+    },
+    Ecosystem {
+        name: "Python",
+        manifests: &["pyproject.toml", "setup.py", "requirements.txt"],
+        ignore: &[
+            "__pycache__/",
+            ".venv/",
+            "venv/",
+            "*.egg-info/",
+            ".pytest_cache/",
+            ".mypy_cache/",
+            "poetry.lock",
+        ],
+    },
+    Ecosystem {
+        name: "Go",
+        manifests: &["go.mod"],
+        ignore: &["vendor/", "go.sum", "*.pb.go"],
+    },
+    Ecosystem {
+        name: "Java",
+        manifests: &["pom.xml", "build.gradle", "build.gradle.kts"],
+        ignore: &["target/", "build/", ".gradle/", "*.class"],
+    },
+];
+
+/// The starter files `digest init` writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarterFiles {
+    /// What the files were tailored to, e.g. `["Rust", "Godot"]`
+    pub detected: Vec<&'static str>,
+    pub digestignore: String,
+    pub config: String,
+}
+
+/// Build a .digestignore and digest.toml for the project at `project_path`
+pub fn starter_files(project_path: &Path) -> StarterFiles {
+    let ecosystems: Vec<&Ecosystem> = ECOSYSTEMS
+        .iter()
+        .filter(|ecosystem| {
+            ecosystem
+                .manifests
+                .iter()
+                .any(|manifest| project_path.join(manifest).is_file())
+        })
+        .collect();
+    let project_types = detect_project_types(project_path);
+
+    let mut detected: Vec<&'static str> = ecosystems.iter().map(|e| e.name).collect();
+    detected.extend(project_types.iter().map(|project_type| project_type.name));
+
+    StarterFiles {
+        digestignore: digestignore(&ecosystems, &project_types),
+        config: config(&project_types),
+        detected,
+    }
+}
+
+fn digestignore(ecosystems: &[&Ecosystem], project_types: &[ProjectType]) -> String {
+    let mut content = String::from(
+        "# Files digest leaves out, in .gitignore syntax. When this file or a .gitignore\n\
+         # exists, digest uses them instead of its built-in defaults.\n\
+         .git/\n\
+         *.log\n\
+         .DS_Store\n",
+    );
+    let sections = ecosystems
+        .iter()
+        .map(|ecosystem| (ecosystem.name, ecosystem.ignore))
+        .chain(
+            project_types
+                .iter()
+                .map(|project_type| (project_type.name, project_type.ignore_patterns)),
+        );
+    for (name, patterns) in sections {
+        if patterns.is_empty() {
+            continue;
+        }
+        content.push_str(&format!("\n# {}\n", name));
+        for pattern in patterns {
+            content.push_str(pattern);
+            content.push('\n');
+        }
+    }
+    content
+}
+
+fn config(project_types: &[ProjectType]) -> String {
+    let mut content = String::from(
+        "# Default options for digest in this project. Keys are the long command-line flags;\n\
+         # flags given on the command line take precedence.\n\
+         max-files = 50\n\
+         max-file-size = 500\n\
+         # format = \"json\"\n\
+         # prioritize = \"churn\"\n\
+         # ignore-pattern = [\"*.snap\"]\n",
+    );
+    if project_types
+        .iter()
+        .any(|project_type| project_type.name == GODOT)
+    {
+        content.push_str("\n# Godot scenes as node trees instead of serialized resources\n");
+        content.push_str("scene-summary = true\n");
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_args;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_starter_files_for_rust_and_node() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+
+        let files = starter_files(temp_dir.path());
+        assert_eq!(files.detected, vec!["Rust", "Node"]);
+        assert!(files
+            .digestignore
+            .contains("\n# Rust\ntarget/\nCargo.lock\n"));
+        assert!(files.digestignore.contains("node_modules/\ndist/\n"));
+        assert!(!files.digestignore.contains("__pycache__/"));
+        assert_eq!(
+            config_args(&files.config).unwrap(),
+            vec!["--max-file-size=500", "--max-files=50"]
+        );
+    }
+
+    #[test]
+    fn test_starter_files_for_godot() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("project.godot"), "config_version=5\n").unwrap();
+
+        let files = starter_files(temp_dir.path());
+        assert_eq!(files.detected, vec!["Godot"]);
+        assert!(files.config.contains("scene-summary = true"));
+    }
+}
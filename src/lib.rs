@@ -1,483 +1,1426 @@
 // Re-export the main module functions for testing
-use anyhow::{Context, Result};
+use log::{debug, warn};
 use serde::Serialize;
-use std::collections::HashSet;
-use std::fs;
-use std::path::Path;
-
-#[derive(Serialize, Debug)]
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use assets::EmbeddedImage;
+use complexity::Complexity;
+use dependencies::DependencySummary;
+use duplicates::DuplicateBlock;
+use entry_points::EntryPoint;
+use error::{DigestError, Result};
+use git::{FileGitInfo, GitInfo};
+use godot::GodotProject;
+use ignores::{IgnoreSource, IgnoreSources};
+use lockfiles::{LockfileMode, LockfileSummary};
+use matcher::CompiledIgnore;
+use pii::PiiFinding;
+#[cfg(feature = "native")]
+use projects::project_type;
+use projects::{has_type, ProjectType, GODOT};
+use reader::TextFile;
+use stats::{LanguageStats, LineStats};
+use submodules::{submodule_of, Submodule, SubmoduleMode};
+use summary::DigestSummary;
+#[cfg(feature = "native")]
+use vfs::OsFs;
+use vfs::Vfs;
+
+pub mod api;
+#[cfg(feature = "native")]
+pub mod archive;
+pub mod assets;
+pub mod budget;
+pub mod complexity;
+pub mod config;
+pub mod contributors;
+pub mod dependencies;
+pub mod diff;
+pub mod docs;
+#[cfg(feature = "native")]
+pub mod doctor;
+pub mod duplicates;
+#[cfg(feature = "native")]
+pub mod encrypt;
+pub mod entry_points;
+pub mod environment;
+pub mod error;
+pub mod exit;
+pub mod extract;
+pub mod format;
+pub mod frameworks;
+pub mod git;
+pub mod github;
+pub mod glob;
+pub mod godot;
+pub mod grep;
+pub mod hash;
+pub mod hook;
+pub mod ignores;
+pub mod imports;
+#[cfg(feature = "native")]
+pub mod init;
+pub mod lockfiles;
+#[cfg(feature = "native")]
+pub mod logging;
+pub mod manifest;
+pub mod matcher;
+pub mod minified;
+pub mod models;
+pub mod notebook;
+#[cfg(feature = "native")]
+pub mod open;
+pub mod paths;
+pub mod pii;
+pub mod policy;
+pub mod presets;
+pub mod priority;
+#[cfg(feature = "native")]
+pub mod progress;
+pub mod projects;
+pub mod reader;
+pub mod schema;
+pub mod split;
+pub mod stats;
+pub mod submodules;
+pub mod suggestions;
+pub mod summary;
+pub mod symbol;
+pub mod template;
+pub mod terraform;
+pub mod test_paths;
+pub mod tokens;
+pub mod tree;
+pub mod update;
+pub mod vfs;
+
+#[derive(Serialize, Debug, Clone)]
 pub struct FileInfo {
     pub path: String,
     pub language: Option<String>,
+    /// Size of the content in bytes
+    pub size: u64,
+    /// Last modification time in seconds since the Unix epoch, when the filesystem reports one
+    pub modified: Option<u64>,
+    /// Lowercase hex SHA-256 of the content
+    pub sha256: String,
+    /// Number of lines in the content
+    pub lines: usize,
     pub content: String,
+    /// Code, comment, and blank line counts, when tokei recognizes the language
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<LineStats>,
+    /// Last commit touching the file, filled in by `annotate_git_info`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<FileGitInfo>,
+    /// Web URL of the file at the digested commit, filled in by `annotate_file_links`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Path of the git submodule the file belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submodule: Option<String>,
+    /// Estimated cyclomatic complexity and nesting, filled in by `annotate_complexity`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complexity: Option<Complexity>,
 }
 
-pub fn should_ignore(path: &Path, ignore_patterns: &HashSet<String>) -> bool {
-    // Get the path as a string
-    let path_str = path.to_string_lossy();
-
-    // Normalize path for matching (replace backslashes with forward slashes on Windows)
-    let path_str = path_str.replace('\\', "/");
-
-    // Check if the path matches any of the ignore patterns
-    for pattern in ignore_patterns {
-        // Special case for the "tests/" directory pattern
-        if pattern == "tests/" {
-            // Get the relative path components
-            let path_components: Vec<&str> = path_str.split('/').collect();
-
-            // Find the index of "tests" in the path
-            let contains_tests = path_components.iter().position(|&c| c == "tests");
-
-            if let Some(index) = contains_tests {
-                // Only match if "tests" is a root-level directory or if it's properly separated by slashes
-                // This should match "tests/file.rs" but not "src/tests/file.rs"
-
-                // Check the path components before "tests"
-                let prefix = &path_components[..index];
-
-                // If "tests" is the first component after the temporary directory path
-                // or if it's directly inside another directory (e.g., "/some/path/tests/")
-                // then we should match it
-                if prefix.is_empty() || prefix.iter().all(|&c| c != "src") {
-                    return true;
-                }
-            }
-
-            // Skip further processing for this pattern
-            continue;
+impl FileInfo {
+    /// Build a file entry from its content, computing size, hash, and line counts
+    pub fn new(path: String, language: Option<String>, content: String) -> Self {
+        let stats = stats::line_stats(Path::new(&path), &content);
+        FileInfo {
+            size: content.len() as u64,
+            modified: None,
+            sha256: hash::sha256_hex(content.as_bytes()),
+            lines: content.lines().count(),
+            stats,
+            git: None,
+            url: None,
+            submodule: None,
+            complexity: None,
+            path,
+            language,
+            content,
         }
+    }
 
-        // Special case for **/test/** pattern since it's common and important
-        if pattern == "**/test/**" {
-            if path_str.contains("/test/") || path_str.starts_with("test/") {
-                return true;
-            }
-        }
+    /// The same file with `content` in place of its own, e.g. after redaction: size, hash,
+    /// and line counts follow the new content, and every other field is kept
+    pub fn with_content(mut self, content: String) -> Self {
+        self.set_content(content);
+        self
+    }
 
-        // Special case for **/test*/** pattern (common in tests)
-        if pattern == "**/test*/**" {
-            // This should match paths containing test, tests, testing, etc. as directories
-            let path_segments: Vec<&str> = path_str.split('/').collect();
-            for (i, segment) in path_segments.iter().enumerate() {
-                // Only match if it's a directory (not a file) and starts with "test"
-                if i < path_segments.len() - 1 && !segment.is_empty() && segment.starts_with("test")
-                {
-                    return true;
-                }
-            }
-        }
+    /// Replace the content in place, as `with_content` does
+    pub fn set_content(&mut self, content: String) {
+        self.size = content.len() as u64;
+        self.sha256 = hash::sha256_hex(content.as_bytes());
+        self.lines = content.lines().count();
+        self.stats = stats::line_stats(Path::new(&self.path), &content);
+        self.content = content;
+    }
+}
 
-        // Special case for **/*.md pattern (common for documentation)
-        if pattern == "**/*.md" {
-            if path_str.ends_with(".md") {
-                return true;
-            }
-        }
+/// Position of a digest within a split output
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct DigestPart {
+    pub index: usize,
+    pub total: usize,
+}
 
-        // Special case for **/*.js pattern
-        if pattern == "**/*.js" {
-            if path_str.ends_with(".js") {
-                return true;
-            }
-        }
+/// Everything digest knows about a project, as rendered by the output formats
+#[derive(Serialize, Debug)]
+pub struct Digest {
+    /// Layout version of the JSON output, see `schema::SCHEMA_VERSION`
+    pub schema_version: u32,
+    /// Name of the tool that wrote the digest
+    pub generated_by: String,
+    /// Version of that tool
+    pub digest_version: String,
+    /// When the digest was generated, RFC 3339 in UTC; absent with `--no-timestamp`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
+    pub project_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part: Option<DigestPart>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    pub git: Option<GitInfo>,
+    /// The git submodules declared in `.gitmodules`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub submodules: Vec<Submodule>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entry_points: Vec<EntryPoint>,
+    /// Main scene, autoloads, and their scripts, for Godot projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub godot: Option<GodotProject>,
+    pub main_language: Option<String>,
+    pub language_breakdown: Vec<LanguageStats>,
+    pub dependencies: Vec<DependencySummary>,
+    /// Paths the walk skipped: unfollowed symlinks, symlink loops, duplicates
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<ExcludedFile>,
+    /// Images, binaries, and oversized files listed by `--mention-skipped`, without content
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedFile>,
+    /// Small images embedded by `--embed-images`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<EmbeddedImage>,
+    /// Lockfiles listed by `--lockfiles summary` in place of their content
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lockfiles: Vec<LockfileSummary>,
+    /// Blocks of code repeated across the included files, found by `--duplicates`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<DuplicateBlock>,
+    /// Personal data found in the included files by `--scan pii`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pii: Vec<PiiFinding>,
+    pub files: Vec<FileInfo>,
+    /// Totals for the footer: files in and out, lines, tokens, and how the digest was made
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<DigestSummary>,
+}
 
-        // Special case for common directory patterns
-        if pattern == "node_modules/" {
-            if path_str.starts_with("node_modules/") || path_str.contains("/node_modules/") {
-                return true;
-            }
-        }
+/// Attach per-file git metadata (last commit, author, age) to collected files
+#[cfg(feature = "native")]
+pub fn annotate_git_info(project_path: &Path, files: &mut [FileInfo]) {
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let mut info = git::file_git_info(project_path, &paths);
+    for file in files.iter_mut() {
+        file.git = info.remove(&file.path.replace('\\', "/"));
+    }
+}
 
-        if pattern == "build/" {
-            if path_str.starts_with("build/") || path_str.contains("/build/") {
-                return true;
-            }
-        }
+/// Link each file to its page on the repository's web UI at `git.commit`. Returns false,
+/// leaving the files alone, when there is no remote with a recognizable web address.
+#[cfg(feature = "native")]
+pub fn annotate_file_links(project_path: &Path, git: &GitInfo, files: &mut [FileInfo]) -> bool {
+    let Some(remote) = &git.remote_url else {
+        return false;
+    };
+    if git::web_url(remote).is_none() {
+        return false;
+    }
+    // Digest paths are relative to the project, which may be a subdirectory of the repository
+    let prefix = git::repo_prefix(project_path).unwrap_or_default();
+    for file in files.iter_mut() {
+        let path = format!("{}{}", prefix, file.path.replace('\\', "/"));
+        file.url = git::blob_url(remote, &git.commit, &path);
+    }
+    true
+}
 
-        // Always ignore .git directory
-        if path_str.contains("/.git/") || path_str == ".git" {
-            return true;
-        }
+pub fn should_ignore(path: &Path, ignore_patterns: &HashSet<String>) -> bool {
+    matching_ignore_pattern(path, ignore_patterns).is_some()
+}
 
-        // Handle different gitignore pattern types
-        let pattern = pattern.trim();
+/// The ignore pattern that excludes `path`, if any. Compile the patterns once with
+/// `CompiledIgnore` when matching many paths.
+pub fn matching_ignore_pattern<'a>(
+    path: &Path,
+    ignore_patterns: &'a HashSet<String>,
+) -> Option<&'a str> {
+    CompiledIgnore::new(ignore_patterns).matching(path)
+}
 
-        // Empty lines or comments
-        if pattern.is_empty() || pattern.starts_with('#') {
-            continue;
-        }
+/// The pattern on one line of an ignore file, following the gitignore rules: `#` starts a
+/// comment, trailing spaces are dropped unless escaped (`foo\ `), and other escapes such as
+/// `\#` or `\!` are kept for the matcher to read as literals
+pub fn parse_ignore_line(line: &str) -> Option<String> {
+    let line = glob::trim_trailing_spaces(line.trim_end_matches('\r'));
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    Some(line.to_string())
+}
 
-        // Negated patterns (we're not supporting these for simplicity)
-        if pattern.starts_with('!') {
-            continue;
-        }
+/// Lines of an ignore file whose pattern fails `glob::validate`, with their 1-based line numbers
+pub fn invalid_ignore_lines(content: &str) -> Vec<(usize, String, glob::PatternError)> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let pattern = parse_ignore_line(line)?;
+            let err = glob::validate(&pattern).err()?;
+            Some((index + 1, pattern, err))
+        })
+        .collect()
+}
 
-        // Handle **/ pattern at the beginning (match any directory depth)
-        if pattern.starts_with("**/") {
-            let suffix = &pattern[3..];
+/// Patterns from the project's .digestignore, or None when it has none
+pub fn check_for_digestignore(project_path: &Path) -> Result<Option<HashSet<String>>> {
+    let digestignore_path = project_path.join(".digestignore");
 
-            // Special case for file extensions like **/*.js
-            if suffix.starts_with('*') && suffix.contains('.') {
-                let extension = suffix.split('.').last().unwrap_or("");
-                if !extension.is_empty() && path_str.ends_with(&format!(".{}", extension)) {
-                    return true;
-                }
-            }
-            // Check if suffix appears anywhere in the path with proper directory boundaries
-            else if path_str == suffix ||
-               path_str.ends_with(&format!("/{}", suffix)) ||
-               // Special case for directories: if suffix ends with '/', then handle it as a directory
-               (suffix.ends_with('/') && (
-                   path_str.ends_with(&suffix[..suffix.len()-1]) ||
-                   path_str.contains(&format!("{}/", &suffix[..suffix.len()-1]))
-               ))
-            {
-                return true;
-            }
-        }
+    if !digestignore_path.exists() {
+        return Ok(None);
+    }
 
-        // Handle pattern ending with /** (match any subdirectory)
-        if pattern.ends_with("/**") {
-            let prefix = &pattern[0..pattern.len() - 3];
-            // The prefix should be treated as a directory name, so it should have a trailing slash
-            // or be at the beginning of the path
-            // For example, "build/**" should match "build/file.js" but not "builds/file.js" or "src/build.js"
-            if path_str.starts_with(&format!("{}/", prefix))
-                || path_str.contains(&format!("/{}/", prefix))
-            {
-                return true;
-            }
-        }
+    // Use the ignore crate to build a gitignore-like matcher from the .digestignore file
+    let content = reader::read_utf8(&digestignore_path)?;
 
-        // Handle /**/ pattern (matches any directory in the middle)
-        if pattern.contains("/**/") {
-            let segments: Vec<&str> = pattern.split("/**/").collect();
+    // Add .git to always ignore
+    let mut patterns = HashSet::from([".git".to_string()]);
 
-            if segments.len() >= 2 {
-                let prefix = segments[0];
-                let suffix = segments[1];
+    patterns.extend(content.lines().filter_map(parse_ignore_line));
 
-                // Check if both prefix and suffix match parts of the path
-                // If prefix is empty, it's a pattern like "/**/suffix"
-                let prefix_matches = prefix.is_empty()
-                    || path_str.starts_with(prefix)
-                    || path_str.contains(&format!("/{}", prefix));
+    Ok(Some(patterns))
+}
 
-                // If suffix is empty, it's a pattern like "prefix/**/"
-                let suffix_matches = suffix.is_empty()
-                    || path_str.ends_with(suffix)
-                    || path_str.contains(&format!("{}/", suffix));
+/// Patterns from the project's .gitignore, or None when it has none
+pub fn check_for_gitignore(project_path: &Path) -> Result<Option<HashSet<String>>> {
+    let gitignore_path = project_path.join(".gitignore");
 
-                if prefix_matches && suffix_matches {
-                    return true;
-                }
-            }
-        }
+    if !gitignore_path.exists() {
+        return Ok(None);
+    }
 
-        // Directory pattern (ends with slash)
-        if pattern.ends_with('/') {
-            let dir_name = &pattern[0..pattern.len() - 1];
-
-            // Special handling for wildcard directory patterns (e.g., "**/test*/")
-            if dir_name.contains('*') {
-                // Handle **/prefix*/ pattern (common case)
-                if dir_name.starts_with("**/") {
-                    let wildcard_part = &dir_name[3..];
-                    if wildcard_part.contains('*') {
-                        // For patterns like "**/test*/"
-                        let parts: Vec<&str> = wildcard_part.split('*').collect();
-                        if parts.len() == 2 {
-                            let prefix = parts[0];
-                            let suffix = parts[1];
-
-                            // This should match any directory that starts with prefix and ends with suffix
-                            // For example, "**/test*/" should match "test/", "testing/", "src/test/", "src/testing/"
-                            let contains_pattern = path_str.split('/').any(|segment| {
-                                !segment.is_empty()
-                                    && segment.starts_with(prefix)
-                                    && segment.ends_with(suffix)
-                            });
+    // Read the .gitignore file
+    let content = reader::read_utf8(&gitignore_path)?;
 
-                            if contains_pattern {
-                                return true;
-                            }
-                        }
-                    }
-                }
+    // Add .git to always ignore
+    let mut patterns = HashSet::from([".git".to_string()]);
 
-                // Skip to next pattern since we've handled wildcards
-                continue;
-            }
+    patterns.extend(content.lines().filter_map(parse_ignore_line));
 
-            // Check if path contains the directory as a complete segment
-            // "test/" should match "test/file.rs" or "src/test/file.rs" but not "testing/file.rs"
-            let matches = path_str == dir_name
-                || path_str.starts_with(&format!("{}/", dir_name))
-                || path_str.contains(&format!("/{}/", dir_name));
+    Ok(Some(patterns))
+}
 
-            if matches {
-                return true;
-            }
+/// Why a path was left out of the digest, as recorded in `Collection::excluded`
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// A symlink that wasn't followed (see `CollectOptions::follow_symlinks`)
+    Symlink,
+    /// A followed symlink that points back at one of its ancestor directories
+    SymlinkLoop,
+    /// A file already collected through another path
+    Duplicate,
+}
 
-            continue; // Skip other pattern matching for directory patterns
-        }
+/// A path left out of the digest, reported in the digest's excluded-files section
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExcludedFile {
+    pub path: String,
+    pub reason: ExclusionReason,
+    /// Symlink target, loop ancestor, or the path the duplicate was collected under
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
 
-        // Special case for *.test.* pattern
-        if pattern == "*.test.*" {
-            if path_str.contains(".test.") {
-                return true;
-            }
-        }
+/// What kind of file `--mention-skipped` lists
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SkippedKind {
+    Image,
+    /// Executables, archives, fonts, media, and anything else that isn't text
+    Binary,
+    /// A code file over the size limit
+    Oversized,
+    /// Minified or bundled JavaScript or CSS, see `minified::is_minified`
+    Minified,
+}
 
-        // Handle glob patterns with * (simplified implementation)
-        if pattern.contains('*') && !pattern.contains("**") {
-            let parts: Vec<&str> = pattern.split('*').collect();
-
-            // Simple cases
-            if parts.len() == 2 {
-                if pattern.starts_with('*') && path_str.ends_with(parts[1]) {
-                    // *suffix pattern (e.g., "*.js")
-                    // Make sure the suffix starts at a valid boundary (e.g., after a / or .)
-                    let last_segment = path_str.split('/').last().unwrap_or("");
-                    if last_segment.ends_with(parts[1])
-                        && (parts[1].is_empty()
-                            || parts[1].starts_with('.')
-                            || last_segment == parts[1])
-                    {
-                        return true;
-                    }
-                } else if pattern.ends_with('*') && path_str.starts_with(parts[0]) {
-                    // prefix* pattern
-                    // Make sure the prefix matches a whole path component
-                    if path_str == parts[0]
-                        || path_str.starts_with(&format!("{}/", parts[0]))
-                        || path_str.contains(&format!("/{}/", parts[0]))
-                    {
-                        return true;
-                    }
-                } else if !parts[0].is_empty() && !parts[1].is_empty() {
-                    // prefix*suffix pattern
-                    // For file extensions like "*.js", make sure we match correct boundary
-                    let file_name = path_str.split('/').last().unwrap_or("");
-                    if parts[1].starts_with('.')
-                        && file_name.contains(&format!("{}{}", parts[0], parts[1]))
-                    {
-                        return true;
-                    } else if path_str.contains(&format!("{}{}", parts[0], parts[1])) {
-                        return true;
-                    }
-                }
-            }
-        } else {
-            // Direct match (either exact or as a substring)
-            if path_str == pattern
-                || path_str.ends_with(&format!("/{}", pattern))
-                || path_str.contains(&format!("/{}/", pattern))
-            {
-                return true;
-            }
-        }
-    }
+/// A file left out for its content, listed by path and size so readers know it exists
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    pub path: String,
+    pub kind: SkippedKind,
+    pub bytes: u64,
+}
 
-    false
+/// Files gathered by `collect_files`
+#[derive(Debug)]
+pub struct Collection {
+    pub files: Vec<FileInfo>,
+    /// Eligible files left out because `max_files` was reached
+    pub overflow: usize,
+    /// Symlinks, loops, and duplicates the walk skipped
+    pub excluded: Vec<ExcludedFile>,
+    /// Images, binaries, and oversized files, when `CollectOptions::mention_skipped` is set
+    pub skipped: Vec<SkippedFile>,
+    /// Small images, when `CollectOptions::embed_images` is set
+    pub images: Vec<EmbeddedImage>,
+    /// Lockfile summaries, when `CollectOptions::lockfiles` is `LockfileMode::Summary`
+    pub lockfiles: Vec<LockfileSummary>,
+    /// Files each stage of the walk left out
+    pub filtered: FilterCounts,
 }
 
-pub fn check_for_digestignore(project_path: &Path) -> Result<HashSet<String>> {
-    let digestignore_path = project_path.join(".digestignore");
+/// How many files each collection stage left out, to explain an empty digest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterCounts {
+    /// Matched an ignore pattern, or left out by `CollectOptions::filter` or `transform`
+    pub ignored: usize,
+    /// Not a code file the digest collects (the extension allowlist)
+    pub not_code: usize,
+    /// Larger than `CollectOptions::max_file_size`
+    pub oversized: usize,
+    /// Not UTF-8 text, a notebook that couldn't be parsed, or unreadable
+    pub unreadable: usize,
+    /// Minified or bundled JavaScript or CSS, unless `CollectOptions::include_minified` is set
+    pub minified: usize,
+    /// Source files without doc comments, when `CollectOptions::docs_only` is set
+    pub undocumented: usize,
+}
 
-    if !digestignore_path.exists() {
-        return Err(anyhow::anyhow!("No .digestignore file found"));
+impl FilterCounts {
+    pub fn total(&self) -> usize {
+        self.ignored
+            + self.not_code
+            + self.oversized
+            + self.unreadable
+            + self.minified
+            + self.undocumented
     }
+}
 
-    // Use the ignore crate to build a gitignore-like matcher from the .digestignore file
-    let content = fs::read_to_string(&digestignore_path).with_context(|| {
-        format!(
-            "Failed to read .digestignore at {}",
-            digestignore_path.display()
-        )
-    })?;
+/// Every file under `project_path` outside `.git`, with no ignore files or hidden-file rules
+/// applied
+#[cfg(feature = "native")]
+pub fn count_all_files(project_path: &Path) -> usize {
+    ignore::WalkBuilder::new(project_path)
+        .standard_filters(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .count()
+}
 
-    // Add .git to always ignore
-    let mut patterns = HashSet::from([".git".to_string()]);
+/// Settings for `collect_files`
+#[derive(Debug, Clone)]
+pub struct CollectOptions {
+    pub max_files: usize,
+    /// Largest file to read, in bytes
+    pub max_file_size: u64,
+    /// Detected project types; each adds the extensions it cares about to the common code
+    /// files, and Godot labels C# scripts as Godot's
+    pub project_types: Vec<ProjectType>,
+    /// Honor .gitignore files in the project. The disk walk reads them itself, so patterns
+    /// loaded from `.gitignore` or `.ignore` into `IgnoreSources` don't apply a second time.
+    pub respect_gitignore: bool,
+    /// Honor the global gitignore (`core.excludesFile`). The disk walk reads it itself; a `Vfs`
+    /// walk applies the patterns `IgnoreSources::load_global_gitignore` adds.
+    pub respect_global_gitignore: bool,
+    /// Honor `.git/info/exclude`. The disk walk reads it itself; a `Vfs` walk applies the
+    /// patterns `IgnoreSources::load_git_exclude` adds.
+    pub respect_git_exclude: bool,
+    /// Honor plain .ignore files, as used by ripgrep and fd
+    pub respect_dot_ignore: bool,
+    /// The project's git submodules, from `submodules::read_submodules`; files inside one are
+    /// tagged with its path
+    pub submodules: Vec<Submodule>,
+    /// Whether to collect the submodules' files, only theirs, or leave them out
+    pub submodule_mode: SubmoduleMode,
+    /// Whether lockfiles (`lockfiles::LOCKFILES`) are collected, left out, or summarized in
+    /// `Collection::lockfiles`
+    pub lockfiles: LockfileMode,
+    /// Walk into symlinked directories and read symlinked files; otherwise symlinks are
+    /// reported as excluded
+    pub follow_symlinks: bool,
+    /// Collect every dotfile and dot-directory; otherwise only `WELL_KNOWN_HIDDEN` ones
+    pub include_hidden: bool,
+    /// Record images, binaries, and oversized files in `Collection::skipped`
+    pub mention_skipped: bool,
+    /// Collect JavaScript and CSS that looks minified or bundled; otherwise it is left out
+    pub include_minified: bool,
+    /// Collect only documentation: READMEs and documentation directories whole, including prose
+    /// such as `.rst` that isn't code (`docs::is_doc_file`), and just the doc comments of
+    /// other files, leaving out those without any
+    pub docs_only: bool,
+    /// Embed PNG, JPEG, GIF, and WebP images up to this many bytes in `Collection::images`
+    pub embed_images: Option<u64>,
+    /// Walk each directory in file-name order, so the collected files don't depend on the
+    /// order the file system lists them in
+    pub sort_paths: bool,
+    /// Set with `CollectOptions::filter`
+    pub filter: Option<FileFilter>,
+    /// Set with `CollectOptions::transform`
+    pub transform: Option<FileTransform>,
+    /// Set with `CollectOptions::pin`
+    pub pin: Option<FilePin>,
+}
 
-    for line in content.lines() {
-        let line = line.trim();
-        // Skip empty lines and comments
-        if !line.is_empty() && !line.starts_with('#') {
-            patterns.insert(line.to_string());
-        }
+impl CollectOptions {
+    /// Leave out the files for which `filter` returns false. It sees every file that passed
+    /// the ignore patterns, before the code-file and size checks and before the file is read;
+    /// files it rejects count as ignored. Replaces an earlier filter.
+    pub fn filter(
+        mut self,
+        filter: impl Fn(&Path, &vfs::Metadata) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filter = Some(FileFilter(Arc::new(filter)));
+        self
+    }
+
+    /// Pass each file through `transform` once it has been read, e.g. to rewrite its content
+    /// (with `FileInfo::with_content`, so its size, hash, and line counts follow);
+    /// returning None leaves the file out, and it counts as ignored. Replaces an earlier
+    /// transform.
+    pub fn transform(
+        mut self,
+        transform: impl Fn(FileInfo) -> Option<FileInfo> + Send + Sync + 'static,
+    ) -> Self {
+        self.transform = Some(FileTransform(Arc::new(transform)));
+        self
     }
 
-    Ok(patterns)
+    /// Read the files for which `pin` returns true even once `max_files` have been collected,
+    /// e.g. entry points the caller ranks ahead of the rest before cutting to its own limit.
+    /// It sees the project-relative path with forward slashes. Replaces an earlier pin.
+    pub fn pin(mut self, pin: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.pin = Some(FilePin(Arc::new(pin)));
+        self
+    }
 }
 
-pub fn check_for_gitignore(project_path: &Path) -> Result<HashSet<String>> {
-    let gitignore_path = project_path.join(".gitignore");
+type FilterFn = dyn Fn(&Path, &vfs::Metadata) -> bool + Send + Sync;
+type TransformFn = dyn Fn(FileInfo) -> Option<FileInfo> + Send + Sync;
+type PinFn = dyn Fn(&str) -> bool + Send + Sync;
 
-    if !gitignore_path.exists() {
-        return Err(anyhow::anyhow!("No .gitignore file found"));
+/// A user-supplied exclusion callback; see `CollectOptions::filter`
+#[derive(Clone)]
+pub struct FileFilter(Arc<FilterFn>);
+
+impl fmt::Debug for FileFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileFilter")
     }
+}
 
-    // Read the .gitignore file
-    let content = fs::read_to_string(&gitignore_path)
-        .with_context(|| format!("Failed to read .gitignore at {}", gitignore_path.display()))?;
+/// A user-supplied rewrite of collected files; see `CollectOptions::transform`
+#[derive(Clone)]
+pub struct FileTransform(Arc<TransformFn>);
 
-    // Add .git to always ignore
-    let mut patterns = HashSet::from([".git".to_string()]);
+impl fmt::Debug for FileTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileTransform")
+    }
+}
 
-    for line in content.lines() {
-        let line = line.trim();
-        // Skip empty lines and comments
-        if !line.is_empty() && !line.starts_with('#') {
-            patterns.insert(line.to_string());
-        }
+/// A user-supplied choice of files read past `max_files`; see `CollectOptions::pin`
+#[derive(Clone)]
+pub struct FilePin(Arc<PinFn>);
+
+impl fmt::Debug for FilePin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FilePin")
     }
+}
 
-    Ok(patterns)
+/// Hidden files and directories collected even when other dotfiles are skipped: CI
+/// workflows and tool configuration that describe how the project is built
+pub const WELL_KNOWN_HIDDEN: &[&str] = &[
+    ".github",
+    ".gitlab",
+    ".gitlab-ci.yml",
+    ".circleci",
+    ".travis.yml",
+    ".cargo",
+    ".devcontainer",
+];
+
+/// Whether a file or directory name is a dotfile that isn't in `WELL_KNOWN_HIDDEN`
+fn is_skipped_hidden(name: &str) -> bool {
+    name.starts_with('.') && name != "." && name != ".." && !WELL_KNOWN_HIDDEN.contains(&name)
 }
 
+impl Default for CollectOptions {
+    fn default() -> Self {
+        CollectOptions {
+            max_files: 50,
+            max_file_size: 500 * 1024,
+            project_types: Vec::new(),
+            respect_gitignore: true,
+            respect_global_gitignore: true,
+            respect_git_exclude: true,
+            respect_dot_ignore: true,
+            submodules: Vec::new(),
+            submodule_mode: SubmoduleMode::Include,
+            lockfiles: LockfileMode::Exclude,
+            follow_symlinks: false,
+            include_hidden: false,
+            mention_skipped: false,
+            include_minified: false,
+            docs_only: false,
+            embed_images: None,
+            sort_paths: false,
+            filter: None,
+            transform: None,
+            pin: None,
+        }
+    }
+}
+
+#[cfg(feature = "native")]
 pub fn collect_relevant_files(
     project_path: &Path,
-    ignore_patterns: &HashSet<String>,
+    ignores: &IgnoreSources,
     max_files: usize,
     max_file_size: u64,
     is_godot_project: bool,
     respect_gitignore: bool,
 ) -> Result<Vec<FileInfo>> {
+    let options = CollectOptions {
+        max_files,
+        max_file_size,
+        project_types: if is_godot_project {
+            project_type(GODOT).into_iter().collect()
+        } else {
+            Vec::new()
+        },
+        respect_gitignore,
+        respect_global_gitignore: respect_gitignore,
+        respect_git_exclude: respect_gitignore,
+        ..CollectOptions::default()
+    };
+    collect_files(project_path, ignores, &options).map(|collection| collection.files)
+}
+
+/// Path of `path` relative to the project root, for reports
+fn display_path(project_path: &Path, path: &Path) -> String {
+    match paths::strip_root(path, project_path) {
+        Some(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Some(relative) => relative.to_string_lossy().to_string(),
+        None => path.to_string_lossy().to_string(),
+    }
+}
+
+/// The ancestor and child of a symlink loop reported by the walker, if `err` is one
+#[cfg(feature = "native")]
+fn symlink_loop(err: &ignore::Error) -> Option<(&Path, &Path)> {
+    match err {
+        ignore::Error::Loop { ancestor, child } => Some((ancestor, child)),
+        ignore::Error::WithPath { err, .. } | ignore::Error::WithDepth { err, .. } => {
+            symlink_loop(err)
+        }
+        _ => None,
+    }
+}
+
+/// Like `collect_relevant_files`, but keeps walking past `max_files` to count the eligible
+/// files that didn't fit (without reading them), and reports the symlinks it skipped
+#[cfg(feature = "native")]
+pub fn collect_files(
+    project_path: &Path,
+    ignores: &IgnoreSources,
+    options: &CollectOptions,
+) -> Result<Collection> {
+    collect_files_with_progress(project_path, ignores, options, |_| {})
+}
+
+/// `collect_files`, calling `on_file` with each file as soon as it has been read
+#[cfg(feature = "native")]
+pub fn collect_files_with_progress(
+    project_path: &Path,
+    ignores: &IgnoreSources,
+    options: &CollectOptions,
+    on_file: impl FnMut(&FileInfo),
+) -> Result<Collection> {
+    let walk = collect_relevant_files_iter(project_path, ignores, options);
+    Ok(drain(walk, on_file))
+}
+
+/// `collect_files` over the tree under `root` in `vfs` rather than the local disk, e.g. an
+/// in-memory project. Gitignore files aren't read there; `ignores` still apply.
+pub fn collect_files_in(
+    vfs: &dyn Vfs,
+    root: &Path,
+    ignores: &IgnoreSources,
+    options: &CollectOptions,
+) -> Result<Collection> {
+    let walk = FileWalk::over(vfs, root, ignores, options);
+    Ok(drain(walk, |_| {}))
+}
+
+/// Run `walk` to the end, calling `on_file` with each file
+fn drain(mut walk: FileWalk<'_>, mut on_file: impl FnMut(&FileInfo)) -> Collection {
     let mut files = Vec::new();
+    // Entries that couldn't be read were logged by the walk and are left out
+    for file in walk.by_ref().flatten() {
+        on_file(&file);
+        files.push(file);
+    }
+    walk.into_collection(files)
+}
 
-    // Configure the walker with appropriate gitignore settings
-    let mut builder = ignore::WalkBuilder::new(project_path);
-    builder
-        .hidden(false) // Include hidden files
-        .git_ignore(respect_gitignore) // Respect .gitignore based on CLI option
-        .git_global(respect_gitignore) // Also control global gitignore
-        .git_exclude(respect_gitignore); // And git exclude rules
+/// Walk the project lazily: each relevant file is read when the iterator reaches it, so a
+/// consumer with its own budget can stop early. Entries that can't be read are logged and
+/// yielded as errors; the walk goes on past them. Files past `max_files` are counted, not
+/// read, as in `collect_files`.
+#[cfg(feature = "native")]
+pub fn collect_relevant_files_iter<'a>(
+    project_path: &Path,
+    ignores: &'a IgnoreSources,
+    options: &CollectOptions,
+) -> FileWalk<'a> {
+    FileWalk::new(project_path, ignores, options)
+}
 
-    let walker = builder.build();
+/// The iterator returned by `collect_relevant_files_iter`, which also tallies what the walk
+/// leaves out
+pub struct FileWalk<'a> {
+    project_path: PathBuf,
+    options: CollectOptions,
+    vfs: &'a dyn Vfs,
+    entries: Entries,
+    ignores: &'a IgnoreSources,
+    /// Sources left out of `compiled_ignore`
+    skipped_sources: SkippedSources,
+    compiled_ignore: CompiledIgnore<'a>,
+    /// Godot's C# scripts are labelled as such
+    is_godot_project: bool,
+    yielded: usize,
+    overflow: usize,
+    excluded: Vec<ExcludedFile>,
+    skipped: Vec<SkippedFile>,
+    images: Vec<EmbeddedImage>,
+    lockfiles: Vec<LockfileSummary>,
+    filtered: FilterCounts,
+    /// Canonical path of every collected file, to drop second copies reached through symlinks
+    seen: HashMap<PathBuf, String>,
+}
 
-    for result in walker {
-        let entry = match result {
-            Ok(entry) => entry,
-            Err(err) => {
-                eprintln!("Error accessing entry: {}", err);
-                continue;
-            }
+impl<'a> FileWalk<'a> {
+    #[cfg(feature = "native")]
+    pub fn new(project_path: &Path, ignores: &'a IgnoreSources, options: &CollectOptions) -> Self {
+        // Configure the walker with appropriate gitignore settings
+        let mut builder = ignore::WalkBuilder::new(project_path);
+        builder
+            .hidden(false) // Hidden entries are filtered below so well-known ones can stay
+            .git_ignore(options.respect_gitignore)
+            .git_global(options.respect_global_gitignore)
+            .git_exclude(options.respect_git_exclude)
+            .ignore(options.respect_dot_ignore)
+            .require_git(false) // .gitignore applies outside a repository too
+            .follow_links(options.follow_symlinks); // The walker detects loops when following
+        if options.sort_paths {
+            builder.sort_by_file_name(|a, b| a.cmp(b));
+        }
+        let include_hidden = options.include_hidden;
+        let excluded_submodules: Vec<PathBuf> = match options.submodule_mode {
+            SubmoduleMode::Exclude => options
+                .submodules
+                .iter()
+                .map(|submodule| project_path.join(&submodule.path))
+                .collect(),
+            SubmoduleMode::Include | SubmoduleMode::Only => Vec::new(),
         };
+        if !include_hidden || !excluded_submodules.is_empty() {
+            // Prune hidden directories and left-out submodules here so the walk never descends
+            // into them
+            builder.filter_entry(move |entry| {
+                let hidden = !include_hidden
+                    && entry.depth() > 0
+                    && is_skipped_hidden(&entry.file_name().to_string_lossy());
+                if hidden {
+                    debug!(
+                        path:% = entry.path().display(), reason = "hidden";
+                        "Skipping hidden entry: {}", entry.path().display()
+                    );
+                }
+                let submodule = excluded_submodules.iter().any(|dir| entry.path() == dir);
+                if submodule {
+                    debug!(
+                        path:% = entry.path().display(), reason = "submodule";
+                        "Skipping submodule: {}", entry.path().display()
+                    );
+                }
+                !hidden && !submodule
+            });
+        }
+
+        FileWalk::with_entries(
+            &OsFs,
+            Entries::Disk(Box::new(builder.build())),
+            project_path,
+            ignores,
+            SkippedSources::on_disk(),
+            options,
+        )
+    }
 
-        let path = entry.path();
+    /// Walk the tree under `root` in `vfs` instead of the local disk. Entries are visited in
+    /// name order; gitignore files and symlinked directories aren't followed, since they
+    /// belong to the disk walker, so the patterns in `ignores` apply here instead, those the
+    /// `respect_*` options turn off aside.
+    pub fn over(
+        vfs: &'a dyn Vfs,
+        root: &Path,
+        ignores: &'a IgnoreSources,
+        options: &CollectOptions,
+    ) -> Self {
+        FileWalk::with_entries(
+            vfs,
+            Entries::Tree(vec![root.to_path_buf()]),
+            root,
+            ignores,
+            SkippedSources::in_vfs(options),
+            options,
+        )
+    }
 
-        // Skip directories
-        if path.is_dir() {
-            continue;
+    fn with_entries(
+        vfs: &'a dyn Vfs,
+        entries: Entries,
+        project_path: &Path,
+        ignores: &'a IgnoreSources,
+        skipped: SkippedSources,
+        options: &CollectOptions,
+    ) -> Self {
+        FileWalk {
+            project_path: project_path.to_path_buf(),
+            options: options.clone(),
+            vfs,
+            entries,
+            ignores,
+            compiled_ignore: ignores.matcher_without(|source| skipped.skips(source)),
+            skipped_sources: skipped,
+            is_godot_project: has_type(&options.project_types, GODOT),
+            yielded: 0,
+            overflow: 0,
+            excluded: Vec::new(),
+            skipped: Vec::new(),
+            images: Vec::new(),
+            lockfiles: Vec::new(),
+            filtered: FilterCounts::default(),
+            seen: HashMap::new(),
         }
+    }
 
-        // Skip files that match ignore patterns
-        if should_ignore(path, ignore_patterns) {
-            continue;
+    /// What the walk has left out so far, with `files`, the ones it yielded; the counts are
+    /// complete once the iterator is exhausted
+    pub fn into_collection(self, files: Vec<FileInfo>) -> Collection {
+        Collection {
+            files,
+            overflow: self.overflow,
+            excluded: self.excluded,
+            skipped: self.skipped,
+            images: self.images,
+            lockfiles: self.lockfiles,
+            filtered: self.filtered,
         }
+    }
 
-        // Check file size
-        let metadata = match fs::metadata(path) {
-            Ok(meta) => meta,
+    /// Record the lockfile at `path` in place of its content, whatever its size
+    fn summarize_lockfile(&mut self, path: &Path) {
+        match reader::read_text(self.vfs, path, u64::MAX) {
+            Ok(TextFile::Text(content)) => {
+                let relative = paths::slash(Path::new(&display_path(&self.project_path, path)));
+                if let Some(summary) = lockfiles::summarize_lockfile(&relative, &content) {
+                    self.lockfiles.push(summary);
+                }
+            }
+            Ok(_) => {
+                debug!(
+                    path:% = path.display(), reason = "not_utf8";
+                    "Skipping lockfile that isn't valid UTF-8: {}", path.display()
+                );
+                self.filtered.unreadable += 1;
+            }
             Err(err) => {
-                eprintln!("Error reading metadata for {}: {}", path.display(), err);
-                continue;
+                warn!(
+                    path:% = path.display(), reason = "read_error", error:% = err;
+                    "Error reading lockfile {}: {}", path.display(), err
+                );
+                self.filtered.unreadable += 1;
             }
-        };
+        }
+    }
+
+    fn mention(&mut self, path: &Path, kind: SkippedKind, bytes: u64) {
+        if self.options.mention_skipped {
+            self.skipped.push(SkippedFile {
+                path: display_path(&self.project_path, path),
+                kind,
+                bytes,
+            });
+        }
+    }
+
+    /// The next path to look at, files and directories alike
+    // Only the disk walk loops, to step past symlink loops
+    #[cfg_attr(not(feature = "native"), allow(clippy::never_loop))]
+    fn next_path(&mut self) -> Option<Result<PathBuf>> {
+        loop {
+            match &mut self.entries {
+                #[cfg(feature = "native")]
+                Entries::Disk(walker) => match walker.next()? {
+                    Ok(entry) => return Some(Ok(entry.into_path())),
+                    Err(err) => {
+                        if let Some((ancestor, child)) = symlink_loop(&err) {
+                            warn!(
+                                path:% = child.display(), target:% = ancestor.display(),
+                                reason = "symlink_loop";
+                                "Skipping symlink loop: {} -> {}", child.display(), ancestor.display()
+                            );
+                            self.excluded.push(ExcludedFile {
+                                path: display_path(&self.project_path, child),
+                                reason: ExclusionReason::SymlinkLoop,
+                                target: Some(display_path(&self.project_path, ancestor)),
+                            });
+                            continue;
+                        }
+                        warn!(reason = "walk_error", error:% = err; "Error accessing entry: {}", err);
+                        return Some(Err(DigestError::Walk(err)));
+                    }
+                },
+                Entries::Tree(stack) => {
+                    let path = stack.pop()?;
+                    let is_dir = self
+                        .vfs
+                        .metadata(&path)
+                        .is_ok_and(|metadata| metadata.is_dir && !metadata.is_symlink);
+                    if is_dir {
+                        let mut children = match self.vfs.read_dir(&path) {
+                            Ok(children) => children,
+                            Err(err) => {
+                                warn!(reason = "walk_error", error:% = err; "Error accessing entry: {}", err);
+                                return Some(Err(DigestError::io("read directory", path)(err)));
+                            }
+                        };
+                        if !self.options.include_hidden {
+                            children.retain(|child| {
+                                let name = child.file_name().unwrap_or_default().to_string_lossy();
+                                !is_skipped_hidden(&name)
+                            });
+                        }
+                        // Popped from the end, so the first name comes out first
+                        children.sort_by(|a, b| b.cmp(a));
+                        stack.extend(children);
+                    }
+                    return Some(Ok(path));
+                }
+            }
+        }
+    }
+}
+
+/// The ignore sources a `FileWalk` leaves to others or turns off, so each applies once
+#[derive(Debug, Clone, Copy)]
+struct SkippedSources {
+    gitignore: bool,
+    dot_ignore: bool,
+    git_exclude: bool,
+    global_gitignore: bool,
+}
+
+impl SkippedSources {
+    /// The disk walker reads every .gitignore and .ignore in the tree and the git excludes
+    /// itself, with negations, or is told not to; patterns loaded from them only give provenance
+    #[cfg(feature = "native")]
+    fn on_disk() -> Self {
+        SkippedSources {
+            gitignore: true,
+            dot_ignore: true,
+            git_exclude: true,
+            global_gitignore: true,
+        }
+    }
+
+    /// A `Vfs` walk reads no ignore files, so whatever was loaded applies unless turned off
+    fn in_vfs(options: &CollectOptions) -> Self {
+        SkippedSources {
+            gitignore: !options.respect_gitignore,
+            dot_ignore: !options.respect_dot_ignore,
+            git_exclude: !options.respect_git_exclude,
+            global_gitignore: !options.respect_global_gitignore,
+        }
+    }
 
-        if metadata.len() > max_file_size {
-            continue;
+    fn skips(&self, source: &IgnoreSource) -> bool {
+        match source {
+            IgnoreSource::File(name) if name == ".gitignore" => self.gitignore,
+            IgnoreSource::File(name) if name == ".ignore" => self.dot_ignore,
+            IgnoreSource::GitExclude => self.git_exclude,
+            IgnoreSource::GlobalGitignore(_) => self.global_gitignore,
+            _ => false,
         }
+    }
+}
 
-        // Check if this is a file we want to include
-        let extension = path.extension().and_then(|ext| ext.to_str());
+/// Where a `FileWalk` gets its paths from
+enum Entries {
+    /// The local disk, walked with gitignore support
+    #[cfg(feature = "native")]
+    Disk(Box<ignore::Walk>),
+    /// Any `Vfs`, walked depth-first from this stack of paths
+    Tree(Vec<PathBuf>),
+}
 
-        // For Godot projects, we want to prioritize certain file types
-        let should_include = if is_godot_project {
-            match extension {
-                Some("gd") | Some("tscn") | Some("cs") | Some("godot") => true,
-                Some("tres") | Some("import") | Some("shader") => true,
-                Some(ext) if is_common_code_file(ext) => true,
-                _ => false,
+impl Iterator for FileWalk<'_> {
+    type Item = Result<FileInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let CollectOptions {
+            max_files,
+            max_file_size,
+            follow_symlinks,
+            mention_skipped,
+            embed_images,
+            ..
+        } = self.options;
+        let project_path = self.project_path.clone();
+        let project_path = project_path.as_path();
+        let is_godot_project = self.is_godot_project;
+
+        loop {
+            let path = match self.next_path()? {
+                Ok(path) => path,
+                Err(err) => return Some(Err(err)),
+            };
+            let path = path.as_path();
+
+            // Skip files that match ignore patterns, matched below the project root so the
+            // directories it sits in don't count
+            let relative = path.strip_prefix(project_path).unwrap_or(path);
+            if let Some(pattern) = self.compiled_ignore.matching(relative) {
+                if !self
+                    .vfs
+                    .metadata(path)
+                    .is_ok_and(|metadata| metadata.is_dir)
+                {
+                    self.filtered.ignored += 1;
+                    let source = self
+                        .ignores
+                        .iter()
+                        .find(|p| p.pattern == pattern && !self.skipped_sources.skips(&p.source))
+                        .map(|origin| origin.source.to_string())
+                        .unwrap_or_default();
+                    debug!(
+                        path:% = path.display(), pattern, source, reason = "ignored";
+                        "Ignoring file: {} (pattern {} from {})", path.display(), pattern, source
+                    );
+                }
+                continue;
             }
-        } else {
-            // For non-Godot projects, use the regular logic
-            match extension {
-                Some(ext) if is_common_code_file(ext) => true,
-                _ => false,
+
+            let metadata = match self.vfs.metadata(path) {
+                Ok(meta) => meta,
+                Err(err) => {
+                    warn!(
+                        path:% = path.display(), reason = "metadata_error", error:% = err;
+                        "Error reading metadata for {}: {}", path.display(), err
+                    );
+                    return Some(Err(DigestError::io("read metadata for", path)(err)));
+                }
+            };
+
+            // Without --follow-symlinks, record where symlinks point instead of reading them
+            if !follow_symlinks && metadata.is_symlink {
+                let target = self
+                    .vfs
+                    .read_link(path)
+                    .map(|target| target.to_string_lossy().to_string())
+                    .ok();
+                debug!(
+                    path:% = path.display(), target = target.as_deref().unwrap_or("?"),
+                    reason = "symlink";
+                    "Skipping symlink: {}", path.display()
+                );
+                self.excluded.push(ExcludedFile {
+                    path: display_path(project_path, path),
+                    reason: ExclusionReason::Symlink,
+                    target,
+                });
+                continue;
             }
-        };
 
-        if !should_include {
-            continue;
-        }
+            // Skip directories
+            if metadata.is_dir {
+                continue;
+            }
 
-        // Read file content
-        let content = match fs::read_to_string(path) {
-            Ok(content) => content,
-            Err(err) => {
-                eprintln!("Error reading file {}: {}", path.display(), err);
+            let submodule = submodule_of(
+                &self.options.submodules,
+                &paths::slash(Path::new(&display_path(project_path, path))),
+            )
+            .map(|submodule| submodule.path.clone());
+            let wanted = match self.options.submodule_mode {
+                SubmoduleMode::Include => true,
+                SubmoduleMode::Exclude => submodule.is_none(),
+                SubmoduleMode::Only => submodule.is_some(),
+            };
+            if !wanted {
+                debug!(
+                    path:% = path.display(), reason = "submodule";
+                    "Skipping file outside the selected submodules: {}", path.display()
+                );
+                self.filtered.ignored += 1;
                 continue;
             }
-        };
 
-        // Determine file language based on extension and project type
-        let language = match extension {
-            Some(ext) => {
-                let lang = match ext {
-                    "rs" => "Rust",
-                    "js" => "JavaScript",
-                    "ts" => "TypeScript",
-                    "py" => "Python",
-                    "java" => "Java",
-                    "go" => "Go",
-                    "c" | "cpp" | "h" | "hpp" => "C/C++",
-                    "rb" => "Ruby",
-                    "php" => "PHP",
-                    "lua" => "Lua",
-                    "cs" => {
-                        if is_godot_project {
-                            "GDScript C#"
-                        } else {
-                            "C#"
-                        }
+            if let Some(FileFilter(filter)) = &self.options.filter {
+                if !filter(path, &metadata) {
+                    debug!(
+                        path:% = path.display(), reason = "filter";
+                        "Skipping file rejected by the filter: {}", path.display()
+                    );
+                    self.filtered.ignored += 1;
+                    continue;
+                }
+            }
+
+            // Lockfiles the ignores kept are left out, summarized, or collected whatever their
+            // extension
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            let lockfile_mode = lockfiles::is_lockfile(file_name).then_some(self.options.lockfiles);
+            if let Some(mode) = lockfile_mode {
+                match mode {
+                    LockfileMode::Include => {}
+                    LockfileMode::Exclude => {
+                        debug!(
+                            path:% = path.display(), reason = "lockfile";
+                            "Skipping lockfile: {}", path.display()
+                        );
+                        self.filtered.ignored += 1;
+                        continue;
+                    }
+                    LockfileMode::Summary => {
+                        self.summarize_lockfile(path);
+                        continue;
+                    }
+                }
+            }
+
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            let is_notebook = extension == Some("ipynb");
+
+            // Notebooks shrink once their outputs are stripped, so they get more room on disk
+            let read_limit = if is_notebook {
+                max_file_size.saturating_mul(notebook::RAW_SIZE_FACTOR)
+            } else {
+                max_file_size
+            };
+
+            // Common code files, plus whatever the detected project types add
+            let is_doc =
+                self.options.docs_only && docs::is_doc_file(&display_path(project_path, path));
+            let should_include = lockfile_mode == Some(LockfileMode::Include)
+                || is_doc && docs::is_prose(&display_path(project_path, path))
+                || match extension {
+                    Some(ext) => {
+                        is_common_code_file(ext)
+                            || self
+                                .options
+                                .project_types
+                                .iter()
+                                .any(|project_type| project_type.includes(ext))
                     }
-                    "html" => "HTML",
-                    "css" => "CSS",
-                    "json" => "JSON",
-                    "md" => "Markdown",
-                    "yml" | "yaml" => "YAML",
-                    "toml" => "TOML",
-                    "gd" => "GDScript",
-                    "tscn" | "tres" => "Godot Scene",
-                    "shader" => "Godot Shader",
-                    _ => "Unknown",
+                    None => false,
                 };
-                Some(lang.to_string())
+
+            if !should_include {
+                debug!(
+                    path:% = path.display(), reason = "not_code";
+                    "Skipping non-code file: {}", path.display()
+                );
+                let embedded = embed_images.and_then(|max_bytes| {
+                    assets::embed_image(self.vfs, path, display_path(project_path, path), max_bytes)
+                });
+                if let Some(image) = embedded {
+                    self.images.push(image);
+                } else if mention_skipped {
+                    if let Some(kind) = assets::classify(self.vfs, path) {
+                        self.mention(path, kind, metadata.len);
+                    }
+                }
+                self.filtered.not_code += 1;
+                continue;
             }
-            None => None,
-        };
 
-        let relative_path = path
-            .strip_prefix(project_path)
-            .with_context(|| format!("Failed to strip prefix from {}", path.display()))?
-            .to_string_lossy()
-            .to_string();
+            if metadata.len > read_limit {
+                debug!(
+                    path:% = path.display(), reason = "too_large", bytes = metadata.len;
+                    "Skipping large file: {} ({} bytes)", path.display(), metadata.len
+                );
+                self.mention(path, SkippedKind::Oversized, metadata.len);
+                self.filtered.oversized += 1;
+                continue;
+            }
 
-        files.push(FileInfo {
-            path: relative_path,
-            language,
-            content,
-        });
+            let relative_path = display_path(project_path, path);
+
+            if follow_symlinks {
+                if let Ok(canonical) = self.vfs.canonicalize(path) {
+                    if let Some(first) = self.seen.get(&canonical) {
+                        debug!(
+                            path:% = path.display(), target = first.as_str(), reason = "duplicate";
+                            "Skipping file already collected as {}: {}", first, path.display()
+                        );
+                        self.excluded.push(ExcludedFile {
+                            path: relative_path,
+                            reason: ExclusionReason::Duplicate,
+                            target: Some(first.clone()),
+                        });
+                        continue;
+                    }
+                    self.seen.insert(canonical, relative_path.clone());
+                }
+            }
+
+            let pinned = self
+                .options
+                .pin
+                .as_ref()
+                .is_some_and(|FilePin(pin)| pin(&paths::slash(Path::new(&relative_path))));
+            if self.yielded >= max_files && !pinned {
+                debug!(
+                    path:% = path.display(), reason = "max_files";
+                    "Skipping file over the file limit: {}", path.display()
+                );
+                self.overflow += 1;
+                continue;
+            }
 
-        if files.len() >= max_files {
-            break;
+            // Read file content, re-checking the size in case the file grew since the metadata call
+            let content = match reader::read_text(self.vfs, path, read_limit) {
+                Ok(TextFile::Text(content)) => content,
+                Ok(TextFile::TooLarge) => {
+                    debug!(
+                        path:% = path.display(), reason = "too_large";
+                        "Skipping file that grew past the size limit: {}", path.display()
+                    );
+                    self.mention(path, SkippedKind::Oversized, metadata.len);
+                    self.filtered.oversized += 1;
+                    continue;
+                }
+                Ok(TextFile::NotUtf8) => {
+                    debug!(
+                        path:% = path.display(), reason = "not_utf8";
+                        "Skipping file that isn't valid UTF-8: {}", path.display()
+                    );
+                    self.mention(path, SkippedKind::Binary, metadata.len);
+                    self.filtered.unreadable += 1;
+                    continue;
+                }
+                Err(err) => {
+                    warn!(
+                        path:% = path.display(), reason = "read_error", error:% = err;
+                        "Error reading file {}: {}", path.display(), err
+                    );
+                    self.filtered.unreadable += 1;
+                    return Some(Err(DigestError::io("read", path)(err)));
+                }
+            };
+
+            // Notebooks are digested as their cell sources, without outputs
+            let content = if is_notebook {
+                match notebook::notebook_to_text(&content) {
+                    Some(text) if text.len() as u64 <= max_file_size => text,
+                    Some(text) => {
+                        debug!(
+                            path:% = path.display(), reason = "too_large", bytes = text.len();
+                            "Skipping large notebook: {} ({} bytes of cells)", path.display(), text.len()
+                        );
+                        self.mention(path, SkippedKind::Oversized, metadata.len);
+                        self.filtered.oversized += 1;
+                        continue;
+                    }
+                    None => {
+                        debug!(
+                            path:% = path.display(), reason = "invalid_notebook";
+                            "Skipping notebook that couldn't be parsed: {}", path.display()
+                        );
+                        self.filtered.unreadable += 1;
+                        continue;
+                    }
+                }
+            } else {
+                content
+            };
+
+            if !self.options.include_minified && minified::is_minified(path, &content) {
+                debug!(
+                    path:% = path.display(), reason = "minified";
+                    "Skipping minified or bundled file: {}", path.display()
+                );
+                self.mention(path, SkippedKind::Minified, metadata.len);
+                self.filtered.minified += 1;
+                continue;
+            }
+
+            let content = if self.options.docs_only && !is_doc {
+                match docs::doc_comments(&display_path(project_path, path), &content) {
+                    Some(comments) => comments,
+                    None => {
+                        debug!(
+                            path:% = path.display(), reason = "undocumented";
+                            "Skipping file without doc comments: {}", path.display()
+                        );
+                        self.filtered.undocumented += 1;
+                        continue;
+                    }
+                }
+            } else {
+                content
+            };
+
+            // Determine file language based on extension and project type
+            let language =
+                extension.map(|ext| file_language(ext, &content, is_godot_project).to_string());
+
+            let file = FileInfo {
+                modified: metadata.modified,
+                submodule,
+                ..FileInfo::new(relative_path, language, content)
+            };
+            let file = match &self.options.transform {
+                Some(FileTransform(transform)) => match transform(file) {
+                    Some(file) => file,
+                    None => {
+                        debug!(
+                            path:% = path.display(), reason = "transform";
+                            "Skipping file dropped by the transform: {}", path.display()
+                        );
+                        self.filtered.ignored += 1;
+                        continue;
+                    }
+                },
+                None => file,
+            };
+            self.yielded += 1;
+            return Some(Ok(file));
         }
     }
+}
 
-    Ok(files)
+/// The language label of a file with `extension`, "Unknown" for extensions digest collects
+/// without naming; headers and `.m` files are told apart by their `content`
+pub fn file_language(extension: &str, content: &str, is_godot_project: bool) -> &'static str {
+    match extension {
+        "rs" => "Rust",
+        "js" => "JavaScript",
+        "ts" => "TypeScript",
+        "py" => "Python",
+        "java" => "Java",
+        "go" => "Go",
+        "c" => "C",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" | "inl" => "C++",
+        "h" => header_language(content),
+        "m" => m_language(content),
+        "mm" => "Objective-C++",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "lua" => "Lua",
+        "cs" => {
+            if is_godot_project {
+                "GDScript C#"
+            } else {
+                "C#"
+            }
+        }
+        "html" => "HTML",
+        "css" => "CSS",
+        "json" | "uproject" | "uplugin" => "JSON",
+        "ini" => "INI",
+        "tf" | "tfvars" => "Terraform",
+        "hcl" => "HCL",
+        "vim" => "Vim Script",
+        "svg" => "SVG",
+        "mmd" | "mermaid" => "Mermaid",
+        "puml" | "plantuml" => "PlantUML",
+        "dot" | "gv" => "Graphviz",
+        "conf" => "Config",
+        "ipynb" => "Jupyter Notebook",
+        "md" => "Markdown",
+        "yml" | "yaml" => "YAML",
+        "toml" => "TOML",
+        "gd" => "GDScript",
+        "tscn" | "tres" => "Godot Scene",
+        "shader" => "Godot Shader",
+        _ => "Unknown",
+    }
+}
+
+/// `.h` headers are shared by C, C++, and Objective-C; tell them apart by what they declare
+fn header_language(content: &str) -> &'static str {
+    const OBJC_MARKERS: &[&str] = &["@interface", "@protocol", "@end", "#import"];
+    const CPP_MARKERS: &[&str] = &[
+        "namespace ",
+        "template<",
+        "template <",
+        "std::",
+        "public:",
+        "private:",
+        "class ",
+        "#include <iostream>",
+    ];
+    if OBJC_MARKERS.iter().any(|marker| content.contains(marker)) {
+        "Objective-C"
+    } else if CPP_MARKERS.iter().any(|marker| content.contains(marker)) {
+        "C++"
+    } else {
+        "C"
+    }
+}
+
+/// `.m` is both Objective-C and MATLAB/Octave; anything else is left unlabelled
+fn m_language(content: &str) -> &'static str {
+    const OBJC_MARKERS: &[&str] = &[
+        "@interface",
+        "@implementation",
+        "@protocol",
+        "@end",
+        "#import",
+        "#include",
+    ];
+    let matlab = content.lines().map(str::trim_start).any(|line| {
+        line.starts_with('%') || line.starts_with("function ") || line.starts_with("function[")
+    });
+    if OBJC_MARKERS.iter().any(|marker| content.contains(marker)) {
+        "Objective-C"
+    } else if matlab {
+        "MATLAB"
+    } else {
+        "Unknown"
+    }
 }
 
 // Helper function to check if a file extension is a common code file
@@ -491,8 +1434,14 @@ fn is_common_code_file(ext: &str) -> bool {
             | "go"
             | "c"
             | "cpp"
+            | "cc"
+            | "cxx"
             | "h"
             | "hpp"
+            | "hh"
+            | "hxx"
+            | "m"
+            | "mm"
             | "rb"
             | "php"
             | "cs"
@@ -508,5 +1457,16 @@ fn is_common_code_file(ext: &str) -> bool {
             | "tscn"
             | "tres"
             | "shader"
+            | "tf"
+            | "tfvars"
+            | "hcl"
+            | "ipynb"
+            | "svg"
+            | "mmd"
+            | "mermaid"
+            | "puml"
+            | "plantuml"
+            | "dot"
+            | "gv"
     )
 }
@@ -0,0 +1,234 @@
+// Dependency lockfiles: recognized by name, and summarized as package counts and hashes in
+// place of their content
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::hash::sha256_hex;
+
+/// Lockfile names and the tool that writes each, matched anywhere in the project
+pub const LOCKFILES: &[(&str, &str)] = &[
+    ("Cargo.lock", "Cargo"),
+    ("package-lock.json", "npm"),
+    ("npm-shrinkwrap.json", "npm"),
+    ("yarn.lock", "Yarn"),
+    ("pnpm-lock.yaml", "pnpm"),
+    ("bun.lock", "Bun"),
+    ("poetry.lock", "Poetry"),
+    ("Pipfile.lock", "Pipenv"),
+    ("uv.lock", "uv"),
+    ("composer.lock", "Composer"),
+    ("Gemfile.lock", "Bundler"),
+    ("go.sum", "Go"),
+    ("flake.lock", "Nix"),
+    ("mix.lock", "Mix"),
+    ("pubspec.lock", "Pub"),
+    ("Podfile.lock", "CocoaPods"),
+    ("packages.lock.json", "NuGet"),
+];
+
+/// What a digest does with the lockfiles it finds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockfileMode {
+    /// Collect them like any other file
+    #[default]
+    Include,
+    /// Leave them out
+    Exclude,
+    /// Leave their content out, listing each with its package count and hash
+    Summary,
+}
+
+/// A lockfile listed in place of its content
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockfileSummary {
+    /// Relative to the project root, with forward slashes
+    pub path: String,
+    /// The tool that writes it, e.g. "Cargo" or "npm"
+    pub ecosystem: String,
+    /// Locked packages, excluding the project itself; absent when the format isn't understood
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packages: Option<usize>,
+    pub lines: usize,
+    pub sha256: String,
+}
+
+/// The ecosystem of a lockfile named `name`, or None if it isn't one
+pub fn lockfile_ecosystem(name: &str) -> Option<&'static str> {
+    LOCKFILES
+        .iter()
+        .find(|(lockfile, _)| *lockfile == name)
+        .map(|(_, ecosystem)| *ecosystem)
+}
+
+/// Whether a file named `name` is a lockfile
+pub fn is_lockfile(name: &str) -> bool {
+    lockfile_ecosystem(name).is_some()
+}
+
+/// The summary of the lockfile at `path`; None if its name isn't a lockfile's
+pub fn summarize_lockfile(path: &str, content: &str) -> Option<LockfileSummary> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let ecosystem = lockfile_ecosystem(name)?;
+    Some(LockfileSummary {
+        path: path.to_string(),
+        ecosystem: ecosystem.to_string(),
+        packages: count_locked_packages(name, content),
+        lines: content.lines().count(),
+        sha256: sha256_hex(content.as_bytes()),
+    })
+}
+
+/// Locked packages in the lockfile named `name`, excluding the project itself: workspace
+/// members, and the editable or virtual root of a uv project. None when the lockfile isn't
+/// parsed or doesn't parse.
+pub fn count_locked_packages(name: &str, content: &str) -> Option<usize> {
+    match name {
+        // Workspace members have no source, so only registry and git packages count
+        "Cargo.lock" => {
+            let lock: toml::Value = content.parse().ok()?;
+            Some(
+                toml_packages(&lock)
+                    .filter(|p| p.get("source").is_some())
+                    .count(),
+            )
+        }
+        "poetry.lock" | "uv.lock" => {
+            let lock: toml::Value = content.parse().ok()?;
+            // uv lists the project itself as an editable or virtual package
+            Some(
+                toml_packages(&lock)
+                    .filter(|p| {
+                        p.get("source").and_then(|s| s.as_table()).is_none_or(|s| {
+                            !s.contains_key("editable") && !s.contains_key("virtual")
+                        })
+                    })
+                    .count(),
+            )
+        }
+        "package-lock.json" | "npm-shrinkwrap.json" => {
+            let lock: serde_json::Value = serde_json::from_str(content).ok()?;
+            // lockfileVersion 2+ keys every package by path, the root project by ""
+            match lock.get("packages").and_then(|p| p.as_object()) {
+                Some(packages) => Some(packages.keys().filter(|k| !k.is_empty()).count()),
+                None => Some(
+                    lock.get("dependencies")
+                        .map_or(0, count_npm_v1_dependencies),
+                ),
+            }
+        }
+        "composer.lock" => {
+            let lock: serde_json::Value = serde_json::from_str(content).ok()?;
+            let count = |key: &str| lock.get(key).and_then(|p| p.as_array()).map(Vec::len);
+            Some(count("packages")? + count("packages-dev").unwrap_or(0))
+        }
+        "Pipfile.lock" => {
+            let lock: serde_json::Value = serde_json::from_str(content).ok()?;
+            let count = |key: &str| lock.get(key).and_then(|p| p.as_object()).map(|p| p.len());
+            Some(count("default")? + count("develop").unwrap_or(0))
+        }
+        // Each resolved package starts with an unindented "name@range:" header
+        "yarn.lock" => Some(
+            content
+                .lines()
+                .filter(|line| !line.starts_with([' ', '#']) && line.trim_end().ends_with(':'))
+                .count(),
+        ),
+        // A module has a line for its source and another for its go.mod, and modules whose
+        // go.mod was only consulted during version selection have just the latter
+        "go.sum" => Some(
+            content
+                .lines()
+                .filter_map(|line| {
+                    let mut fields = line.split_whitespace();
+                    let module = fields.next()?;
+                    let version = fields.next()?;
+                    (!version.ends_with("/go.mod")).then_some(module)
+                })
+                .collect::<HashSet<_>>()
+                .len(),
+        ),
+        _ => None,
+    }
+}
+
+/// The `[[package]]` tables of a TOML lockfile
+fn toml_packages(lock: &toml::Value) -> impl Iterator<Item = &toml::Value> {
+    lock.get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+}
+
+/// Packages in a version 1 package-lock.json `dependencies` tree, nested ones included
+fn count_npm_v1_dependencies(dependencies: &serde_json::Value) -> usize {
+    dependencies
+        .as_object()
+        .map(|deps| {
+            deps.values()
+                .map(|dep| {
+                    1 + dep
+                        .get("dependencies")
+                        .map(count_npm_v1_dependencies)
+                        .unwrap_or(0)
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_lockfile() {
+        let cargo = "version = 3\n\n\
+                     [[package]]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+                     [[package]]\nname = \"serde\"\nversion = \"1.0.0\"\n\
+                     source = \"registry+https://github.com/rust-lang/crates.io-index\"\n";
+        let summary = summarize_lockfile("Cargo.lock", cargo).unwrap();
+        assert_eq!(summary.ecosystem, "Cargo");
+        assert_eq!(summary.packages, Some(1));
+        assert_eq!(summary.lines, 10);
+        assert_eq!(summary.sha256, sha256_hex(cargo.as_bytes()));
+
+        let npm = r#"{"lockfileVersion": 3, "packages": {"": {}, "node_modules/a": {}, "node_modules/b": {}}}"#;
+        let summary = summarize_lockfile("web/package-lock.json", npm).unwrap();
+        assert_eq!(summary.path, "web/package-lock.json");
+        assert_eq!(summary.packages, Some(2));
+
+        let poetry = "[[package]]\nname = \"requests\"\n\n[[package]]\nname = \"idna\"\n";
+        assert_eq!(
+            summarize_lockfile("poetry.lock", poetry).unwrap().packages,
+            Some(2)
+        );
+
+        // uv lists the project itself, which isn't a dependency
+        let uv = "[[package]]\nname = \"app\"\nsource = { editable = \".\" }\n\n\
+                  [[package]]\nname = \"idna\"\nsource = { registry = \"https://pypi.org/simple\" }\n";
+        assert_eq!(summarize_lockfile("uv.lock", uv).unwrap().packages, Some(1));
+
+        let go_sum = "golang.org/x/text v0.3.0 h1:abc=\n\
+                      golang.org/x/text v0.3.0/go.mod h1:def=\n\
+                      golang.org/x/text v0.3.2 h1:ghi=\n\
+                      golang.org/x/text v0.3.2/go.mod h1:jkl=\n\
+                      golang.org/x/tools v0.1.0/go.mod h1:mno=\n";
+        assert_eq!(
+            summarize_lockfile("go.sum", go_sum).unwrap().packages,
+            Some(1)
+        );
+
+        // Known by name but not parsed, or not parseable
+        assert_eq!(
+            summarize_lockfile("flake.lock", "{}").unwrap().packages,
+            None
+        );
+        assert_eq!(
+            summarize_lockfile("Cargo.lock", "not toml [")
+                .unwrap()
+                .packages,
+            None
+        );
+        assert!(summarize_lockfile("src/lock.rs", "").is_none());
+    }
+}
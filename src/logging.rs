@@ -0,0 +1,42 @@
+// Logger setup: env_logger's text output, or one JSON object per record for log aggregation
+use log::kv::{self, Key, Value, VisitSource};
+use serde_json::{Map, Value as JsonValue};
+use std::io::Write;
+
+/// Initialize the global logger; levels still come from RUST_LOG (errors only by default)
+/// unless `quiet` turns logging off entirely
+pub fn init_logger(json: bool, quiet: bool) {
+    let mut builder = env_logger::Builder::from_default_env();
+    if quiet {
+        builder.filter_level(log::LevelFilter::Off);
+    }
+    if json {
+        builder.format(|buf, record| {
+            let mut fields = Map::new();
+            fields.insert("timestamp".into(), buf.timestamp().to_string().into());
+            fields.insert("level".into(), record.level().as_str().into());
+            fields.insert("target".into(), record.target().into());
+            fields.insert("message".into(), record.args().to_string().into());
+            let _ = record.key_values().visit(&mut JsonFields(&mut fields));
+            writeln!(buf, "{}", JsonValue::Object(fields))
+        });
+    }
+    builder.init();
+}
+
+/// Copies structured key-values (path, pattern, reason, ...) into the JSON record
+struct JsonFields<'a>(&'a mut Map<String, JsonValue>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(n) = value.to_u64() {
+            JsonValue::from(n)
+        } else if let Some(b) = value.to_bool() {
+            JsonValue::from(b)
+        } else {
+            JsonValue::from(value.to_string())
+        };
+        self.0.insert(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
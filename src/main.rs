@@ -1,23 +1,121 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use ignore::WalkBuilder;
-use log::{debug, info, warn};
+use clap::{Arg, ArgAction, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::{generate, Shell};
+use digest::api::public_api_file;
+use digest::archive::{archive_kind, extract_archive};
+use digest::assets::format_size;
+use digest::budget::Budget;
+use digest::complexity::annotate_complexity;
+use digest::config::{load_config_args, CONFIG_FILE};
+use digest::contributors::{format_top_files, top_contributors};
+use digest::dependencies::summarize_dependencies;
+use digest::diff::{diff_digests, DigestDiff, FileChange};
+use digest::docs::is_doc_file;
+use digest::doctor::{diagnose, Finding, Severity, IGNORE_FILES};
+use digest::duplicates::find_duplicates;
+use digest::encrypt::{encrypt, Recipient};
+use digest::entry_points::detect_entry_points;
+use digest::environment::{env_options, is_ci, is_truthy, ENV_PREFIX};
+use digest::error::DigestError;
+use digest::exit::{classify, ErrorKind, ErrorReport};
+use digest::extract::{extract, format_extract, Extract, Selector};
+use digest::format::{DigestFormatter, ExecFormatter, GroupBy, JsonFormatter, MarkdownFormatter};
+use digest::frameworks::{detect_python_frameworks, FrameworkSpec};
+use digest::git::{churn_counts, git_info, hooks_dir, repo_prefix, GitInfo};
+use digest::github::{annotation, write_outputs, GITHUB_OUTPUT};
+use digest::glob::validate as validate_pattern;
+use digest::godot::{read_godot_project, summarize_scenes};
+use digest::grep::filter_by_content;
+use digest::hash::{sha256_hex, sha256sum_line, sidecar_path};
+use digest::hook::{hook_script, HookAction, HookKind, HOOK_MARKER, STABLE_ARGS};
+use digest::ignores::{
+    format_patterns, IgnorePattern, IgnoreSource, IgnoreSources, TEST_DATA_PATTERNS,
+};
+use digest::imports::ImportGraph;
+use digest::init::starter_files;
+use digest::lockfiles::{is_lockfile, LockfileMode};
+use digest::logging::init_logger;
+use digest::manifest::Manifest;
+use digest::models::{find_model, ModelSpec, MODELS};
+use digest::open::open;
+use digest::paths::{slash, strip_root};
+use digest::pii::{scan_pii, PiiFinding};
+use digest::policy::Policy;
+use digest::presets::{find_preset, PresetSpec, PRESETS};
+use digest::priority::{path_weight, rank_files, PathWeight};
+use digest::progress::Progress;
+use digest::projects::{detect_project_types, has_type, ProjectType, GODOT};
+use digest::reader::read_utf8;
+use digest::schema::{json_schema, SCHEMA_VERSION};
+use digest::split::{manifest_path, pack_parts, part_path};
+use digest::stats::{finish_breakdown, included_breakdown, LanguageStats};
+use digest::submodules::{read_submodules, SubmoduleMode};
+use digest::suggestions::{append_suggestions, format_suggestions, suggest_ignores};
+use digest::summary::{exclusion_counts, generated_at, DigestSummary, GENERATED_BY, TOOL_VERSION};
+use digest::symbol::select_symbol_files;
+use digest::template::render_template;
+use digest::terraform::redact_terraform;
+use digest::test_paths::is_test_path;
+use digest::tokens::estimate_tokens;
+use digest::tree::{render_tree, render_tree_with_totals, Totals};
+use digest::update::update_document;
+use digest::{
+    annotate_file_links, annotate_git_info, collect_files_with_progress, count_all_files,
+    invalid_ignore_lines, parse_ignore_line, CollectOptions, Digest, DigestPart, FileInfo,
+    FilterCounts,
+};
+use log::{debug, info};
+use regex::Regex;
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
+use std::ffi::OsString;
 use std::fs;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 use tokei::{Config, Languages};
 
 #[derive(Parser, Debug)]
 #[clap(
     name = "digest",
     about = "Creates a digest of a codebase for LLM consumption",
-    version
+    version,
+    args_conflicts_with_subcommands = true,
+    args_override_self = true
 )]
 struct Cli {
-    /// The path to the project directory (defaults to current directory)
-    #[clap(index = 1)]
+    #[clap(subcommand)]
+    command: Option<Commands>,
+
+    #[clap(flatten)]
+    digest: DigestArgs,
+
+    /// How errors are printed to stderr (json for scripts)
+    #[clap(
+        long,
+        value_enum,
+        global = true,
+        default_value = "text",
+        value_name = "FORMAT"
+    )]
+    error_format: ErrorFormat,
+
+    /// How log records are printed to stderr (json adds path, pattern, and reason fields); set levels with RUST_LOG
+    #[clap(
+        long,
+        value_enum,
+        global = true,
+        default_value = "text",
+        value_name = "FORMAT"
+    )]
+    log_format: LogFormat,
+}
+
+/// Options shared by the default digest command and `digest symbol`
+#[derive(Args, Debug, Clone)]
+struct DigestArgs {
+    /// The path to the project directory or a .zip/.tar/.tar.gz archive (defaults to current directory)
     project_path: Option<PathBuf>,
 
     /// Maximum number of files to include in the digest
@@ -28,17 +126,29 @@ struct Cli {
     #[clap(short = 's', long, default_value = "500")]
     max_file_size: u64,
 
-    /// Output format (json or markdown)
-    #[clap(short, long, default_value = "markdown")]
-    format: String,
+    /// Output format: markdown, json, or exec:COMMAND to pipe the JSON digest through an external formatter
+    #[clap(short, long, default_value = "markdown", value_parser = parse_format)]
+    format: Format,
+
+    /// Organize the Markdown Files section under a subheading per directory or per language
+    #[clap(long, value_enum, value_name = "GROUPING", default_value = "none")]
+    group_by: Grouping,
 
     /// Output file (defaults to stdout)
     #[clap(short, long)]
     output: Option<PathBuf>,
 
-    /// List files that would be included without generating the digest
-    #[clap(short, long)]
-    list: bool,
+    /// List files that would be included without generating the digest (--list=detailed adds sizes, tokens, and cuts)
+    #[clap(
+        short,
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "paths"
+    )]
+    list: Option<ListMode>,
 
     /// Disable using .gitignore for ignore patterns
     #[clap(long)]
@@ -48,171 +158,2690 @@ struct Cli {
     #[clap(long)]
     no_digestignore: bool,
 
-    /// Disable all ignore patterns (both .gitignore and .digestignore)
+    /// Don't apply the built-in ignore defaults for the project's languages, project types, and
+    /// frameworks (node_modules, target, .DS_Store, test data, ...)
+    #[clap(long)]
+    no_default_ignores: bool,
+
+    /// Keep test snapshots, fixtures, golden files, and HTTP cassettes, which the defaults leave
+    /// out
+    #[clap(long)]
+    include_test_data: bool,
+
+    /// Disable plain .ignore files (as used by ripgrep and fd)
+    #[clap(long)]
+    no_dot_ignore: bool,
+
+    /// Disable the global gitignore (core.excludesFile)
+    #[clap(long)]
+    no_global_gitignore: bool,
+
+    /// Disable .git/info/exclude
+    #[clap(long)]
+    no_git_exclude: bool,
+
+    /// Don't read default options from the project's digest.toml
+    #[clap(long)]
+    no_config: bool,
+
+    /// Disable all ignore patterns (.gitignore, .digestignore, .ignore, and git excludes)
     #[clap(long)]
     no_ignore: bool,
 
-    /// Additional patterns to ignore (can be specified multiple times)
+    /// Whether to collect hidden files and directories; `exclude` still keeps well-known
+    /// configuration such as .github workflows
+    #[clap(long, value_enum, value_name = "POLICY", default_value = "exclude")]
+    hidden: Hidden,
+
+    /// Whether to digest the git submodules declared in .gitmodules along with the project, leave
+    /// them out, or digest only them; their files are listed in a section per submodule
+    #[clap(long, value_enum, value_name = "MODE", default_value = "include")]
+    submodules: Submodules,
+
+    /// Whether to digest lockfiles (Cargo.lock, package-lock.json, poetry.lock, ...), leave them
+    /// out, or list each with its package count and hash instead of its content
+    #[clap(long, value_enum, value_name = "MODE", default_value = "exclude")]
+    lockfiles: Lockfiles,
+
+    /// Follow symlinked files and directories (loops and duplicate files are skipped)
+    #[clap(long)]
+    follow_symlinks: bool,
+
+    /// Replace Godot .tscn scenes with a summary of their node tree, attached scripts, and
+    /// signal connections instead of the full serialized resource
+    #[clap(long)]
+    scene_summary: bool,
+
+    /// List images, binaries, and files over --max-file-size by path and size in a "Skipped
+    /// Files" section, without their content
+    #[clap(long)]
+    mention_skipped: bool,
+
+    /// Keep JavaScript and CSS that looks minified or bundled (long lines, a sourceMappingURL
+    /// comment, or a bundler's runtime), which is otherwise left out
+    #[clap(long)]
+    include_minified: bool,
+
+    /// Collect only documentation, for "explain this project" prompts: READMEs, docs/ and ADR
+    /// directories, and the doc comments of source files with the items they document
+    #[clap(long)]
+    docs_only: bool,
+
+    /// Leave out tests: files under test/, tests/, spec/, or __tests__/, and names such as
+    /// *_test.*, *.test.*, *.spec.*, or test_*.py
+    #[clap(long, conflicts_with = "tests_only")]
+    exclude_tests: bool,
+
+    /// Collect only the tests that --exclude-tests would leave out
+    #[clap(long)]
+    tests_only: bool,
+
+    /// Reduce Rust files to their public API, for "write client code against this crate"
+    /// prompts: pub items as signatures with their doc comments; other files are left out
+    #[clap(long)]
+    public_api: bool,
+
+    /// Link each file heading to the file on the repository's web UI (GitHub, GitLab,
+    /// Bitbucket, ...) at the current commit; needs a git remote
+    #[clap(long)]
+    link_files: bool,
+
+    /// Embed PNG, JPEG, GIF, and WebP images up to this size in KB, as base64 in JSON and as
+    /// data URIs in Markdown, for models that accept images (SVG is included as source)
+    #[clap(long, value_name = "KB")]
+    embed_images: Option<u64>,
+
+    /// Additional patterns to ignore (can be specified multiple times); `!pattern` keeps files
+    /// the defaults or .digestignore leave out
     #[clap(long = "ignore-pattern", value_name = "PATTERN")]
     ignore_patterns: Vec<String>,
+
+    /// Validate --ignore-pattern values and the project's ignore files, then exit without
+    /// collecting files
+    #[clap(long)]
+    check_patterns: bool,
+
+    /// Only include files whose contents match this regex (can be specified multiple times)
+    #[clap(long, value_name = "PATTERN")]
+    grep: Vec<String>,
+
+    /// Exclude files whose contents match this regex (can be specified multiple times)
+    #[clap(long, value_name = "PATTERN")]
+    grep_exclude: Vec<String>,
+
+    /// Encrypt the output to an age recipient, e.g. age:age1... or 'age:ssh-ed25519 AAAA...'
+    /// (repeatable; runs age or rage, which must be installed)
+    #[clap(
+        long,
+        value_name = "age:RECIPIENT",
+        value_parser = Recipient::parse,
+        conflicts_with = "check"
+    )]
+    encrypt: Vec<Recipient>,
+
+    /// A data-egress policy to enforce on top of the project's digest.policy.toml
+    #[clap(long, value_name = "PATH")]
+    policy: Option<PathBuf>,
+
+    /// Scan collected files for sensitive data before it reaches the digest
+    #[clap(long, value_enum, value_name = "KIND")]
+    scan: Option<Scan>,
+
+    /// What --scan does with what it finds
+    #[clap(
+        long,
+        value_enum,
+        default_value = "report",
+        value_name = "ACTION",
+        requires = "scan"
+    )]
+    scan_action: ScanAction,
+
+    /// Annotate each file and the file index with estimated cyclomatic complexity and nesting depth
+    #[clap(long)]
+    complexity: bool,
+
+    /// Report blocks of code repeated across the included files in a Duplicate Code section,
+    /// counting blocks of at least MIN_LINES lines (default 6)
+    #[clap(
+        long,
+        value_name = "MIN_LINES",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "6"
+    )]
+    duplicates: Option<usize>,
+
+    /// Also include files importing or imported by the --grep or symbol matches, up to this
+    /// many hops away in the import graph
+    #[clap(long, value_name = "HOPS")]
+    expand_context: Option<usize>,
+
+    /// Annotate each file with its last commit, author, and age from git
+    #[clap(long)]
+    git_file_info: bool,
+
+    /// Rank files before applying --max-files instead of keeping walk order
+    #[clap(long, value_enum, value_name = "STRATEGY")]
+    prioritize: Option<Prioritize>,
+
+    /// Compute the language breakdown from the included files or the whole tree
+    #[clap(long, value_enum, default_value = "included", value_name = "SCOPE")]
+    breakdown: Breakdown,
+
+    /// Months of git history considered by --prioritize churn
+    #[clap(long, default_value = "6", value_name = "MONTHS")]
+    churn_months: u32,
+
+    /// Prepend task instructions and rank matching files first (code-review, onboarding, security-audit)
+    #[clap(long, value_name = "PRESET", value_parser = parse_preset)]
+    preset: Option<&'static PresetSpec>,
+
+    /// Rank files matching a glob by a weight before --max-files cuts the list, e.g.
+    /// 'src/core/**=10' or 'examples/**=-5'; the weights of matching rules add up (repeatable)
+    #[clap(long, value_name = "PATTERN=WEIGHT", value_parser = PathWeight::parse)]
+    priority: Vec<PathWeight>,
+
+    /// How --list prints files: text, json, lines (paths only), or nul (NUL-terminated paths for xargs -0)
+    #[clap(
+        long,
+        value_enum,
+        default_value = "text",
+        value_name = "FORMAT",
+        requires = "list"
+    )]
+    list_format: ListFormat,
+
+    /// Leave out the generation timestamp and timing so identical inputs give identical output
+    #[clap(long)]
+    no_timestamp: bool,
+
+    /// Leave out the git branch, commit, worktree state, and remote, which change with every
+    /// commit, e.g. for a digest committed alongside the code
+    #[clap(long)]
+    no_git_info: bool,
+
+    /// Compare the digest with the existing --output file instead of writing it, and exit
+    /// with code 7 when they differ
+    #[clap(
+        long,
+        requires = "output",
+        conflicts_with_all = ["split_tokens", "per_directory", "list"]
+    )]
+    check: bool,
+
+    /// Write a SHA-256 sidecar (`<output>.sha256`) next to each output file, so archived digests
+    /// can be verified, and list the SHA-256 of each file's digested content in the Markdown
+    /// footer
+    #[clap(long)]
+    checksum: bool,
+
+    /// Write an empty digest and exit successfully when no files are left, instead of failing
+    /// with exit code 3
+    #[clap(long)]
+    allow_empty: bool,
+
+    /// Fail instead of warning when --max-files leaves eligible files out
+    #[clap(long)]
+    strict: bool,
+
+    /// Report files left out by --max-files, budget overruns, and errors as GitHub Actions
+    /// annotations, and write digest metrics to the step's GITHUB_OUTPUT
+    #[clap(long)]
+    github_annotations: bool,
+
+    /// Estimate input cost and context-window fit for a model (e.g. claude-sonnet, gpt-4o)
+    #[clap(long, value_name = "MODEL", value_parser = parse_model)]
+    model: Option<&'static ModelSpec>,
+
+    /// Exit with an error instead of a warning when the digest exceeds the model's context window
+    #[clap(long, requires = "model")]
+    fail_if_over_budget: bool,
+
+    /// Print the N files contributing the most tokens, with ignore patterns that would drop them
+    #[clap(long, value_name = "N")]
+    top_files: Option<usize>,
+
+    /// Suggest .digestignore patterns for included lockfiles, snapshots, fixtures, and bulk data
+    #[clap(long)]
+    suggest_ignores: bool,
+
+    /// Append the suggested patterns to the project's .digestignore (implies --suggest-ignores)
+    #[clap(long)]
+    apply_suggestions: bool,
+
+    /// Exit with an error if the digest exceeds this size, in tokens (100000, 100k) or bytes (500KB, 2MB)
+    #[clap(long, value_name = "LIMIT")]
+    fail_if_over: Option<Budget>,
+
+    /// Split the output into numbered parts of at most this many tokens (requires --output)
+    #[clap(long, value_name = "TOKENS", requires = "output")]
+    split_tokens: Option<usize>,
+
+    /// Wrap the digest in a handlebars prompt template ({{project_name}}, {{tree}}, {{digest}}, {{#each files}})
+    #[clap(long, value_name = "PATH")]
+    template: Option<PathBuf>,
+
+    /// Write one digest per top-level directory into this directory, each with its own --max-files budget
+    #[clap(long, value_name = "DIR", conflicts_with_all = ["output", "split_tokens", "list"])]
+    per_directory: Option<PathBuf>,
+
+    /// Update this Markdown digest in place, keeping the sections of files unchanged since it was written
+    #[clap(long, value_name = "PATH", conflicts_with_all = ["output", "check", "split_tokens", "per_directory", "list", "encrypt"])]
+    update: Option<PathBuf>,
+
+    /// Also write a JSON manifest of the included files, their hashes and token counts, and the effective options
+    #[clap(long, conflicts_with_all = ["check", "split_tokens", "per_directory", "list"])]
+    manifest: bool,
+
+    /// Every option's value once digest.toml and DIGEST_* variables are applied, for --manifest
+    #[clap(skip)]
+    config: BTreeMap<String, serde_json::Value>,
+
+    /// Print the digest to a terminal even when it's over the size digest refuses to print there
+    #[clap(long)]
+    force: bool,
+
+    /// Open the output file once it's written: HTML in $BROWSER, anything else in $VISUAL or
+    /// $EDITOR, falling back to the system's default application
+    #[clap(long, requires = "output", conflicts_with_all = ["check", "encrypt"])]
+    open: bool,
+
+    /// Stable output for scripts: no logging, warnings, or progress; only the digest, or with
+    /// --list a single JSON summary line
+    #[clap(long, conflicts_with = "list_format")]
+    porcelain: bool,
 }
 
-#[derive(Serialize, Debug)]
-pub struct FileInfo {
-    pub path: String,
-    pub language: Option<String>,
-    pub content: String,
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Compare two JSON digests and report added, removed, and changed files
+    Diff {
+        /// The older JSON digest
+        old: PathBuf,
+
+        /// The newer JSON digest
+        new: PathBuf,
+
+        /// Print the comparison as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Write a starter .digestignore and digest.toml for the detected project type
+    Init {
+        /// The project directory (defaults to current directory)
+        project_path: Option<PathBuf>,
+
+        /// Overwrite existing files
+        #[clap(long)]
+        force: bool,
+    },
+    /// Check digest.toml and the ignore files for mistakes and conflicting patterns
+    Doctor {
+        /// The project directory (defaults to current directory)
+        project_path: Option<PathBuf>,
+    },
+    /// List the ignore patterns a digest run would apply, in layer order, with where each came
+    /// from
+    Patterns {
+        /// List only the defaults digest adds itself, without the ignore files or --ignore-pattern
+        #[clap(long)]
+        only_defaults: bool,
+
+        /// Print the patterns as JSON
+        #[clap(long)]
+        json: bool,
+
+        #[clap(flatten)]
+        digest: Box<DigestArgs>,
+    },
+    /// Preview the files a digest would include as a tree, with lines and tokens per directory
+    Tree {
+        #[clap(flatten)]
+        digest: Box<DigestArgs>,
+    },
+    /// Print a shell completion script (e.g. `digest completions bash > /etc/bash_completion.d/digest`)
+    Completions {
+        /// The shell to generate completions for
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+    /// Install or run git hooks that keep a committed digest current
+    Hook {
+        #[clap(subcommand)]
+        command: HookCommand,
+    },
+    /// Print the JSON Schema of the --format json output
+    Schema,
+    /// Print single functions, types, or impl blocks with the imports they use, selected as
+    /// FILE::ITEM (e.g. src/engine.rs::Engine::tick); only Rust (.rs) and Python (.py) files are
+    /// supported
+    Extract {
+        /// Items to extract, e.g. src/engine.rs::Engine::tick or app/models.py::User
+        #[clap(required = true, value_name = "FILE::ITEM", value_parser = Selector::parse)]
+        selectors: Vec<Selector>,
+
+        /// Print the extracts as JSON
+        #[clap(long)]
+        json: bool,
+
+        /// A data-egress policy to enforce on top of the current directory's digest.policy.toml
+        #[clap(long, value_name = "PATH")]
+        policy: Option<PathBuf>,
+    },
+    /// Digest only the files that define or reference a symbol
+    Symbol {
+        /// The symbol name (struct, function, class, ...) to look for
+        name: String,
+
+        #[clap(flatten)]
+        digest: Box<DigestArgs>,
+    },
 }
 
-#[derive(Serialize, Debug)]
-struct Digest {
-    project_name: String,
-    main_language: Option<String>,
-    language_breakdown: HashMap<String, usize>,
-    files: Vec<FileInfo>,
+#[derive(Subcommand, Debug)]
+enum HookCommand {
+    /// Install a git hook that regenerates the digest and stages it with each commit, or
+    /// checks that the committed digest is current
+    Install {
+        /// The project directory (defaults to current directory)
+        project_path: Option<PathBuf>,
+
+        /// The hook to install
+        #[clap(long, value_enum, default_value = "pre-commit")]
+        hook: HookName,
+
+        /// Fail when the digest is out of date instead of regenerating it (always the case
+        /// for pre-push, which runs after the commit is made)
+        #[clap(long)]
+        check: bool,
+
+        /// The committed digest, relative to the project directory
+        #[clap(long, value_name = "PATH", default_value = "digest.md")]
+        output: PathBuf,
+
+        /// Replace an existing hook that digest didn't install
+        #[clap(long)]
+        force: bool,
+    },
+    /// Exit with code 7 when the committed digest doesn't match a fresh one
+    Check {
+        /// The project directory (defaults to current directory)
+        project_path: Option<PathBuf>,
+
+        /// The committed digest, relative to the project directory
+        #[clap(long, value_name = "PATH", default_value = "digest.md")]
+        output: PathBuf,
+    },
 }
 
-fn main() -> Result<()> {
-    env_logger::init();
-    let cli = Cli::parse();
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum HookName {
+    /// Runs before each commit is made
+    PreCommit,
+    /// Runs before each push
+    PrePush,
+}
 
-    // Determine project path
-    let project_path = match cli.project_path {
-        Some(path) => path,
-        None => env::current_dir()?,
-    };
+impl From<HookName> for HookKind {
+    fn from(name: HookName) -> Self {
+        match name {
+            HookName::PreCommit => HookKind::PreCommit,
+            HookName::PrePush => HookKind::PrePush,
+        }
+    }
+}
 
-    info!("Analyzing project at: {}", project_path.display());
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Format {
+    /// Headings, tables, and fenced code blocks
+    Markdown,
+    /// The digest structure as pretty-printed JSON
+    Json,
+    /// The JSON digest piped through an external command, whose stdout becomes the output
+    Exec(String),
+}
 
-    // Check if it's a Godot project
-    let is_godot_project = is_godot_project(&project_path);
-    if is_godot_project {
-        info!("Detected Godot project");
+impl Format {
+    /// The format as --format names it
+    fn name(&self) -> String {
+        match self {
+            Format::Markdown => "markdown".to_string(),
+            Format::Json => "json".to_string(),
+            Format::Exec(command) => format!("exec:{}", command),
+        }
     }
 
-    // Check if it's a Lua project
-    let is_lua_project = is_lua_project(&project_path);
-    if is_lua_project {
-        info!("Detected Lua project");
+    fn formatter(
+        &self,
+        group_by: GroupBy,
+        checksums: bool,
+        markers: bool,
+    ) -> Box<dyn DigestFormatter> {
+        match self {
+            Format::Markdown => Box::new(MarkdownFormatter {
+                group_by,
+                checksums,
+                markers,
+            }),
+            Format::Json => Box::new(JsonFormatter),
+            Format::Exec(command) => Box::new(ExecFormatter {
+                command: command.clone(),
+            }),
+        }
     }
+}
 
-    // Step 1: Determine the predominant language
-    let languages = detect_languages(&project_path)?;
-    let language_breakdown = get_language_breakdown(&languages);
-    let main_language = get_main_language(&language_breakdown);
+fn parse_format(value: &str) -> Result<Format, String> {
+    match value {
+        "markdown" => Ok(Format::Markdown),
+        "json" => Ok(Format::Json),
+        _ => match value.strip_prefix("exec:") {
+            Some(command) => match shlex::split(command) {
+                Some(words) if !words.is_empty() => Ok(Format::Exec(command.to_string())),
+                Some(_) => Err("exec: needs a command, e.g. exec:./my_formatter".to_string()),
+                None => Err(format!("exec:{} has an unbalanced quote", command)),
+            },
+            None => Err(format!(
+                "unknown format '{}' (expected markdown, json, or exec:COMMAND)",
+                value
+            )),
+        },
+    }
+}
 
-    debug!("Main language detected: {:?}", main_language);
-    debug!("Language breakdown: {:?}", language_breakdown);
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Grouping {
+    /// One flat list of files
+    None,
+    /// A subheading per directory
+    Dir,
+    /// A subheading per language
+    Language,
+}
 
-    // Step 2: Get ignore patterns from .digestignore, .gitignore, or defaults
-    let mut ignore_patterns = HashSet::new();
+impl From<Grouping> for GroupBy {
+    fn from(grouping: Grouping) -> Self {
+        match grouping {
+            Grouping::None => GroupBy::None,
+            Grouping::Dir => GroupBy::Dir,
+            Grouping::Language => GroupBy::Language,
+        }
+    }
+}
 
-    // Don't process any ignore files if --no-ignore is used
-    if !cli.no_ignore {
-        // Try to get patterns from .digestignore, unless --no-digestignore is used
-        let using_digestignore = if !cli.no_digestignore {
-            match check_for_digestignore(&project_path) {
-                Ok(digestignore_patterns) => {
-                    ignore_patterns.extend(digestignore_patterns);
-                    true
-                }
-                Err(_) => {
-                    debug!("No .digestignore file found.");
-                    false
-                }
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ListMode {
+    /// One path per included file
+    Paths,
+    /// Every candidate with size, estimated tokens, language, and whether limits cut it
+    Detailed,
+    /// The included files as a directory tree, with lines and tokens rolled up per directory
+    Tree,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Scan {
+    /// Emails, phone numbers, US SSNs, UK National Insurance numbers, card numbers, and IBANs
+    Pii,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ScanAction {
+    /// List what was found, and where, in the digest and on stderr
+    Report,
+    /// Also replace each value with a <redacted:kind> marker
+    Redact,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ListFormat {
+    /// Human-readable list or table
+    Text,
+    /// JSON array with path, language, bytes, tokens, and whether each file is included
+    Json,
+    /// One path per line
+    Lines,
+    /// Paths terminated by NUL bytes, safe for any file name
+    Nul,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ErrorFormat {
+    /// Human-readable message with its causes
+    Text,
+    /// One JSON object with kind, exit code, message, and causes
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LogFormat {
+    /// env_logger's plain text lines
+    Text,
+    /// One JSON object per record with level, target, message, and structured fields
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Breakdown {
+    /// Lines of the files included in the digest
+    Included,
+    /// Lines of every file tokei finds in the project, including ignored ones
+    Full,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Hidden {
+    /// Collect dotfiles and dot-directories like any other file
+    Include,
+    /// Skip them, except well-known configuration (.github, .gitlab-ci.yml, .cargo, ...)
+    Exclude,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Submodules {
+    /// Digest the project and its submodules
+    Include,
+    /// Leave the submodule directories out
+    Exclude,
+    /// Digest only the files inside submodules
+    Only,
+}
+
+impl From<Submodules> for SubmoduleMode {
+    fn from(submodules: Submodules) -> Self {
+        match submodules {
+            Submodules::Include => SubmoduleMode::Include,
+            Submodules::Exclude => SubmoduleMode::Exclude,
+            Submodules::Only => SubmoduleMode::Only,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Lockfiles {
+    /// Digest lockfiles like any other file
+    Include,
+    /// Leave lockfiles out
+    Exclude,
+    /// List each lockfile with its package count and hash, without its content
+    Summary,
+}
+
+impl From<Lockfiles> for LockfileMode {
+    fn from(lockfiles: Lockfiles) -> Self {
+        match lockfiles {
+            Lockfiles::Include => LockfileMode::Include,
+            Lockfiles::Exclude => LockfileMode::Exclude,
+            Lockfiles::Summary => LockfileMode::Summary,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Prioritize {
+    /// Files with the most commits in the last --churn-months months first
+    Churn,
+    /// Files with the highest estimated cyclomatic complexity first
+    Complexity,
+}
+
+fn main() {
+    let cli = match parse_cli(env::args_os()) {
+        Ok(cli) => cli,
+        Err(err) if !err.use_stderr() => err.exit(), // --help and --version
+        Err(err) => {
+            // Arguments didn't parse, so look for --error-format json by hand
+            let args: Vec<String> = env::args().collect();
+            let json = args.iter().any(|a| a == "--error-format=json")
+                || args
+                    .windows(2)
+                    .any(|pair| pair[0] == "--error-format" && pair[1] == "json");
+            if !json {
+                err.exit();
             }
-        } else {
-            debug!("Skipping .digestignore due to --no-digestignore flag.");
-            false
-        };
+            let err = DigestError::Usage(err.to_string().trim_end().to_string()).into();
+            report_error(&err, ErrorFormat::Json);
+            std::process::exit(ErrorKind::Usage.exit_code());
+        }
+    };
 
-        // Try to get patterns from .gitignore, unless --no-gitignore is used
-        let using_gitignore = if !cli.no_gitignore {
-            match check_for_gitignore(&project_path) {
-                Ok(gitignore_patterns) => {
-                    ignore_patterns.extend(gitignore_patterns);
-                    true
-                }
-                Err(_) => {
-                    debug!("No .gitignore file found.");
-                    false
-                }
+    let cli = match apply_config(cli, env::args_os().collect()) {
+        Ok(cli) => cli,
+        Err(err) => {
+            report_error(&err, ErrorFormat::Text);
+            std::process::exit(classify(err.as_ref()).exit_code());
+        }
+    };
+
+    let porcelain = match &cli.command {
+        Some(Commands::Symbol { digest, .. } | Commands::Tree { digest }) => digest.porcelain,
+        Some(_) => false,
+        None => cli.digest.porcelain,
+    };
+    // CI logs are read after the fact: keep them to the digest and warnings unless RUST_LOG
+    // asks for more
+    let quiet = porcelain || (is_ci() && env::var_os("RUST_LOG").is_none());
+    init_logger(cli.log_format == LogFormat::Json, quiet);
+
+    let result = match &cli.command {
+        Some(command) => run_command(command),
+        None => run_digest(&cli.digest, None),
+    };
+
+    if let Err(err) = result {
+        let annotations = match &cli.command {
+            Some(Commands::Symbol { digest, .. } | Commands::Tree { digest }) => {
+                digest.github_annotations
             }
-        } else {
-            debug!("Skipping .gitignore due to --no-gitignore flag.");
-            false
+            Some(_) => false,
+            None => cli.digest.github_annotations,
+        };
+        if annotations {
+            eprintln!(
+                "{}",
+                annotation("error", None, "digest", &format!("{:#}", err))
+            );
+        }
+        report_error(&err, cli.error_format);
+        std::process::exit(classify(err.as_ref()).exit_code());
+    }
+}
+
+/// Re-parse the command line `args` with the options from the project's digest.toml and the
+/// DIGEST_* environment variables in front, so the environment overrides digest.toml and flags
+/// given on the command line override both. For `symbol`, `tree`, and `patterns` they go right
+/// after the subcommand, where its digest options are parsed.
+fn apply_config(cli: Cli, mut args: Vec<OsString>) -> Result<Cli> {
+    let (digest, subcommand) = match &cli.command {
+        None => (&cli.digest, None),
+        Some(Commands::Symbol { digest, .. }) => (&**digest, Some("symbol")),
+        Some(Commands::Tree { digest }) => (&**digest, Some("tree")),
+        Some(Commands::Patterns { digest, .. }) => (&**digest, Some("patterns")),
+        Some(_) => return Ok(with_effective_config(cli, &args)),
+    };
+    let env_args = env_args(&env_options())?;
+    let no_config = digest.no_config || env_args.iter().any(|arg| arg == "--no-config");
+    let config_args = if no_config {
+        Vec::new()
+    } else {
+        let project_path = match &digest.project_path {
+            Some(path) => path.clone(),
+            None => env::current_dir()?,
         };
+        load_config_args(&project_path)?.unwrap_or_default()
+    };
+    if config_args.is_empty() && env_args.is_empty() {
+        return Ok(with_effective_config(cli, &args));
+    }
+    if !config_args.is_empty() {
+        debug!("Options from {}: {}", CONFIG_FILE, config_args.join(" "));
+    }
+    if !env_args.is_empty() {
+        debug!("Options from the environment: {}", env_args.join(" "));
+    }
 
-        // If no ignore files were found or used, use default patterns
-        if ignore_patterns.is_empty() {
-            info!("No ignore files found or used. Using default ignore patterns.");
-            ignore_patterns = build_ignore_patterns(&main_language, is_godot_project);
-        } else {
-            let mut ignore_sources = Vec::new();
-            if using_digestignore {
-                ignore_sources.push(".digestignore");
+    let source = match (config_args.is_empty(), env_args.is_empty()) {
+        (false, true) => CONFIG_FILE.to_string(),
+        (true, false) => format!("{}* environment variables", ENV_PREFIX),
+        _ => format!("{} or {}* environment variables", CONFIG_FILE, ENV_PREFIX),
+    };
+    // Only global options such as --error-format can come before the subcommand
+    let insert_at = match subcommand {
+        Some(name) => args
+            .iter()
+            .position(|arg| arg == name)
+            .map_or(1, |index| index + 1),
+        None => 1,
+    };
+    let given = long_options(args[insert_at..].iter().map(|arg| arg.to_string_lossy()));
+    let env_args = without_lists(env_args, &given, subcommand);
+    let given = given
+        .union(&long_options(env_args.iter().map(Into::into)))
+        .cloned()
+        .collect();
+    let config_args = without_lists(config_args, &given, subcommand);
+    args.splice(
+        insert_at..insert_at,
+        config_args.into_iter().chain(env_args).map(OsString::from),
+    );
+    let cli = parse_cli(&args).map_err(|err| {
+        let message = err.to_string();
+        // Only the first line: the rest is clap's usage and --help hint for the command line
+        let message = message.lines().next().unwrap_or_default();
+        let message = message.trim_start_matches("error: ").trim_end();
+        DigestError::Usage(format!("{} (options in {})", message, source))
+    })?;
+    Ok(with_effective_config(cli, &args))
+}
+
+/// The command-line parser, with a hidden `--no-<switch>` for every switch (`--<switch>` for one
+/// named `no-...`), so a switch turned on by digest.toml or the environment can be turned off
+/// again; whichever comes last wins
+fn cli_command() -> clap::Command {
+    let command = with_negations(Cli::command());
+    let subcommands: Vec<String> = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+    subcommands.iter().fold(command, |command, name| {
+        command.mut_subcommand(name, with_negations)
+    })
+}
+
+fn with_negations(command: clap::Command) -> clap::Command {
+    let longs: HashSet<&str> = command.get_arguments().filter_map(Arg::get_long).collect();
+    let negations: Vec<Arg> = command
+        .get_arguments()
+        .filter(|arg| matches!(arg.get_action(), ArgAction::SetTrue) && !arg.is_global_set())
+        .filter_map(|arg| {
+            let long = arg.get_long()?;
+            let negation = match long.strip_prefix("no-") {
+                Some(positive) => positive.to_string(),
+                None => format!("no-{}", long),
+            };
+            if longs.contains(negation.as_str()) {
+                return None;
             }
-            if using_gitignore {
-                ignore_sources.push(".gitignore");
+            Some(
+                Arg::new(format!("negate-{}", arg.get_id()))
+                    .long(negation)
+                    .action(ArgAction::SetTrue)
+                    .overrides_with(arg.get_id().clone())
+                    .hide(true),
+            )
+        })
+        .collect();
+    command.args(negations)
+}
+
+/// Parse `args` with [`cli_command`]
+fn parse_cli<I, T>(args: I) -> Result<Cli, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = cli_command().try_get_matches_from(args)?;
+    Cli::from_arg_matches(&matches)
+}
+
+/// The long options named in `args`, whether as `--name value` or `--name=value`
+fn long_options<'a>(args: impl Iterator<Item = std::borrow::Cow<'a, str>>) -> HashSet<String> {
+    args.take_while(|arg| arg != "--")
+        .filter_map(|arg| {
+            let name = arg.strip_prefix("--")?;
+            Some(name.split('=').next().unwrap_or(name).to_string())
+        })
+        .collect()
+}
+
+/// `args` without the repeatable options also `given` later, so a list such as
+/// `--ignore-pattern` on the command line replaces the one from digest.toml rather than adding
+/// to it
+fn without_lists(
+    args: Vec<String>,
+    given: &HashSet<String>,
+    subcommand: Option<&str>,
+) -> Vec<String> {
+    let command = Cli::command();
+    let command = match subcommand.and_then(|name| command.find_subcommand(name)) {
+        Some(subcommand) => subcommand.clone(),
+        None => command,
+    };
+    let replaced: HashSet<&str> = command
+        .get_arguments()
+        .filter(|arg| matches!(arg.get_action(), ArgAction::Append))
+        .filter_map(Arg::get_long)
+        .filter(|long| given.contains(*long))
+        .collect();
+    args.into_iter()
+        .filter(|arg| {
+            let name = arg.trim_start_matches("--");
+            !replaced.contains(name.split('=').next().unwrap_or(name))
+        })
+        .collect()
+}
+
+/// `cli` with the digest options' `config` filled in from the command line `args` it was
+/// parsed from
+fn with_effective_config(mut cli: Cli, args: &[OsString]) -> Cli {
+    let digest = match &mut cli.command {
+        Some(
+            Commands::Symbol { digest, .. }
+            | Commands::Tree { digest }
+            | Commands::Patterns { digest, .. },
+        ) => &mut **digest,
+        Some(_) => return cli,
+        None => &mut cli.digest,
+    };
+    digest.config = effective_config(args);
+    cli
+}
+
+/// Every long option of the digest run `args` parse to, with the value it ends up with:
+/// switches as booleans, repeatable options as lists, and the rest as given, or null when unset
+fn effective_config(args: &[OsString]) -> BTreeMap<String, serde_json::Value> {
+    // The arguments without the --no-<switch> negations, which only show up as the switch
+    let command = Cli::command();
+    let Ok(matches) = cli_command().try_get_matches_from(args) else {
+        return BTreeMap::new();
+    };
+    let (command, matches) = match matches.subcommand() {
+        Some((name, matches)) => match command.find_subcommand(name) {
+            Some(subcommand) => (subcommand.clone(), matches.clone()),
+            None => return BTreeMap::new(),
+        },
+        None => (command, matches),
+    };
+
+    let mut config = BTreeMap::new();
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        let id = arg.get_id().as_str();
+        let values: Vec<String> = match matches.try_get_raw(id) {
+            Ok(Some(values)) => values
+                .map(|value| value.to_string_lossy().to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let value = match arg.get_action() {
+            ArgAction::Help | ArgAction::Version => continue,
+            ArgAction::SetTrue | ArgAction::SetFalse => {
+                serde_json::Value::Bool(values.first().is_some_and(|value| value == "true"))
             }
-            info!("Using ignore patterns from: {}", ignore_sources.join(", "));
-        }
-    } else {
-        info!("Ignoring all ignore files due to --no-ignore flag.");
-        // Always ignore .git directory at minimum
-        ignore_patterns.insert(".git".to_string());
+            ArgAction::Append => serde_json::Value::from(values),
+            _ => values
+                .into_iter()
+                .next()
+                .map_or(serde_json::Value::Null, serde_json::Value::String),
+        };
+        config.insert(long.to_string(), value);
     }
+    config
+}
 
-    // Add patterns from --ignore-pattern CLI arguments
-    if !cli.ignore_patterns.is_empty() {
-        info!(
-            "Adding {} custom ignore patterns from command line",
-            cli.ignore_patterns.len()
-        );
-        for pattern in &cli.ignore_patterns {
-            ignore_patterns.insert(pattern.clone());
+/// The DIGEST_* variables that name a digest flag, as command-line arguments: a flag that
+/// takes a value gets it as `--flag=value`, and a switch is turned on by `1`, `true`, or `yes`.
+/// Variables that name no flag are left alone, since other tools may share the prefix.
+/// DIGEST_MAX_TOKENS is --fail-if-over, given in tokens only.
+fn env_args(options: &[(String, String)]) -> Result<Vec<String>> {
+    let command = cli_command();
+    let mut args = Vec::new();
+    for (name, value) in options {
+        let name = if name == "max-tokens" {
+            if !matches!(value.parse(), Ok(Budget::Tokens(_))) {
+                return Err(DigestError::Usage(format!(
+                    "{}MAX_TOKENS={} is not a token count such as 100000 or 100k; \
+                     set {}FAIL_IF_OVER for a limit in bytes",
+                    ENV_PREFIX, value, ENV_PREFIX
+                ))
+                .into());
+            }
+            "fail-if-over"
+        } else {
+            name.as_str()
+        };
+        let Some(arg) = command.get_arguments().find(|arg| {
+            arg.get_long() == Some(name)
+                || arg
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&name))
+        }) else {
+            debug!(
+                "Ignoring {}{}: no such option",
+                ENV_PREFIX,
+                name.to_uppercase().replace('-', "_")
+            );
+            continue;
+        };
+        if arg.get_action().takes_values() {
+            args.push(format!("--{}={}", name, value));
+        } else if is_truthy(value) {
+            args.push(format!("--{}", name));
         }
     }
+    Ok(args)
+}
 
-    // Step 3: Collect relevant files
-    let files = collect_relevant_files(
-        &project_path,
-        &ignore_patterns,
-        cli.max_files,
-        cli.max_file_size * 1024, // Convert KB to bytes
-        is_godot_project,
-        !cli.no_gitignore && !cli.no_ignore, // Respect gitignore unless disabled
-    )?;
+fn report_error(err: &anyhow::Error, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {:?}", err),
+        ErrorFormat::Json => match serde_json::to_string(&ErrorReport::new(err.as_ref())) {
+            Ok(json) => eprintln!("{}", json),
+            Err(_) => eprintln!("Error: {:?}", err),
+        },
+    }
+}
 
-    info!("Found {} relevant files", files.len());
+/// Build and write a digest; with `symbol`, only files defining or referencing it are kept
+fn run_digest(cli: &DigestArgs, symbol: Option<&str>) -> Result<()> {
+    let started = Instant::now();
+    let renderer = Renderer::new(cli)?;
+    if cli.update.is_some() && cli.format != Format::Markdown {
+        return Err(DigestError::Usage(
+            "--update works on Markdown digests; drop --format or use --output".to_string(),
+        )
+        .into());
+    }
 
-    // If list option is specified, just print the file paths and exit
-    if cli.list {
-        println!("Files that would be included in the digest:");
-        for file in &files {
-            println!("{}", file.path);
-        }
-        return Ok(());
+    // Determine project path
+    let project_path = match cli.project_path.clone() {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    validate_cli_patterns(cli)?;
+    if cli.check_patterns {
+        return check_patterns(cli, &project_path);
     }
 
-    // Step 4: Create the digest
-    let project_name = project_path
+    let mut project_name = project_path
         .file_name()
         .and_then(|name| name.to_str())
         .unwrap_or("unknown")
         .to_string();
 
-    let digest = Digest {
-        project_name,
-        main_language: main_language.clone(),
+    // Archives are unpacked to a temporary directory that lives until the end of the run
+    let extracted = match archive_kind(&project_path) {
+        Some(kind) => {
+            info!("Extracting archive {}", project_path.display());
+            Some(extract_archive(&project_path, kind)?)
+        }
+        None => None,
+    };
+    let project_path = match &extracted {
+        Some(archive) => {
+            project_name = archive.name.clone();
+            archive.root.clone()
+        }
+        None => project_path,
+    };
+
+    info!("Analyzing project at: {}", project_path.display());
+
+    let policy = load_policy(cli.policy.as_deref(), &project_path)?;
+
+    // Steps 1 and 2: the project types, frameworks, and languages, and the ignore patterns
+    // they call for
+    let ResolvedProject {
+        project_types,
+        frameworks,
         language_breakdown,
-        files,
+        ignores,
+    } = resolve_project(cli, &project_path)?;
+    let main_language = get_main_language(&language_breakdown);
+    let is_godot_project = has_type(&project_types, GODOT);
+
+    // The main scene and autoloads of a Godot project, and the scripts they attach
+    let godot = if is_godot_project {
+        read_godot_project(&project_path)
+    } else {
+        None
+    };
+
+    let submodules = read_submodules(&project_path);
+    for submodule in &submodules {
+        info!("Found submodule {}", submodule.path);
+    }
+
+    let entry_points = detect_entry_points(&project_path);
+    debug!("Found {} entry points", entry_points.len());
+
+    // Step 3: Collect relevant files
+    // When ranking or filtering, collect every eligible file and cut to --max-files afterwards
+    let collect_all = cli.prioritize.is_some()
+        || cli.preset.is_some()
+        || !cli.priority.is_empty()
+        || cli.docs_only
+        || cli.per_directory.is_some()
+        || !cli.grep.is_empty()
+        || !cli.grep_exclude.is_empty()
+        || cli.expand_context.is_some()
+        || symbol.is_some()
+        || cli.list == Some(ListMode::Detailed);
+    // A digest written into the project doesn't take in its own previous version or
+    // manifest; they're dropped after the walk, so the walk keeps slots for them
+    let mut own_outputs: Vec<PathBuf> = cli.output.iter().chain(&cli.update).cloned().collect();
+    if cli.manifest {
+        own_outputs.push(manifest_file(cli));
+    }
+    let own_outputs: Vec<String> = own_outputs
+        .iter()
+        .filter_map(|output| output_in_project(&project_path, output))
+        .collect();
+    let collect_limit = if collect_all {
+        usize::MAX
+    } else {
+        cli.max_files + own_outputs.len()
+    };
+
+    let options = CollectOptions {
+        max_files: collect_limit,
+        max_file_size: cli.max_file_size * 1024, // Convert KB to bytes
+        project_types: project_types.clone(),
+        respect_gitignore: !cli.no_gitignore && !cli.no_ignore,
+        respect_global_gitignore: !cli.no_global_gitignore && !cli.no_ignore,
+        respect_git_exclude: !cli.no_git_exclude && !cli.no_ignore,
+        respect_dot_ignore: !cli.no_dot_ignore && !cli.no_ignore,
+        submodules: submodules.clone(),
+        submodule_mode: cli.submodules.into(),
+        lockfiles: cli.lockfiles.into(),
+        follow_symlinks: cli.follow_symlinks,
+        include_hidden: cli.hidden == Hidden::Include,
+        mention_skipped: cli.mention_skipped,
+        include_minified: cli.include_minified,
+        docs_only: cli.docs_only,
+        embed_images: cli.embed_images.map(|kb| kb * 1024),
+        sort_paths: is_ci(),
+        filter: None,
+        transform: None,
+        pin: None,
+    };
+    let options = if cli.public_api {
+        options.transform(public_api_file)
+    } else {
+        options
+    };
+    let options = if cli.exclude_tests || cli.tests_only {
+        let root = project_path.clone();
+        let want_tests = cli.tests_only;
+        options.filter(move |path, _| {
+            is_test_path(path.strip_prefix(&root).unwrap_or(path)) == want_tests
+        })
+    } else {
+        options
+    };
+    // The walk stops reading at the limit, but still reads the entry points, framework key
+    // files, and Godot main scene, autoloads, and scripts, so one it reaches late can be ranked
+    // into the digest
+    let key_paths: HashSet<String> = entry_points.iter().map(|e| e.path.clone()).collect();
+    let key_frameworks = frameworks.clone();
+    let key_scenes = godot.clone();
+    let options = options.pin(move |path| {
+        key_paths.contains(path)
+            || key_frameworks
+                .iter()
+                .any(|framework| framework.score(path) > 0)
+            || key_scenes
+                .as_ref()
+                .is_some_and(|godot| godot.score(path) > 0)
+    });
+    // Output going to files leaves the terminal free for a progress bar
+    let mut progress = Progress::new(
+        !cli.porcelain && !is_ci() && (cli.output.is_some() || cli.per_directory.is_some()),
+    );
+    let collection = collect_files_with_progress(&project_path, &ignores, &options, |file| {
+        progress.file(file)
+    });
+    progress.finish();
+    let collection = collection?;
+    let collection_overflow = collection.overflow;
+    let excluded = collection.excluded;
+    let skipped = collection.skipped;
+    let images = collection.images;
+    let lockfiles = collection.lockfiles;
+    let mut filtered = collection.filtered;
+    let mut files = collection.files;
+    if !own_outputs.is_empty() {
+        let before = files.len();
+        files.retain(|file| !own_outputs.contains(&file.path.replace('\\', "/")));
+        filtered.ignored += before - files.len();
+    }
+    let collected = files.len();
+    // Files the walk kept that --grep or symbol selection then removed, and what removed them
+    let mut selected_out: Vec<(usize, String, &str)> = Vec::new();
+
+    // Sensitive Terraform variables never make it into a digest
+    redact_terraform(&project_path, &mut files);
+    policy.redact(&mut files);
+    let denied = files
+        .iter()
+        .filter(|file| policy.denied_by(&file.path).is_some())
+        .count();
+
+    let pii = match cli.scan {
+        Some(Scan::Pii) => scan_pii(&mut files, cli.scan_action == ScanAction::Redact),
+        None => Vec::new(),
     };
 
-    // Step 5: Output the digest
-    output_digest(&digest, &cli.format, &cli.output)?;
+    if cli.scene_summary {
+        summarize_scenes(&mut files);
+    }
+
+    if cli.complexity || cli.prioritize == Some(Prioritize::Complexity) {
+        annotate_complexity(&mut files);
+    }
+
+    // Context expansion draws the neighbors of the selected files from everything collected
+    let seeded = !cli.grep.is_empty() || !cli.grep_exclude.is_empty() || symbol.is_some();
+    let context_pool = match cli.expand_context {
+        Some(_) if !seeded => {
+            warn_user(
+                cli,
+                "--expand-context has no seed files to expand from; give --grep or use `digest symbol`",
+            );
+            None
+        }
+        Some(_) => Some(files.clone()),
+        None => None,
+    };
+
+    if !cli.grep.is_empty() || !cli.grep_exclude.is_empty() {
+        let include = compile_patterns(&cli.grep, "--grep")?;
+        let exclude = compile_patterns(&cli.grep_exclude, "--grep-exclude")?;
+        let before = files.len();
+        filter_by_content(&mut files, &include, &exclude);
+        debug!("{} files left after content filtering", files.len());
+        selected_out.push((
+            before - files.len(),
+            "didn't match --grep/--grep-exclude".to_string(),
+            "loosen or drop --grep and --grep-exclude",
+        ));
+    }
+
+    if let Some(Prioritize::Churn) = cli.prioritize {
+        let churn = churn_counts(&project_path, cli.churn_months);
+        debug!("Churn data for {} files", churn.len());
+        rank_files(&mut files, |file| {
+            churn
+                .get(&file.path.replace('\\', "/"))
+                .copied()
+                .unwrap_or(0) as i64
+        });
+    }
+
+    if let Some(Prioritize::Complexity) = cli.prioritize {
+        rank_files(&mut files, |file| {
+            file.complexity.map_or(0, |c| c.cyclomatic as i64)
+        });
+    }
+
+    // Files central to the detected project types (scenes, descriptors) outrank churn
+    if !project_types.is_empty() {
+        rank_files(&mut files, |file| {
+            project_types
+                .iter()
+                .map(|project_type| project_type.score(&file.path))
+                .max()
+                .unwrap_or(0)
+        });
+    }
+
+    // The Godot main scene, autoloads, and their scripts outrank other project files
+    if let Some(godot) = &godot {
+        rank_files(&mut files, |file| godot.score(&file.path));
+    }
+
+    // Framework key files (settings, urls, models, views) outrank project types, entry points outrank them
+    if !frameworks.is_empty() {
+        rank_files(&mut files, |file| {
+            frameworks
+                .iter()
+                .map(|framework| framework.score(&file.path))
+                .max()
+                .unwrap_or(0)
+        });
+    }
+
+    // Entry points outrank churn but yield to presets and symbol selection
+    rank_files(&mut files, |file| {
+        let path = file.path.replace('\\', "/");
+        entry_points.iter().any(|e| e.path == path) as i64
+    });
+
+    // With --docs-only, READMEs and documentation directories come before doc comments
+    if cli.docs_only {
+        rank_files(&mut files, |file| is_doc_file(&file.path) as i64);
+    }
+
+    // Preset ranking is applied after churn and entry points so it dominates; they break ties
+    if let Some(preset) = cli.preset {
+        rank_files(&mut files, |file| preset.score(&file.path));
+    }
+
+    // Configured path weights override the built-in orderings
+    if !cli.priority.is_empty() {
+        rank_files(&mut files, |file| path_weight(&cli.priority, &file.path));
+    }
+
+    // Symbol selection ranks defining files first, ahead of any other ordering
+    if let Some(name) = symbol {
+        let before = files.len();
+        let defining = select_symbol_files(&mut files, name);
+        selected_out.push((
+            before - files.len(),
+            format!("don't define or reference '{}'", name),
+            "check the symbol's spelling and case, or run without `digest symbol`",
+        ));
+        if defining == 0 && !files.is_empty() {
+            warn_user(
+                cli,
+                &format!(
+                    "no definition found for '{}'; including {} referencing files",
+                    name,
+                    files.len()
+                ),
+            );
+        }
+        debug!(
+            "{} files define '{}', {} reference it",
+            defining,
+            name,
+            files.len() - defining
+        );
+    }
+
+    // Neighbors in the import graph follow the selected files, nearest first
+    if let (Some(hops), Some(pool)) = (cli.expand_context, context_pool) {
+        let graph = ImportGraph::build(&pool);
+        let seeds: Vec<String> = files.iter().map(|f| f.path.replace('\\', "/")).collect();
+        let seeds: Vec<&str> = seeds.iter().map(String::as_str).collect();
+        let neighbors = graph.neighborhood(&seeds, hops);
+        debug!(
+            "{} files within {} imports of {} selected files",
+            neighbors.len(),
+            hops,
+            seeds.len()
+        );
+        let mut pool: HashMap<String, FileInfo> = pool
+            .into_iter()
+            .map(|file| (file.path.replace('\\', "/"), file))
+            .collect();
+        files.extend(
+            neighbors
+                .into_iter()
+                .filter_map(|(path, _)| pool.remove(&path)),
+        );
+    }
+
+    if files.is_empty() {
+        let diagnosis = explain_empty(
+            cli,
+            &project_path,
+            &filtered,
+            collected,
+            &selected_out,
+            denied,
+        );
+        if !cli.allow_empty {
+            return Err(DigestError::NoFiles(diagnosis).into());
+        }
+        warn_user(cli, &diagnosis);
+    }
+
+    let git = git_info(&project_path);
+    if let Some(git) = &git {
+        debug!("Git HEAD: {} (dirty: {})", git.commit, git.dirty);
+    }
+
+    // Files the walk kept that --grep or symbol selection then removed
+    let content_filtered = collected - files.len();
+
+    let digest = Digest {
+        schema_version: SCHEMA_VERSION,
+        generated_by: GENERATED_BY.to_string(),
+        digest_version: TOOL_VERSION.to_string(),
+        generated_at: (!cli.no_timestamp).then(generated_at),
+        project_name,
+        part: None,
+        instructions: cli.preset.map(|preset| preset.instructions.to_string()),
+        git,
+        submodules,
+        entry_points,
+        godot,
+        main_language: main_language.clone(),
+        language_breakdown,
+        dependencies: Vec::new(),
+        excluded,
+        skipped,
+        images,
+        lockfiles,
+        duplicates: Vec::new(),
+        pii,
+        files: Vec::new(),
+        summary: None,
+    };
+
+    if let Some(out_dir) = &cli.per_directory {
+        // Checked before the per-directory --max-files cuts, so a file any group could take counts
+        policy.enforce(&files, &digest.images)?;
+        return write_per_directory_digests(
+            digest,
+            files,
+            cli,
+            &renderer,
+            &project_path,
+            out_dir,
+            started,
+        );
+    }
+
+    let left_out = collection_overflow + files.len().saturating_sub(cli.max_files);
+    let cut = files.split_off(cli.max_files.min(files.len()));
+    if cli.github_annotations {
+        annotate_left_out(cli, &project_path, &cut, collection_overflow);
+    }
+    report_truncation(cli, left_out, "")?;
+
+    info!("Found {} relevant files", files.len());
+    policy.enforce(&files, &digest.images)?;
+
+    let pii = included_findings(digest.pii.clone(), &files);
+    report_pii(cli, &pii);
+
+    if cli.git_file_info {
+        annotate_git_info(&project_path, &mut files);
+    }
+    if cli.link_files {
+        link_files(cli, &project_path, digest.git.as_ref(), &mut files);
+    }
+
+    // If list option is specified, just print the file list and exit
+    if let Some(mode) = cli.list {
+        let entries = list_entries(mode, &files, &cut);
+        if cli.porcelain {
+            println!(
+                "{}",
+                serde_json::to_string(&ListSummary::new(&entries, left_out))?
+            );
+        } else {
+            print!(
+                "{}",
+                format_list(mode, cli.list_format, &entries, cli.max_files)?
+            );
+        }
+        return Ok(());
+    }
+
+    // Step 4: Create the digest
+    let dependencies = summarize_dependencies(&project_path);
+    debug!("Found {} dependency manifests", dependencies.len());
+
+    let language_breakdown = match cli.breakdown {
+        Breakdown::Included => included_breakdown(&files),
+        Breakdown::Full => digest.language_breakdown.clone(),
+    };
+
+    let excluded_counts = exclusion_counts(&filtered, &digest.excluded, content_filtered, left_out);
+    let summary = DigestSummary::new(&files, excluded_counts, elapsed_ms(cli, started));
+    let duplicates = cli
+        .duplicates
+        .map_or_else(Vec::new, |min_lines| find_duplicates(&files, min_lines));
+    debug!("Found {} duplicated blocks", duplicates.len());
+    let digest = Digest {
+        git: digest.git.clone().filter(|_| !cli.no_git_info),
+        main_language: get_main_language(&language_breakdown),
+        language_breakdown,
+        dependencies,
+        duplicates,
+        pii,
+        files,
+        summary: Some(summary),
+        ..digest
+    };
+
+    if let Some(n) = cli.top_files {
+        if !cli.porcelain {
+            eprint!("{}", format_top_files(&top_contributors(&digest.files, n)));
+        }
+    }
+
+    if cli.suggest_ignores || cli.apply_suggestions {
+        suggest_ignore_additions(cli, &project_path, extracted.is_some(), &digest.files)?;
+    }
+
+    // Step 5: Render, check budgets, and output the digest
+    if let Some(part_budget) = cli.split_tokens {
+        return write_split_digest(digest, cli, &renderer, part_budget);
+    }
+
+    let content = renderer.render(&digest)?;
+    let checked = check_budgets(cli, &content);
+    if cli.github_annotations {
+        write_github_outputs(
+            cli,
+            digest.summary.as_ref(),
+            estimate_tokens(&content),
+            content.len(),
+            is_over_budget(cli, &content),
+        )?;
+    }
+    checked?;
+    let written = match &cli.update {
+        Some(path) => update_output(cli, &content, path)?,
+        None => write_output(cli, &content, &cli.output)?,
+    };
+    if cli.manifest {
+        write_manifest(cli, &digest, &written)?;
+    }
+    if let Some(path) = cli.output.as_ref().filter(|_| cli.open) {
+        open_output(cli, path);
+    }
+
+    Ok(())
+}
+
+/// Write `content` over the --update digest at `path`, keeping the sections of files whose
+/// content hasn't changed, and return the document. A digest that comes out the same isn't
+/// rewritten.
+fn update_output(cli: &DigestArgs, content: &str, path: &Path) -> Result<String> {
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(DigestError::io("read", path)(err).into()),
+    };
+    let (updated, summary) = update_document(&existing, content);
+    if updated == existing {
+        info!("{} is up to date", path.display());
+        return Ok(existing);
+    }
+    let written = write_output(cli, &updated, &Some(path.to_path_buf()))?;
+    info!(
+        "Updated {}: {} sections changed, {} added, {} removed, {} unchanged",
+        path.display(),
+        summary.changed,
+        summary.added,
+        summary.removed,
+        summary.unchanged
+    );
+    Ok(written)
+}
+
+/// Where --manifest goes: beside the output file, or digest.manifest.json in the current
+/// directory when the digest goes to stdout
+fn manifest_file(cli: &DigestArgs) -> PathBuf {
+    let output = cli.output.as_deref().or(cli.update.as_deref());
+    manifest_path(output.unwrap_or(Path::new("digest")))
+}
+
+/// Write the --manifest sidecar describing `digest`, as rendered into `written`
+fn write_manifest(cli: &DigestArgs, digest: &Digest, written: &str) -> Result<()> {
+    let output = cli.output.as_deref().or(cli.update.as_deref());
+    let manifest = Manifest::new(digest, written, output, &cli.format.name(), &cli.config);
+    let path = manifest_file(cli);
+    fs::write(&path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    info!("Manifest written to {}", path.display());
+    Ok(())
+}
+
+/// Launch the written digest for --open. The digest is already on disk, so a failure to
+/// launch is a warning; in CI there's no one to look at it.
+fn open_output(cli: &DigestArgs, path: &Path) {
+    if is_ci() {
+        warn_user(cli, "--open does nothing in CI");
+        return;
+    }
+    if let Err(err) = open(path) {
+        warn_user(cli, &format!("Couldn't open {}: {}", path.display(), err));
+    }
+}
+
+/// Print the --suggest-ignores patterns and, with --apply-suggestions, append them to
+/// .digestignore for the next run
+fn suggest_ignore_additions(
+    cli: &DigestArgs,
+    project_path: &Path,
+    is_archive: bool,
+    files: &[FileInfo],
+) -> Result<()> {
+    let suggestions = suggest_ignores(files);
+    if !cli.porcelain {
+        eprint!("{}", format_suggestions(&suggestions));
+    }
+    if !cli.apply_suggestions || suggestions.is_empty() {
+        return Ok(());
+    }
+    if is_archive {
+        warn_user(cli, "--apply-suggestions can't write into an archive; add the patterns to its project's .digestignore");
+        return Ok(());
+    }
+
+    let ignore_file = project_path.join(".digestignore");
+    let added = append_suggestions(&ignore_file, &suggestions)
+        .with_context(|| format!("Failed to update {}", ignore_file.display()))?;
+    if !cli.porcelain {
+        eprintln!(
+            "Added {} patterns to {}",
+            added.len(),
+            ignore_file.display()
+        );
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+struct ListEntry<'a> {
+    path: &'a str,
+    language: Option<&'a str>,
+    bytes: usize,
+    lines: usize,
+    tokens: usize,
+    included: bool,
+}
+
+/// The one-line --list output of --porcelain
+#[derive(Serialize, Debug)]
+struct ListSummary<'a> {
+    included: usize,
+    left_out: usize,
+    bytes: usize,
+    tokens: usize,
+    files: Vec<&'a str>,
+}
+
+impl<'a> ListSummary<'a> {
+    fn new(entries: &[ListEntry<'a>], left_out: usize) -> Self {
+        let included: Vec<&ListEntry> = entries.iter().filter(|e| e.included).collect();
+        ListSummary {
+            included: included.len(),
+            left_out,
+            bytes: included.iter().map(|e| e.bytes).sum(),
+            tokens: included.iter().map(|e| e.tokens).sum(),
+            files: included.iter().map(|e| e.path).collect(),
+        }
+    }
+}
+
+/// Rows for --list: the included files, or with --list=detailed every candidate by token cost
+fn list_entries<'a>(
+    mode: ListMode,
+    included: &'a [FileInfo],
+    cut: &'a [FileInfo],
+) -> Vec<ListEntry<'a>> {
+    let entry = |file: &'a FileInfo, included: bool| ListEntry {
+        path: &file.path,
+        language: file.language.as_deref(),
+        bytes: file.content.len(),
+        lines: file.content.lines().count(),
+        tokens: estimate_tokens(&file.content),
+        included,
+    };
+
+    let mut entries: Vec<ListEntry> = included.iter().map(|file| entry(file, true)).collect();
+    if mode == ListMode::Detailed {
+        entries.extend(cut.iter().map(|file| entry(file, false)));
+        entries.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.path.cmp(b.path)));
+    }
+    entries
+}
+
+fn format_list(
+    mode: ListMode,
+    format: ListFormat,
+    entries: &[ListEntry],
+    max_files: usize,
+) -> Result<String> {
+    let output = match (format, mode) {
+        (ListFormat::Json, _) => serde_json::to_string_pretty(entries)? + "\n",
+        (ListFormat::Lines, _) => entries.iter().map(|e| format!("{}\n", e.path)).collect(),
+        (ListFormat::Nul, _) => entries.iter().map(|e| format!("{}\0", e.path)).collect(),
+        (ListFormat::Text, ListMode::Paths) => {
+            let mut output = String::from("Files that would be included in the digest:\n");
+            for entry in entries {
+                output.push_str(&format!("{}\n", entry.path));
+            }
+            output
+        }
+        (ListFormat::Text, ListMode::Detailed) => format_detailed_list(entries, max_files),
+        (ListFormat::Text, ListMode::Tree) => {
+            let files: Vec<(&str, Totals)> = entries
+                .iter()
+                .map(|e| {
+                    let totals = Totals {
+                        lines: e.lines,
+                        tokens: e.tokens,
+                    };
+                    (e.path, totals)
+                })
+                .collect();
+            render_tree_with_totals(&files)
+        }
+    };
+    Ok(output)
+}
+
+/// Table of every candidate file, most expensive first, marking those cut by --max-files
+fn format_detailed_list(entries: &[ListEntry], max_files: usize) -> String {
+    let mut output = format!(
+        "{:>8}  {:>9}  {:<12}  {:<8}  PATH\n",
+        "TOKENS", "BYTES", "LANGUAGE", "STATUS"
+    );
+    for entry in entries {
+        output.push_str(&format!(
+            "{:>8}  {:>9}  {:<12}  {:<8}  {}\n",
+            entry.tokens,
+            entry.bytes,
+            entry.language.unwrap_or("-"),
+            if entry.included { "included" } else { "cut" },
+            entry.path
+        ));
+    }
+
+    let included: Vec<&ListEntry> = entries.iter().filter(|e| e.included).collect();
+    output.push_str(&format!(
+        "\n{} of {} candidate files included (--max-files {}), ~{} tokens of file content\n",
+        included.len(),
+        entries.len(),
+        max_files,
+        included.iter().map(|e| e.tokens).sum::<usize>()
+    ));
+    output
+}
+
+/// Warn (or fail with --strict) when --max-files left eligible files out of the digest
+fn report_truncation(cli: &DigestArgs, left_out: usize, scope: &str) -> Result<()> {
+    if left_out == 0 {
+        return Ok(());
+    }
+    if cli.strict {
+        return Err(DigestError::BudgetExceeded(format!(
+            "--max-files {} reached{}: {} more eligible files would be left out",
+            cli.max_files, scope, left_out
+        ))
+        .into());
+    }
+    warn_user(
+        cli,
+        &format!(
+            "--max-files {} reached{}: {} more eligible files were left out (raise --max-files or narrow the selection)",
+            cli.max_files, scope, left_out
+        ),
+    );
+    Ok(())
+}
+
+/// Milliseconds since `started` for the summary, or `None` with --no-timestamp
+fn elapsed_ms(cli: &DigestArgs, started: Instant) -> Option<u64> {
+    (!cli.no_timestamp).then(|| started.elapsed().as_millis() as u64)
+}
+
+/// Why no files are left to digest, stage by stage, with a hint for the stage that left out
+/// the most. `collected` is the number of files the walk kept, `selected_out` what --grep and
+/// symbol selection removed from them, and `denied` how many of them the policy's deny_paths
+/// cover.
+fn explain_empty(
+    cli: &DigestArgs,
+    project_path: &Path,
+    filtered: &FilterCounts,
+    collected: usize,
+    selected_out: &[(usize, String, &str)],
+    denied: usize,
+) -> String {
+    let mut message = format!("No files to digest in {}", project_path.display());
+    let stages = if collected > 0 {
+        message.push_str(&format!(" ({} files collected):", collected));
+        selected_out.to_vec()
+    } else {
+        let total = count_all_files(project_path);
+        if total == 0 {
+            message.push_str(": the directory has no files");
+            return message;
+        }
+        message.push_str(&format!(" ({} files found):", total));
+        walk_stages(cli, filtered, total)
+    };
+
+    for (count, what, _) in &stages {
+        if *count > 0 {
+            message.push_str(&format!("\n  - {} {}", count, what));
+        }
+    }
+    if let Some((_, _, hint)) = stages
+        .iter()
+        .filter(|stage| stage.0 > 0)
+        .rev() // Ties go to the earlier stage
+        .max_by_key(|stage| stage.0)
+    {
+        message.push_str(&format!("\nHint: {}", hint));
+    }
+    if denied > 0 {
+        message.push_str(&format!(
+            "\nNote: the policy's deny_paths cover {} of the collected files; selecting them \
+             fails the run",
+            denied
+        ));
+    }
+    message
+}
+
+/// What each stage of the walk left out of the `total` files under the project
+fn walk_stages(
+    cli: &DigestArgs,
+    filtered: &FilterCounts,
+    total: usize,
+) -> Vec<(usize, String, &'static str)> {
+    // Whatever the walk never reached was pruned by ignore files or the hidden-file rules
+    let walker = total.saturating_sub(filtered.total());
+    let (ignored, ignored_hint) = if cli.exclude_tests || cli.tests_only {
+        (
+            "matched ignore patterns or were left out by --exclude-tests/--tests-only",
+            "drop --exclude-tests or --tests-only, or run with RUST_LOG=digest=debug to see why \
+             each file was skipped",
+        )
+    } else {
+        (
+            "matched ignore patterns",
+            "run with RUST_LOG=digest=debug to see which pattern matched each file, or check \
+             them with `digest doctor`",
+        )
+    };
+    vec![
+        (filtered.ignored, ignored.to_string(), ignored_hint),
+        (
+            filtered.not_code,
+            "aren't code files digest collects (by extension)".to_string(),
+            "add --mention-skipped to list images and binaries by path",
+        ),
+        (
+            filtered.oversized,
+            format!("exceed --max-file-size ({} KB)", cli.max_file_size),
+            "raise --max-file-size",
+        ),
+        (
+            filtered.unreadable,
+            "aren't UTF-8 text or couldn't be read".to_string(),
+            "convert the files to UTF-8",
+        ),
+        (
+            filtered.minified,
+            "look minified or bundled".to_string(),
+            "add --include-minified to keep them",
+        ),
+        (
+            filtered.undocumented,
+            "have no doc comments".to_string(),
+            "drop --docs-only to collect the code itself",
+        ),
+        (
+            walker,
+            "were left out by .gitignore, .ignore, git excludes, or as hidden files".to_string(),
+            "try --no-gitignore, --no-dot-ignore, or --hidden include",
+        ),
+    ]
+}
+
+/// The project's digest.policy.toml combined with the one given by --policy
+fn load_policy(extra: Option<&Path>, project_path: &Path) -> Result<Policy> {
+    let policy = Policy::load_project(project_path)?.unwrap_or_default();
+    Ok(match extra {
+        Some(path) => policy.merge(Policy::load(path)?),
+        None => policy,
+    })
+}
+
+/// The --scan findings in files that made it into the digest
+fn included_findings(mut findings: Vec<PiiFinding>, files: &[FileInfo]) -> Vec<PiiFinding> {
+    let included: HashSet<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    findings.retain(|finding| included.contains(finding.path.as_str()));
+    findings
+}
+
+/// Tell the user what --scan found before the digest is shared; redacted values need no warning
+fn report_pii(cli: &DigestArgs, findings: &[PiiFinding]) {
+    if findings.is_empty() {
+        return;
+    }
+    let files: HashSet<&str> = findings.iter().map(|f| f.path.as_str()).collect();
+    if findings.iter().all(|finding| finding.redacted) {
+        info!(
+            "Redacted {} personal data values in {} files",
+            findings.len(),
+            files.len()
+        );
+        return;
+    }
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for finding in findings {
+        *counts.entry(finding.kind.as_str()).or_default() += 1;
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect();
+    warn_user(
+        cli,
+        &format!(
+            "--scan pii found possible personal data in {} files ({}); see the Personal Data section, or redact it with --scan-action redact",
+            files.len(),
+            counts.join(", ")
+        ),
+    );
+}
+
+/// Print a warning for the person running digest; --porcelain silences it
+fn warn_user(cli: &DigestArgs, message: &str) {
+    if !cli.porcelain {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+/// Whether `content` is over the --model context window or the --fail-if-over limit
+fn is_over_budget(cli: &DigestArgs, content: &str) -> bool {
+    let over_model = cli
+        .model
+        .is_some_and(|model| !model.fits(estimate_tokens(content)));
+    let over_limit = cli
+        .fail_if_over
+        .is_some_and(|budget| budget.is_exceeded_by(content));
+    over_model || over_limit
+}
+
+/// Annotate each file --max-files cut for --github-annotations, at its path in the repository
+fn annotate_left_out(cli: &DigestArgs, project_path: &Path, cut: &[FileInfo], overflow: usize) {
+    let prefix = repo_prefix(project_path).unwrap_or_default();
+    let title = "Left out of the digest";
+    for file in cut {
+        let message = format!(
+            "{} was left out: --max-files {} reached",
+            file.path, cli.max_files
+        );
+        let path = format!("{}{}", prefix, file.path.replace('\\', "/"));
+        eprintln!("{}", annotation("warning", Some(&path), title, &message));
+    }
+    // Files past the limit during the walk were never read, so they have no paths to annotate
+    if overflow > 0 {
+        let message = format!(
+            "{} more eligible files were left out: --max-files {} reached",
+            overflow, cli.max_files
+        );
+        eprintln!("{}", annotation("warning", None, title, &message));
+    }
+}
+
+/// Write digest metrics as step outputs for --github-annotations
+fn write_github_outputs(
+    cli: &DigestArgs,
+    summary: Option<&DigestSummary>,
+    digest_tokens: usize,
+    digest_bytes: usize,
+    over_budget: bool,
+) -> Result<()> {
+    let Some(path) = env::var_os(GITHUB_OUTPUT) else {
+        warn_user(
+            cli,
+            &format!(
+                "{} is not set; digest metrics are not written",
+                GITHUB_OUTPUT
+            ),
+        );
+        return Ok(());
+    };
+    let mut outputs = Vec::new();
+    if let Some(summary) = summary {
+        outputs.extend([
+            ("files_included", summary.files_included.to_string()),
+            ("files_excluded", summary.files_excluded_total().to_string()),
+            ("total_lines", summary.total_lines.to_string()),
+        ]);
+    }
+    outputs.extend([
+        ("digest_tokens", digest_tokens.to_string()),
+        ("digest_bytes", digest_bytes.to_string()),
+        ("over_budget", over_budget.to_string()),
+    ]);
+    Ok(write_outputs(Path::new(&path), &outputs)?)
+}
+
+fn check_budgets(cli: &DigestArgs, content: &str) -> Result<()> {
+    if let Some(model) = cli.model {
+        check_model_budget(model, content, cli)?;
+    }
+    if let Some(budget) = cli.fail_if_over {
+        let size = budget.measure(content);
+        if size > budget.limit() {
+            return Err(DigestError::BudgetExceeded(format!(
+                "Digest is {} {}, over the --fail-if-over limit of {}",
+                size,
+                budget.unit(),
+                budget
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Where `output` sits in the project, e.g. `docs/digest.md`, or None outside it
+fn output_in_project(project_path: &Path, output: &Path) -> Option<String> {
+    let name = output.file_name()?;
+    let parent = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let output = parent.canonicalize().ok()?.join(name);
+    let relative = strip_root(&output, &project_path.canonicalize().ok()?)?;
+    Some(slash(&relative))
+}
+
+/// Top-level directory a path belongs to for --per-directory, `_root` for root files
+fn group_of(path: &str) -> String {
+    match path.replace('\\', "/").split_once('/') {
+        Some((dir, _)) => dir.to_string(),
+        None => "_root".to_string(),
+    }
+}
+
+/// Write one digest per top-level directory (files at the root are grouped as `_root`)
+fn write_per_directory_digests(
+    base: Digest,
+    files: Vec<FileInfo>,
+    cli: &DigestArgs,
+    renderer: &Renderer,
+    project_path: &Path,
+    out_dir: &Path,
+    started: Instant,
+) -> Result<()> {
+    let mut groups: BTreeMap<String, Vec<FileInfo>> = BTreeMap::new();
+    for file in files {
+        let group = group_of(&file.path);
+        groups.entry(group).or_default().push(file);
+    }
+
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    let extension = renderer.formatter.extension();
+
+    for (group, mut group_files) in groups {
+        let left_out = group_files.len().saturating_sub(cli.max_files);
+        group_files.truncate(cli.max_files);
+        report_truncation(cli, left_out, &format!(" in {}", group))?;
+        if cli.git_file_info {
+            annotate_git_info(project_path, &mut group_files);
+        }
+        if cli.link_files {
+            link_files(cli, project_path, base.git.as_ref(), &mut group_files);
+        }
+
+        // Each directory is summarized on its own; root files share the project's summary
+        let dir = if group == "_root" {
+            project_path.to_path_buf()
+        } else {
+            project_path.join(&group)
+        };
+        let language_breakdown = match cli.breakdown {
+            Breakdown::Included => included_breakdown(&group_files),
+            Breakdown::Full if group == "_root" => base.language_breakdown.clone(),
+            Breakdown::Full => get_language_breakdown(&detect_languages(&dir)?),
+        };
+        let dependencies = summarize_dependencies(&dir);
+
+        // The walk's exclusions aren't attributed to directories; only the --max-files cut is
+        let mut excluded_counts = BTreeMap::from([("max_files", left_out)]);
+        excluded_counts.retain(|_, count| *count > 0);
+        let summary = DigestSummary::new(&group_files, excluded_counts, elapsed_ms(cli, started));
+
+        let digest = Digest {
+            schema_version: SCHEMA_VERSION,
+            generated_by: base.generated_by.clone(),
+            digest_version: base.digest_version.clone(),
+            generated_at: base.generated_at.clone(),
+            project_name: format!("{}/{}", base.project_name, group),
+            part: None,
+            instructions: base.instructions.clone(),
+            git: base.git.clone().filter(|_| !cli.no_git_info),
+            submodules: base
+                .submodules
+                .iter()
+                .filter(|submodule| group_of(&submodule.path) == group)
+                .cloned()
+                .collect(),
+            entry_points: base
+                .entry_points
+                .iter()
+                .filter(|e| group_of(&e.path) == group)
+                .cloned()
+                .collect(),
+            godot: base.godot.clone().filter(|_| group == "_root"),
+            main_language: get_main_language(&language_breakdown),
+            language_breakdown,
+            dependencies,
+            excluded: base
+                .excluded
+                .iter()
+                .filter(|e| group_of(&e.path) == group)
+                .cloned()
+                .collect(),
+            skipped: base
+                .skipped
+                .iter()
+                .filter(|s| group_of(&s.path) == group)
+                .cloned()
+                .collect(),
+            images: base
+                .images
+                .iter()
+                .filter(|i| group_of(&i.path) == group)
+                .cloned()
+                .collect(),
+            lockfiles: base
+                .lockfiles
+                .iter()
+                .filter(|l| group_of(&l.path) == group)
+                .cloned()
+                .collect(),
+            duplicates: cli.duplicates.map_or_else(Vec::new, |min_lines| {
+                find_duplicates(&group_files, min_lines)
+            }),
+            pii: included_findings(base.pii.clone(), &group_files),
+            files: group_files,
+            summary: Some(summary),
+        };
+
+        let content = renderer.render(&digest)?;
+        check_budgets(cli, &content)?;
+        let path = out_dir.join(format!("{}.{}", group, extension));
+        write_output(cli, &content, &Some(path))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+struct SplitManifest {
+    part_budget_tokens: usize,
+    parts: Vec<SplitManifestPart>,
+}
+
+#[derive(Serialize, Debug)]
+struct SplitManifestPart {
+    path: String,
+    tokens: usize,
+    files: Vec<String>,
+}
+
+/// Write the digest as numbered parts that each fit in `part_budget` tokens, plus a manifest
+fn write_split_digest(
+    mut digest: Digest,
+    cli: &DigestArgs,
+    renderer: &Renderer,
+    part_budget: usize,
+) -> Result<()> {
+    let output = cli
+        .output
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("--split-tokens requires --output to name the parts"))?;
+
+    // Every part repeats the shared header, so measure it once and each file on top of it
+    let files = std::mem::take(&mut digest.files);
+    digest.part = Some(DigestPart {
+        index: files.len(),
+        total: files.len(),
+    });
+    let overhead = estimate_tokens(&renderer.render(&digest)?);
+    let mut costs = Vec::with_capacity(files.len());
+    for file in &files {
+        digest.files = vec![file.clone()];
+        let tokens = estimate_tokens(&renderer.render(&digest)?);
+        costs.push(tokens.saturating_sub(overhead));
+    }
+
+    let ranges = pack_parts(&costs, overhead, part_budget);
+    let total = ranges.len().max(1);
+    let mut files = files.into_iter();
+    let mut manifest = SplitManifest {
+        part_budget_tokens: part_budget,
+        parts: Vec::new(),
+    };
+
+    let summary = digest.summary.clone();
+    let (mut digest_tokens, mut digest_bytes, mut over_budget) = (0, 0, false);
+    for index in 1..=total {
+        let count = ranges.get(index - 1).map(|r| r.len()).unwrap_or(0);
+        digest.files = files.by_ref().take(count).collect();
+        digest.part = Some(DigestPart { index, total });
+
+        let content = renderer.render(&digest)?;
+        let tokens = estimate_tokens(&content);
+        if tokens > part_budget {
+            warn_user(
+                cli,
+                &format!(
+                    "part {} is ~{} tokens, over the {}-token budget (a single file is too large to split)",
+                    index, tokens, part_budget
+                ),
+            );
+        }
+        digest_tokens += tokens;
+        digest_bytes += content.len();
+        over_budget |= is_over_budget(cli, &content);
+        // Metrics cover every part written, including one that fails a budget check
+        let checked = check_budgets(cli, &content);
+        if cli.github_annotations && (index == total || checked.is_err()) {
+            write_github_outputs(
+                cli,
+                summary.as_ref(),
+                digest_tokens,
+                digest_bytes,
+                over_budget,
+            )?;
+        }
+        checked?;
+
+        let path = part_path(output, index);
+        write_output(cli, &content, &Some(path.clone()))?;
+        manifest.parts.push(SplitManifestPart {
+            // Parts sit next to the manifest, so record them by file name
+            path: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            tokens,
+            files: digest.files.iter().map(|f| f.path.clone()).collect(),
+        });
+    }
+
+    let manifest_file = manifest_path(output);
+    fs::write(&manifest_file, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_file.display()))?;
+    info!(
+        "Digest split into {} parts; manifest written to {}",
+        total,
+        manifest_file.display()
+    );
+
+    Ok(())
+}
+
+fn run_hook(command: &HookCommand) -> Result<()> {
+    match command {
+        HookCommand::Install {
+            project_path,
+            hook,
+            check,
+            output,
+            force,
+        } => {
+            let project_path = match project_path {
+                Some(path) => path.clone(),
+                None => env::current_dir()?,
+            };
+            let (Some(hooks_dir), Some(prefix)) =
+                (hooks_dir(&project_path), repo_prefix(&project_path))
+            else {
+                return Err(DigestError::Usage(format!(
+                    "{} is not in a git repository",
+                    project_path.display()
+                ))
+                .into());
+            };
+            let kind = HookKind::from(*hook);
+            let action = if *check || kind == HookKind::PrePush {
+                HookAction::Check
+            } else {
+                HookAction::Regenerate
+            };
+            let project = match prefix.trim_end_matches('/') {
+                "" => PathBuf::from("."),
+                prefix => PathBuf::from(prefix),
+            };
+
+            let path = hooks_dir.join(kind.file_name());
+            let existing = fs::read_to_string(&path).ok();
+            if existing.is_some_and(|script| !script.contains(HOOK_MARKER)) && !*force {
+                return Err(DigestError::Usage(format!(
+                    "{} already exists and wasn't installed by digest (use --force to replace it)",
+                    path.display()
+                ))
+                .into());
+            }
+            fs::create_dir_all(&hooks_dir)
+                .with_context(|| format!("Failed to create {}", hooks_dir.display()))?;
+            fs::write(&path, hook_script(action, &project, output))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                    .with_context(|| format!("Failed to make {} executable", path.display()))?;
+            }
+            eprintln!("Installed {}", path.display());
+            Ok(())
+        }
+        HookCommand::Check {
+            project_path,
+            output,
+        } => {
+            let project_path = match project_path {
+                Some(path) => path.clone(),
+                None => env::current_dir()?,
+            };
+            // The same options the regenerating hook passes, read through digest.toml and the
+            // environment like any other run
+            let mut args: Vec<OsString> = vec!["digest".into()];
+            args.extend(STABLE_ARGS.iter().map(OsString::from));
+            args.push("--check".into());
+            args.push("--output".into());
+            args.push(project_path.join(output).into());
+            args.push(project_path.into());
+            let cli = parse_cli(&args)
+                .map_err(|err| DigestError::Usage(err.to_string().trim_end().to_string()))?;
+            let cli = apply_config(cli, args)?;
+            run_digest(&cli.digest, None)
+        }
+    }
+}
+
+/// The digest run `digest tree` previews: the merged CLI and config options, listed as a tree
+///
+/// `--per-directory` is dropped since it writes digests before the list is reached, and the tree
+/// shows what a single digest of the project collects. The other output options stay, so the
+/// digest's own output files are left out of the tree as they are from the digest.
+fn tree_args(digest: &DigestArgs) -> DigestArgs {
+    DigestArgs {
+        list: Some(ListMode::Tree),
+        per_directory: None,
+        ..digest.clone()
+    }
+}
+
+fn run_command(command: &Commands) -> Result<()> {
+    match command {
+        Commands::Symbol { name, digest } => run_digest(digest, Some(name)),
+        Commands::Tree { digest } => run_digest(&tree_args(digest), None),
+        Commands::Init {
+            project_path,
+            force,
+        } => {
+            let project_path = match project_path {
+                Some(path) => path.clone(),
+                None => env::current_dir()?,
+            };
+            let files = starter_files(&project_path);
+            if files.detected.is_empty() {
+                eprintln!("No known project type detected; writing generic defaults");
+            } else {
+                eprintln!("Detected: {}", files.detected.join(", "));
+            }
+            for (name, content) in [
+                (".digestignore", &files.digestignore),
+                (CONFIG_FILE, &files.config),
+            ] {
+                let path = project_path.join(name);
+                if path.exists() && !*force {
+                    eprintln!("Kept existing {} (use --force to overwrite)", name);
+                    continue;
+                }
+                fs::write(&path, content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                eprintln!("Wrote {}", path.display());
+            }
+            Ok(())
+        }
+        Commands::Hook { command } => run_hook(command),
+        Commands::Doctor { project_path } => {
+            let project_path = match project_path {
+                Some(path) => path.clone(),
+                None => env::current_dir()?,
+            };
+            run_doctor(&project_path)
+        }
+        Commands::Patterns {
+            only_defaults,
+            json,
+            digest,
+        } => run_patterns(digest, *only_defaults, *json),
+        Commands::Completions { shell } => {
+            // Generated into a buffer, since clap_complete panics on write errors such as a
+            // closed pipe
+            let mut script = Vec::new();
+            generate(*shell, &mut Cli::command(), "digest", &mut script);
+            std::io::stdout().write_all(&script)?;
+            Ok(())
+        }
+        Commands::Schema => {
+            println!("{}", serde_json::to_string_pretty(&json_schema())?);
+            Ok(())
+        }
+        Commands::Extract {
+            selectors,
+            json,
+            policy,
+        } => {
+            let root = env::current_dir()?;
+            // DIGEST_POLICY applies here as it does to a digest run
+            let extra = policy.clone().or_else(|| {
+                env_options()
+                    .into_iter()
+                    .find(|(name, _)| name == "policy")
+                    .map(|(_, path)| PathBuf::from(path))
+            });
+            let policy = load_policy(extra.as_deref(), &root)?;
+            let extracts = extract_items(selectors, &policy, &root)?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&extracts)?);
+            } else {
+                let sections: Vec<String> = extracts.iter().map(format_extract).collect();
+                print!("{}", sections.join("\n"));
+            }
+            Ok(())
+        }
+        Commands::Diff { old, new, json } => {
+            let old_json = fs::read_to_string(old)
+                .with_context(|| format!("Failed to read {}", old.display()))?;
+            let new_json = fs::read_to_string(new)
+                .with_context(|| format!("Failed to read {}", new.display()))?;
+            let diff = diff_digests(&old_json, &new_json)?;
+
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{}", format_diff(&diff));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The items `selectors` pick out, held to `policy` like a digest of the project at `root`:
+/// files are redacted before an item is cut out of them, and each extract as printed has to
+/// pass the policy's rules
+fn extract_items(selectors: &[Selector], policy: &Policy, root: &Path) -> Result<Vec<Extract>> {
+    let mut extracts = Vec::new();
+    let mut printed = Vec::new();
+    for selector in selectors {
+        let path = Path::new(&selector.path);
+        let relative = strip_root(path, root).unwrap_or_else(|| path.to_path_buf());
+        let relative = slash(&relative).trim_start_matches("./").to_string();
+        let mut file = [FileInfo::new(relative.clone(), None, read_utf8(path)?)];
+        policy.redact(&mut file);
+        let extract = extract(selector, &file[0].content).map_err(DigestError::Extract)?;
+        printed.push(FileInfo::new(relative, None, format_extract(&extract)));
+        extracts.push(extract);
+    }
+    policy.enforce(&printed, &[])?;
+    Ok(extracts)
+}
+
+/// The layered ignore patterns of a run: the `.git` directory, the project's ignore files, the
+/// defaults for its language, types, and frameworks, and --ignore-pattern values
+fn resolve_ignores(
+    cli: &DigestArgs,
+    project_path: &Path,
+    languages: &[String],
+    project_types: &[ProjectType],
+    frameworks: &[&FrameworkSpec],
+) -> Result<IgnoreSources> {
+    let mut ignores = IgnoreSources::new().with(IgnoreSource::Builtin, [".git"]);
+
+    // Don't process any ignore files if --no-ignore is used
+    if !cli.no_ignore {
+        // Defaults layer underneath the ignore files, so a sparse .gitignore doesn't let
+        // node_modules or build output in, and a `!pattern` in .digestignore or
+        // --ignore-pattern can take one back
+        if cli.no_default_ignores {
+            debug!("Skipping default ignore patterns due to --no-default-ignores flag.");
+        } else {
+            let mut defaults: Vec<String> =
+                build_ignore_patterns(languages, has_type(project_types, GODOT))
+                    .into_iter()
+                    .collect();
+            if cli.include_test_data {
+                debug!("Keeping test data due to --include-test-data flag.");
+            } else {
+                defaults.extend(TEST_DATA_PATTERNS.iter().map(|p| p.to_string()));
+            }
+            if cli.lockfiles != Lockfiles::Exclude {
+                // --lockfiles include and summary take back the lockfiles the defaults leave out
+                defaults.retain(|pattern| !is_lockfile_pattern(pattern));
+            }
+            defaults.sort();
+            ignores.add(IgnoreSource::Defaults, defaults);
+
+            // Project-type defaults such as Unreal build output and Terraform state
+            for project_type in project_types {
+                ignores.add(
+                    IgnoreSource::ProjectType(project_type.name.to_string()),
+                    project_type.ignore_patterns.iter().copied(),
+                );
+            }
+
+            // Framework-generated code such as Django migrations, and Python tool caches
+            for framework in frameworks {
+                ignores.add(
+                    IgnoreSource::ProjectType(framework.name.to_string()),
+                    framework.ignore.iter().copied(),
+                );
+            }
+        }
+
+        let mut ignore_files = Vec::new();
+        for (name, skip, flag) in [
+            (".digestignore", cli.no_digestignore, "--no-digestignore"),
+            (".gitignore", cli.no_gitignore, "--no-gitignore"),
+        ] {
+            if skip {
+                debug!("Skipping {} due to {} flag.", name, flag);
+            } else if ignores.load_file(project_path, name)? {
+                ignore_files.push(name);
+            } else {
+                debug!("No {} file found.", name);
+            }
+        }
+
+        // The git excludes, which the walker applies; loaded here for their provenance
+        if cli.no_git_exclude {
+            debug!("Skipping .git/info/exclude due to --no-git-exclude flag.");
+        } else if ignores.load_git_exclude(project_path)? {
+            ignore_files.push(".git/info/exclude");
+        }
+        if cli.no_global_gitignore {
+            debug!("Skipping the global gitignore due to --no-global-gitignore flag.");
+        } else if ignores.load_global_gitignore(project_path)? {
+            ignore_files.push("the global gitignore");
+        }
+
+        if ignore_files.is_empty() {
+            info!("No ignore files found or used.");
+        } else {
+            info!("Using ignore patterns from: {}", ignore_files.join(", "));
+        }
+    } else {
+        info!("Ignoring all ignore files due to --no-ignore flag.");
+    }
+
+    // Add patterns from --ignore-pattern CLI arguments
+    if !cli.ignore_patterns.is_empty() {
+        info!(
+            "Adding {} custom ignore patterns from command line",
+            cli.ignore_patterns.len()
+        );
+        ignores.add(
+            IgnoreSource::CommandLine,
+            cli.ignore_patterns.iter().cloned(),
+        );
+    }
+
+    Ok(ignores)
+}
+
+/// `digest patterns`: print the ignore patterns the same flags would give a digest run
+fn run_patterns(cli: &DigestArgs, only_defaults: bool, json: bool) -> Result<()> {
+    let project_path = match cli.project_path.clone() {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+    validate_cli_patterns(cli)?;
+    let ignores = resolve_project(cli, &project_path)?.ignores;
+
+    let patterns: Vec<&IgnorePattern> = ignores
+        .iter()
+        .filter(|pattern| !only_defaults || pattern.source.is_default())
+        .collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&patterns)?);
+    } else {
+        print!("{}", format_patterns(patterns));
+    }
+    Ok(())
+}
+
+/// What a run detects in the project before walking it
+struct ResolvedProject {
+    project_types: Vec<ProjectType>,
+    /// Python web frameworks, whose key files are ranked first
+    frameworks: Vec<&'static FrameworkSpec>,
+    language_breakdown: Vec<LanguageStats>,
+    /// From .digestignore, .gitignore, the git excludes, the defaults, and --ignore-pattern
+    ignores: IgnoreSources,
+}
+
+/// Detect the project types, frameworks, and languages of `project_path` and layer the ignore
+/// patterns for them, the same way for `digest` and `digest patterns`
+fn resolve_project(cli: &DigestArgs, project_path: &Path) -> Result<ResolvedProject> {
+    // One shallow scan feeds every project-type detector
+    let project_types = detect_project_types(project_path);
+    for project_type in &project_types {
+        info!("Detected {} project", project_type.name);
+    }
+
+    let frameworks = detect_python_frameworks(project_path);
+    for framework in &frameworks {
+        info!("Detected {} project", framework.name);
+    }
+
+    // The predominant languages pick the default ignore patterns
+    let languages = detect_languages(project_path)?;
+    let language_breakdown = get_language_breakdown(&languages);
+    debug!(
+        "Main language detected: {:?}",
+        get_main_language(&language_breakdown)
+    );
+    debug!("Language breakdown: {:?}", language_breakdown);
+
+    let ignores = resolve_ignores(
+        cli,
+        project_path,
+        &default_languages(&language_breakdown),
+        &project_types,
+        &frameworks,
+    )?;
+    Ok(ResolvedProject {
+        project_types,
+        frameworks,
+        language_breakdown,
+        ignores,
+    })
+}
+
+/// The first --ignore-pattern the matcher couldn't use, as an error naming it
+fn validate_cli_patterns(cli: &DigestArgs) -> Result<()> {
+    for pattern in &cli.ignore_patterns {
+        // `!pattern` re-includes what the defaults and ignore files leave out
+        if let Err(err) = validate_pattern(pattern) {
+            return Err(DigestError::PatternInvalid {
+                flag: "ignore".to_string(),
+                pattern: pattern.clone(),
+                message: err.to_string(),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// --check-patterns: report invalid patterns in the project's ignore files by file, line, and
+/// column (--ignore-pattern values were already checked)
+fn check_patterns(cli: &DigestArgs, project_path: &Path) -> Result<()> {
+    let mut checked = cli.ignore_patterns.len();
+    let mut invalid = 0;
+    for file in IGNORE_FILES {
+        let Ok(content) = fs::read_to_string(project_path.join(file)) else {
+            continue;
+        };
+        checked += content.lines().filter_map(parse_ignore_line).count();
+        for (line, pattern, err) in invalid_ignore_lines(&content) {
+            invalid += 1;
+            eprintln!(
+                "{}:{}:{}: invalid pattern '{}': {}",
+                file, line, err.position, pattern, err.message
+            );
+        }
+    }
+    if invalid > 0 {
+        return Err(DigestError::PatternsInvalid { invalid, checked }.into());
+    }
+    if !cli.porcelain {
+        eprintln!("All patterns are valid ({} checked)", checked);
+    }
+    Ok(())
+}
+
+/// Print what `digest doctor` finds, failing if any of it is an error
+fn run_doctor(project_path: &Path) -> Result<()> {
+    let mut findings = diagnose(project_path);
+
+    // Whether digest.toml names real flags with valid values is up to clap
+    if let Ok(Some(config_args)) = load_config_args(project_path) {
+        let args = std::iter::once("digest".to_string()).chain(config_args);
+        if let Err(err) = parse_cli(args) {
+            let message = err.to_string();
+            let message = message.trim_start_matches("error: ");
+            let message = message.lines().next().unwrap_or_default();
+            findings.insert(0, Finding::error(format!("{}: {}", CONFIG_FILE, message)));
+        }
+    }
+
+    if findings.is_empty() {
+        println!("No problems found in {}", project_path.display());
+        return Ok(());
+    }
+    for finding in &findings {
+        let label = match finding.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        println!("{}: {}", label, finding.message);
+    }
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(DigestError::Usage(format!("digest doctor found {} error(s)", errors)).into());
+    }
+    Ok(())
+}
+
+fn format_diff(diff: &DigestDiff) -> String {
+    let mut output = String::new();
+
+    for file in &diff.files {
+        let marker = match file.change {
+            FileChange::Added => '+',
+            FileChange::Removed => '-',
+            FileChange::Changed => '~',
+        };
+        output.push_str(&format!(
+            "{} {} ({} -> {} tokens, {:+}; +{}/-{} lines)\n",
+            marker,
+            file.path,
+            file.old_tokens,
+            file.new_tokens,
+            file.token_delta(),
+            file.lines_added,
+            file.lines_removed
+        ));
+    }
+
+    let count = |change| diff.files.iter().filter(|f| f.change == change).count();
+    output.push_str(&format!(
+        "\n{} added, {} removed, {} changed, {} unchanged\n",
+        count(FileChange::Added),
+        count(FileChange::Removed),
+        count(FileChange::Changed),
+        diff.unchanged
+    ));
+    output.push_str(&format!(
+        "Total tokens: {} -> {} ({:+})\n",
+        diff.old_total_tokens,
+        diff.new_total_tokens,
+        diff.token_delta()
+    ));
 
-    Ok(())
+    output
 }
 
 fn detect_languages(project_path: &Path) -> Result<Languages> {
@@ -222,35 +2851,78 @@ fn detect_languages(project_path: &Path) -> Result<Languages> {
     Ok(languages)
 }
 
-fn get_language_breakdown(languages: &Languages) -> HashMap<String, usize> {
-    let mut breakdown = HashMap::new();
+fn get_language_breakdown(languages: &Languages) -> Vec<LanguageStats> {
+    let mut breakdown = Vec::new();
 
     for (language, stats) in languages {
-        let language_name = format!("{}", language);
-        let count = stats.code + stats.comments + stats.blanks;
-        breakdown.insert(language_name, count);
+        let mut entry = LanguageStats::new(format!("{}", language));
+        entry.files = stats.reports.len();
+        entry.lines = stats.code + stats.comments + stats.blanks;
+        entry.bytes = stats
+            .reports
+            .iter()
+            .filter_map(|report| fs::metadata(&report.name).ok())
+            .map(|meta| meta.len())
+            .sum();
+        breakdown.push(entry);
     }
 
-    breakdown
+    finish_breakdown(breakdown)
+}
+
+fn get_main_language(language_breakdown: &[LanguageStats]) -> Option<String> {
+    language_breakdown
+        .iter()
+        .max_by_key(|l| l.lines)
+        .map(|l| l.language.clone())
 }
 
-fn get_main_language(language_breakdown: &HashMap<String, usize>) -> Option<String> {
+/// Languages, as tokei names them, and the build output and caches each leaves in a project
+const LANGUAGE_IGNORES: &[(&[&str], &[&str])] = &[
+    (
+        &["JavaScript", "TypeScript", "JSX", "TSX"],
+        &["node_modules", "*.min.js", "*.bundle.js"],
+    ),
+    (
+        &["Python"],
+        &[
+            "__pycache__",
+            "*.pyc",
+            ".pytest_cache",
+            ".tox",
+            ".mypy_cache",
+        ],
+    ),
+    (&["Rust"], &["target", "Cargo.lock"]),
+    (&["Java"], &["*.class", "bin", "out"]),
+    (&["Go"], &["vendor", "*.pb.go"]),
+    // Compiled Lua files and the LuaRocks package directory
+    (&["Lua"], &["*.luac", "luarocks"]),
+    (&["C#"], &["bin", "obj", "*.dll"]),
+];
+
+/// Share of a project's lines, in percent, from which a language's defaults apply
+const LANGUAGE_DEFAULTS_SHARE: f64 = 5.0;
+
+/// The languages whose defaults apply: the main language and any other with at least
+/// `LANGUAGE_DEFAULTS_SHARE` percent of the lines
+fn default_languages(language_breakdown: &[LanguageStats]) -> Vec<String> {
+    let main_language = get_main_language(language_breakdown);
     language_breakdown
         .iter()
-        .max_by_key(|(_, &count)| count)
-        .map(|(lang, _)| lang.clone())
+        .filter(|l| {
+            l.percentage >= LANGUAGE_DEFAULTS_SHARE || Some(&l.language) == main_language.as_ref()
+        })
+        .map(|l| l.language.clone())
+        .collect()
 }
 
-pub fn build_ignore_patterns(
-    main_language: &Option<String>,
-    is_godot_project: bool,
-) -> HashSet<String> {
+pub fn build_ignore_patterns(languages: &[String], is_godot_project: bool) -> HashSet<String> {
     // Common patterns to ignore across all languages
     let mut patterns = HashSet::from([
         ".git".to_string(),
-        ".github".to_string(),
-        ".vscode".to_string(),
-        ".idea".to_string(),
+        ".vscode/".to_string(),
+        ".idea/".to_string(),
         "node_modules".to_string(),
         "target".to_string(),
         "build".to_string(),
@@ -264,47 +2936,33 @@ pub fn build_ignore_patterns(
         "*.lock".to_string(),
         "yarn.lock".to_string(),
         "package-lock.json".to_string(),
+        // Test and coverage output
+        "coverage/".to_string(),
+        ".nyc_output/".to_string(),
+        "htmlcov/".to_string(),
+        "*.lcov".to_string(),
+        "junit*.xml".to_string(),
+        ".pytest_cache/".to_string(),
+        "target/debug/".to_string(),
+        // IDE project files, editor state, swap and backup files
+        ".vs/".to_string(),
+        "*.iml".to_string(),
+        "*.suo".to_string(),
+        ".fleet/".to_string(),
+        ".zed/".to_string(),
+        "*.swp".to_string(),
+        "*~".to_string(),
     ]);
 
-    // Add language-specific patterns
-    if let Some(lang) = main_language {
-        match lang.as_str() {
-            "JavaScript" | "TypeScript" => {
-                patterns.insert("node_modules".to_string());
-                patterns.insert("*.min.js".to_string());
-                patterns.insert("*.bundle.js".to_string());
-            }
-            "Python" => {
-                patterns.insert("__pycache__".to_string());
-                patterns.insert("*.pyc".to_string());
-                patterns.insert(".pytest_cache".to_string());
-            }
-            "Rust" => {
-                patterns.insert("target".to_string());
-                patterns.insert("Cargo.lock".to_string());
-            }
-            "Java" => {
-                patterns.insert("*.class".to_string());
-                patterns.insert("bin".to_string());
-                patterns.insert("out".to_string());
-            }
-            "Go" => {
-                patterns.insert("vendor".to_string());
-                patterns.insert("*.pb.go".to_string());
-            }
-            "Lua" => {
-                patterns.insert("*.luac".to_string()); // Compiled Lua files
-                patterns.insert("luarocks".to_string()); // LuaRocks package manager directory
-            }
-            "C#" => {
-                // If it's not a Godot project, use default C# ignores
-                if !is_godot_project {
-                    patterns.insert("bin".to_string());
-                    patterns.insert("obj".to_string());
-                    patterns.insert("*.dll".to_string());
-                }
-            }
-            _ => {}
+    // Add the patterns of every language in the project
+    for (names, language_patterns) in LANGUAGE_IGNORES {
+        let present = languages
+            .iter()
+            .any(|language| names.contains(&language.as_str()));
+        // Godot keeps its C# scripts' build output under .godot, and bin/ may hold game files
+        let godot_csharp = is_godot_project && names.contains(&"C#");
+        if present && !godot_csharp {
+            patterns.extend(language_patterns.iter().map(|p| p.to_string()));
         }
     }
 
@@ -319,598 +2977,208 @@ pub fn build_ignore_patterns(
     patterns
 }
 
-pub fn check_for_digestignore(project_path: &Path) -> Result<HashSet<String>> {
-    let digestignore_path = project_path.join(".digestignore");
-
-    if !digestignore_path.exists() {
-        return Err(anyhow::anyhow!("No .digestignore file found"));
-    }
-
-    info!(
-        "Using .digestignore file at {}",
-        digestignore_path.display()
-    );
-
-    // Use the ignore crate to build a gitignore-like matcher from the .digestignore file
-    let content = fs::read_to_string(&digestignore_path).with_context(|| {
-        format!(
-            "Failed to read .digestignore at {}",
-            digestignore_path.display()
-        )
-    })?;
-
-    // Add .git to always ignore
-    let mut patterns = HashSet::from([".git".to_string()]);
-
-    for line in content.lines() {
-        let line = line.trim();
-        // Skip empty lines and comments
-        if !line.is_empty() && !line.starts_with('#') {
-            patterns.insert(line.to_string());
-        }
-    }
-
-    Ok(patterns)
+/// Whether the default ignore `pattern` is there to leave out lockfiles
+fn is_lockfile_pattern(pattern: &str) -> bool {
+    pattern == "*.lock" || is_lockfile(pattern)
 }
 
-pub fn check_for_gitignore(project_path: &Path) -> Result<HashSet<String>> {
-    let gitignore_path = project_path.join(".gitignore");
-
-    if !gitignore_path.exists() {
-        return Err(anyhow::anyhow!("No .gitignore file found"));
-    }
-
-    info!("Using .gitignore file at {}", gitignore_path.display());
-
-    // Read the .gitignore file
-    let content = fs::read_to_string(&gitignore_path)
-        .with_context(|| format!("Failed to read .gitignore at {}", gitignore_path.display()))?;
-
-    // Add .git to always ignore
-    let mut patterns = HashSet::from([".git".to_string()]);
-
-    for line in content.lines() {
-        let line = line.trim();
-        // Skip empty lines and comments
-        if !line.is_empty() && !line.starts_with('#') {
-            patterns.insert(line.to_string());
-        }
-    }
-
-    Ok(patterns)
+fn parse_model(name: &str) -> Result<&'static ModelSpec, String> {
+    find_model(name).ok_or_else(|| {
+        let known: Vec<&str> = MODELS.iter().map(|m| m.name).collect();
+        format!("unknown model '{}' (known: {})", name, known.join(", "))
+    })
 }
 
-pub fn should_ignore(path: &Path, ignore_patterns: &HashSet<String>) -> bool {
-    // Get the path as a string
-    let path_str = path.to_string_lossy();
-
-    // Normalize path for matching (replace backslashes with forward slashes on Windows)
-    let path_str = path_str.replace('\\', "/");
-
-    // Check if the path matches any of the ignore patterns
-    for pattern in ignore_patterns {
-        // Special case for **/test/** pattern since it's common and important
-        if pattern == "**/test/**" {
-            if path_str.contains("/test/") || path_str.starts_with("test/") {
-                debug!("Ignoring {} - matches **/test/** pattern", path_str);
-                return true;
-            }
-        }
-
-        // Always ignore .git directory
-        if path_str.contains("/.git/") || path_str == ".git" {
-            debug!("Ignoring {} - matches .git pattern", path_str);
-            return true;
-        }
-
-        // Handle different gitignore pattern types
-        let pattern = pattern.trim();
-
-        // Empty lines or comments
-        if pattern.is_empty() || pattern.starts_with('#') {
-            continue;
-        }
-
-        // Negated patterns (we're not supporting these for simplicity)
-        if pattern.starts_with('!') {
-            continue;
-        }
-
-        // Handle **/ pattern at the beginning (match any directory depth)
-        if pattern.starts_with("**/") {
-            let suffix = &pattern[3..];
-            // Check if suffix appears anywhere in the path
-            if path_str == suffix
-                || path_str.ends_with(suffix)
-                || path_str.contains(&format!("/{}", suffix))
-            {
-                debug!("Ignoring {} - matches **/ pattern: {}", path_str, pattern);
-                return true;
-            }
-        }
-
-        // Handle pattern ending with /** (match any subdirectory)
-        if pattern.ends_with("/**") {
-            let prefix = &pattern[0..pattern.len() - 3];
-            if path_str.starts_with(prefix) || path_str.contains(&format!("/{}", prefix)) {
-                debug!("Ignoring {} - matches /** pattern: {}", path_str, pattern);
-                return true;
-            }
-        }
-
-        // Handle /**/ pattern (matches any directory in the middle)
-        if pattern.contains("/**/") {
-            let segments: Vec<&str> = pattern.split("/**/").collect();
-
-            if segments.len() >= 2 {
-                let prefix = segments[0];
-                let suffix = segments[1];
-
-                // Check if both prefix and suffix match parts of the path
-                // If prefix is empty, it's a pattern like "/**/suffix"
-                let prefix_matches = prefix.is_empty()
-                    || path_str.starts_with(prefix)
-                    || path_str.contains(&format!("/{}", prefix));
-
-                // If suffix is empty, it's a pattern like "prefix/**/"
-                let suffix_matches = suffix.is_empty()
-                    || path_str.ends_with(suffix)
-                    || path_str.contains(&format!("{}/", suffix));
-
-                if prefix_matches && suffix_matches {
-                    debug!("Ignoring {} - matches /**/ pattern: {}", path_str, pattern);
-                    return true;
+fn compile_patterns(patterns: &[String], flag: &str) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|err| {
+                DigestError::PatternInvalid {
+                    flag: flag.to_string(),
+                    pattern: pattern.clone(),
+                    message: err.to_string(),
                 }
-            }
-        }
-
-        // Directory pattern (ends with slash)
-        if pattern.ends_with('/') {
-            let dir_name = &pattern[0..pattern.len() - 1];
-
-            // Check if path contains the directory as a complete segment
-            // "test/" should match "test/file.rs" or "src/test/file.rs" but not "testing/file.rs"
-            let matches = path_str == dir_name
-                || path_str.starts_with(&format!("{}/", dir_name))
-                || path_str.contains(&format!("/{}/", dir_name));
+                .into()
+            })
+        })
+        .collect()
+}
 
-            if matches {
-                debug!(
-                    "Ignoring {} - matches directory pattern: {}",
-                    path_str, pattern
-                );
-                return true;
-            }
+fn parse_preset(name: &str) -> Result<&'static PresetSpec, String> {
+    find_preset(name).ok_or_else(|| {
+        let known: Vec<&str> = PRESETS.iter().map(|p| p.name).collect();
+        format!("unknown preset '{}' (known: {})", name, known.join(", "))
+    })
+}
 
-            continue; // Skip other pattern matching for directory patterns
-        }
+fn check_model_budget(model: &ModelSpec, content: &str, cli: &DigestArgs) -> Result<()> {
+    let tokens = estimate_tokens(content);
+    let usage = tokens as f64 * 100.0 / model.context_window as f64;
+
+    if !cli.porcelain {
+        eprintln!(
+            "Estimated {} input tokens for {}: ${:.4} ({:.1}% of the {}-token context window)",
+            tokens,
+            model.name,
+            model.input_cost(tokens),
+            usage,
+            model.context_window
+        );
+    }
 
-        // Special case for *.test.* pattern
-        if pattern == "*.test.*" {
-            if path_str.contains(".test.") {
-                debug!("Ignoring {} - matches *.test.* pattern", path_str);
-                return true;
-            }
+    if !model.fits(tokens) {
+        let message = format!(
+            "Digest needs ~{} tokens but {} accepts at most {}",
+            tokens, model.name, model.context_window
+        );
+        if cli.fail_if_over_budget {
+            return Err(DigestError::BudgetExceeded(message).into());
         }
-
-        // Handle glob patterns with * (simplified implementation)
-        if pattern.contains('*') && !pattern.contains("**") {
-            let parts: Vec<&str> = pattern.split('*').collect();
-
-            // Simple cases
-            if parts.len() == 2 {
-                if pattern.starts_with('*') && path_str.ends_with(parts[1]) {
-                    // *suffix pattern
-                    debug!(
-                        "Ignoring {} - matches *suffix pattern: {}",
-                        path_str, pattern
-                    );
-                    return true;
-                } else if pattern.ends_with('*') && path_str.starts_with(parts[0]) {
-                    // prefix* pattern
-                    debug!(
-                        "Ignoring {} - matches prefix* pattern: {}",
-                        path_str, pattern
-                    );
-                    return true;
-                } else if path_str.starts_with(parts[0]) && path_str.ends_with(parts[1]) {
-                    // prefix*suffix pattern
-                    debug!(
-                        "Ignoring {} - matches prefix*suffix pattern: {}",
-                        path_str, pattern
-                    );
-                    return true;
-                }
-            }
-        } else {
-            // Direct match (either exact or as a substring)
-            if path_str == pattern
-                || path_str.ends_with(pattern)
-                || path_str.contains(&format!("/{}", pattern))
-            {
-                debug!(
-                    "Ignoring {} - matches direct pattern: {}",
-                    path_str, pattern
-                );
-                return true;
-            }
+        if cli.github_annotations {
+            let title = "Over the context window";
+            eprintln!("{}", annotation("warning", None, title, &message));
         }
+        warn_user(cli, &message);
     }
 
-    false
+    Ok(())
 }
 
-pub fn collect_relevant_files(
+/// Point files at their blob URLs for --link-files, warning when that isn't possible
+fn link_files(
+    cli: &DigestArgs,
     project_path: &Path,
-    ignore_patterns: &HashSet<String>,
-    max_files: usize,
-    max_file_size: u64,
-    is_godot_project: bool,
-    respect_gitignore: bool,
-) -> Result<Vec<FileInfo>> {
-    let mut files = Vec::new();
-
-    // Configure the walker with appropriate gitignore settings
-    let mut builder = WalkBuilder::new(project_path);
-    builder
-        .hidden(false) // Include hidden files
-        .git_ignore(respect_gitignore) // Respect .gitignore based on CLI option
-        .git_global(respect_gitignore) // Also control global gitignore
-        .git_exclude(respect_gitignore); // And git exclude rules
-
-    let walker = builder.build();
-
-    for result in walker {
-        let entry = match result {
-            Ok(entry) => entry,
-            Err(err) => {
-                warn!("Error accessing entry: {}", err);
-                continue;
-            }
-        };
-
-        let path = entry.path();
-
-        // Skip directories
-        if path.is_dir() {
-            continue;
-        }
-
-        // Skip files that match ignore patterns
-        if should_ignore(path, ignore_patterns) {
-            debug!("Ignoring file: {}", path.display());
-            continue;
-        }
-
-        // Check file size
-        let metadata = match fs::metadata(path) {
-            Ok(meta) => meta,
-            Err(err) => {
-                warn!("Error reading metadata for {}: {}", path.display(), err);
-                continue;
-            }
-        };
-
-        if metadata.len() > max_file_size {
-            debug!(
-                "Skipping large file: {} ({} bytes)",
-                path.display(),
-                metadata.len()
-            );
-            continue;
-        }
-
-        // Check if this is a file we want to include
-        let extension = path.extension().and_then(|ext| ext.to_str());
-
-        // For Godot projects, we want to prioritize certain file types
-        let should_include = if is_godot_project {
-            match extension {
-                Some("gd") | Some("tscn") | Some("cs") | Some("godot") => true,
-                Some("tres") | Some("import") | Some("shader") => true,
-                Some(ext) if is_common_code_file(ext) => true,
-                _ => false,
-            }
-        } else {
-            // For non-Godot projects, use the regular logic
-            match extension {
-                Some(ext) if is_common_code_file(ext) => true,
-                _ => false,
-            }
-        };
-
-        if !should_include {
-            debug!("Skipping non-code file: {}", path.display());
-            continue;
-        }
+    git: Option<&GitInfo>,
+    files: &mut [FileInfo],
+) {
+    let Some(git) = git else {
+        warn_user(
+            cli,
+            "--link-files needs a git repository; file headings are not linked",
+        );
+        return;
+    };
+    if !annotate_file_links(project_path, git, files) {
+        warn_user(
+            cli,
+            "--link-files needs a remote with a web address; file headings are not linked",
+        );
+    } else if git.dirty {
+        warn_user(
+            cli,
+            "the worktree has uncommitted changes; linked files may differ from the digest",
+        );
+    }
+}
 
-        // Read file content
-        let content = match fs::read_to_string(path) {
-            Ok(content) => content,
-            Err(err) => {
-                warn!("Error reading file {}: {}", path.display(), err);
-                continue;
-            }
-        };
+/// Renders digests in the selected format, optionally wrapped in a prompt template
+struct Renderer {
+    formatter: Box<dyn DigestFormatter>,
+    template: Option<String>,
+}
 
-        // Determine file language based on extension and project type
-        let language = match extension {
-            Some(ext) => {
-                let lang = match ext {
-                    "rs" => "Rust",
-                    "js" => "JavaScript",
-                    "ts" => "TypeScript",
-                    "py" => "Python",
-                    "java" => "Java",
-                    "go" => "Go",
-                    "c" | "cpp" | "h" | "hpp" => "C/C++",
-                    "rb" => "Ruby",
-                    "php" => "PHP",
-                    "lua" => "Lua",
-                    "cs" => {
-                        if is_godot_project {
-                            "GDScript C#"
-                        } else {
-                            "C#"
-                        }
-                    }
-                    "html" => "HTML",
-                    "css" => "CSS",
-                    "json" => "JSON",
-                    "md" => "Markdown",
-                    "yml" | "yaml" => "YAML",
-                    "toml" => "TOML",
-                    "gd" => "GDScript",
-                    "tscn" | "tres" => "Godot Scene",
-                    "shader" => "Godot Shader",
-                    _ => "Unknown",
-                };
-                Some(lang.to_string())
-            }
+impl Renderer {
+    fn new(cli: &DigestArgs) -> Result<Self> {
+        let template = match &cli.template {
+            Some(path) => Some(
+                fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read template {}", path.display()))?,
+            ),
             None => None,
         };
+        Ok(Renderer {
+            formatter: cli.format.formatter(
+                cli.group_by.into(),
+                cli.checksum,
+                cli.update.is_some(),
+            ),
+            template,
+        })
+    }
 
-        let relative_path = path
-            .strip_prefix(project_path)
-            .with_context(|| format!("Failed to strip prefix from {}", path.display()))?
-            .to_string_lossy()
-            .to_string();
-
-        files.push(FileInfo {
-            path: relative_path,
-            language,
-            content,
-        });
+    fn render(&self, digest: &Digest) -> Result<String> {
+        let content = self.formatter.render(digest)?;
+        let Some(template) = &self.template else {
+            return Ok(content);
+        };
 
-        if files.len() >= max_files {
-            break;
-        }
+        // Templates see every digest field plus the file tree and the rendered digest
+        let paths: Vec<&str> = digest.files.iter().map(|f| f.path.as_str()).collect();
+        let mut data = serde_json::to_value(digest)?;
+        data["tree"] = serde_json::Value::String(render_tree(&paths));
+        data["digest"] = serde_json::Value::String(content);
+        Ok(render_template(template, &data)?)
     }
-
-    Ok(files)
 }
 
-fn output_digest(digest: &Digest, format: &str, output_path: &Option<PathBuf>) -> Result<()> {
-    let content = match format {
-        "json" => serde_json::to_string_pretty(digest)?,
-        "markdown" => format_markdown(digest),
-        _ => return Err(anyhow::anyhow!("Unsupported output format: {}", format)),
-    };
-
-    match output_path {
+/// Largest digest printed to an interactive terminal without --force, in bytes
+const TTY_LIMIT: usize = 1024 * 1024;
+
+/// Write the digest to `output_path` or stdout; with --checksum, a file gets a `.sha256`
+/// sidecar and stdout's checksum goes to stderr. Returns the text written, before any
+/// encryption, which on stdout ends with a newline.
+fn write_output(cli: &DigestArgs, content: &str, output_path: &Option<PathBuf>) -> Result<String> {
+    let written = match output_path {
+        Some(path) if cli.check => {
+            let existing = fs::read_to_string(path).unwrap_or_default();
+            if existing != content {
+                return Err(DigestError::Stale { path: path.clone() }.into());
+            }
+            info!("{} is up to date", path.display());
+            content.to_string()
+        }
         Some(path) => {
-            fs::write(path, content)?;
+            // An encrypted digest is written in age's binary format; its checksum covers that
+            let bytes = if cli.encrypt.is_empty() {
+                content.as_bytes().to_vec()
+            } else {
+                encrypt(content.as_bytes(), &cli.encrypt, false)?
+            };
+            fs::write(path, &bytes)?;
             info!("Digest written to {}", path.display());
+            if cli.checksum {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let sidecar = sidecar_path(path);
+                fs::write(&sidecar, sha256sum_line(&bytes, &name))
+                    .with_context(|| format!("Failed to write {}", sidecar.display()))?;
+                info!("Checksum written to {}", sidecar.display());
+            }
+            content.to_string()
         }
         None => {
-            // Print to stdout
-            println!("{}", content);
-        }
-    }
-
-    Ok(())
-}
-
-fn format_markdown(digest: &Digest) -> String {
-    let mut output = String::new();
-
-    // Project header
-    output.push_str(&format!("# Project Digest: {}\n\n", digest.project_name));
-
-    // Language summary
-    output.push_str("## Language Breakdown\n\n");
-    if let Some(main) = &digest.main_language {
-        output.push_str(&format!("Main language: **{}**\n\n", main));
-    }
-
-    output.push_str("| Language | Lines |\n");
-    output.push_str("|----------|-------|\n");
-
-    let mut languages: Vec<(String, usize)> = digest
-        .language_breakdown
-        .iter()
-        .map(|(k, v)| (k.clone(), *v))
-        .collect();
-    languages.sort_by(|a, b| b.1.cmp(&a.1));
-
-    for (lang, count) in languages {
-        output.push_str(&format!("| {} | {} |\n", lang, count));
-    }
-    output.push_str("\n");
-
-    // Files
-    output.push_str("## Files\n\n");
-
-    for file in &digest.files {
-        output.push_str(&format!("### {}\n\n", file.path));
-
-        output.push_str("```");
-        if let Some(lang) = &file.language {
-            let lang_tag = match lang.as_str() {
-                "JavaScript" => "js",
-                "TypeScript" => "ts",
-                "Python" => "python",
-                "Rust" => "rust",
-                "Java" => "java",
-                "Go" => "go",
-                "C/C++" => "cpp",
-                "Ruby" => "ruby",
-                "PHP" => "php",
-                "Lua" => "lua",
-                "C#" => "csharp",
-                "GDScript C#" => "csharp",
-                "HTML" => "html",
-                "CSS" => "css",
-                "JSON" => "json",
-                "Markdown" => "md",
-                "YAML" => "yaml",
-                "TOML" => "toml",
-                "GDScript" => "gdscript",
-                "Godot Scene" => "gdscript",
-                "Godot Shader" => "glsl",
-                _ => "",
+            // Print to stdout, ASCII-armored when encrypted
+            let written = format!("{}\n", content);
+            let printed = if cli.encrypt.is_empty() {
+                written.clone()
+            } else {
+                String::from_utf8(encrypt(written.as_bytes(), &cli.encrypt, true)?)?
             };
-            if !lang_tag.is_empty() {
-                output.push_str(lang_tag);
-            }
-        }
-        output.push_str("\n");
-        output.push_str(&file.content);
-        output.push_str("\n```\n\n");
-    }
-
-    output
-}
-
-// Extension trait to make Path to string conversion more convenient
-trait PathToStringExt {
-    fn to_string_lossy(&self) -> String;
-}
-
-impl PathToStringExt for Path {
-    fn to_string_lossy(&self) -> String {
-        self.to_string_lossy().to_string()
-    }
-}
-
-// Function to detect if a project is a Godot project
-pub fn is_godot_project(project_path: &Path) -> bool {
-    // Check for project.godot file, which is the main project file for Godot projects
-    let project_godot_path = project_path.join("project.godot");
-    if project_godot_path.exists() {
-        return true;
-    }
-
-    // Check for godot/ or .godot/ directories
-    let godot_dir = project_path.join("godot");
-    let hidden_godot_dir = project_path.join(".godot");
-    if godot_dir.exists() || hidden_godot_dir.exists() {
-        return true;
-    }
-
-    // Look for .tscn or .gd files in the project
-    let mut builder = WalkBuilder::new(project_path);
-    builder
-        .hidden(false)
-        .git_ignore(true) // Always respect .gitignore for detection
-        .max_depth(Some(3)); // Only check a few levels deep for performance
-
-    let walker = builder.build();
-
-    for result in walker {
-        if let Ok(entry) = result {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if let Some(ext_str) = ext.to_str() {
-                        if ext_str == "tscn" || ext_str == "gd" {
-                            return true;
-                        }
-                    }
+            if printed.len() > TTY_LIMIT && !cli.force && std::io::stdout().is_terminal() {
+                return Err(DigestError::TooLargeForTerminal {
+                    size: format_size(printed.len() as u64),
                 }
+                .into());
             }
-        }
-    }
-
-    false
-}
-
-// Helper function to check if a file extension is a common code file
-fn is_common_code_file(ext: &str) -> bool {
-    matches!(
-        ext,
-        "rs" | "js"
-            | "ts"
-            | "py"
-            | "java"
-            | "go"
-            | "c"
-            | "cpp"
-            | "h"
-            | "hpp"
-            | "rb"
-            | "php"
-            | "cs"
-            | "html"
-            | "css"
-            | "json"
-            | "md"
-            | "yml"
-            | "yaml"
-            | "toml"
-            | "lua"
-            | "gd"
-            | "tscn"
-            | "tres"
-            | "shader"
-    )
-}
-
-// Function to detect if a project is a Lua project
-pub fn is_lua_project(project_path: &Path) -> bool {
-    // Common Lua project files
-    let lua_files = ["init.lua", "main.lua", "conf.lua", "config.lua"];
-    for file in lua_files.iter() {
-        if project_path.join(file).exists() {
-            return true;
-        }
-    }
-
-    // Look for a concentration of Lua files in the project
-    let mut builder = WalkBuilder::new(project_path);
-    builder
-        .hidden(false)
-        .git_ignore(true) // Always respect .gitignore for detection
-        .max_depth(Some(3)); // Only check a few levels deep for performance
-
-    let walker = builder.build();
-
-    let mut lua_file_count = 0;
-    for result in walker {
-        if let Ok(entry) = result {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if let Some(ext_str) = ext.to_str() {
-                        if ext_str == "lua" {
-                            lua_file_count += 1;
-                            if lua_file_count >= 5 {
-                                // If we find at least 5 Lua files, consider it a Lua project
-                                return true;
-                            }
-                        }
-                    }
-                }
+            print!("{}", printed);
+            if cli.checksum && !cli.porcelain {
+                eprintln!("SHA-256 of the digest: {}", sha256_hex(printed.as_bytes()));
             }
+            written
         }
-    }
+    };
 
-    false
+    Ok(written)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use digest::matcher::CompiledIgnore;
+    use digest::should_ignore;
     use std::path::PathBuf;
 
     #[test]
@@ -944,4 +3212,362 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_defaults_leave_out_test_artifacts() {
+        let defaults = build_ignore_patterns(&[], false);
+        let compiled = CompiledIgnore::new(&defaults);
+        for path in [
+            "coverage/lcov-report/index.html",
+            "web/.nyc_output/out.json",
+            "htmlcov/index.html",
+            "coverage.lcov",
+            "reports/junit-unit.xml",
+            ".pytest_cache/v/cache/nodeids",
+            "target/debug/build/out.rs",
+        ] {
+            assert!(compiled.is_ignored(Path::new(path)), "{}", path);
+        }
+        assert!(!compiled.is_ignored(Path::new("src/coverage.rs")));
+    }
+
+    #[test]
+    fn test_defaults_cover_every_sizable_language() {
+        let stats = |language: &str, lines: usize| LanguageStats {
+            lines,
+            ..LanguageStats::new(language.to_string())
+        };
+        let breakdown = finish_breakdown(vec![
+            stats("Rust", 8000),
+            stats("TypeScript", 1500),
+            stats("Python", 200),
+            stats("Markdown", 300),
+        ]);
+        let languages = default_languages(&breakdown);
+        assert_eq!(languages, vec!["Rust", "TypeScript"]);
+
+        let defaults = build_ignore_patterns(&languages, false);
+        assert!(defaults.contains("*.min.js"));
+        assert!(defaults.contains("target"));
+        // Python is under the threshold
+        assert!(!defaults.contains("__pycache__"));
+        // Godot's C# keeps its bin directory
+        let csharp = ["C#".to_string()];
+        assert!(build_ignore_patterns(&csharp, false).contains("obj"));
+        assert!(!build_ignore_patterns(&csharp, true).contains("obj"));
+    }
+
+    #[test]
+    fn test_defaults_leave_out_editor_files() {
+        let defaults = build_ignore_patterns(&[], false);
+        let compiled = CompiledIgnore::new(&defaults);
+        for path in [
+            ".idea/workspace.xml",
+            ".vscode/settings.json",
+            "app/app.iml",
+            ".vs/digest/v17/.suo",
+            "Project.suo",
+            ".fleet/settings.json",
+            ".zed/settings.json",
+            "src/.main.rs.swp",
+            "src/main.rs~",
+        ] {
+            assert!(compiled.is_ignored(Path::new(path)), "{}", path);
+        }
+        assert!(!compiled.is_ignored(Path::new("src/swap.rs")));
+        assert!(!compiled.is_ignored(Path::new("docs/zed.md")));
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(parse_format("markdown"), Ok(Format::Markdown));
+        assert_eq!(parse_format("json"), Ok(Format::Json));
+        assert_eq!(
+            parse_format("exec:./fmt --org"),
+            Ok(Format::Exec("./fmt --org".to_string()))
+        );
+        assert!(parse_format("exec:").is_err());
+        assert!(parse_format("exec:jq -r '.files[]").is_err());
+        assert!(parse_format("yaml").is_err());
+    }
+
+    #[test]
+    fn test_lockfile_modes_take_back_the_default_lockfile_patterns() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let defaults = |mode: &str| {
+            let cli = Cli::parse_from(["digest", "--no-global-gitignore", "--lockfiles", mode]);
+            let rust = ["Rust".to_string()];
+            let ignores = resolve_ignores(&cli.digest, root, &rust, &[], &[]).unwrap();
+            ignores
+                .iter()
+                .filter(|pattern| pattern.source == IgnoreSource::Defaults)
+                .map(|pattern| pattern.pattern.clone())
+                .collect::<Vec<_>>()
+        };
+        let excluded = defaults("exclude");
+        for pattern in ["*.lock", "yarn.lock", "package-lock.json", "Cargo.lock"] {
+            assert!(excluded.iter().any(|p| p == pattern), "{}", pattern);
+        }
+        let summarized = defaults("summary");
+        assert!(!summarized.iter().any(|p| is_lockfile_pattern(p)));
+        assert!(summarized.iter().any(|p| p == "target"));
+    }
+
+    #[test]
+    fn test_output_in_project() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("docs")).unwrap();
+
+        assert_eq!(
+            output_in_project(root, &root.join("docs/digest.md")).as_deref(),
+            Some("docs/digest.md")
+        );
+        assert_eq!(
+            output_in_project(&root.join("docs"), &root.join("digest.md")),
+            None
+        );
+        assert_eq!(
+            output_in_project(root, &root.join("missing/digest.md")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_write_output_returns_what_it_wrote() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cli = parse_cli(["digest"]).unwrap().digest;
+        assert_eq!(write_output(&cli, "# Digest", &None).unwrap(), "# Digest\n");
+
+        let path = temp_dir.path().join("digest.md");
+        let written = write_output(&cli, "# Digest", &Some(path.clone())).unwrap();
+        assert_eq!(written, fs::read_to_string(&path).unwrap());
+    }
+
+    #[test]
+    fn test_effective_config() {
+        let args: Vec<OsString> = [
+            "digest",
+            "--max-files=7",
+            "--grep",
+            "a",
+            "--grep",
+            "b",
+            "--checksum",
+        ]
+        .iter()
+        .map(OsString::from)
+        .collect();
+        let config = effective_config(&args);
+        assert_eq!(config["max-files"], "7");
+        assert_eq!(config["grep"], serde_json::json!(["a", "b"]));
+        assert_eq!(config["checksum"], true);
+        assert_eq!(config["manifest"], false);
+        assert_eq!(config["output"], serde_json::Value::Null);
+        assert!(!config.contains_key("help"));
+
+        let cli = with_effective_config(
+            Cli::try_parse_from(["digest", "symbol", "main", "--max-files=3"]).unwrap(),
+            &["digest", "symbol", "main", "--max-files=3"].map(OsString::from),
+        );
+        let Some(Commands::Symbol { digest, .. }) = &cli.command else {
+            panic!("expected the symbol command");
+        };
+        assert_eq!(digest.config["max-files"], "3");
+    }
+
+    #[test]
+    fn test_env_args() {
+        let options = [
+            ("link-files", "yes"),
+            ("max-tokens", "100k"),
+            ("max-files", "20"),
+            ("no-such-option", "1"),
+            ("strict", "0"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        assert_eq!(
+            env_args(&options).unwrap(),
+            vec!["--link-files", "--fail-if-over=100k", "--max-files=20"]
+        );
+        assert!(parse_cli(["digest", "--max-tokens=100k"]).is_err());
+    }
+
+    #[test]
+    fn test_env_max_tokens_rejects_bytes() {
+        let options = [("max-tokens".to_string(), "2MB".to_string())];
+        let err = env_args(&options).unwrap_err();
+        assert_eq!(classify(err.as_ref()), ErrorKind::Usage);
+        assert!(err.to_string().contains("DIGEST_MAX_TOKENS=2MB"), "{}", err);
+        let options = [("fail-if-over".to_string(), "2MB".to_string())];
+        assert_eq!(env_args(&options).unwrap(), vec!["--fail-if-over=2MB"]);
+    }
+
+    #[test]
+    fn test_switches_can_be_turned_off() {
+        let cli = parse_cli(["digest", "--link-files", "--no-link-files"]).unwrap();
+        assert!(!cli.digest.link_files);
+        let cli = parse_cli(["digest", "--no-link-files", "--link-files"]).unwrap();
+        assert!(cli.digest.link_files);
+        let cli = parse_cli(["digest", "--no-gitignore", "--gitignore"]).unwrap();
+        assert!(!cli.digest.no_gitignore);
+
+        let Some(Commands::Tree { digest }) =
+            parse_cli(["digest", "tree", "--checksum", "--no-checksum"])
+                .unwrap()
+                .command
+        else {
+            panic!("expected the tree command");
+        };
+        assert!(!digest.checksum);
+    }
+
+    #[test]
+    fn test_config_applies_to_subcommands() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join(CONFIG_FILE),
+            "max-files = 3\nchecksum = true\nignore-pattern = [\"*.snap\", \"fixtures/\"]\n",
+        )
+        .unwrap();
+
+        let args: Vec<OsString> = vec![
+            "digest".into(),
+            "tree".into(),
+            root.into(),
+            "--no-checksum".into(),
+            "--ignore-pattern=*.log".into(),
+        ];
+        let cli = apply_config(parse_cli(&args).unwrap(), args).unwrap();
+        let Some(Commands::Tree { digest }) = &cli.command else {
+            panic!("expected the tree command");
+        };
+        assert_eq!(digest.max_files, 3);
+        assert!(!digest.checksum);
+        // A list on the command line replaces the configured one
+        assert_eq!(digest.ignore_patterns, vec!["*.log"]);
+        assert_eq!(digest.config["max-files"], "3");
+    }
+
+    #[test]
+    fn test_tree_previews_the_configured_digest() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join(CONFIG_FILE),
+            "per-directory = \"out\"\nmax-files = 3\nignore-pattern = [\"*.snap\"]\n",
+        )
+        .unwrap();
+
+        let args: Vec<OsString> = vec!["digest".into(), "tree".into(), root.into()];
+        let cli = apply_config(parse_cli(&args).unwrap(), args).unwrap();
+        let Some(Commands::Tree { digest }) = &cli.command else {
+            panic!("expected the tree command");
+        };
+        let tree = tree_args(digest);
+        assert_eq!(tree.list, Some(ListMode::Tree));
+        assert_eq!(tree.per_directory, None);
+        assert_eq!(tree.ignore_patterns, vec!["*.snap"]);
+        assert_eq!(tree.max_files, 3);
+    }
+
+    #[test]
+    fn test_patterns_resolve_like_a_digest_run() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join(CONFIG_FILE),
+            "no-default-ignores = true\nno-global-gitignore = true\nignore-pattern = [\"*.snap\"]\n",
+        )
+        .unwrap();
+
+        let args: Vec<OsString> = vec!["digest".into(), "patterns".into(), root.into()];
+        let cli = apply_config(parse_cli(&args).unwrap(), args).unwrap();
+        let Some(Commands::Patterns { digest, .. }) = &cli.command else {
+            panic!("expected the patterns command");
+        };
+        let ignores = resolve_project(digest, root).unwrap().ignores;
+        let sources: Vec<(&str, &IgnoreSource)> = ignores
+            .iter()
+            .map(|pattern| (pattern.pattern.as_str(), &pattern.source))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                (".git", &IgnoreSource::Builtin),
+                ("*.snap", &IgnoreSource::CommandLine)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_follows_the_policy() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("secrets")).unwrap();
+        fs::write(
+            root.join("app.py"),
+            "def token():\n    api_key = \"sk-123\"\n    return api_key\n\n\ndef leak():\n    return \"INTERNAL ONLY\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("secrets/keys.py"), "def key():\n    return 1\n").unwrap();
+        fs::write(
+            root.join(digest::policy::POLICY_FILE),
+            "deny_paths = [\"secrets/\"]\nforbidden_strings = [\"INTERNAL ONLY\"]\nredact_patterns = ['sk-[0-9]+']\n",
+        )
+        .unwrap();
+        let policy = load_policy(None, root).unwrap();
+        let select = |selector: &str| {
+            let selector = Selector::parse(&format!("{}/{}", root.display(), selector)).unwrap();
+            extract_items(&[selector], &policy, root)
+        };
+
+        let extracts = select("app.py::token").unwrap();
+        assert!(extracts[0].excerpts[0].text.contains("<redacted:policy>"));
+        assert!(!extracts[0].excerpts[0].text.contains("sk-123"));
+        for selector in ["app.py::leak", "secrets/keys.py::key"] {
+            let err = select(selector).unwrap_err();
+            assert_eq!(
+                classify(err.as_ref()),
+                ErrorKind::PolicyViolation,
+                "{}",
+                selector
+            );
+        }
+    }
+
+    #[test]
+    fn test_explain_empty_names_each_selection() {
+        let cli = parse_cli(["digest"]).unwrap().digest;
+        let selected_out = [
+            (
+                3,
+                "didn't match --grep/--grep-exclude".to_string(),
+                "loosen --grep",
+            ),
+            (
+                2,
+                "don't define or reference 'Engine'".to_string(),
+                "check the symbol",
+            ),
+        ];
+        let message = explain_empty(
+            &cli,
+            Path::new("proj"),
+            &FilterCounts::default(),
+            5,
+            &selected_out,
+            1,
+        );
+        assert_eq!(
+            message,
+            "No files to digest in proj (5 files collected):\n  \
+             - 3 didn't match --grep/--grep-exclude\n  \
+             - 2 don't define or reference 'Engine'\n\
+             Hint: loosen --grep\n\
+             Note: the policy's deny_paths cover 1 of the collected files; selecting them fails the run"
+        );
+    }
 }
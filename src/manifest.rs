@@ -0,0 +1,158 @@
+// The --manifest sidecar: the included files, their hashes and token counts, and the options
+// in effect, as JSON for tools that shouldn't have to parse the rendered digest
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::hash::sha256_hex;
+use crate::tokens::estimate_tokens;
+use crate::Digest;
+
+/// What a written digest holds and how it was made
+#[derive(Serialize, Debug)]
+pub struct Manifest<'a> {
+    pub generated_by: &'a str,
+    pub digest_version: &'a str,
+    pub generated_at: Option<&'a str>,
+    pub project_name: &'a str,
+    /// HEAD commit of the digested project, when it's a git repository
+    pub commit: Option<&'a str>,
+    pub output: ManifestOutput,
+    pub files: Vec<ManifestFile<'a>>,
+    /// Every option by long name, with the value it had once digest.toml, DIGEST_*
+    /// variables, and the command line were applied
+    pub config: &'a BTreeMap<String, Value>,
+}
+
+/// The rendered digest the manifest describes
+#[derive(Serialize, Debug)]
+pub struct ManifestOutput {
+    /// The output file, or None for stdout
+    pub path: Option<String>,
+    pub format: String,
+    pub bytes: usize,
+    pub tokens: usize,
+    /// SHA-256 of the rendered text, before any encryption
+    pub sha256: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ManifestFile<'a> {
+    pub path: &'a str,
+    pub language: Option<&'a str>,
+    pub bytes: u64,
+    pub lines: usize,
+    /// Estimated tokens of the file's content
+    pub tokens: usize,
+    pub sha256: &'a str,
+}
+
+impl<'a> Manifest<'a> {
+    /// The manifest of `digest`, rendered in `format` as `rendered` and written to `output`
+    pub fn new(
+        digest: &'a Digest,
+        rendered: &str,
+        output: Option<&Path>,
+        format: &str,
+        config: &'a BTreeMap<String, Value>,
+    ) -> Self {
+        Manifest {
+            generated_by: &digest.generated_by,
+            digest_version: &digest.digest_version,
+            generated_at: digest.generated_at.as_deref(),
+            project_name: &digest.project_name,
+            commit: digest.git.as_ref().map(|git| git.commit.as_str()),
+            output: ManifestOutput {
+                path: output.map(|path| path.display().to_string()),
+                format: format.to_string(),
+                bytes: rendered.len(),
+                tokens: estimate_tokens(rendered),
+                sha256: sha256_hex(rendered.as_bytes()),
+            },
+            files: digest
+                .files
+                .iter()
+                .map(|file| ManifestFile {
+                    path: &file.path,
+                    language: file.language.as_deref(),
+                    bytes: file.size,
+                    lines: file.lines,
+                    tokens: estimate_tokens(&file.content),
+                    sha256: &file.sha256,
+                })
+                .collect(),
+            config,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::{GENERATED_BY, TOOL_VERSION};
+    use crate::FileInfo;
+
+    #[test]
+    fn test_manifest_lists_files_and_config() {
+        let digest = Digest {
+            schema_version: crate::schema::SCHEMA_VERSION,
+            generated_by: GENERATED_BY.to_string(),
+            digest_version: TOOL_VERSION.to_string(),
+            generated_at: None,
+            project_name: "demo".to_string(),
+            part: None,
+            instructions: None,
+            git: None,
+            submodules: Vec::new(),
+            entry_points: Vec::new(),
+            godot: None,
+            main_language: None,
+            language_breakdown: Vec::new(),
+            dependencies: Vec::new(),
+            excluded: Vec::new(),
+            skipped: Vec::new(),
+            images: Vec::new(),
+            lockfiles: Vec::new(),
+            duplicates: Vec::new(),
+            pii: Vec::new(),
+            files: vec![FileInfo::new(
+                "src/a.rs".to_string(),
+                Some("Rust".to_string()),
+                "abc".to_string(),
+            )],
+            summary: None,
+        };
+        let config = BTreeMap::from([
+            ("max-files".to_string(), Value::from("50")),
+            ("checksum".to_string(), Value::Bool(false)),
+        ]);
+        let manifest = Manifest::new(
+            &digest,
+            "abc",
+            Some(Path::new("digest.md")),
+            "markdown",
+            &config,
+        );
+        let json = serde_json::to_value(&manifest).unwrap();
+        let hash = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(json["project_name"], "demo");
+        assert_eq!(json["commit"], Value::Null);
+        assert_eq!(json["output"]["path"], "digest.md");
+        assert_eq!(json["output"]["bytes"], 3);
+        assert_eq!(json["output"]["sha256"], hash);
+        assert_eq!(
+            json["files"],
+            serde_json::json!([{
+                "path": "src/a.rs",
+                "language": "Rust",
+                "bytes": 3,
+                "lines": 1,
+                "tokens": estimate_tokens("abc"),
+                "sha256": hash,
+            }])
+        );
+        assert_eq!(json["config"]["max-files"], "50");
+        assert_eq!(json["config"]["checksum"], false);
+    }
+}
@@ -0,0 +1,623 @@
+// Ignore patterns compiled once per walk instead of re-parsed for every file
+use std::path::Path;
+
+use crate::glob;
+use crate::paths;
+use crate::test_paths::in_test_dir;
+
+/// A string test against the normalized path
+#[derive(Debug, Clone)]
+enum Atom {
+    Eq(String),
+    StartsWith(String),
+    EndsWith(String),
+    Contains(String),
+}
+
+impl Atom {
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            Atom::Eq(s) => path == s,
+            Atom::StartsWith(s) => path.starts_with(s.as_str()),
+            Atom::EndsWith(s) => path.ends_with(s.as_str()),
+            Atom::Contains(s) => path.contains(s.as_str()),
+        }
+    }
+
+    fn fold_case(&mut self) {
+        let (Atom::Eq(s) | Atom::StartsWith(s) | Atom::EndsWith(s) | Atom::Contains(s)) = self;
+        *s = s.to_lowercase();
+    }
+}
+
+fn any(atoms: &[Atom], path: &str) -> bool {
+    atoms.iter().any(|atom| atom.matches(path))
+}
+
+/// One test derived from a pattern; a pattern compiles to the checks its syntax calls for,
+/// tried in order
+#[derive(Debug, Clone)]
+enum Check {
+    /// Any of the atoms matches
+    Any(Vec<Atom>),
+    /// Both lists have a matching atom; an empty list always matches
+    Both(Vec<Atom>, Vec<Atom>),
+    /// `**/test*/**`: a directory whose name starts with `test`
+    TestPrefixDir,
+    /// `**/prefix*suffix/`: a path segment with the given prefix and suffix
+    SegmentWildcard { prefix: String, suffix: String },
+    /// A pattern with a single `*` and no `**`
+    Star {
+        prefix: String,
+        suffix: String,
+        starts_star: bool,
+        ends_star: bool,
+        prefix_dir: String,
+        inner_prefix_dir: String,
+        joined: String,
+    },
+    /// `?`, character classes, and escapes
+    Glob(String),
+}
+
+impl Check {
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            Check::Any(atoms) => any(atoms, path),
+            Check::Both(first, second) => {
+                (first.is_empty() || any(first, path)) && (second.is_empty() || any(second, path))
+            }
+            Check::TestPrefixDir => in_test_dir(path, |dir| dir.starts_with("test")),
+            Check::SegmentWildcard { prefix, suffix } => path.split('/').any(|segment| {
+                !segment.is_empty()
+                    && segment.starts_with(prefix.as_str())
+                    && segment.ends_with(suffix.as_str())
+            }),
+            Check::Star {
+                prefix,
+                suffix,
+                starts_star,
+                ends_star,
+                prefix_dir,
+                inner_prefix_dir,
+                joined,
+            } => {
+                if *starts_star && path.ends_with(suffix.as_str()) {
+                    // *suffix pattern (e.g., "*.js" or "*~"): the star stays within the file name
+                    let last_segment = path.split('/').next_back().unwrap_or("");
+                    last_segment.ends_with(suffix.as_str())
+                } else if *ends_star && path.starts_with(prefix.as_str()) {
+                    // prefix* pattern: the prefix must match a whole path component
+                    path == prefix
+                        || path.starts_with(prefix_dir.as_str())
+                        || path.contains(inner_prefix_dir.as_str())
+                } else if !prefix.is_empty() && !suffix.is_empty() {
+                    // prefix*suffix pattern: the star spans part of a single path component
+                    let fits = |candidate: &str| {
+                        candidate.len() >= joined.len()
+                            && candidate.starts_with(prefix.as_str())
+                            && candidate.ends_with(suffix.as_str())
+                            && !candidate[prefix.len()..candidate.len() - suffix.len()]
+                                .contains('/')
+                    };
+                    if prefix.contains('/') {
+                        // Like the other patterns with a slash, matched from any directory
+                        // boundary, against the rest of the path or a directory in it
+                        let starts = path.match_indices('/').map(|(slash, _)| slash + 1);
+                        std::iter::once(0).chain(starts).any(|start| {
+                            let rest = &path[start..];
+                            rest.match_indices('/')
+                                .map(|(end, _)| &rest[..end])
+                                .chain([rest])
+                                .any(fits)
+                        })
+                    } else {
+                        path.split('/').any(fits)
+                    }
+                } else {
+                    false
+                }
+            }
+            Check::Glob(pattern) => glob::path_matches(pattern, path),
+        }
+    }
+
+    /// Lowercase every string the check compares, for matching lowercased paths
+    fn fold_case(&mut self) {
+        match self {
+            Check::Any(atoms) => atoms.iter_mut().for_each(Atom::fold_case),
+            Check::Both(first, second) => first.iter_mut().chain(second).for_each(Atom::fold_case),
+            Check::TestPrefixDir => {}
+            Check::SegmentWildcard { prefix, suffix } => {
+                for s in [prefix, suffix] {
+                    *s = s.to_lowercase();
+                }
+            }
+            Check::Star {
+                prefix,
+                suffix,
+                prefix_dir,
+                inner_prefix_dir,
+                joined,
+                ..
+            } => {
+                for s in [prefix, suffix, prefix_dir, inner_prefix_dir, joined] {
+                    *s = s.to_lowercase();
+                }
+            }
+            Check::Glob(pattern) => *pattern = pattern.to_lowercase(),
+        }
+    }
+}
+
+/// The checks for one pattern, in the order they are tried, each with the pattern it reports
+fn compile_pattern<'a>(raw: &'a str, checks: &mut Vec<(Check, &'a str)>) {
+    use Atom::*;
+
+    // Special cases for patterns that are common and important
+    match raw {
+        "**/test/**" => checks.push((
+            Check::Any(vec![Contains("/test/".into()), StartsWith("test/".into())]),
+            raw,
+        )),
+        "**/test*/**" => checks.push((Check::TestPrefixDir, raw)),
+        "**/*.md" => checks.push((Check::Any(vec![EndsWith(".md".into())]), raw)),
+        "**/*.js" => checks.push((Check::Any(vec![EndsWith(".js".into())]), raw)),
+        "node_modules/" | "build/" => {
+            let dir = &raw[..raw.len() - 1];
+            checks.push((
+                Check::Any(vec![
+                    StartsWith(format!("{}/", dir)),
+                    Contains(format!("/{}/", dir)),
+                ]),
+                raw,
+            ));
+        }
+        _ => {}
+    }
+
+    // Handle different gitignore pattern types
+    let pattern = glob::trim_trailing_spaces(raw);
+
+    // Empty lines and comments match nothing
+    if pattern.is_empty() || pattern.starts_with('#') {
+        return;
+    }
+
+    // Single-character wildcards, character classes, and escapes go through the glob matcher
+    if pattern.contains(['?', '[', '\\']) {
+        checks.push((Check::Glob(pattern.to_string()), pattern));
+        return;
+    }
+
+    // Handle **/ pattern at the beginning (match any directory depth)
+    if let Some(suffix) = pattern.strip_prefix("**/") {
+        if suffix.starts_with('*') && suffix.contains('.') {
+            // File extensions like **/*.js
+            let extension = suffix.split('.').next_back().unwrap_or("");
+            if !extension.is_empty() {
+                checks.push((
+                    Check::Any(vec![EndsWith(format!(".{}", extension))]),
+                    pattern,
+                ));
+            }
+        } else {
+            // The suffix appears in the path on directory boundaries
+            let mut atoms = vec![Eq(suffix.into()), EndsWith(format!("/{}", suffix))];
+            if let Some(dir) = suffix.strip_suffix('/') {
+                atoms.push(EndsWith(dir.into()));
+                atoms.push(Contains(format!("{}/", dir)));
+            }
+            checks.push((Check::Any(atoms), pattern));
+        }
+    }
+
+    // Handle pattern ending with /** (match any subdirectory)
+    // For example, "build/**" should match "build/file.js" but not "builds/file.js"
+    if let Some(prefix) = pattern.strip_suffix("/**") {
+        checks.push((
+            Check::Any(vec![
+                StartsWith(format!("{}/", prefix)),
+                Contains(format!("/{}/", prefix)),
+            ]),
+            pattern,
+        ));
+    }
+
+    // Handle /**/ pattern (matches any directory in the middle)
+    if pattern.contains("/**/") {
+        let mut segments = pattern.split("/**/");
+        let prefix = segments.next().unwrap_or("");
+        let suffix = segments.next().unwrap_or("");
+        let prefix_atoms = if prefix.is_empty() {
+            Vec::new()
+        } else {
+            vec![StartsWith(prefix.into()), Contains(format!("/{}", prefix))]
+        };
+        let suffix_atoms = if suffix.is_empty() {
+            Vec::new()
+        } else {
+            vec![EndsWith(suffix.into()), Contains(format!("{}/", suffix))]
+        };
+        checks.push((Check::Both(prefix_atoms, suffix_atoms), pattern));
+    }
+
+    // Directory pattern (ends with slash)
+    if let Some(dir_name) = pattern.strip_suffix('/') {
+        if dir_name.contains('*') {
+            // Wildcard directory patterns like "**/test*/" match "test/", "src/testing/", ...
+            if let Some(wildcard_part) = dir_name.strip_prefix("**/") {
+                let parts: Vec<&str> = wildcard_part.split('*').collect();
+                if parts.len() == 2 {
+                    checks.push((
+                        Check::SegmentWildcard {
+                            prefix: parts[0].into(),
+                            suffix: parts[1].into(),
+                        },
+                        pattern,
+                    ));
+                }
+            }
+            return;
+        }
+
+        // "test/" should match "test/file.rs" or "src/test/file.rs" but not "testing/file.rs"
+        checks.push((
+            Check::Any(vec![
+                Eq(dir_name.into()),
+                StartsWith(format!("{}/", dir_name)),
+                Contains(format!("/{}/", dir_name)),
+            ]),
+            pattern,
+        ));
+        return;
+    }
+
+    // Special case for *.test.* pattern
+    if pattern == "*.test.*" {
+        checks.push((Check::Any(vec![Contains(".test.".into())]), pattern));
+    }
+
+    if pattern.contains('*') && !pattern.contains("**") {
+        let parts: Vec<&str> = pattern.split('*').collect();
+        if parts.len() == 2 {
+            checks.push((
+                Check::Star {
+                    prefix: parts[0].into(),
+                    suffix: parts[1].into(),
+                    starts_star: pattern.starts_with('*'),
+                    ends_star: pattern.ends_with('*'),
+                    prefix_dir: format!("{}/", parts[0]),
+                    inner_prefix_dir: format!("/{}/", parts[0]),
+                    joined: format!("{}{}", parts[0], parts[1]),
+                },
+                pattern,
+            ));
+        }
+    } else {
+        // Direct match, as the whole path or on directory boundaries
+        checks.push((
+            Check::Any(vec![
+                Eq(pattern.into()),
+                StartsWith(format!("{}/", pattern)),
+                EndsWith(format!("/{}", pattern)),
+                Contains(format!("/{}/", pattern)),
+            ]),
+            pattern,
+        ));
+    }
+}
+
+/// A set of ignore patterns compiled for repeated matching during a walk
+#[derive(Debug, Clone)]
+pub struct CompiledIgnore<'a> {
+    checks: Vec<(Check, &'a str)>,
+    /// Parallel to `checks`: whether the check comes from a `!pattern` that re-includes paths
+    negated: Vec<bool>,
+    case_insensitive: bool,
+}
+
+impl<'a> CompiledIgnore<'a> {
+    /// Compile `patterns`; where several match a path, the first one is reported. A `!pattern`
+    /// re-includes what the patterns before it ignore, as in a gitignore file, so it only means
+    /// something in an ordered list. Matching ignores case where the file system does
+    /// (`paths::CASE_INSENSITIVE`).
+    pub fn new<S>(patterns: impl IntoIterator<Item = &'a S>) -> Self
+    where
+        S: AsRef<str> + ?Sized + 'a,
+    {
+        CompiledIgnore::with_case(patterns, paths::CASE_INSENSITIVE)
+    }
+
+    /// Compile `patterns`, matching `Node_Modules/` against `node_modules/` when
+    /// `case_insensitive` is set
+    pub fn with_case<S>(patterns: impl IntoIterator<Item = &'a S>, case_insensitive: bool) -> Self
+    where
+        S: AsRef<str> + ?Sized + 'a,
+    {
+        let mut checks = Vec::new();
+        let mut negated = Vec::new();
+        let mut patterns = patterns.into_iter().peekable();
+        // Any set of patterns ignores the .git directory
+        if patterns.peek().is_some() {
+            checks.push((
+                Check::Any(vec![
+                    Atom::Contains("/.git/".into()),
+                    Atom::StartsWith(".git/".into()),
+                    Atom::Eq(".git".into()),
+                ]),
+                ".git",
+            ));
+        }
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            match pattern.strip_prefix('!') {
+                Some(reincluded) => {
+                    let mut reincludes = Vec::new();
+                    compile_pattern(reincluded, &mut reincludes);
+                    negated.resize(checks.len(), false);
+                    negated.resize(checks.len() + reincludes.len(), true);
+                    checks.extend(reincludes);
+                }
+                None => compile_pattern(pattern, &mut checks),
+            }
+        }
+        negated.resize(checks.len(), false);
+        if case_insensitive {
+            checks.iter_mut().for_each(|(check, _)| check.fold_case());
+        }
+        CompiledIgnore {
+            checks,
+            negated,
+            case_insensitive,
+        }
+    }
+
+    /// The pattern that excludes `path`, if any. Give paths relative to the project root, so
+    /// a pattern such as `build` can't match a directory the project happens to sit in.
+    pub fn matching(&self, path: &Path) -> Option<&'a str> {
+        // Forward slashes, without a `\\?\` long-path prefix
+        let path = paths::slash(path);
+        let path = if self.case_insensitive {
+            path.to_lowercase()
+        } else {
+            path
+        };
+        // The last pattern to decide wins; among ignores in a row, the first is reported
+        let mut matched = None;
+        for ((check, pattern), &negated) in self.checks.iter().zip(&self.negated) {
+            if negated {
+                if matched.is_some() && check.matches(&path) {
+                    matched = None;
+                }
+            } else if matched.is_none() && check.matches(&path) {
+                matched = Some(*pattern);
+            }
+        }
+        matched
+    }
+
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.matching(path).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_compiled_ignore_reports_matching_pattern() {
+        let patterns: HashSet<String> = ["target/", "*.log", "tests/", "x?.rs"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let compiled = CompiledIgnore::new(&patterns);
+
+        assert_eq!(
+            compiled.matching(Path::new("/repo/target/debug/a.rs")),
+            Some("target/")
+        );
+        assert_eq!(
+            compiled.matching(Path::new("/repo/logs/run.log")),
+            Some("*.log")
+        );
+        assert_eq!(
+            compiled.matching(Path::new("/repo/tests/a.rs")),
+            Some("tests/")
+        );
+        assert_eq!(
+            compiled.matching(Path::new("/repo/src/tests/a.rs")),
+            Some("tests/")
+        );
+        assert_eq!(compiled.matching(Path::new("/repo/x1.rs")), Some("x?.rs"));
+        assert_eq!(
+            compiled.matching(Path::new("/repo/.git/HEAD")),
+            Some(".git")
+        );
+        assert!(!compiled.is_ignored(Path::new("/repo/src/main.rs")));
+    }
+
+    #[test]
+    fn test_star_within_a_path_component() {
+        let patterns = ["junit*.xml".to_string(), "src/gen*.rs".to_string()];
+        let compiled = CompiledIgnore::new(&patterns);
+        for (path, ignored) in [
+            ("/repo/junit.xml", true),
+            ("/repo/reports/junit-results.xml", true),
+            ("/repo/reports/TEST-junit.xml", false),
+            ("/repo/junit/results.xml", false),
+            ("/repo/src/generated.rs", true),
+            ("/repo/src/gen.rs", true),
+            ("/repo/src/nested/gen_a.rs", false),
+            ("/repo/lib/src/gen_b.rs", true),
+        ] {
+            assert_eq!(compiled.is_ignored(Path::new(path)), ignored, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_prefix_star_suffix_stays_within_one_component() {
+        let patterns = ["junit*.xml", "ab*ba", "src/gen*.rs"];
+        let cases = [
+            // (path, case-insensitive, pattern)
+            ("junit-unit.xml", false, Some("junit*.xml")),
+            ("reports/junit.xml", false, Some("junit*.xml")),
+            // A directory named like the pattern covers what it holds
+            ("out/junit-report.xml/part.txt", false, Some("junit*.xml")),
+            // The prefix starts a component and the suffix ends one
+            ("reports/TEST-junit-a.xml", false, None),
+            ("reports/junit-a.xml.bak", false, None),
+            // The star doesn't cross a slash
+            ("junit/reports/a.xml", false, None),
+            ("src/gen/a.rs", false, None),
+            ("src/generated.rs", false, Some("src/gen*.rs")),
+            ("crates/app/src/gen_b.rs", false, Some("src/gen*.rs")),
+            // Prefix and suffix don't overlap
+            ("aba", false, None),
+            ("abba", false, Some("ab*ba")),
+            ("Reports/JUnit-Unit.XML", true, Some("junit*.xml")),
+            ("Reports/JUnit-Unit.XML", false, None),
+            (r"C:\repo\reports\junit-a.xml", false, Some("junit*.xml")),
+        ];
+        for (path, case_insensitive, expected) in cases {
+            let compiled = CompiledIgnore::with_case(&patterns, case_insensitive);
+            assert_eq!(
+                compiled.matching(Path::new(path)),
+                expected,
+                "{} (case-insensitive: {})",
+                path,
+                case_insensitive
+            );
+        }
+    }
+
+    #[test]
+    fn test_leading_star_within_the_file_name() {
+        let patterns = ["*~".to_string(), "*_test.go".to_string()];
+        let compiled = CompiledIgnore::new(&patterns);
+        for (path, ignored) in [
+            ("/repo/src/main.rs~", true),
+            ("/repo/pkg/parse_test.go", true),
+            ("/repo/pkg/parse.go", false),
+            ("/repo/backup~/main.rs", false),
+        ] {
+            assert_eq!(compiled.is_ignored(Path::new(path)), ignored, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_leading_star_suffix_ends_the_file_name() {
+        let patterns = ["*~", "*_test.go", "*.swp", "*.iml"];
+        let cases = [
+            // (path, case-insensitive, pattern)
+            ("notes.txt~", false, Some("*~")),
+            ("docs/~", false, Some("*~")),
+            ("_test.go", false, Some("*_test.go")),
+            ("pkg/parse_test.go", false, Some("*_test.go")),
+            ("src/.main.rs.swp", false, Some("*.swp")),
+            // The suffix has to end the path, not a directory or file name part way through it
+            ("backup~/main.rs", false, None),
+            ("pkg/parse_test.go.orig", false, None),
+            ("pkg/parsetest.go", false, None),
+            ("app/App.IML", true, Some("*.iml")),
+            ("app/App.IML", false, None),
+            (r"C:\repo\src\main.rs~", false, Some("*~")),
+        ];
+        for (path, case_insensitive, expected) in cases {
+            let compiled = CompiledIgnore::with_case(&patterns, case_insensitive);
+            assert_eq!(
+                compiled.matching(Path::new(path)),
+                expected,
+                "{} (case-insensitive: {})",
+                path,
+                case_insensitive
+            );
+        }
+    }
+
+    #[test]
+    fn test_windows_paths_matrix() {
+        let patterns = [
+            "node_modules/",
+            "target/",
+            "build/",
+            "*.min.js",
+            "tests/",
+            "x?.rs",
+            "docs/**",
+        ];
+        let cases = [
+            // (path, case-insensitive, pattern)
+            (r"C:\repo\node_modules\x.js", false, Some("node_modules/")),
+            (r"C:\repo\Node_Modules\x.js", true, Some("node_modules/")),
+            (r"C:\repo\Node_Modules\x.js", false, None),
+            (r"\\?\C:\repo\target\debug\a.rs", false, Some("target/")),
+            (
+                r"\\?\UNC\srv\share\repo\build\out.js",
+                false,
+                Some("build/"),
+            ),
+            (r"\\srv\share\repo\Build\out.js", true, Some("build/")),
+            (r"C:\repo\dist\App.Min.JS", true, Some("*.min.js")),
+            (r"C:\repo\dist\App.Min.JS", false, None),
+            (r"C:\repo\tests\a.rs", false, Some("tests/")),
+            (r"C:\repo\src\tests\a.rs", false, Some("tests/")),
+            (r"C:\repo\X1.RS", true, Some("x?.rs")),
+            (r"C:\repo\Docs\guide\a.md", true, Some("docs/**")),
+            (r"C:\repo\.GIT\HEAD", true, Some(".git")),
+            (r"C:\repo\src\main.rs", true, None),
+        ];
+        for (path, case_insensitive, expected) in cases {
+            let compiled = CompiledIgnore::with_case(&patterns, case_insensitive);
+            assert_eq!(
+                compiled.matching(Path::new(path)),
+                expected,
+                "{} (case-insensitive: {})",
+                path,
+                case_insensitive
+            );
+        }
+    }
+
+    #[test]
+    fn test_relative_paths_match_on_component_boundaries() {
+        let compiled = CompiledIgnore::new(["build", "target", ".git"]);
+        assert_eq!(compiled.matching(Path::new("build/out.js")), Some("build"));
+        assert_eq!(
+            compiled.matching(Path::new("crates/app/target/debug/a.rs")),
+            Some("target")
+        );
+        assert_eq!(compiled.matching(Path::new(".git/HEAD")), Some(".git"));
+        assert!(!compiled.is_ignored(Path::new("src/builder.rs")));
+    }
+
+    #[test]
+    fn test_negated_patterns_reinclude() {
+        let patterns = [
+            "*.js",
+            "fixtures/",
+            "!dist/app.js",
+            "!fixtures/keep/",
+            "dist/",
+        ];
+        let compiled = CompiledIgnore::new(patterns);
+        assert_eq!(compiled.matching(Path::new("src/a.js")), Some("*.js"));
+        assert_eq!(
+            compiled.matching(Path::new("fixtures/a.rs")),
+            Some("fixtures/")
+        );
+        assert_eq!(compiled.matching(Path::new("fixtures/keep/a.rs")), None);
+        // A later pattern ignores the re-included file again
+        assert_eq!(compiled.matching(Path::new("dist/app.js")), Some("dist/"));
+        assert_eq!(
+            CompiledIgnore::new(["*.js", "!dist/app.js"]).matching(Path::new("dist/app.js")),
+            None
+        );
+        // Negations alone ignore nothing, and the .git directory always stays out
+        let compiled = CompiledIgnore::new(["!src/"]);
+        assert!(!compiled.is_ignored(Path::new("src/main.rs")));
+        assert_eq!(compiled.matching(Path::new(".git/HEAD")), Some(".git"));
+    }
+}
@@ -0,0 +1,98 @@
+// Minified and bundled JavaScript and CSS, recognized by their content rather than a `.min.`
+// in the name
+use std::path::Path;
+
+/// Extensions whose content is checked
+pub const CHECKED_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "css"];
+
+/// Average length of the non-blank lines above which a file counts as minified
+pub const MAX_AVERAGE_LINE: usize = 200;
+
+/// Files smaller than this are too short for their line lengths to say much
+const MIN_BYTES: usize = 1024;
+
+/// Runtime code that bundlers inline into their output
+const BUNDLE_MARKERS: &[&str] = &["__webpack_require__", "parcelRequire", "__vite__mapDeps"];
+
+/// How far into a file a bundler's runtime is looked for: bundles open with it, while source
+/// that merely names it (a webpack plugin, a loader's tests) usually does so further down
+const BUNDLE_HEADER_BYTES: usize = 512;
+
+/// Whether the JavaScript or CSS file at `path` looks minified or bundled: it links a source
+/// map, opens with a bundler's runtime, or its lines average over `MAX_AVERAGE_LINE` characters
+pub fn is_minified(path: &Path, content: &str) -> bool {
+    let checked = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| CHECKED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+    if !checked {
+        return false;
+    }
+    has_source_map(content)
+        || has_bundle_runtime(content)
+        || average_line_length(content) > MAX_AVERAGE_LINE && content.len() >= MIN_BYTES
+}
+
+/// A `sourceMappingURL` comment on one of the last lines, as build tools append it
+fn has_source_map(content: &str) -> bool {
+    content
+        .lines()
+        .rev()
+        .filter(|line| !line.trim().is_empty())
+        .take(3)
+        .any(|line| {
+            let line = line.trim_start();
+            line.starts_with("//# sourceMappingURL=") || line.starts_with("/*# sourceMappingURL=")
+        })
+}
+
+/// One of `BUNDLE_MARKERS` within the first `BUNDLE_HEADER_BYTES` of the file
+fn has_bundle_runtime(content: &str) -> bool {
+    let mut end = content.len().min(BUNDLE_HEADER_BYTES);
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    let header = &content[..end];
+    BUNDLE_MARKERS.iter().any(|marker| header.contains(marker))
+}
+
+fn average_line_length(content: &str) -> usize {
+    let (lines, chars) = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .fold((0, 0), |(lines, chars), line| {
+            (lines + 1, chars + line.len())
+        });
+    chars.checked_div(lines).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_minified() {
+        let minified = format!("!function(){{{}}}();\n", "var a=1,b=2;".repeat(200));
+        let readable = "function add(a, b) {\n    return a + b;\n}\n".repeat(40);
+        let mapped = format!("{}//# sourceMappingURL=app.js.map\n", readable);
+        let bundled = format!("var m = __webpack_require__(42);\n{}", readable);
+
+        assert!(is_minified(Path::new("dist/bundle.js"), &minified));
+        assert!(is_minified(Path::new("static/app.css"), &minified));
+        assert!(is_minified(Path::new("dist/app.js"), &mapped));
+        assert!(is_minified(Path::new("dist/main.mjs"), &bundled));
+
+        assert!(!is_minified(Path::new("src/app.js"), &readable));
+        // Only JavaScript and CSS are checked
+        assert!(!is_minified(Path::new("data/rows.json"), &minified));
+        // A mention of source maps in the middle of a file isn't a link to one
+        let mentioned = format!("// sourceMappingURL= comments are stripped\n{}", readable);
+        assert!(!is_minified(Path::new("src/strip.js"), &mentioned));
+        // Source that names a bundler's runtime below its header isn't a bundle
+        let plugin = format!("{}const name = \"__webpack_require__\";\n", readable);
+        assert!(!is_minified(Path::new("src/plugin.js"), &plugin));
+        // A single long line in a short file isn't enough to tell
+        assert!(!is_minified(Path::new("src/config.js"), &"x".repeat(300)));
+    }
+}
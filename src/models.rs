@@ -0,0 +1,78 @@
+// Known LLM context windows and input pricing for budget estimates
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelSpec {
+    /// Name accepted by --model
+    pub name: &'static str,
+    /// Maximum number of input tokens the model accepts
+    pub context_window: usize,
+    /// List price in USD per million input tokens
+    pub input_usd_per_mtok: f64,
+}
+
+/// Models digest knows about. Prices are list prices for uncached input and drift over
+/// time, so estimates are indicative only.
+pub const MODELS: &[ModelSpec] = &[
+    ModelSpec {
+        name: "claude-opus",
+        context_window: 200_000,
+        input_usd_per_mtok: 15.0,
+    },
+    ModelSpec {
+        name: "claude-sonnet",
+        context_window: 200_000,
+        input_usd_per_mtok: 3.0,
+    },
+    ModelSpec {
+        name: "claude-haiku",
+        context_window: 200_000,
+        input_usd_per_mtok: 0.8,
+    },
+    ModelSpec {
+        name: "gpt-4o",
+        context_window: 128_000,
+        input_usd_per_mtok: 2.5,
+    },
+    ModelSpec {
+        name: "gpt-4o-mini",
+        context_window: 128_000,
+        input_usd_per_mtok: 0.15,
+    },
+    ModelSpec {
+        name: "gemini-1.5-pro",
+        context_window: 2_000_000,
+        input_usd_per_mtok: 1.25,
+    },
+];
+
+/// Look up a model by its --model name (case-insensitive)
+pub fn find_model(name: &str) -> Option<&'static ModelSpec> {
+    MODELS.iter().find(|m| m.name.eq_ignore_ascii_case(name))
+}
+
+impl ModelSpec {
+    /// Estimated cost in USD of sending `tokens` input tokens
+    pub fn input_cost(&self, tokens: usize) -> f64 {
+        tokens as f64 * self.input_usd_per_mtok / 1_000_000.0
+    }
+
+    /// Whether `tokens` input tokens fit in the context window
+    pub fn fits(&self, tokens: usize) -> bool {
+        tokens <= self.context_window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_model_and_cost() {
+        let model = find_model("Claude-Sonnet").unwrap();
+        assert_eq!(model.name, "claude-sonnet");
+        assert!((model.input_cost(1_000_000) - 3.0).abs() < f64::EPSILON);
+        assert!(model.fits(200_000));
+        assert!(!model.fits(200_001));
+        assert!(find_model("unknown-model").is_none());
+    }
+}
@@ -0,0 +1,100 @@
+// Jupyter notebooks as readable scripts: cell sources only, outputs and embedded images dropped
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Notebooks may be this many times `--max-file-size` on disk, since outputs usually dominate
+/// their size; the converted text must still fit the limit
+pub const RAW_SIZE_FACTOR: u64 = 10;
+
+/// Inline base64 images in markdown, e.g. `![plot](data:image/png;base64,...)`
+fn data_uri() -> &'static Regex {
+    static DATA_URI: OnceLock<Regex> = OnceLock::new();
+    DATA_URI.get_or_init(|| {
+        Regex::new(r"data:[a-z]+/[a-z0-9.+-]+;base64,[A-Za-z0-9+/=\s]+").expect("valid regex")
+    })
+}
+
+/// A cell's source, stored either as one string or as a list of lines
+fn cell_source(cell: &Value) -> String {
+    match &cell["source"] {
+        Value::String(source) => source.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Render a notebook in the percent format editors and jupytext understand: `# %%` before
+/// each code cell and `# %% [markdown]` before markdown cells, whose lines are commented out.
+/// Returns `None` if `json` isn't a notebook.
+pub fn notebook_to_text(json: &str) -> Option<String> {
+    let notebook: Value = serde_json::from_str(json).ok()?;
+    let cells = notebook["cells"].as_array()?;
+
+    let language = notebook["metadata"]["kernelspec"]["language"]
+        .as_str()
+        .or_else(|| notebook["metadata"]["language_info"]["name"].as_str())
+        .unwrap_or("python");
+    let comment = match language {
+        "javascript" | "typescript" | "rust" | "c++" | "go" | "scala" | "java" => "//",
+        "matlab" | "octave" => "%",
+        _ => "#",
+    };
+
+    let mut output = String::new();
+    for cell in cells {
+        let source = cell_source(cell);
+        let source = source.trim_end();
+        match cell["cell_type"].as_str() {
+            Some("code") => {
+                output.push_str(&format!("{} %%\n", comment));
+                output.push_str(source);
+            }
+            Some("markdown") => {
+                output.push_str(&format!("{} %% [markdown]\n", comment));
+                let source = data_uri().replace_all(source, "embedded image removed");
+                let commented: Vec<String> = source
+                    .lines()
+                    .map(|line| format!("{} {}", comment, line).trim_end().to_string())
+                    .collect();
+                output.push_str(&commented.join("\n"));
+            }
+            // Raw cells are passed through for nbconvert; they rarely matter to a reader
+            _ => continue,
+        }
+        output.push_str("\n\n");
+    }
+
+    Some(output.trim_end().to_string() + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_outputs_and_images() {
+        let notebook = r##"{
+            "metadata": {"kernelspec": {"language": "python"}},
+            "nbformat": 4,
+            "cells": [
+                {"cell_type": "markdown", "source": ["# Analysis\n", "![plot](data:image/png;base64,iVBORw0KGgo=)"]},
+                {"cell_type": "code", "source": "import pandas as pd\ndf = pd.read_csv('x.csv')",
+                 "outputs": [{"output_type": "display_data", "data": {"image/png": "iVBORw0KGgo="}}]},
+                {"cell_type": "raw", "source": "ignored"}
+            ]
+        }"##;
+
+        assert_eq!(
+            notebook_to_text(notebook).unwrap(),
+            "# %% [markdown]\n# # Analysis\n# ![plot](embedded image removed)\n\n\
+             # %%\nimport pandas as pd\ndf = pd.read_csv('x.csv')\n"
+        );
+    }
+
+    #[test]
+    fn test_rejects_non_notebooks() {
+        assert!(notebook_to_text("not json").is_none());
+        assert!(notebook_to_text(r#"{"name": "package"}"#).is_none());
+    }
+}
@@ -0,0 +1,113 @@
+// Launching a written digest for --open: HTML in the browser, anything else in the editor
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::{DigestError, Result};
+
+/// Extensions that open in a browser rather than an editor
+const BROWSER_EXTENSIONS: &[&str] = &["html", "htm"];
+
+/// The platform's "open with the default application" command
+fn system_opener() -> Vec<String> {
+    let command: &[&str] = if cfg!(target_os = "macos") {
+        &["open"]
+    } else if cfg!(windows) {
+        &["cmd", "/C", "start", ""]
+    } else {
+        &["xdg-open"]
+    };
+    command.iter().map(|part| part.to_string()).collect()
+}
+
+/// The command that opens `path`, given `lookup` for environment variables: `$BROWSER` for
+/// HTML, `$VISUAL` then `$EDITOR` for the rest, and the system opener when those are unset.
+/// Variables may hold arguments, split as a shell would, as in `EDITOR="code --wait"` or
+/// `VISUAL="'/Applications/Sublime Text.app/Contents/MacOS/subl' -w"`; one with unbalanced
+/// quotes counts as unset.
+pub fn open_command(path: &Path, lookup: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let html = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| BROWSER_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+    let variables: &[&str] = if html {
+        &["BROWSER"]
+    } else {
+        &["VISUAL", "EDITOR"]
+    };
+    let mut command = variables
+        .iter()
+        .filter_map(|variable| lookup(variable))
+        .filter_map(|value| shlex::split(&value))
+        .find(|command| !command.is_empty())
+        .unwrap_or_else(system_opener);
+    command.push(path.display().to_string());
+    command
+}
+
+/// Open `path` and wait for the command to return, so a terminal editor has the terminal
+pub fn open(path: &Path) -> Result<()> {
+    let command = open_command(path, |variable| std::env::var(variable).ok());
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .status()
+        .map_err(DigestError::io("run", command[0].clone()))?;
+    if !status.success() {
+        let failure = format!("{} exited with {}", command[0], status);
+        return Err(DigestError::io("open", path)(std::io::Error::other(
+            failure,
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(path: &str, variables: &[(&str, &str)]) -> Vec<String> {
+        open_command(Path::new(path), |name| {
+            variables
+                .iter()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_open_command() {
+        let env = [("EDITOR", "vim"), ("BROWSER", "firefox")];
+        assert_eq!(command("digest.md", &env), vec!["vim", "digest.md"]);
+        assert_eq!(command("digest.json", &env), vec!["vim", "digest.json"]);
+        assert_eq!(command("digest.HTML", &env), vec!["firefox", "digest.HTML"]);
+
+        let env = [("VISUAL", "code --wait"), ("EDITOR", "vim")];
+        assert_eq!(
+            command("out/digest.md", &env),
+            vec!["code", "--wait", "out/digest.md"]
+        );
+
+        let mut fallback = system_opener();
+        fallback.push("digest.html".to_string());
+        assert_eq!(command("digest.html", &[("EDITOR", "vim")]), fallback);
+        assert_eq!(command("digest.html", &[("BROWSER", " ")]), fallback);
+    }
+
+    #[test]
+    fn test_open_command_quoted_editor() {
+        let env = [("EDITOR", r#""/opt/Sublime Text/subl" -w --name 'my notes'"#)];
+        assert_eq!(
+            command("digest.md", &env),
+            vec![
+                "/opt/Sublime Text/subl",
+                "-w",
+                "--name",
+                "my notes",
+                "digest.md"
+            ]
+        );
+
+        // Unbalanced quotes leave the variable out, falling back to the next one
+        let env = [("VISUAL", "'code --wait"), ("EDITOR", "vim")];
+        assert_eq!(command("digest.md", &env), vec!["vim", "digest.md"]);
+    }
+}
@@ -0,0 +1,142 @@
+// Paths as digest compares and prints them: forward slashes, without Windows verbatim prefixes,
+// and case-folded where the file system ignores case
+use std::path::{Path, PathBuf};
+
+/// Whether the platform's usual file systems treat `Src` and `src` as the same name (NTFS and
+/// APFS by default); ignore patterns and project roots are compared accordingly
+pub const CASE_INSENSITIVE: bool = cfg!(any(windows, target_os = "macos"));
+
+/// `path` with forward slashes, and a `\\?\C:\` long-path prefix reduced to `C:/` and
+/// `\\?\UNC\server\share` to `//server/share`
+pub fn slash(path: &Path) -> String {
+    slash_str(&path.to_string_lossy())
+}
+
+fn slash_str(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    for verbatim in ["//?/", "//./"] {
+        if let Some(rest) = path.strip_prefix(verbatim) {
+            return match rest.get(..4) {
+                Some(unc) if unc.eq_ignore_ascii_case("UNC/") => format!("//{}", &rest[4..]),
+                _ => rest.to_string(),
+            };
+        }
+    }
+    path
+}
+
+/// The components of the slashed `path`, without empty and `.` ones; a leading `/` or a
+/// `//server/share` UNC root counts as one component
+fn components(path: &str) -> Vec<&str> {
+    let (unc, rest) = match path.strip_prefix("//") {
+        Some(rest) => match rest.splitn(3, '/').collect::<Vec<_>>()[..] {
+            [server, share, rest] => (Some(&path[..2 + server.len() + 1 + share.len()]), rest),
+            _ => (Some(path), ""),
+        },
+        None => match path.strip_prefix('/') {
+            Some(rest) => (Some("/"), rest),
+            None => (None, path),
+        },
+    };
+    unc.into_iter()
+        .chain(rest.split('/').filter(|c| !c.is_empty() && *c != "."))
+        .collect()
+}
+
+fn same_name(a: &str, b: &str, case_insensitive: bool) -> bool {
+    if case_insensitive {
+        a.to_lowercase() == b.to_lowercase()
+    } else {
+        a == b
+    }
+}
+
+/// `path` relative to `root`. Besides `Path::strip_prefix`, this accepts the two spelled
+/// differently: one with a `\\?\` prefix and one without (as `canonicalize` returns them on
+/// Windows), mixed separators, or, on case-insensitive file systems, different case.
+pub fn strip_root(path: &Path, root: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = path.strip_prefix(root) {
+        return Some(relative.to_path_buf());
+    }
+    strip_root_with(&slash(path), &slash(root), CASE_INSENSITIVE).map(PathBuf::from)
+}
+
+fn strip_root_with(path: &str, root: &str, case_insensitive: bool) -> Option<String> {
+    let path = components(path);
+    let root = components(root);
+    if root.len() > path.len() {
+        return None;
+    }
+    let under_root = root
+        .iter()
+        .zip(&path)
+        .all(|(a, b)| same_name(a, b, case_insensitive));
+    under_root.then(|| path[root.len()..].join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slash_drops_verbatim_prefixes() {
+        for (path, expected) in [
+            (r"C:\proj\src\main.rs", "C:/proj/src/main.rs"),
+            (r"\\?\C:\proj\src\main.rs", "C:/proj/src/main.rs"),
+            (r"\\.\C:\proj", "C:/proj"),
+            (r"\\?\UNC\server\share\proj", "//server/share/proj"),
+            (r"\\server\share\proj", "//server/share/proj"),
+            ("/home/me/proj", "/home/me/proj"),
+        ] {
+            assert_eq!(slash_str(path), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_strip_root_matrix() {
+        let cases = [
+            // (path, root, case-insensitive, relative)
+            (r"C:\proj\src\a.rs", r"C:\proj", false, Some("src/a.rs")),
+            (r"\\?\C:\proj\src\a.rs", r"C:\proj", false, Some("src/a.rs")),
+            (
+                r"C:\proj\src\a.rs",
+                r"\\?\C:\proj\",
+                false,
+                Some("src/a.rs"),
+            ),
+            (r"c:\Proj\src\a.rs", r"C:\proj", true, Some("src/a.rs")),
+            (r"c:\Proj\src\a.rs", r"C:\proj", false, None),
+            (r"D:\proj\src\a.rs", r"C:\proj", true, None),
+            (
+                r"\\?\UNC\srv\share\proj\a.rs",
+                r"\\srv\share\proj",
+                false,
+                Some("a.rs"),
+            ),
+            (r"\\srv\share2\proj\a.rs", r"\\srv\share", false, None),
+            (r"C:\project\a.rs", r"C:\proj", true, None),
+            ("./src/a.rs", ".", false, Some("src/a.rs")),
+            ("/proj/a.rs", "proj", false, None),
+            (r"C:\proj", r"C:\proj", false, Some("")),
+        ];
+        for (path, root, case_insensitive, expected) in cases {
+            assert_eq!(
+                strip_root_with(&slash_str(path), &slash_str(root), case_insensitive).as_deref(),
+                expected,
+                "{} under {}",
+                path,
+                root
+            );
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_strip_root_on_windows() {
+        let root = Path::new(r"C:\Proj");
+        assert_eq!(
+            strip_root(Path::new(r"\\?\c:\proj\src\a.rs"), root),
+            Some(PathBuf::from("src/a.rs"))
+        );
+    }
+}
@@ -0,0 +1,323 @@
+// Personal data detection for --scan pii: emails, phone numbers, national IDs, and payment
+// details in collected files, reported by location or redacted before they reach a prompt
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+use crate::FileInfo;
+
+/// A kind of personal data the scan recognizes
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    /// US Social Security number
+    Ssn,
+    /// UK National Insurance number
+    Nino,
+    /// Payment card number passing the Luhn check
+    CardNumber,
+    /// International bank account number passing its mod-97 check
+    Iban,
+}
+
+impl PiiKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PiiKind::Email => "email",
+            PiiKind::Phone => "phone",
+            PiiKind::Ssn => "ssn",
+            PiiKind::Nino => "nino",
+            PiiKind::CardNumber => "card_number",
+            PiiKind::Iban => "iban",
+        }
+    }
+
+    /// How the value reads in the report
+    pub fn label(self) -> &'static str {
+        match self {
+            PiiKind::Email => "Email address",
+            PiiKind::Phone => "Phone number",
+            PiiKind::Ssn => "US Social Security number",
+            PiiKind::Nino => "UK National Insurance number",
+            PiiKind::CardNumber => "Payment card number",
+            PiiKind::Iban => "IBAN",
+        }
+    }
+}
+
+/// Where a value was found; the value itself stays out of the report
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PiiFinding {
+    pub path: String,
+    pub line: usize,
+    pub kind: PiiKind,
+    /// Whether the value was replaced in the digest
+    pub redacted: bool,
+}
+
+/// Domains reserved for documentation and testing (RFC 2606, RFC 6761)
+const PLACEHOLDER_DOMAINS: &[&str] = &["example.com", "example.org", "example.net"];
+const PLACEHOLDER_TLDS: &[&str] = &["example", "test", "invalid", "localhost", "local"];
+
+fn patterns() -> &'static [(PiiKind, Regex)] {
+    static PATTERNS: OnceLock<Vec<(PiiKind, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (
+                PiiKind::Email,
+                r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+            ),
+            (
+                PiiKind::Iban,
+                r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,4})?\b",
+            ),
+            (
+                PiiKind::CardNumber,
+                r"\b\d{4}(?:[ -]?\d{4}){2}[ -]?\d{1,7}\b",
+            ),
+            (PiiKind::Ssn, r"\b\d{3}-\d{2}-\d{4}\b"),
+            (
+                PiiKind::Nino,
+                r"\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?\d{2} ?\d{2} ?\d{2} ?[A-D]\b",
+            ),
+            (
+                PiiKind::Phone,
+                r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{3}\)|\b\d{3})[ .-]\d{3}[ .-]\d{4}\b|\+\d{10,14}\b",
+            ),
+        ]
+        .into_iter()
+        .map(|(kind, pattern)| (kind, Regex::new(pattern).expect("valid regex")))
+        .collect()
+    })
+}
+
+/// Whether a match of `kind` is a real-looking value rather than a placeholder, a version, or
+/// a number that fails its checksum
+fn is_plausible(kind: PiiKind, value: &str) -> bool {
+    match kind {
+        PiiKind::Email => {
+            let domain = value.rsplit('@').next().unwrap_or_default().to_lowercase();
+            let tld = domain.rsplit('.').next().unwrap_or_default();
+            !PLACEHOLDER_DOMAINS
+                .iter()
+                .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)))
+                && !PLACEHOLDER_TLDS.contains(&tld)
+                && !value.starts_with("git@")
+        }
+        PiiKind::Ssn => {
+            let parts: Vec<&str> = value.split('-').collect();
+            let area = parts[0];
+            area != "000"
+                && area != "666"
+                && !area.starts_with('9')
+                && parts[1] != "00"
+                && parts[2] != "0000"
+        }
+        PiiKind::CardNumber => {
+            let digits: Vec<u32> = value.chars().filter_map(|c| c.to_digit(10)).collect();
+            (13..=19).contains(&digits.len()) && luhn(&digits)
+        }
+        PiiKind::Iban => iban_checksum(&value.replace(' ', "")),
+        PiiKind::Nino | PiiKind::Phone => true,
+    }
+}
+
+fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (!i.is_multiple_of(2), d * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => d,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// ISO 13616: the country code and check digits moved to the end, letters as numbers, mod 97
+fn iban_checksum(iban: &str) -> bool {
+    if !(15..=34).contains(&iban.len()) {
+        return false;
+    }
+    let rearranged = iban[4..].chars().chain(iban[..4].chars());
+    let mut remainder = 0u32;
+    for c in rearranged {
+        let value = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+/// The personal data in `content`, as (line number, kind, byte range) in order of position.
+/// Earlier kinds in the pattern list win overlapping matches, so a card number isn't also
+/// reported as a phone number.
+fn detect(content: &str) -> Vec<(usize, PiiKind, std::ops::Range<usize>)> {
+    let mut found: Vec<(PiiKind, std::ops::Range<usize>)> = Vec::new();
+    for (kind, pattern) in patterns() {
+        for m in pattern.find_iter(content) {
+            let overlaps = found
+                .iter()
+                .any(|(_, range)| range.start < m.end() && m.start() < range.end);
+            if !overlaps && is_plausible(*kind, m.as_str()) {
+                found.push((*kind, m.range()));
+            }
+        }
+    }
+    found.sort_by_key(|(_, range)| range.start);
+
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    found
+        .into_iter()
+        .map(|(kind, range)| {
+            let line = line_starts.partition_point(|&start| start <= range.start);
+            (line, kind, range)
+        })
+        .collect()
+}
+
+/// Scan `files` for personal data. With `redact`, each value is replaced by
+/// `<redacted:kind>` in the file's content.
+pub fn scan_pii(files: &mut [FileInfo], redact: bool) -> Vec<PiiFinding> {
+    let mut findings = Vec::new();
+    for file in files.iter_mut() {
+        let found = detect(&file.content);
+        if found.is_empty() {
+            continue;
+        }
+        findings.extend(found.iter().map(|(line, kind, _)| PiiFinding {
+            path: file.path.clone(),
+            line: *line,
+            kind: *kind,
+            redacted: redact,
+        }));
+        if !redact {
+            continue;
+        }
+
+        let mut content = String::with_capacity(file.content.len());
+        let mut copied = 0;
+        for (_, kind, range) in &found {
+            content.push_str(&file.content[copied..range.start]);
+            content.push_str(&format!("<redacted:{}>", kind.as_str()));
+            copied = range.end;
+        }
+        content.push_str(&file.content[copied..]);
+        file.set_content(content);
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::complexity::Complexity;
+
+    fn kinds(content: &str) -> Vec<(usize, PiiKind)> {
+        detect(content)
+            .into_iter()
+            .map(|(line, kind, _)| (line, kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_personal_data() {
+        let fixture = "name,email,phone\n\
+                       Ada,ada.lovelace@gmail.com,(415) 555-0132\n\
+                       ssn: 123-45-6789\n\
+                       card: 4111 1111 1111 1111\n\
+                       iban: GB82 WEST 1234 5698 7654 32\n\
+                       nino: AB 12 34 56 C\n\
+                       intl: +442071838750\n";
+        assert_eq!(
+            kinds(fixture),
+            vec![
+                (2, PiiKind::Email),
+                (2, PiiKind::Phone),
+                (3, PiiKind::Ssn),
+                (4, PiiKind::CardNumber),
+                (5, PiiKind::Iban),
+                (6, PiiKind::Nino),
+                (7, PiiKind::Phone),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skips_placeholders_and_lookalikes() {
+        let content = "contact = \"someone@example.com\"\n\
+                       url = \"git@github.com:org/repo.git\"\n\
+                       dev@app.test\n\
+                       ssn = 000-12-3456\n\
+                       order = 4111 1111 1111 1112\n\
+                       version = 1.2.3\n\
+                       date = 2024-01-15\n";
+        assert!(kinds(content).is_empty(), "{:?}", kinds(content));
+    }
+
+    #[test]
+    fn test_scan_redacts_values() {
+        let mut files = vec![FileInfo::new(
+            "fixtures/users.json".to_string(),
+            Some("JSON".to_string()),
+            "{\"email\": \"jane@corp.io\", \"ssn\": \"219-09-9999\"}\n".to_string(),
+        )];
+        let findings = scan_pii(&mut files, true);
+        assert_eq!(
+            findings
+                .iter()
+                .map(|f| (f.line, f.kind, f.redacted))
+                .collect::<Vec<_>>(),
+            vec![(1, PiiKind::Email, true), (1, PiiKind::Ssn, true)]
+        );
+        assert_eq!(
+            files[0].content,
+            "{\"email\": \"<redacted:email>\", \"ssn\": \"<redacted:ssn>\"}\n"
+        );
+
+        let mut clean = vec![FileInfo::new(
+            "a.rs".to_string(),
+            None,
+            "fn a() {}\n".to_string(),
+        )];
+        assert!(scan_pii(&mut clean, true).is_empty());
+    }
+
+    #[test]
+    fn test_redaction_keeps_file_metadata() {
+        let mut files = vec![FileInfo {
+            modified: Some(1_700_000_000),
+            submodule: Some("vendor/users".to_string()),
+            complexity: Some(Complexity {
+                cyclomatic: 2,
+                max_nesting: 1,
+            }),
+            ..FileInfo::new(
+                "vendor/users/seed.py".to_string(),
+                Some("Python".to_string()),
+                "admin = 'jane@corp.io'\n".to_string(),
+            )
+        }];
+        scan_pii(&mut files, true);
+
+        let file = &files[0];
+        assert_eq!(file.content, "admin = '<redacted:email>'\n");
+        assert_eq!(file.size, file.content.len() as u64);
+        assert_eq!(file.modified, Some(1_700_000_000));
+        assert_eq!(file.submodule.as_deref(), Some("vendor/users"));
+        assert_eq!(file.complexity.map(|c| c.cyclomatic), Some(2));
+        assert_eq!(file.language.as_deref(), Some("Python"));
+    }
+}
@@ -0,0 +1,356 @@
+// Data-egress policy from digest.policy.toml: paths that may not be exported, values that must
+// be redacted, and limits whose violation fails the run instead of producing a digest
+use regex::Regex;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+
+use crate::assets::EmbeddedImage;
+use crate::error::{DigestError, Result};
+use crate::glob::{anchored_path_matches, path_matches, validate};
+use crate::pii::scan_pii;
+use crate::reader::read_utf8;
+use crate::FileInfo;
+
+/// Policy file read from the project root
+pub const POLICY_FILE: &str = "digest.policy.toml";
+
+/// What replaces a value matched by `redact_patterns`
+const REDACTED: &str = "<redacted:policy>";
+
+/// The rules of a policy file, as written
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    deny_paths: Vec<String>,
+    #[serde(default)]
+    redact_pii: bool,
+    #[serde(default)]
+    redact_patterns: Vec<String>,
+    max_file_bytes: Option<u64>,
+    #[serde(default)]
+    forbidden_strings: Vec<String>,
+}
+
+/// Rules a digest has to satisfy before it is written
+#[derive(Debug, Default)]
+pub struct Policy {
+    /// Globs of files that may not appear in a digest; anchored at the root when they hold a
+    /// slash, matched at any depth otherwise
+    pub deny_paths: Vec<String>,
+    /// Redact personal data as `--scan pii --scan-action redact` does
+    pub redact_pii: bool,
+    /// Regexes whose matches are always redacted
+    pub redact_patterns: Vec<Regex>,
+    /// Largest file content allowed, in bytes, after redaction
+    pub max_file_bytes: Option<u64>,
+    /// Text no included file may contain
+    pub forbidden_strings: Vec<String>,
+}
+
+/// A rule an included file breaks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    DeniedPath {
+        path: String,
+        pattern: String,
+    },
+    TooLarge {
+        path: String,
+        bytes: u64,
+        limit: u64,
+    },
+    Forbidden {
+        path: String,
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::DeniedPath { path, pattern } => {
+                write!(f, "{}: denied by deny_paths '{}'", path, pattern)
+            }
+            Violation::TooLarge { path, bytes, limit } => {
+                write!(
+                    f,
+                    "{}: {} bytes, over max_file_bytes {}",
+                    path, bytes, limit
+                )
+            }
+            Violation::Forbidden { path, line, text } => {
+                write!(f, "{}:{}: contains forbidden string '{}'", path, line, text)
+            }
+        }
+    }
+}
+
+impl Policy {
+    /// Parse a policy file's content; `path` names it in errors
+    pub fn parse(content: &str, path: &Path) -> Result<Policy> {
+        let invalid = |message: String| DigestError::Config {
+            path: path.to_path_buf(),
+            message,
+        };
+        let file: PolicyFile = toml::from_str(content).map_err(|err| invalid(err.to_string()))?;
+        for pattern in &file.deny_paths {
+            validate(pattern)
+                .map_err(|err| invalid(format!("deny_paths '{}': {}", pattern, err)))?;
+        }
+        let redact_patterns = file
+            .redact_patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|err| invalid(format!("redact_patterns '{}': {}", pattern, err)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Policy {
+            deny_paths: file.deny_paths,
+            redact_pii: file.redact_pii,
+            redact_patterns,
+            max_file_bytes: file.max_file_bytes,
+            forbidden_strings: file.forbidden_strings,
+        })
+    }
+
+    /// Read a policy file
+    pub fn load(path: &Path) -> Result<Policy> {
+        Policy::parse(&read_utf8(path)?, path)
+    }
+
+    /// The project's digest.policy.toml, or `None` without one
+    pub fn load_project(project_path: &Path) -> Result<Option<Policy>> {
+        let path = project_path.join(POLICY_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Policy::load(&path).map(Some)
+    }
+
+    /// Both policies at once: every rule of either applies, and the lower size limit
+    pub fn merge(mut self, other: Policy) -> Policy {
+        self.deny_paths.extend(other.deny_paths);
+        self.redact_pii |= other.redact_pii;
+        self.redact_patterns.extend(other.redact_patterns);
+        self.max_file_bytes = match (self.max_file_bytes, other.max_file_bytes) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.forbidden_strings.extend(other.forbidden_strings);
+        self
+    }
+
+    /// Apply the mandatory redactions to `files`
+    pub fn redact(&self, files: &mut [FileInfo]) {
+        if self.redact_pii {
+            scan_pii(files, true);
+        }
+        if self.redact_patterns.is_empty() {
+            return;
+        }
+        for file in files.iter_mut() {
+            let mut content = file.content.clone();
+            for pattern in &self.redact_patterns {
+                content = pattern.replace_all(&content, REDACTED).into_owned();
+            }
+            if content != file.content {
+                file.set_content(content);
+            }
+        }
+    }
+
+    /// The first `deny_paths` pattern matching the project-relative `path`
+    pub fn denied_by(&self, path: &str) -> Option<&str> {
+        let path = path.replace('\\', "/");
+        self.deny_paths
+            .iter()
+            .find(|pattern| {
+                if pattern.trim_end_matches('/').contains('/') {
+                    anchored_path_matches(pattern, &path)
+                } else {
+                    path_matches(pattern, &path)
+                }
+            })
+            .map(String::as_str)
+    }
+
+    /// Every rule the `files` and embedded `images` about to be digested break; images are
+    /// held to `deny_paths` and `max_file_bytes`
+    pub fn violations(&self, files: &[FileInfo], images: &[EmbeddedImage]) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for file in files {
+            if let Some(pattern) = self.denied_by(&file.path) {
+                violations.push(Violation::DeniedPath {
+                    path: file.path.clone(),
+                    pattern: pattern.to_string(),
+                });
+            }
+            if let Some(limit) = self.max_file_bytes.filter(|&limit| file.size > limit) {
+                violations.push(Violation::TooLarge {
+                    path: file.path.clone(),
+                    bytes: file.size,
+                    limit,
+                });
+            }
+            for text in &self.forbidden_strings {
+                if let Some(at) = file.content.find(text.as_str()) {
+                    violations.push(Violation::Forbidden {
+                        path: file.path.clone(),
+                        line: file.content[..at].matches('\n').count() + 1,
+                        text: text.clone(),
+                    });
+                }
+            }
+        }
+        for image in images {
+            if let Some(pattern) = self.denied_by(&image.path) {
+                violations.push(Violation::DeniedPath {
+                    path: image.path.clone(),
+                    pattern: pattern.to_string(),
+                });
+            }
+            if let Some(limit) = self.max_file_bytes.filter(|&limit| image.bytes > limit) {
+                violations.push(Violation::TooLarge {
+                    path: image.path.clone(),
+                    bytes: image.bytes,
+                    limit,
+                });
+            }
+        }
+        violations
+    }
+
+    /// Fail with `DigestError::PolicyViolation` when any of `files` or `images` breaks a rule
+    pub fn enforce(&self, files: &[FileInfo], images: &[EmbeddedImage]) -> Result<()> {
+        let violations = self.violations(files, images);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        let list: Vec<String> = violations.iter().map(|v| format!("  {}", v)).collect();
+        Err(DigestError::PolicyViolation(format!(
+            "The digest would break the data-egress policy in {} place{}:\n{}",
+            violations.len(),
+            if violations.len() == 1 { "" } else { "s" },
+            list.join("\n")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> FileInfo {
+        FileInfo::new(path.to_string(), None, content.to_string())
+    }
+
+    fn policy(content: &str) -> Policy {
+        Policy::parse(content, Path::new(POLICY_FILE)).unwrap()
+    }
+
+    #[test]
+    fn test_policy_violations() {
+        let policy = policy(
+            r#"
+deny_paths = ["secrets/", "*.pem", "config/prod/**"]
+max_file_bytes = 20
+forbidden_strings = ["INTERNAL ONLY"]
+"#,
+        );
+        let files = [
+            file("src/main.rs", "fn main() {}\n"),
+            file("secrets/keys.rs", ""),
+            file("deploy/certs/server.pem", ""),
+            file("config/prod/db.yaml", ""),
+            file("lib/config/prod/db.yaml", ""),
+            file("src/big.rs", &"x".repeat(21)),
+            file("docs/a.md", "Public\nINTERNAL ONLY: roadmap\n"),
+        ];
+        let violations: Vec<String> = policy
+            .violations(&files, &[])
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                "secrets/keys.rs: denied by deny_paths 'secrets/'",
+                "deploy/certs/server.pem: denied by deny_paths '*.pem'",
+                "config/prod/db.yaml: denied by deny_paths 'config/prod/**'",
+                "src/big.rs: 21 bytes, over max_file_bytes 20",
+                "docs/a.md: 30 bytes, over max_file_bytes 20",
+                "docs/a.md:2: contains forbidden string 'INTERNAL ONLY'",
+            ]
+        );
+
+        let err = policy.enforce(&files[..2], &[]).unwrap_err();
+        assert!(matches!(err, DigestError::PolicyViolation(_)));
+        assert!(policy.enforce(&files[..1], &[]).is_ok());
+    }
+
+    #[test]
+    fn test_policy_covers_embedded_images() {
+        let policy = policy("deny_paths = [\"secrets/\"]\nmax_file_bytes = 100\n");
+        let image = |path: &str, bytes| EmbeddedImage {
+            path: path.to_string(),
+            media_type: "image/png",
+            bytes,
+            data: String::new(),
+        };
+        let images = [
+            image("docs/logo.png", 50),
+            image("secrets/badge.png", 50),
+            image("docs/diagram.png", 101),
+        ];
+        let violations: Vec<String> = policy
+            .violations(&[], &images)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                "secrets/badge.png: denied by deny_paths 'secrets/'",
+                "docs/diagram.png: 101 bytes, over max_file_bytes 100",
+            ]
+        );
+        assert!(policy.enforce(&[], &images[..1]).is_ok());
+    }
+
+    #[test]
+    fn test_policy_redactions() {
+        let policy = policy(
+            r#"
+redact_pii = true
+redact_patterns = ['(?i)api_key\s*=\s*"[^"]*"']
+"#,
+        );
+        let mut files = [file(
+            "app.py",
+            "API_KEY = \"sk-123\"\nowner = \"jane@corp.io\"\n",
+        )];
+        policy.redact(&mut files);
+        assert_eq!(
+            files[0].content,
+            "<redacted:policy>\nowner = \"<redacted:email>\"\n"
+        );
+    }
+
+    #[test]
+    fn test_policy_merge_and_errors() {
+        let merged = policy("max_file_bytes = 100\ndeny_paths = [\"*.pem\"]\n")
+            .merge(policy("max_file_bytes = 50\nredact_pii = true\n"));
+        assert_eq!(merged.max_file_bytes, Some(50));
+        assert!(merged.redact_pii);
+        assert_eq!(merged.deny_paths, vec!["*.pem"]);
+
+        let path = Path::new(POLICY_FILE);
+        assert!(Policy::parse("deny_path = []\n", path).is_err());
+        assert!(Policy::parse("redact_patterns = [\"(\"]\n", path).is_err());
+        assert!(Policy::parse("deny_paths = [\"[\"]\n", path).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+// Built-in prompt presets: an instruction block plus a file ranking tuned for a task
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresetSpec {
+    /// Name accepted by --preset
+    pub name: &'static str,
+    /// Instructions placed before the digest
+    pub instructions: &'static str,
+    /// Path fragments that move a file ahead of the rest
+    pub boost: &'static [&'static str],
+    /// Path fragments that move a file behind the rest
+    pub demote: &'static [&'static str],
+}
+
+pub const PRESETS: &[PresetSpec] = &[
+    PresetSpec {
+        name: "code-review",
+        instructions: "You are reviewing the code below. Point out bugs, unclear logic, \
+missing error handling, and risky changes. Reference files by path and quote the lines \
+you are commenting on. Prefer concrete suggestions over general advice.",
+        boost: &["src/", "lib/", "app/", "pkg/", "internal/"],
+        demote: &[
+            "test",
+            "spec",
+            "fixture",
+            "mock",
+            "example",
+            "docs/",
+            "vendor/",
+            "generated",
+        ],
+    },
+    PresetSpec {
+        name: "onboarding",
+        instructions: "You are helping a new contributor understand this project. Explain \
+what it does, how the code is organised, where execution starts, and which modules matter \
+most. Call out conventions a newcomer should follow.",
+        boost: &[
+            "readme",
+            "contributing",
+            "architecture",
+            "docs/",
+            "main.",
+            "lib.",
+            "index.",
+            "app.",
+            "cargo.toml",
+            "package.json",
+            "pyproject.toml",
+            "go.mod",
+        ],
+        demote: &["test", "spec", "fixture", "mock", "generated"],
+    },
+    PresetSpec {
+        name: "security-audit",
+        instructions: "You are auditing the code below for security issues. Look for \
+injection, broken authentication or authorization, unsafe deserialization, secrets in code \
+or configuration, weak cryptography, and missing input validation. Rate each finding by \
+severity and explain how it could be exploited.",
+        boost: &[
+            "auth",
+            "login",
+            "session",
+            "token",
+            "password",
+            "secret",
+            "credential",
+            "crypt",
+            "permission",
+            "security",
+            "middleware",
+            "config",
+            "settings",
+            ".env",
+            "dockerfile",
+        ],
+        demote: &["docs/", "example", "fixture", "mock"],
+    },
+];
+
+/// Look up a preset by its --preset name (case-insensitive)
+pub fn find_preset(name: &str) -> Option<&'static PresetSpec> {
+    PRESETS.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+impl PresetSpec {
+    /// Ranking score for a file path: -1 for demoted paths, 1 for boosted ones, 0 otherwise
+    pub fn score(&self, path: &str) -> i64 {
+        let path = path.replace('\\', "/").to_lowercase();
+        if self.demote.iter().any(|fragment| path.contains(fragment)) {
+            -1
+        } else if self.boost.iter().any(|fragment| path.contains(fragment)) {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_security_audit_boosts_auth_files() {
+        let preset = find_preset("security-audit").unwrap();
+        assert_eq!(preset.score("src/auth/login.rs"), 1);
+        assert_eq!(preset.score("config/settings.py"), 1);
+        assert_eq!(preset.score("src/render.rs"), 0);
+        assert_eq!(preset.score("docs/guide.md"), -1);
+    }
+
+    #[test]
+    fn test_find_preset_is_case_insensitive() {
+        assert_eq!(find_preset("Code-Review").unwrap().name, "code-review");
+        assert!(find_preset("unknown").is_none());
+    }
+}
@@ -0,0 +1,82 @@
+// File ranking used to decide which files survive the --max-files cut
+use crate::glob::{anchored_path_matches, path_matches, validate};
+use crate::FileInfo;
+
+/// A `PATTERN=WEIGHT` rule from --priority or the `[priority]` table of digest.toml
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathWeight {
+    /// A glob, anchored at the project root when it contains a slash, like a gitignore pattern
+    pub pattern: String,
+    pub weight: i64,
+}
+
+impl PathWeight {
+    /// Parse `src/core/**=10`; the weight follows the last `=`
+    pub fn parse(rule: &str) -> Result<PathWeight, String> {
+        let (pattern, weight) = rule
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected PATTERN=WEIGHT, got '{}'", rule))?;
+        let weight = weight
+            .trim()
+            .parse()
+            .map_err(|_| format!("weight '{}' is not a whole number", weight.trim()))?;
+        let pattern = pattern.trim();
+        validate(pattern).map_err(|err| format!("pattern '{}': {}", pattern, err))?;
+        Ok(PathWeight {
+            pattern: pattern.to_string(),
+            weight,
+        })
+    }
+
+    /// Whether the rule covers `path`, relative to the project root
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.replace('\\', "/");
+        if self.pattern.trim_end_matches('/').contains('/') {
+            anchored_path_matches(&self.pattern, &path)
+        } else {
+            path_matches(&self.pattern, &path)
+        }
+    }
+}
+
+/// Ranking score of `path`: the sum of the weights of every rule that covers it
+pub fn path_weight(weights: &[PathWeight], path: &str) -> i64 {
+    weights
+        .iter()
+        .filter(|rule| rule.matches(path))
+        .map(|rule| rule.weight)
+        .sum()
+}
+
+/// Order files by descending score, keeping walk order for files with equal scores
+pub fn rank_files<F>(files: &mut [FileInfo], score: F)
+where
+    F: Fn(&FileInfo) -> i64,
+{
+    files.sort_by_cached_key(|file| std::cmp::Reverse(score(file)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_weight() {
+        let weights = [
+            PathWeight::parse("src/core/**=10").unwrap(),
+            PathWeight::parse("examples/=-5").unwrap(),
+            PathWeight::parse("*_test.rs = -2").unwrap(),
+        ];
+        assert_eq!(path_weight(&weights, "src/core/engine.rs"), 10);
+        assert_eq!(path_weight(&weights, "src/core/engine_test.rs"), 8);
+        assert_eq!(path_weight(&weights, "examples/demo.rs"), -5);
+        // Patterns with a slash are anchored at the root; others match at any depth
+        assert_eq!(path_weight(&weights, "vendor/src/core/lib.rs"), 0);
+        assert_eq!(path_weight(&weights, "tests/parse_test.rs"), -2);
+        assert_eq!(path_weight(&weights, "src\\core\\mod.rs"), 10);
+
+        assert!(PathWeight::parse("src/**").is_err());
+        assert!(PathWeight::parse("src/**=high").is_err());
+        assert!(PathWeight::parse("src/[a-=1").is_err());
+    }
+}
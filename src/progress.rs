@@ -0,0 +1,56 @@
+// Progress bar for long runs: files read, bytes, and estimated tokens so far
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+use std::time::Duration;
+
+use crate::tokens::estimate_tokens;
+use crate::FileInfo;
+
+/// A spinner on stderr that counts collected files; does nothing when disabled
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    bytes: u64,
+    tokens: usize,
+}
+
+impl Progress {
+    /// Show progress only if `wanted` and stderr is a terminal, so piped and logged runs
+    /// stay clean
+    pub fn new(wanted: bool) -> Self {
+        let bar = (wanted && std::io::stderr().is_terminal()).then(|| {
+            let bar = ProgressBar::new_spinner();
+            bar.set_style(
+                ProgressStyle::with_template("{spinner} {pos} files read, {msg} [{elapsed}]")
+                    .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+            );
+            bar.enable_steady_tick(Duration::from_millis(100));
+            bar
+        });
+        Progress {
+            bar,
+            bytes: 0,
+            tokens: 0,
+        }
+    }
+
+    /// Count one collected file
+    pub fn file(&mut self, file: &FileInfo) {
+        if let Some(bar) = &self.bar {
+            self.bytes += file.size;
+            self.tokens += estimate_tokens(&file.content);
+            bar.inc(1);
+            bar.set_message(format!(
+                "{}, ~{} tokens",
+                HumanBytes(self.bytes),
+                self.tokens
+            ));
+        }
+    }
+
+    /// Remove the spinner from the terminal
+    pub fn finish(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
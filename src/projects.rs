@@ -0,0 +1,574 @@
+// Project-type detection: one shallow scan of the project shared by a registry of detectors
+#[cfg(feature = "native")]
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "native")]
+use crate::paths;
+use crate::terraform::TERRAFORM_IGNORE_PATTERNS;
+
+/// How deep the shared scan looks; project markers live near the root
+#[cfg(feature = "native")]
+const SCAN_DEPTH: usize = 3;
+
+/// Directories Unreal Engine generates: compiled binaries, build intermediates, the shared
+/// derived data cache, and logs, autosaves, and crash dumps
+pub const UNREAL_IGNORE_PATTERNS: &[&str] =
+    &["Binaries/", "Intermediate/", "DerivedDataCache/", "Saved/"];
+
+pub const GODOT: &str = "Godot";
+pub const UNREAL: &str = "Unreal Engine";
+pub const TERRAFORM: &str = "Terraform";
+pub const LUA: &str = "Lua";
+pub const LOVE: &str = "LÖVE";
+pub const NEOVIM_PLUGIN: &str = "Neovim plugin";
+pub const OPENRESTY: &str = "OpenResty";
+
+/// Files and directories near the project root, relative to it with `/` separators
+#[derive(Debug, Clone, Default)]
+pub struct ProjectScan {
+    pub root: PathBuf,
+    pub files: Vec<String>,
+    pub dirs: HashSet<String>,
+}
+
+impl ProjectScan {
+    /// Walk the top levels of `root`, respecting `.gitignore` but not skipping hidden entries
+    #[cfg(feature = "native")]
+    pub fn new(root: &Path) -> Self {
+        let mut scan = ProjectScan {
+            root: root.to_path_buf(),
+            ..ProjectScan::default()
+        };
+        let walker = WalkBuilder::new(root)
+            .hidden(false)
+            .git_ignore(true)
+            .max_depth(Some(SCAN_DEPTH))
+            .build();
+        for entry in walker.flatten() {
+            let Some(relative) = paths::strip_root(entry.path(), root) else {
+                continue;
+            };
+            let relative = paths::slash(&relative);
+            if relative.is_empty() {
+                continue;
+            }
+            match entry.file_type() {
+                Some(file_type) if file_type.is_dir() => {
+                    scan.dirs.insert(relative);
+                }
+                Some(_) => scan.files.push(relative),
+                None => {}
+            }
+        }
+        scan
+    }
+
+    pub fn has_file(&self, path: &str) -> bool {
+        self.files.iter().any(|file| file == path)
+    }
+
+    pub fn has_dir(&self, path: &str) -> bool {
+        self.dirs.contains(path)
+    }
+
+    /// Files with the given extension anywhere in the scan
+    pub fn count_extension(&self, extension: &str) -> usize {
+        self.files
+            .iter()
+            .filter(|file| has_extension(file, extension))
+            .count()
+    }
+
+    /// Whether a file with the given extension sits directly at the root
+    pub fn root_has_extension(&self, extension: &str) -> bool {
+        self.files
+            .iter()
+            .any(|file| !file.contains('/') && has_extension(file, extension))
+    }
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path).extension().and_then(|ext| ext.to_str()) == Some(extension)
+}
+
+/// Recognizes one kind of project and describes how to digest it
+pub trait ProjectDetector: Sync {
+    fn name(&self) -> &'static str;
+
+    fn detect(&self, scan: &ProjectScan) -> bool;
+
+    /// Orders detected types, and ranks files with prioritized extensions; higher wins
+    fn priority(&self) -> i64 {
+        0
+    }
+
+    /// Patterns skipped by default for this kind of project
+    fn ignore_patterns(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Extensions of the files that matter most in this kind of project
+    fn prioritized_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Extensions collected in this kind of project on top of the common code files
+    fn included_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Files that matter even more than prioritized extensions: a file name, matched in any
+    /// directory, or a directory ending in `/`, matched from the project root
+    fn key_files(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+/// A project type found by a detector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectType {
+    pub name: &'static str,
+    pub priority: i64,
+    pub ignore_patterns: &'static [&'static str],
+    pub prioritized_extensions: &'static [&'static str],
+    pub included_extensions: &'static [&'static str],
+    pub key_files: &'static [&'static str],
+}
+
+impl ProjectType {
+    /// Whether files with `extension` are collected because of this project type
+    pub fn includes(&self, extension: &str) -> bool {
+        self.included_extensions.contains(&extension)
+    }
+
+    /// The rank of a file: just above the type's priority for key files, the priority itself
+    /// if its extension is prioritized, else 0
+    pub fn score(&self, path: &str) -> i64 {
+        let path = path.replace('\\', "/");
+        let key = self
+            .key_files
+            .iter()
+            .any(|key| match key.strip_suffix('/') {
+                Some(dir) => path.starts_with(key) || path == dir,
+                None => path == *key || path.ends_with(&format!("/{}", key)),
+            });
+        let prioritized = self
+            .prioritized_extensions
+            .iter()
+            .any(|extension| has_extension(&path, extension));
+        if key {
+            self.priority + 1
+        } else if prioritized {
+            self.priority
+        } else {
+            0
+        }
+    }
+}
+
+struct Godot;
+
+impl ProjectDetector for Godot {
+    fn name(&self) -> &'static str {
+        GODOT
+    }
+
+    fn detect(&self, scan: &ProjectScan) -> bool {
+        // project.godot is the main project file; godot/ and .godot/ hold engine data
+        scan.has_file("project.godot")
+            || scan.has_dir("godot")
+            || scan.has_dir(".godot")
+            || scan.count_extension("tscn") > 0
+            || scan.count_extension("gd") > 0
+    }
+
+    fn priority(&self) -> i64 {
+        30
+    }
+
+    fn prioritized_extensions(&self) -> &'static [&'static str] {
+        &["godot", "gd", "tscn"]
+    }
+
+    fn included_extensions(&self) -> &'static [&'static str] {
+        &["gd", "tscn", "cs", "godot", "tres", "import", "shader"]
+    }
+}
+
+struct Unreal;
+
+impl ProjectDetector for Unreal {
+    fn name(&self) -> &'static str {
+        UNREAL
+    }
+
+    /// An Unreal Engine project has a `.uproject` descriptor at its root
+    fn detect(&self, scan: &ProjectScan) -> bool {
+        scan.root_has_extension("uproject")
+    }
+
+    fn priority(&self) -> i64 {
+        30
+    }
+
+    fn ignore_patterns(&self) -> &'static [&'static str] {
+        UNREAL_IGNORE_PATTERNS
+    }
+
+    fn prioritized_extensions(&self) -> &'static [&'static str] {
+        &["uproject", "uplugin"]
+    }
+
+    /// Project and plugin descriptors, inline C++ sources, and `Config/*.ini`
+    fn included_extensions(&self) -> &'static [&'static str] {
+        &["uproject", "uplugin", "inl", "ini"]
+    }
+}
+
+struct Terraform;
+
+impl ProjectDetector for Terraform {
+    fn name(&self) -> &'static str {
+        TERRAFORM
+    }
+
+    /// A Terraform project has `.tf` files at its root
+    fn detect(&self, scan: &ProjectScan) -> bool {
+        scan.root_has_extension("tf")
+    }
+
+    fn priority(&self) -> i64 {
+        10
+    }
+
+    fn ignore_patterns(&self) -> &'static [&'static str] {
+        TERRAFORM_IGNORE_PATTERNS
+    }
+
+    fn prioritized_extensions(&self) -> &'static [&'static str] {
+        &["tf"]
+    }
+}
+
+struct Lua;
+
+impl ProjectDetector for Lua {
+    fn name(&self) -> &'static str {
+        LUA
+    }
+
+    /// A common Lua entry file at the root, or a concentration of Lua files
+    fn detect(&self, scan: &ProjectScan) -> bool {
+        ["init.lua", "main.lua", "conf.lua", "config.lua"]
+            .iter()
+            .any(|file| scan.has_file(file))
+            || scan.count_extension("lua") >= 5
+    }
+
+    fn priority(&self) -> i64 {
+        20
+    }
+
+    /// Rocks installed into the project tree by LuaRocks
+    fn ignore_patterns(&self) -> &'static [&'static str] {
+        LUA_IGNORE_PATTERNS
+    }
+
+    fn prioritized_extensions(&self) -> &'static [&'static str] {
+        &["lua"]
+    }
+}
+
+const LUA_IGNORE_PATTERNS: &[&str] = &["lua_modules/", ".luarocks/"];
+
+struct Love;
+
+impl ProjectDetector for Love {
+    fn name(&self) -> &'static str {
+        LOVE
+    }
+
+    /// A LÖVE game has `main.lua` and `conf.lua` at its root
+    fn detect(&self, scan: &ProjectScan) -> bool {
+        scan.has_file("main.lua") && scan.has_file("conf.lua")
+    }
+
+    fn priority(&self) -> i64 {
+        25
+    }
+
+    /// Rocks, plus `.love` archives packaged from the game itself
+    fn ignore_patterns(&self) -> &'static [&'static str] {
+        &["lua_modules/", ".luarocks/", "*.love"]
+    }
+
+    fn prioritized_extensions(&self) -> &'static [&'static str] {
+        &["lua"]
+    }
+
+    fn key_files(&self) -> &'static [&'static str] {
+        &["main.lua", "conf.lua"]
+    }
+}
+
+struct NeovimPlugin;
+
+impl ProjectDetector for NeovimPlugin {
+    fn name(&self) -> &'static str {
+        NEOVIM_PLUGIN
+    }
+
+    /// Neovim loads `plugin/` at startup and `lua/` on `require`
+    fn detect(&self, scan: &ProjectScan) -> bool {
+        scan.has_dir("lua") && scan.has_dir("plugin")
+    }
+
+    fn priority(&self) -> i64 {
+        25
+    }
+
+    /// Rocks, and the help tags file `:helptags` generates
+    fn ignore_patterns(&self) -> &'static [&'static str] {
+        &["lua_modules/", ".luarocks/", "doc/tags"]
+    }
+
+    fn prioritized_extensions(&self) -> &'static [&'static str] {
+        &["lua", "vim"]
+    }
+
+    /// `vim` scripts are common next to Lua in plugins
+    fn included_extensions(&self) -> &'static [&'static str] {
+        &["vim"]
+    }
+
+    fn key_files(&self) -> &'static [&'static str] {
+        &["plugin/", "ftplugin/"]
+    }
+}
+
+struct OpenResty;
+
+impl OpenResty {
+    /// nginx configurations an OpenResty app is usually started with
+    const CONFIGS: &'static [&'static str] = &["nginx.conf", "conf/nginx.conf"];
+}
+
+impl ProjectDetector for OpenResty {
+    fn name(&self) -> &'static str {
+        OPENRESTY
+    }
+
+    /// An nginx configuration that runs Lua through `*_by_lua` directives
+    fn detect(&self, scan: &ProjectScan) -> bool {
+        Self::CONFIGS.iter().any(|config| {
+            scan.has_file(config)
+                && std::fs::read_to_string(scan.root.join(config))
+                    .is_ok_and(|content| content.contains("_by_lua"))
+        })
+    }
+
+    fn priority(&self) -> i64 {
+        25
+    }
+
+    /// Rocks and OPM packages, logs, and the temp directories nginx creates next to its
+    /// configuration
+    fn ignore_patterns(&self) -> &'static [&'static str] {
+        &["lua_modules/", ".luarocks/", "logs/", "*_temp/"]
+    }
+
+    fn prioritized_extensions(&self) -> &'static [&'static str] {
+        &["lua", "conf"]
+    }
+
+    /// The nginx configuration is where routes map to Lua handlers
+    fn included_extensions(&self) -> &'static [&'static str] {
+        &["conf"]
+    }
+
+    fn key_files(&self) -> &'static [&'static str] {
+        &["nginx.conf"]
+    }
+}
+
+/// Every known detector
+pub static DETECTORS: &[&dyn ProjectDetector] = &[
+    &Godot,
+    &Unreal,
+    &Terraform,
+    &Lua,
+    &Love,
+    &NeovimPlugin,
+    &OpenResty,
+];
+
+fn project_type_of(detector: &dyn ProjectDetector) -> ProjectType {
+    ProjectType {
+        name: detector.name(),
+        priority: detector.priority(),
+        ignore_patterns: detector.ignore_patterns(),
+        prioritized_extensions: detector.prioritized_extensions(),
+        included_extensions: detector.included_extensions(),
+        key_files: detector.key_files(),
+    }
+}
+
+/// The registered project type called `name`, regardless of detection
+pub fn project_type(name: &str) -> Option<ProjectType> {
+    DETECTORS
+        .iter()
+        .find(|detector| detector.name() == name)
+        .map(|detector| project_type_of(*detector))
+}
+
+/// The project types `scan` matches, highest priority first. A project can be several
+/// types at once, such as a Godot game with Lua tooling; their rules combine.
+pub fn detect_in(scan: &ProjectScan) -> Vec<ProjectType> {
+    let mut types: Vec<ProjectType> = DETECTORS
+        .iter()
+        .filter(|detector| detector.detect(scan))
+        .map(|detector| project_type_of(*detector))
+        .collect();
+    types.sort_by_key(|project_type| std::cmp::Reverse(project_type.priority));
+    types
+}
+
+/// Scan `project_path` once and run every detector over the result
+#[cfg(feature = "native")]
+pub fn detect_project_types(project_path: &Path) -> Vec<ProjectType> {
+    detect_in(&ProjectScan::new(project_path))
+}
+
+pub fn has_type(types: &[ProjectType], name: &str) -> bool {
+    types.iter().any(|project_type| project_type.name == name)
+}
+
+#[cfg(feature = "native")]
+pub fn is_godot_project(project_path: &Path) -> bool {
+    Godot.detect(&ProjectScan::new(project_path))
+}
+
+#[cfg(feature = "native")]
+pub fn is_unreal_project(project_path: &Path) -> bool {
+    Unreal.detect(&ProjectScan::new(project_path))
+}
+
+/// A plain Lua project, or a LÖVE game, Neovim plugin, or OpenResty app
+#[cfg(feature = "native")]
+pub fn is_lua_project(project_path: &Path) -> bool {
+    let scan = ProjectScan::new(project_path);
+    let detectors: [&dyn ProjectDetector; 4] = [&Lua, &Love, &NeovimPlugin, &OpenResty];
+    detectors.iter().any(|detector| detector.detect(&scan))
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::should_ignore;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn names(types: &[ProjectType]) -> Vec<&'static str> {
+        types.iter().map(|project_type| project_type.name).collect()
+    }
+
+    #[test]
+    fn test_detects_types_from_one_scan() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        assert!(detect_project_types(root).is_empty());
+
+        fs::write(root.join("project.godot"), "").unwrap();
+        fs::write(root.join("main.tf"), "").unwrap();
+        fs::create_dir_all(root.join("scripts/ai")).unwrap();
+        fs::write(root.join("scripts/ai/brain.lua"), "").unwrap();
+
+        let scan = ProjectScan::new(root);
+        assert!(scan.has_dir("scripts/ai"));
+        assert_eq!(scan.count_extension("lua"), 1);
+        assert_eq!(names(&detect_in(&scan)), vec![GODOT, TERRAFORM]);
+    }
+
+    #[test]
+    fn test_unreal_detection_and_ignores() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(!is_unreal_project(temp_dir.path()));
+        fs::write(temp_dir.path().join("Shooter.uproject"), "{}").unwrap();
+        assert!(is_unreal_project(temp_dir.path()));
+
+        let types = detect_project_types(temp_dir.path());
+        assert_eq!(types[0].ignore_patterns, UNREAL_IGNORE_PATTERNS);
+        assert_eq!(types[0].score("Shooter.uproject"), 30);
+        assert_eq!(types[0].score("Source/Shooter.cpp"), 0);
+        assert!(types[0].includes("inl"));
+        assert_eq!(project_type(UNREAL).as_ref(), Some(&types[0]));
+
+        let patterns: HashSet<String> = UNREAL_IGNORE_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let root = temp_dir.path();
+        assert!(should_ignore(&root.join("Binaries/Win64/a.dll"), &patterns));
+        assert!(should_ignore(
+            &root.join("Plugins/Fx/Binaries/a.so"),
+            &patterns
+        ));
+        assert!(!should_ignore(&root.join("Source/Shooter.cpp"), &patterns));
+    }
+
+    #[test]
+    fn test_lua_frameworks() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("lua/surround")).unwrap();
+        fs::create_dir_all(root.join("plugin")).unwrap();
+        fs::write(root.join("lua/surround/util.lua"), "").unwrap();
+        fs::write(root.join("plugin/surround.lua"), "").unwrap();
+        assert!(is_lua_project(root));
+
+        let types = detect_project_types(root);
+        assert_eq!(names(&types), vec![NEOVIM_PLUGIN]);
+        assert_eq!(types[0].score("plugin/surround.lua"), 26);
+        assert_eq!(types[0].score("lua/surround/util.lua"), 25);
+        assert!(types[0].ignore_patterns.contains(&"doc/tags"));
+
+        fs::write(root.join("main.lua"), "").unwrap();
+        fs::write(root.join("conf.lua"), "").unwrap();
+        assert_eq!(
+            names(&detect_project_types(root)),
+            vec![LOVE, NEOVIM_PLUGIN, LUA]
+        );
+    }
+
+    #[test]
+    fn test_openresty_needs_lua_directives() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("conf")).unwrap();
+        fs::write(root.join("conf/nginx.conf"), "location / { root html; }").unwrap();
+        assert!(detect_project_types(root).is_empty());
+
+        fs::write(
+            root.join("conf/nginx.conf"),
+            "location /api { content_by_lua_file app/api.lua; }",
+        )
+        .unwrap();
+        let types = detect_project_types(root);
+        assert_eq!(names(&types), vec![OPENRESTY]);
+        assert!(types[0].includes("conf"));
+        assert_eq!(types[0].score("conf/nginx.conf"), 26);
+    }
+
+    #[test]
+    fn test_lua_needs_an_entry_file_or_several_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..4 {
+            fs::write(temp_dir.path().join(format!("m{}.lua", i)), "").unwrap();
+        }
+        assert!(!is_lua_project(temp_dir.path()));
+        fs::write(temp_dir.path().join("m4.lua"), "").unwrap();
+        assert!(is_lua_project(temp_dir.path()));
+    }
+}
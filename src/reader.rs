@@ -0,0 +1,117 @@
+// Size-capped, chunked reading of source files with incremental UTF-8 validation
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::error::{DigestError, Result};
+use crate::vfs::Vfs;
+
+/// Bytes read per chunk
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// What `read_text` found
+#[derive(Debug, PartialEq, Eq)]
+pub enum TextFile {
+    Text(String),
+    /// The file holds more than the byte limit; reading stopped at the limit
+    TooLarge,
+    /// The file isn't valid UTF-8; reading stopped at the first invalid sequence
+    NotUtf8,
+}
+
+/// Read a UTF-8 file of at most `max_bytes` in fixed-size chunks.
+///
+/// Unlike `fs::read_to_string`, this never holds more than `max_bytes` plus one chunk, and
+/// gives up on binary files as soon as an invalid byte shows up instead of after reading
+/// them whole.
+pub fn read_text(vfs: &dyn Vfs, path: &Path, max_bytes: u64) -> io::Result<TextFile> {
+    let size_hint = vfs
+        .metadata(path)
+        .map(|m| m.len)
+        .unwrap_or(0)
+        .min(max_bytes);
+    read_text_from(vfs.open(path)?, max_bytes, size_hint as usize)
+}
+
+/// Read a whole UTF-8 file such as an ignore or config file, telling a file that isn't text
+/// apart from one that couldn't be read
+pub fn read_utf8(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).map_err(DigestError::io("read", path))?;
+    String::from_utf8(bytes).map_err(|_| DigestError::NotUtf8 {
+        path: path.to_path_buf(),
+    })
+}
+
+fn read_text_from(reader: impl Read, max_bytes: u64, size_hint: usize) -> io::Result<TextFile> {
+    // One byte past the limit is enough to tell the file is too large
+    let mut reader = reader.take(max_bytes.saturating_add(1));
+    let mut text = String::with_capacity(size_hint);
+    let mut chunk = vec![0; CHUNK_SIZE];
+    // Bytes of a character split across chunks
+    let mut pending: Vec<u8> = Vec::new();
+    let mut total: u64 = 0;
+
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        total += n as u64;
+        if total > max_bytes {
+            return Ok(TextFile::TooLarge);
+        }
+
+        pending.extend_from_slice(&chunk[..n]);
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(valid) => valid.len(),
+            // An invalid sequence, as opposed to a character cut off at the chunk boundary
+            Err(err) if err.error_len().is_some() => return Ok(TextFile::NotUtf8),
+            Err(err) => err.valid_up_to(),
+        };
+        text.push_str(std::str::from_utf8(&pending[..valid]).unwrap_or_default());
+        pending.drain(..valid);
+    }
+
+    if pending.is_empty() {
+        Ok(TextFile::Text(text))
+    } else {
+        Ok(TextFile::NotUtf8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_multibyte_text_across_chunks() {
+        // Put a 3-byte character across the first chunk boundary
+        let content = format!("{}€ tail", "a".repeat(CHUNK_SIZE - 1));
+        let result = read_text_from(content.as_bytes(), 1 << 20, 0).unwrap();
+        assert_eq!(result, TextFile::Text(content));
+    }
+
+    #[test]
+    fn test_stops_at_limit_and_invalid_bytes() {
+        let content = "x".repeat(100);
+        assert_eq!(
+            read_text_from(content.as_bytes(), 99, 0).unwrap(),
+            TextFile::TooLarge
+        );
+        assert_eq!(
+            read_text_from(content.as_bytes(), 100, 0).unwrap(),
+            TextFile::Text(content)
+        );
+        assert_eq!(
+            read_text_from(&[b'a', 0xff, b'b'][..], 100, 0).unwrap(),
+            TextFile::NotUtf8
+        );
+        // A character cut off by the end of the file
+        assert_eq!(
+            read_text_from(&"€".as_bytes()[..2], 100, 0).unwrap(),
+            TextFile::NotUtf8
+        );
+    }
+}
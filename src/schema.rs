@@ -0,0 +1,347 @@
+// JSON Schema for the `--format json` digest, printed by `digest schema`
+use serde_json::{json, Value};
+
+/// Version of the JSON digest layout. Bump it only for changes that can break consumers
+/// (removed or renamed fields, changed types); new optional fields keep the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema (draft 2020-12) describing the JSON digest
+pub fn json_schema() -> Value {
+    let nullable_string = json!({ "type": ["string", "null"] });
+
+    let git_info = json!({
+        "type": ["object", "null"],
+        "description": "Repository state the digest was generated from",
+        "required": ["branch", "commit", "dirty", "remote_url"],
+        "properties": {
+            "branch": nullable_string,
+            "commit": { "type": "string" },
+            "dirty": { "type": "boolean" },
+            "remote_url": nullable_string
+        }
+    });
+
+    let file_git_info = json!({
+        "type": "object",
+        "description": "Last commit touching the file (--git-file-info)",
+        "required": ["commit", "author", "date", "age_days"],
+        "properties": {
+            "commit": { "type": "string" },
+            "author": { "type": "string" },
+            "date": { "type": "string" },
+            "age_days": { "type": "integer", "minimum": 0 }
+        }
+    });
+
+    let line_stats = json!({
+        "type": "object",
+        "required": ["code", "comments", "blanks"],
+        "properties": {
+            "code": { "type": "integer", "minimum": 0 },
+            "comments": { "type": "integer", "minimum": 0 },
+            "blanks": { "type": "integer", "minimum": 0 }
+        }
+    });
+
+    let file = json!({
+        "type": "object",
+        "required": ["path", "language", "size", "modified", "sha256", "lines", "content"],
+        "properties": {
+            "path": { "type": "string" },
+            "language": nullable_string,
+            "size": { "type": "integer", "minimum": 0 },
+            "modified": {
+                "type": ["integer", "null"],
+                "description": "Modification time in seconds since the Unix epoch"
+            },
+            "sha256": { "type": "string", "pattern": "^[0-9a-f]{64}$" },
+            "lines": { "type": "integer", "minimum": 0 },
+            "content": { "type": "string" },
+            "stats": line_stats,
+            "git": file_git_info,
+            "url": {
+                "type": "string",
+                "description": "Web URL of the file at the digested commit (--link-files)"
+            },
+            "submodule": {
+                "type": "string",
+                "description": "Path of the git submodule the file belongs to"
+            },
+            "complexity": {
+                "type": "object",
+                "description": "Estimated complexity (--complexity)",
+                "required": ["cyclomatic", "max_nesting"],
+                "properties": {
+                    "cyclomatic": { "type": "integer", "minimum": 1 },
+                    "max_nesting": { "type": "integer", "minimum": 0 }
+                }
+            }
+        }
+    });
+
+    let language_stats = json!({
+        "type": "object",
+        "required": ["language", "files", "lines", "bytes", "percentage"],
+        "properties": {
+            "language": { "type": "string" },
+            "files": { "type": "integer", "minimum": 0 },
+            "lines": { "type": "integer", "minimum": 0 },
+            "bytes": { "type": "integer", "minimum": 0 },
+            "percentage": { "type": "number", "minimum": 0, "maximum": 100 }
+        }
+    });
+
+    let dependency_summary = json!({
+        "type": "object",
+        "required": ["ecosystem", "manifest", "direct", "lockfile", "transitive_count"],
+        "properties": {
+            "ecosystem": { "type": "string" },
+            "manifest": { "type": "string" },
+            "direct": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "version", "kind"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "version": nullable_string,
+                        "kind": { "enum": ["normal", "dev", "build"] }
+                    }
+                }
+            },
+            "lockfile": nullable_string,
+            "transitive_count": { "type": ["integer", "null"], "minimum": 0 }
+        }
+    });
+
+    let summary = json!({
+        "type": "object",
+        "description": "Totals: files included and excluded by reason, lines, tokens, and how the digest was made",
+        "required": [
+            "files_included",
+            "files_excluded",
+            "total_lines",
+            "total_tokens",
+            "tool_version"
+        ],
+        "properties": {
+            "files_included": { "type": "integer", "minimum": 0 },
+            "files_excluded": {
+                "type": "object",
+                "description": "Files left out, by reason; reasons with no files are omitted",
+                "properties": {
+                    "ignored": { "type": "integer", "minimum": 0 },
+                    "not_code": { "type": "integer", "minimum": 0 },
+                    "oversized": { "type": "integer", "minimum": 0 },
+                    "unreadable": { "type": "integer", "minimum": 0 },
+                    "minified": { "type": "integer", "minimum": 0 },
+                    "undocumented": { "type": "integer", "minimum": 0 },
+                    "content_filter": { "type": "integer", "minimum": 0 },
+                    "max_files": { "type": "integer", "minimum": 0 },
+                    "symlink": { "type": "integer", "minimum": 0 },
+                    "symlink_loop": { "type": "integer", "minimum": 0 },
+                    "duplicate": { "type": "integer", "minimum": 0 }
+                },
+                "additionalProperties": false
+            },
+            "total_lines": { "type": "integer", "minimum": 0 },
+            "total_tokens": { "type": "integer", "minimum": 0 },
+            "generation_ms": { "type": "integer", "minimum": 0 },
+            "tool_version": { "type": "string" }
+        }
+    });
+
+    let pii_finding = json!({
+        "type": "object",
+        "required": ["path", "line", "kind", "redacted"],
+        "properties": {
+            "path": { "type": "string" },
+            "line": { "type": "integer", "minimum": 1 },
+            "kind": { "enum": ["email", "phone", "ssn", "nino", "card_number", "iban"] },
+            "redacted": {
+                "type": "boolean",
+                "description": "Whether the value was replaced in the digest (--scan-action redact)"
+            }
+        }
+    });
+
+    let duplicate_block = json!({
+        "type": "object",
+        "required": ["lines", "occurrences"],
+        "properties": {
+            "lines": {
+                "type": "integer",
+                "minimum": 1,
+                "description": "Lines the copies share, not counting blank lines and lone braces"
+            },
+            "occurrences": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "start_line", "end_line"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "start_line": { "type": "integer", "minimum": 1 },
+                        "end_line": { "type": "integer", "minimum": 1 }
+                    }
+                }
+            }
+        }
+    });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Digest",
+        "description": format!("JSON output of digest, schema version {}", SCHEMA_VERSION),
+        "type": "object",
+        "required": [
+            "schema_version",
+            "project_name",
+            "git",
+            "main_language",
+            "language_breakdown",
+            "dependencies",
+            "files"
+        ],
+        "properties": {
+            "schema_version": { "const": SCHEMA_VERSION },
+            "generated_by": { "type": "string", "description": "Name of the tool that wrote the digest" },
+            "digest_version": { "type": "string", "description": "Version of that tool" },
+            "generated_at": {
+                "type": "string",
+                "format": "date-time",
+                "description": "When the digest was generated, in UTC (absent with --no-timestamp)"
+            },
+            "project_name": { "type": "string" },
+            "part": {
+                "type": "object",
+                "description": "Position within a --split-tokens output",
+                "required": ["index", "total"],
+                "properties": {
+                    "index": { "type": "integer", "minimum": 1 },
+                    "total": { "type": "integer", "minimum": 1 }
+                }
+            },
+            "instructions": { "type": "string" },
+            "git": git_info,
+            "submodules": {
+                "type": "array",
+                "description": "Git submodules declared in .gitmodules",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "path"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "path": { "type": "string" },
+                        "url": { "type": "string" }
+                    }
+                }
+            },
+            "entry_points": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "ecosystem"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "ecosystem": { "type": "string" }
+                    }
+                }
+            },
+            "godot": {
+                "type": "object",
+                "description": "Main scene, autoloads, and the scripts they attach, from project.godot",
+                "properties": {
+                    "name": { "type": "string" },
+                    "main_scene": { "type": "string" },
+                    "autoloads": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["name", "path"],
+                            "properties": {
+                                "name": { "type": "string" },
+                                "path": { "type": "string" }
+                            }
+                        }
+                    },
+                    "scripts": { "type": "array", "items": { "type": "string" } }
+                }
+            },
+            "main_language": nullable_string,
+            "language_breakdown": { "type": "array", "items": language_stats },
+            "dependencies": { "type": "array", "items": dependency_summary },
+            "excluded": {
+                "type": "array",
+                "description": "Paths the walk skipped: unfollowed symlinks, symlink loops, duplicates",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "reason"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "reason": { "enum": ["symlink", "symlink_loop", "duplicate"] },
+                        "target": { "type": "string" }
+                    }
+                }
+            },
+            "skipped": {
+                "type": "array",
+                "description": "Images, binaries, and oversized files listed by --mention-skipped, without content",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "kind", "bytes"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "kind": { "enum": ["image", "binary", "oversized", "minified"] },
+                        "bytes": { "type": "integer", "minimum": 0 }
+                    }
+                }
+            },
+            "images": {
+                "type": "array",
+                "description": "Small images embedded by --embed-images",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "media_type", "bytes", "data"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "media_type": { "type": "string" },
+                        "bytes": { "type": "integer", "minimum": 0 },
+                        "data": { "type": "string" }
+                    }
+                }
+            },
+            "lockfiles": {
+                "type": "array",
+                "description": "Lockfiles listed by --lockfiles summary in place of their content",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "ecosystem", "lines", "sha256"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "ecosystem": { "type": "string" },
+                        "packages": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Locked packages, excluding the project itself"
+                        },
+                        "lines": { "type": "integer", "minimum": 0 },
+                        "sha256": { "type": "string" }
+                    }
+                }
+            },
+            "duplicates": {
+                "type": "array",
+                "description": "Blocks of code repeated across the included files, found by --duplicates",
+                "items": duplicate_block
+            },
+            "pii": {
+                "type": "array",
+                "description": "Personal data found in the included files by --scan pii",
+                "items": pii_finding
+            },
+            "files": { "type": "array", "items": file },
+            "summary": summary
+        }
+    })
+}
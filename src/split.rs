@@ -0,0 +1,84 @@
+// Packing of files into multiple output parts under a token budget
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Group consecutive items into parts whose total cost, plus a per-part `overhead`,
+/// stays within `budget`.
+///
+/// Items are kept in order. An item that doesn't fit even in an empty part gets a part
+/// of its own, so the result may contain parts over budget; callers can detect them
+/// with the same costs.
+pub fn pack_parts(costs: &[usize], overhead: usize, budget: usize) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut used = overhead;
+
+    for (i, cost) in costs.iter().enumerate() {
+        if i > start && used + cost > budget {
+            parts.push(start..i);
+            start = i;
+            used = overhead;
+        }
+        used += cost;
+    }
+    if start < costs.len() {
+        parts.push(start..costs.len());
+    }
+
+    parts
+}
+
+/// Path of part `index` (1-based) for an output path, e.g. `digest.md` -> `digest.part2.md`
+pub fn part_path(output: &Path, index: usize) -> PathBuf {
+    sibling_path(output, &format!("part{}", index))
+}
+
+/// Path of the manifest describing a split output, e.g. `digest.md` -> `digest.manifest.json`
+pub fn manifest_path(output: &Path) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "digest".to_string());
+    output.with_file_name(format!("{}.manifest.json", stem))
+}
+
+fn sibling_path(output: &Path, infix: &str) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "digest".to_string());
+    let name = match output.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, infix, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, infix),
+    };
+    output.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_parts() {
+        assert_eq!(pack_parts(&[3, 3, 3, 3], 1, 7), vec![0..2, 2..4]);
+        assert_eq!(pack_parts(&[3, 10, 3], 1, 7), vec![0..1, 1..2, 2..3]);
+        assert_eq!(pack_parts(&[1, 1, 1], 0, 100), vec![0..3]);
+        assert!(pack_parts(&[], 5, 10).is_empty());
+    }
+
+    #[test]
+    fn test_part_paths() {
+        assert_eq!(
+            part_path(Path::new("out/digest.md"), 2),
+            PathBuf::from("out/digest.part2.md")
+        );
+        assert_eq!(
+            part_path(Path::new("digest"), 1),
+            PathBuf::from("digest.part1")
+        );
+        assert_eq!(
+            manifest_path(Path::new("out/digest.md")),
+            PathBuf::from("out/digest.manifest.json")
+        );
+    }
+}
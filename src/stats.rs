@@ -0,0 +1,157 @@
+// Per-file line statistics from tokei
+#[cfg(feature = "native")]
+use crate::FileInfo;
+use serde::Serialize;
+#[cfg(feature = "native")]
+use std::collections::HashMap;
+use std::path::Path;
+#[cfg(feature = "native")]
+use tokei::{Config, LanguageType};
+
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineStats {
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
+/// Count code, comment, and blank lines, or None if tokei doesn't recognize the file's language
+#[cfg(feature = "native")]
+pub fn line_stats(path: &Path, content: &str) -> Option<LineStats> {
+    let config = Config::default();
+    let language = LanguageType::from_path(path, &config)?;
+    // Fold embedded blobs (e.g. code blocks in Markdown) into the file's own counts
+    let stats = language.parse_from_str(content, &config).summarise();
+    Some(LineStats {
+        code: stats.code,
+        comments: stats.comments,
+        blanks: stats.blanks,
+    })
+}
+
+/// Without tokei no language is recognized
+#[cfg(not(feature = "native"))]
+pub fn line_stats(_path: &Path, _content: &str) -> Option<LineStats> {
+    None
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LanguageStats {
+    /// Language name as reported by tokei
+    pub language: String,
+    pub files: usize,
+    /// Code, comment, and blank lines together
+    pub lines: usize,
+    pub bytes: u64,
+    /// Share of all lines in the breakdown, rounded to two decimals
+    pub percentage: f64,
+}
+
+impl LanguageStats {
+    pub fn new(language: String) -> Self {
+        LanguageStats {
+            language,
+            files: 0,
+            lines: 0,
+            bytes: 0,
+            percentage: 0.0,
+        }
+    }
+}
+
+/// Fill in percentages and order languages by line count, largest first
+pub fn finish_breakdown(mut breakdown: Vec<LanguageStats>) -> Vec<LanguageStats> {
+    let total: usize = breakdown.iter().map(|l| l.lines).sum();
+    for language in &mut breakdown {
+        language.percentage = if total == 0 {
+            0.0
+        } else {
+            (language.lines as f64 * 10_000.0 / total as f64).round() / 100.0
+        };
+    }
+    breakdown.sort_by(|a, b| {
+        b.lines
+            .cmp(&a.lines)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+    breakdown
+}
+
+/// Per-language totals across `files`, using the same language names as the whole-tree breakdown
+#[cfg(feature = "native")]
+pub fn included_breakdown(files: &[FileInfo]) -> Vec<LanguageStats> {
+    let config = Config::default();
+    let mut breakdown: HashMap<String, LanguageStats> = HashMap::new();
+    for file in files {
+        let (Some(stats), Some(language)) =
+            (file.stats, LanguageType::from_path(&file.path, &config))
+        else {
+            continue;
+        };
+        let name = language.to_string();
+        let entry = breakdown
+            .entry(name.clone())
+            .or_insert_with(|| LanguageStats::new(name));
+        entry.files += 1;
+        entry.lines += stats.code + stats.comments + stats.blanks;
+        entry.bytes += file.content.len() as u64;
+    }
+    finish_breakdown(breakdown.into_values().collect())
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_stats() {
+        let content = "// answer\nfn main() {\n\n    println!(\"42\");\n}\n";
+        assert_eq!(
+            line_stats(Path::new("src/main.rs"), content),
+            Some(LineStats {
+                code: 3,
+                comments: 1,
+                blanks: 1,
+            })
+        );
+        assert_eq!(line_stats(Path::new("data.unknown"), "x"), None);
+    }
+
+    #[test]
+    fn test_included_breakdown() {
+        let file =
+            |path: &str, content: &str| FileInfo::new(path.to_string(), None, content.to_string());
+        let files = [
+            file(
+                "src/main.rs",
+                "fn main() {}
+",
+            ),
+            file(
+                "src/lib.rs",
+                "// lib
+
+pub fn f() {}
+",
+            ),
+            file(
+                "script.py",
+                "print(1)
+",
+            ),
+            file(
+                "notes.unknown",
+                "text
+",
+            ),
+        ];
+
+        let breakdown = included_breakdown(&files);
+        let summary: Vec<(&str, usize, usize, f64)> = breakdown
+            .iter()
+            .map(|l| (l.language.as_str(), l.files, l.lines, l.percentage))
+            .collect();
+        assert_eq!(summary, vec![("Rust", 2, 4, 80.0), ("Python", 1, 1, 20.0)]);
+        assert_eq!(breakdown[1].bytes, 9);
+    }
+}
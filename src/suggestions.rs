@@ -0,0 +1,292 @@
+// Ignore patterns for included files that are rarely worth a digest's tokens: lockfiles,
+// snapshots, fixtures, and bulk data
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::assets::format_size;
+use crate::lockfiles::is_lockfile;
+use crate::tokens::estimate_tokens;
+use crate::FileInfo;
+
+const SNAPSHOT_DIRS: &[&str] = &["__snapshots__", "snapshots"];
+const FIXTURE_DIRS: &[&str] = &[
+    "fixtures",
+    "__fixtures__",
+    "testdata",
+    "test_data",
+    "test-data",
+];
+const DATA_EXTENSIONS: &[&str] = &["json", "jsonl", "ndjson", "geojson", "csv", "tsv", "xml"];
+
+/// Data files of one extension in one directory are suggested once they add up to this many bytes
+pub const DATA_LIMIT: u64 = 256 * 1024;
+
+/// A pattern for .digestignore and the included files it would leave out
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub pattern: String,
+    /// Why the files are likely unwanted, e.g. "lockfile" or "1.2 MB of JSON data"
+    pub reason: String,
+    pub files: usize,
+    /// Estimated tokens of the files' content
+    pub tokens: usize,
+}
+
+/// Suggested patterns for the included `files`, those saving the most tokens first. A file is
+/// covered by at most one suggestion: lockfiles, then snapshots, fixtures, and bulk data.
+pub fn suggest_ignores(files: &[FileInfo]) -> Vec<Suggestion> {
+    let mut suggestions: BTreeMap<String, Suggestion> = BTreeMap::new();
+    let mut add = |pattern: String, reason: String, file: &FileInfo| {
+        let suggestion = suggestions
+            .entry(pattern.clone())
+            .or_insert_with(|| Suggestion {
+                pattern,
+                reason,
+                files: 0,
+                tokens: 0,
+            });
+        suggestion.files += 1;
+        suggestion.tokens += estimate_tokens(&file.content);
+    };
+
+    // Bulk data is grouped by directory and extension before deciding
+    let mut data: BTreeMap<(&str, &str), Vec<&FileInfo>> = BTreeMap::new();
+    let mut per_dir: BTreeMap<&str, usize> = BTreeMap::new();
+    for file in files {
+        let path = file.path.as_str();
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        *per_dir.entry(dir).or_default() += 1;
+        let dirs: Vec<&str> = dir.split('/').collect();
+
+        if is_lockfile(name) {
+            add(name.to_string(), "lockfile".to_string(), file);
+        } else if let Some(snapshots) = dirs.iter().find(|d| SNAPSHOT_DIRS.contains(d)) {
+            add(
+                format!("{}/", snapshots),
+                "test snapshots".to_string(),
+                file,
+            );
+        } else if name.ends_with(".snap") {
+            add("*.snap".to_string(), "test snapshots".to_string(), file);
+        } else if let Some(fixtures) = dirs.iter().find(|d| FIXTURE_DIRS.contains(d)) {
+            add(format!("{}/", fixtures), "test fixtures".to_string(), file);
+        } else if let Some(extension) = name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension)
+            .filter(|extension| DATA_EXTENSIONS.contains(extension))
+        {
+            data.entry((dir, extension)).or_default().push(file);
+        }
+    }
+
+    for ((dir, extension), group) in data {
+        let bytes: u64 = group.iter().map(|file| file.size).sum();
+        if bytes < DATA_LIMIT {
+            continue;
+        }
+        let reason = format!(
+            "{} of {} data",
+            format_size(bytes),
+            extension.to_uppercase()
+        );
+        // The directory when the data is all it holds, otherwise each file: the root can't be
+        // ignored, and a `*.json` pattern would reach into every directory
+        let whole_dir = !dir.is_empty() && per_dir[dir] == group.len();
+        for file in group {
+            let pattern = if whole_dir {
+                format!("{}/", dir)
+            } else {
+                file.path.clone()
+            };
+            add(pattern, reason.clone(), file);
+        }
+    }
+
+    let mut suggestions: Vec<Suggestion> = suggestions.into_values().collect();
+    suggestions.sort_by(|a, b| {
+        b.tokens
+            .cmp(&a.tokens)
+            .then_with(|| a.pattern.cmp(&b.pattern))
+    });
+    suggestions
+}
+
+/// Suggestions as a list for the terminal, with how much each would save
+pub fn format_suggestions(suggestions: &[Suggestion]) -> String {
+    if suggestions.is_empty() {
+        return "No ignore suggestions: nothing included looks like lockfiles, snapshots, \
+                fixtures, or bulk data\n"
+            .to_string();
+    }
+    let width = suggestions
+        .iter()
+        .map(|s| s.pattern.len())
+        .max()
+        .unwrap_or(0);
+    let mut output = String::from("Suggested .digestignore additions:\n");
+    for suggestion in suggestions {
+        output.push_str(&format!(
+            "  {:<width$}  {} ({} {}, ~{} tokens)\n",
+            suggestion.pattern,
+            suggestion.reason,
+            suggestion.files,
+            if suggestion.files == 1 {
+                "file"
+            } else {
+                "files"
+            },
+            suggestion.tokens
+        ));
+    }
+    output
+}
+
+/// Append the suggested patterns that `ignore_file` doesn't list yet, creating it if needed;
+/// returns the patterns added
+pub fn append_suggestions(
+    ignore_file: &Path,
+    suggestions: &[Suggestion],
+) -> io::Result<Vec<String>> {
+    let existing = match fs::read_to_string(ignore_file) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let listed: Vec<&str> = existing.lines().map(str::trim).collect();
+    let added: Vec<String> = suggestions
+        .iter()
+        .map(|s| s.pattern.clone())
+        .filter(|pattern| !listed.contains(&pattern.as_str()))
+        .collect();
+    if added.is_empty() {
+        return Ok(added);
+    }
+
+    let mut text = String::new();
+    if !existing.is_empty() {
+        if !existing.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push('\n');
+    }
+    text.push_str("# Suggested by digest --apply-suggestions\n");
+    for pattern in &added {
+        text.push_str(pattern);
+        text.push('\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(ignore_file)?
+        .write_all(text.as_bytes())?;
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::CompiledIgnore;
+
+    fn file(path: &str, content: &str) -> FileInfo {
+        FileInfo::new(path.to_string(), None, content.to_string())
+    }
+
+    #[test]
+    fn test_suggest_ignores() {
+        let big = "{\"row\": 1}\n".repeat(30_000);
+        let files = [
+            file("src/main.rs", "fn main() {}\n"),
+            file("web/package-lock.json", "{}\n"),
+            file("Cargo.lock", "version = 3\n"),
+            file("src/__snapshots__/app.test.js.snap", "exports[`a`] = 1;\n"),
+            file("tests/fixtures/input.json", "{}\n"),
+            file("tests/testdata/big.csv", "a,b\n"),
+            file("data/samples/a.json", &big),
+            file("data/samples/b.json", &big),
+            file("data/samples/c.json", &big),
+            file("data/mixed/d.json", &big),
+            file("data/mixed/reader.py", "import json\n"),
+            file("config.json", "{}\n"),
+            file("dump.xml", &big),
+        ];
+
+        let suggestions = suggest_ignores(&files);
+        let found: Vec<(&str, &str, usize)> = suggestions
+            .iter()
+            .map(|s| (s.pattern.as_str(), s.reason.as_str(), s.files))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("data/samples/", "966.8 KB of JSON data", 3),
+                ("data/mixed/d.json", "322.3 KB of JSON data", 1),
+                ("dump.xml", "322.3 KB of XML data", 1),
+                ("__snapshots__/", "test snapshots", 1),
+                ("Cargo.lock", "lockfile", 1),
+                ("fixtures/", "test fixtures", 1),
+                ("package-lock.json", "lockfile", 1),
+                ("testdata/", "test fixtures", 1),
+            ]
+        );
+
+        // Each pattern leaves out the files it was suggested for, and no others
+        let matched = |pattern: &String| -> Vec<&str> {
+            let matcher = CompiledIgnore::new([pattern]);
+            files
+                .iter()
+                .map(|f| f.path.as_str())
+                .filter(|path| matcher.is_ignored(Path::new(path)))
+                .collect()
+        };
+        for suggestion in &suggestions {
+            assert_eq!(
+                matched(&suggestion.pattern).len(),
+                suggestion.files,
+                "{}",
+                suggestion.pattern
+            );
+        }
+
+        let listed = format_suggestions(&suggestions);
+        assert!(listed.starts_with("Suggested .digestignore additions:\n  data/samples/ "));
+        assert!(listed.contains("  test fixtures (1 file, ~"));
+        assert!(suggest_ignores(&files[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_append_suggestions() {
+        let dir = tempfile::tempdir().unwrap();
+        let ignore_file = dir.path().join(".digestignore");
+        fs::write(&ignore_file, "*.log\nCargo.lock").unwrap();
+
+        let suggestion = |pattern: &str| Suggestion {
+            pattern: pattern.to_string(),
+            reason: "lockfile".to_string(),
+            files: 1,
+            tokens: 10,
+        };
+        let suggestions = [suggestion("Cargo.lock"), suggestion("fixtures/")];
+        assert_eq!(
+            append_suggestions(&ignore_file, &suggestions).unwrap(),
+            vec!["fixtures/"]
+        );
+        assert_eq!(
+            fs::read_to_string(&ignore_file).unwrap(),
+            "*.log\nCargo.lock\n\n# Suggested by digest --apply-suggestions\nfixtures/\n"
+        );
+        // Applying again adds nothing
+        assert!(append_suggestions(&ignore_file, &suggestions)
+            .unwrap()
+            .is_empty());
+
+        let fresh = dir.path().join("new").join(".digestignore");
+        fs::create_dir_all(fresh.parent().unwrap()).unwrap();
+        append_suggestions(&fresh, &suggestions).unwrap();
+        assert!(fs::read_to_string(&fresh)
+            .unwrap()
+            .starts_with("# Suggested by digest"));
+    }
+}
@@ -0,0 +1,179 @@
+// The digest footer: what went in, what was left out and why, and how the digest was made
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::tokens::estimate_tokens;
+use crate::{ExcludedFile, ExclusionReason, FileInfo, FilterCounts};
+
+/// Name of the tool, as recorded in `Digest::generated_by`
+pub const GENERATED_BY: &str = "digest";
+
+/// Version of the digest tool, as recorded in digests and their summaries
+pub const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Totals rendered at the end of every digest
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DigestSummary {
+    pub files_included: usize,
+    /// Files left out, by reason; reasons with no files are omitted
+    pub files_excluded: BTreeMap<&'static str, usize>,
+    pub total_lines: usize,
+    /// Estimated tokens of the included file contents
+    pub total_tokens: usize,
+    /// Milliseconds spent collecting and analyzing files, before rendering; absent with
+    /// `--no-timestamp` so identical inputs give identical output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_ms: Option<u64>,
+    pub tool_version: &'static str,
+}
+
+impl DigestSummary {
+    pub fn new(
+        files: &[FileInfo],
+        files_excluded: BTreeMap<&'static str, usize>,
+        generation_ms: Option<u64>,
+    ) -> Self {
+        DigestSummary {
+            files_included: files.len(),
+            files_excluded,
+            total_lines: files.iter().map(|file| file.lines).sum(),
+            total_tokens: files
+                .iter()
+                .map(|file| estimate_tokens(&file.content))
+                .sum(),
+            generation_ms,
+            tool_version: TOOL_VERSION,
+        }
+    }
+
+    pub fn files_excluded_total(&self) -> usize {
+        self.files_excluded.values().sum()
+    }
+}
+
+/// The time `secs` after the Unix epoch as an RFC 3339 UTC timestamp, e.g.
+/// `2024-05-01T12:30:00Z`
+pub fn rfc3339_utc(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let seconds = secs % 86_400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// When this digest is being generated, as RFC 3339. `SOURCE_DATE_EPOCH` overrides the
+/// clock, as for other reproducible-build tools.
+pub fn generated_at() -> String {
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0)
+        });
+    rfc3339_utc(secs)
+}
+
+/// Exclusion counts by reason: the walk's filters, the symlinks and duplicates it skipped,
+/// files removed by content filters (`--grep`, symbol selection), and files over `--max-files`
+pub fn exclusion_counts(
+    filtered: &FilterCounts,
+    excluded: &[ExcludedFile],
+    content_filtered: usize,
+    max_files: usize,
+) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::from([
+        ("ignored", filtered.ignored),
+        ("not_code", filtered.not_code),
+        ("oversized", filtered.oversized),
+        ("unreadable", filtered.unreadable),
+        ("minified", filtered.minified),
+        ("undocumented", filtered.undocumented),
+        ("content_filter", content_filtered),
+        ("max_files", max_files),
+    ]);
+    for file in excluded {
+        let reason = match file.reason {
+            ExclusionReason::Symlink => "symlink",
+            ExclusionReason::SymlinkLoop => "symlink_loop",
+            ExclusionReason::Duplicate => "duplicate",
+        };
+        *counts.entry(reason).or_default() += 1;
+    }
+    counts.retain(|_, count| *count > 0);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_totals() {
+        let files = vec![
+            FileInfo::new("a.rs".to_string(), None, "fn a() {}\n".to_string()),
+            FileInfo::new(
+                "b.rs".to_string(),
+                None,
+                "fn b() {}\nfn c() {}\n".to_string(),
+            ),
+        ];
+        let filtered = FilterCounts {
+            ignored: 3,
+            not_code: 1,
+            ..FilterCounts::default()
+        };
+        let excluded = vec![ExcludedFile {
+            path: "link.rs".to_string(),
+            reason: ExclusionReason::Symlink,
+            target: None,
+        }];
+
+        let summary = DigestSummary::new(
+            &files,
+            exclusion_counts(&filtered, &excluded, 0, 2),
+            Some(7),
+        );
+        assert_eq!(summary.files_included, 2);
+        assert_eq!(
+            summary.files_excluded,
+            BTreeMap::from([
+                ("ignored", 3),
+                ("max_files", 2),
+                ("not_code", 1),
+                ("symlink", 1)
+            ])
+        );
+        assert_eq!(summary.files_excluded_total(), 7);
+        assert_eq!(summary.total_lines, 3);
+        assert_eq!(summary.generation_ms, Some(7));
+        assert_eq!(summary.tool_version, TOOL_VERSION);
+    }
+
+    #[test]
+    fn test_rfc3339_utc() {
+        assert_eq!(rfc3339_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339_utc(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339_utc(1_714_566_600), "2024-05-01T12:30:00Z");
+    }
+}
@@ -0,0 +1,52 @@
+// Symbol-centric selection for `digest symbol`: the defining files plus every file referencing the name
+use crate::priority::rank_files;
+use crate::FileInfo;
+use regex::Regex;
+
+/// Keywords that introduce a named definition across the languages digest commonly sees
+const DEFINITION_KEYWORDS: &str = "struct|enum|trait|union|type|typedef|fn|mod|macro_rules!|class|interface|def|func|function|const|static|let|var|val|object|record|namespace|module|define";
+
+/// Keep only files mentioning `name` as a whole word, with defining files ranked first.
+/// Returns how many of the kept files appear to define the symbol.
+pub fn select_symbol_files(files: &mut Vec<FileInfo>, name: &str) -> usize {
+    let escaped = regex::escape(name);
+    let reference = Regex::new(&format!(r"\b{}\b", escaped)).expect("escaped symbol regex");
+    let definition = Regex::new(&format!(
+        r"(?m)(?:^|\W)(?:{})\s+{}\b",
+        DEFINITION_KEYWORDS, escaped
+    ))
+    .expect("escaped symbol regex");
+
+    files.retain(|file| reference.is_match(&file.content));
+    rank_files(files, |file| definition.is_match(&file.content) as i64);
+
+    files
+        .iter()
+        .take_while(|file| definition.is_match(&file.content))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str) -> FileInfo {
+        FileInfo::new(path.to_string(), None, content.to_string())
+    }
+
+    #[test]
+    fn test_select_symbol_files() {
+        let mut files = vec![
+            file("src/main.rs", "let p = MyStruct::new();"),
+            file("src/other.rs", "struct MyStructBuilder;"),
+            file("src/my_struct.rs", "pub struct MyStruct {\n    x: u32,\n}"),
+            file("app.py", "class MyStruct:\n    pass"),
+        ];
+
+        let defining = select_symbol_files(&mut files, "MyStruct");
+
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/my_struct.rs", "app.py", "src/main.rs"]);
+        assert_eq!(defining, 2);
+    }
+}
@@ -0,0 +1,52 @@
+// Handlebars prompt templates wrapped around the digest
+use handlebars::Handlebars;
+use serde::Serialize;
+
+use crate::error::{DigestError, Result};
+
+/// Render a handlebars `template` with `data` as its context.
+///
+/// Output is not HTML-escaped since prompts are plain text and code must stay verbatim.
+pub fn render_template<T: Serialize>(template: &str, data: &T) -> Result<String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars
+        .register_template_string("prompt", template)
+        .map_err(|err| DigestError::Template {
+            action: "parse",
+            message: err.to_string(),
+        })?;
+    handlebars
+        .render("prompt", data)
+        .map_err(|err| DigestError::Template {
+            action: "render",
+            message: err.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template_without_escaping() {
+        let data = serde_json::json!({
+            "project_name": "demo",
+            "files": [
+                { "path": "a.rs", "content": "if a < b && c {}" },
+                { "path": "b.rs", "content": "" }
+            ]
+        });
+        let output = render_template(
+            "Review {{project_name}}:\n{{#each files}}- {{path}}: {{content}}\n{{/each}}",
+            &data,
+        )
+        .unwrap();
+        assert_eq!(output, "Review demo:\n- a.rs: if a < b && c {}\n- b.rs: \n");
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        assert!(render_template("{{#each files}}", &serde_json::json!({})).is_err());
+    }
+}
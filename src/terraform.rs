@@ -0,0 +1,275 @@
+// Terraform support: project detection, default ignores, and redaction of sensitive values
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::FileInfo;
+
+/// Provider plugins, state (which holds secrets in plain text), and the provider lock file
+pub const TERRAFORM_IGNORE_PATTERNS: &[&str] = &[
+    ".terraform/",
+    "*.tfstate",
+    "*.tfstate.backup",
+    ".terraform.lock.hcl",
+];
+
+/// What sensitive values are replaced with
+const REDACTED: &str = "\"<redacted>\"";
+
+/// Net change in bracket depth over a line; braces inside strings are rare enough in
+/// variable blocks to ignore
+fn bracket_delta(line: &str) -> i32 {
+    line.chars()
+        .map(|c| match c {
+            '{' | '[' | '(' => 1,
+            '}' | ']' | ')' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The value after `name =` on a line, if the line assigns `name`
+fn assigned_value<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.trim_start().strip_prefix(name)?;
+    let rest = rest.trim_start().strip_prefix('=')?;
+    Some(rest.trim())
+}
+
+/// Number of lines a value starting on `lines[0]` spans: multi-line maps, lists, and heredocs
+fn value_span(lines: &[&str], value: &str) -> usize {
+    if let Some(marker) = value.strip_prefix("<<") {
+        let marker = marker.trim_start_matches('-').trim();
+        return lines
+            .iter()
+            .skip(1)
+            .position(|line| line.trim() == marker)
+            .map_or(lines.len(), |end| end + 2);
+    }
+    let mut depth = bracket_delta(value);
+    let mut span = 1;
+    while depth > 0 && span < lines.len() {
+        depth += bracket_delta(lines[span]);
+        span += 1;
+    }
+    span
+}
+
+/// Replace the value assigned to `name` in `lines`, keeping indentation
+fn redact_assignment(lines: &[&str], name: &str, out: &mut Vec<String>) -> usize {
+    let line = lines[0];
+    match assigned_value(line, name) {
+        Some(value) => {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            out.push(format!("{}{} = {}", indent, name, REDACTED));
+            value_span(lines, value)
+        }
+        None => {
+            out.push(line.to_string());
+            1
+        }
+    }
+}
+
+/// `variable "name" {` blocks: name, first line, and one past the last line
+fn variable_blocks(lines: &[&str]) -> Vec<(String, usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        let name = trimmed
+            .strip_prefix("variable")
+            .and_then(|rest| rest.trim_start().strip_prefix('"'))
+            .and_then(|rest| rest.split('"').next());
+        match name {
+            Some(name) if trimmed.contains('{') => {
+                let mut depth = bracket_delta(lines[i]);
+                let mut end = i + 1;
+                while depth > 0 && end < lines.len() {
+                    depth += bracket_delta(lines[end]);
+                    end += 1;
+                }
+                blocks.push((name.to_string(), i, end));
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    blocks
+}
+
+fn is_sensitive(block: &[&str]) -> bool {
+    block
+        .iter()
+        .any(|line| assigned_value(line, "sensitive") == Some("true"))
+}
+
+/// Names of the variables declared with `sensitive = true`
+pub fn sensitive_variables(content: &str) -> HashSet<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    variable_blocks(&lines)
+        .into_iter()
+        .filter(|(_, start, end)| is_sensitive(&lines[*start..*end]))
+        .map(|(name, _, _)| name)
+        .collect()
+}
+
+/// Replace the defaults of sensitive variables in a `.tf` file
+pub fn redact_tf(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    for (_, start, end) in variable_blocks(&lines) {
+        out.extend(lines[i..start].iter().map(|line| line.to_string()));
+        let block = &lines[start..end];
+        if is_sensitive(block) {
+            let mut j = 0;
+            while j < block.len() {
+                j += redact_assignment(&block[j..], "default", &mut out);
+            }
+        } else {
+            out.extend(block.iter().map(|line| line.to_string()));
+        }
+        i = end;
+    }
+    out.extend(lines[i..].iter().map(|line| line.to_string()));
+    join_lines(out, content)
+}
+
+/// Replace the values of `sensitive` variables in a `.tfvars` file
+pub fn redact_tfvars(content: &str, sensitive: &HashSet<String>) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let name = lines[i]
+            .split('=')
+            .next()
+            .map(|name| name.trim().trim_matches('"'))
+            .filter(|name| sensitive.contains(*name) && lines[i].contains('='));
+        i += match name {
+            Some(name) => {
+                let indent_len = lines[i].len() - lines[i].trim_start().len();
+                let value = lines[i][indent_len..]
+                    .split_once('=')
+                    .map_or("", |(_, v)| v.trim());
+                out.push(format!(
+                    "{}{} = {}",
+                    &lines[i][..indent_len],
+                    name,
+                    REDACTED
+                ));
+                value_span(&lines[i..], value)
+            }
+            None => {
+                out.push(lines[i].to_string());
+                1
+            }
+        };
+    }
+    join_lines(out, content)
+}
+
+fn join_lines(lines: Vec<String>, original: &str) -> String {
+    let mut joined = lines.join("\n");
+    if original.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
+
+/// Redact sensitive variable defaults in `.tf` files, and the values `.tfvars` files assign to
+/// sensitive variables declared by the `.tf` files next to them
+pub fn redact_terraform(project_path: &Path, files: &mut [FileInfo]) {
+    for file in files.iter_mut() {
+        let path = Path::new(&file.path);
+        let redacted = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tf") => redact_tf(&file.content),
+            Some("tfvars") => {
+                let dir = project_path.join(path.parent().unwrap_or(Path::new("")));
+                let sensitive = module_sensitive_variables(&dir);
+                if sensitive.is_empty() {
+                    continue;
+                }
+                redact_tfvars(&file.content, &sensitive)
+            }
+            _ => continue,
+        };
+        if redacted != file.content {
+            file.set_content(redacted);
+        }
+    }
+}
+
+/// Sensitive variables declared by the `.tf` files of the module in `dir`
+fn module_sensitive_variables(dir: &Path) -> HashSet<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return HashSet::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().extension().and_then(|ext| ext.to_str()) == Some("tf"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .flat_map(|content| sensitive_variables(&content))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARIABLES: &str = r#"variable "region" {
+  default = "eu-west-1"
+}
+
+variable "db_password" {
+  type      = string
+  sensitive = true
+  default   = "hunter2"
+}
+
+variable "tokens" {
+  sensitive = true
+  default = {
+    ci = "abc"
+  }
+}
+"#;
+
+    #[test]
+    fn test_redacts_sensitive_defaults_only() {
+        let redacted = redact_tf(VARIABLES);
+        assert!(redacted.contains(r#"default = "eu-west-1""#));
+        assert!(!redacted.contains("hunter2"));
+        assert!(!redacted.contains("abc"));
+        assert!(redacted.contains(r#"  default = "<redacted>""#));
+        assert!(redacted.ends_with("}\n"));
+        assert_eq!(redacted.matches("<redacted>").count(), 2);
+    }
+
+    #[test]
+    fn test_redacts_tfvars_assignments() {
+        let sensitive = sensitive_variables(VARIABLES);
+        assert_eq!(
+            sensitive,
+            HashSet::from(["db_password".to_string(), "tokens".to_string()])
+        );
+
+        let tfvars =
+            "region = \"us-east-1\"\ndb_password = \"s3cret\"\ntokens = {\n  ci = \"x\"\n}\n";
+        let redacted = redact_tfvars(tfvars, &sensitive);
+        assert_eq!(
+            redacted,
+            "region = \"us-east-1\"\ndb_password = \"<redacted>\"\ntokens = \"<redacted>\"\n"
+        );
+    }
+
+    #[test]
+    fn test_heredoc_default_is_removed_whole() {
+        let tf =
+            "variable \"key\" {\n  sensitive = true\n  default = <<EOT\nline1\nline2\nEOT\n}\n";
+        assert_eq!(
+            redact_tf(tf),
+            "variable \"key\" {\n  sensitive = true\n  default = \"<redacted>\"\n}\n"
+        );
+    }
+}
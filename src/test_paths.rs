@@ -0,0 +1,66 @@
+// Test files recognized by path, for --exclude-tests and --tests-only
+use std::path::Path;
+
+/// Directories holding tests, matched anywhere in the project
+pub const TEST_DIRS: &[&str] = &["test", "tests", "spec", "specs", "__tests__"];
+
+/// File-name markers of tests next to the code: `parse_test.go`, `app.test.ts`,
+/// `user_spec.rb`, `api.spec.js`
+const TEST_MARKERS: &[&str] = &["_test.", ".test.", "_spec.", ".spec."];
+
+/// Whether the file at `path`, relative to the project root, is a test: it sits in one of
+/// `TEST_DIRS`, its name carries a test marker, or it is a pytest `test_*.py` module
+pub fn is_test_path(path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/").to_lowercase();
+    let name = path.rsplit('/').next().unwrap_or("");
+    in_test_dir(&path, |dir| TEST_DIRS.contains(&dir))
+        || TEST_MARKERS.iter().any(|marker| name.contains(marker))
+        || name.starts_with("test_") && name.ends_with(".py")
+}
+
+/// Whether one of the directories of the slash-separated `path`, not its file name, is a test
+/// directory by `is_test_dir`; the `**/test*/**` ignore pattern checks paths the same way
+pub(crate) fn in_test_dir(path: &str, is_test_dir: impl Fn(&str) -> bool) -> bool {
+    let mut dirs = path.split('/').filter(|segment| !segment.is_empty());
+    dirs.next_back();
+    dirs.any(is_test_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_test_path() {
+        for path in [
+            "tests/cli.rs",
+            "src/tests/parse.rs",
+            "pkg/parse_test.go",
+            "web/src/app.test.ts",
+            "spec/models/user_spec.rb",
+            "web/src/__tests__/app.js",
+            "api.spec.js",
+            "pkg\\tests\\a.py",
+            "test_cli.py",
+        ] {
+            assert!(is_test_path(Path::new(path)), "{}", path);
+        }
+        for path in [
+            "src/main.rs",
+            "src/testing.rs",
+            "src/contest/score.rs",
+            "docs/test_plan.md",
+            "specification.md",
+        ] {
+            assert!(!is_test_path(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_in_test_dir_skips_the_file_name() {
+        let prefixed = |dir: &str| dir.starts_with("test");
+        assert!(in_test_dir("src/testing/a.rs", prefixed));
+        assert!(!in_test_dir("src/testing.rs", prefixed));
+        assert!(!in_test_dir("testing.rs", prefixed));
+    }
+}
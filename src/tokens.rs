@@ -0,0 +1,27 @@
+// Token estimation shared by budgeting, reporting and diffing
+
+/// Approximate number of characters per token for typical source code
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimate how many LLM tokens `text` will consume.
+///
+/// This is a model-agnostic heuristic (roughly four characters per token) that is
+/// close enough for budgeting without shipping a tokenizer per model.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        // Multi-byte characters count once
+        assert_eq!(estimate_tokens("ééééé"), 2);
+    }
+}
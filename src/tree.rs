@@ -0,0 +1,117 @@
+// Plain-text rendering of file paths as a directory tree
+use std::collections::BTreeMap;
+
+/// Line and estimated token counts of a file, or summed over a directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub lines: usize,
+    pub tokens: usize,
+}
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    totals: Totals,
+}
+
+impl Node {
+    fn from_paths<'a>(paths: impl IntoIterator<Item = (&'a str, Totals)>) -> Self {
+        let mut root = Node::default();
+        for (path, totals) in paths {
+            let normalized = path.replace('\\', "/");
+            let mut node = &mut root;
+            node.add(totals);
+            for component in normalized.split('/').filter(|c| !c.is_empty()) {
+                node = node.children.entry(component.to_string()).or_default();
+                node.add(totals);
+            }
+        }
+        root
+    }
+
+    fn add(&mut self, totals: Totals) {
+        self.totals.lines += totals.lines;
+        self.totals.tokens += totals.tokens;
+    }
+}
+
+/// Render relative paths as an indented tree, in the style of the `tree` command
+pub fn render_tree<S: AsRef<str>>(paths: &[S]) -> String {
+    let root = Node::from_paths(paths.iter().map(|p| (p.as_ref(), Totals::default())));
+    let mut output = String::from(".\n");
+    render_children(&root, "", &|name, _| name.to_string(), &mut output);
+    output
+}
+
+/// Render relative paths as a tree like [`render_tree`], with each file's lines and tokens
+/// and each directory's rolled up from everything below it
+pub fn render_tree_with_totals<S: AsRef<str>>(files: &[(S, Totals)]) -> String {
+    let root = Node::from_paths(files.iter().map(|(p, totals)| (p.as_ref(), *totals)));
+    let label = |name: &str, totals: Totals| {
+        format!(
+            "{} ({} lines, {} tokens)",
+            name, totals.lines, totals.tokens
+        )
+    };
+    let mut output = label(".", root.totals) + "\n";
+    render_children(&root, "", &label, &mut output);
+    output
+}
+
+fn render_children(
+    node: &Node,
+    prefix: &str,
+    label: &dyn Fn(&str, Totals) -> String,
+    output: &mut String,
+) {
+    let count = node.children.len();
+    for (i, (name, child)) in node.children.iter().enumerate() {
+        let last = i + 1 == count;
+        output.push_str(prefix);
+        output.push_str(if last { "└── " } else { "├── " });
+        output.push_str(&label(name, child.totals));
+        output.push('\n');
+
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render_children(child, &child_prefix, label, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_tree() {
+        let tree = render_tree(&["src/main.rs", "README.md", "src/lib.rs", "src/util/mod.rs"]);
+        assert_eq!(
+            tree,
+            ".\n\
+             ├── README.md\n\
+             └── src\n    \
+             ├── lib.rs\n    \
+             ├── main.rs\n    \
+             └── util\n        \
+             └── mod.rs\n"
+        );
+    }
+
+    #[test]
+    fn test_render_tree_with_totals() {
+        let totals = |lines, tokens| Totals { lines, tokens };
+        let tree = render_tree_with_totals(&[
+            ("src/main.rs", totals(40, 300)),
+            ("README.md", totals(10, 80)),
+            ("src/util/mod.rs", totals(5, 20)),
+        ]);
+        assert_eq!(
+            tree,
+            ". (55 lines, 400 tokens)\n\
+             ├── README.md (10 lines, 80 tokens)\n\
+             └── src (45 lines, 320 tokens)\n    \
+             ├── main.rs (40 lines, 300 tokens)\n    \
+             └── util (5 lines, 20 tokens)\n        \
+             └── mod.rs (5 lines, 20 tokens)\n"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+// Update-in-place for --update: each file section of a Markdown digest is marked with the
+// hash of the file's content, so a rerun keeps the sections of unchanged files as they were
+// and replaces only the rest
+use std::collections::HashMap;
+
+/// Opens a marked section; the hash and path follow on the same line
+const BEGIN: &str = "<!-- digest:file ";
+/// Closes a marked section, followed by the same hash as its opening marker so that markers
+/// within the content (a digest of a digest) can't close it early
+const END: &str = "<!-- digest:end ";
+
+/// `section` wrapped in the markers `update_document` recognizes, keyed by `path` and the
+/// SHA-256 of the file's content
+pub fn mark_section(path: &str, sha256: &str, section: &str) -> String {
+    format!(
+        "{}sha256={} path={} -->\n{}{}",
+        BEGIN,
+        sha256,
+        path,
+        section,
+        closing(sha256)
+    )
+}
+
+fn closing(sha256: &str) -> String {
+    format!("{}sha256={} -->\n", END, sha256)
+}
+
+/// A marked section of a document
+struct Section<'a> {
+    path: &'a str,
+    sha256: &'a str,
+    /// Where the section sits in the document, markers included
+    range: std::ops::Range<usize>,
+}
+
+/// The marked sections of `document` in order. A section runs from its opening marker to the
+/// first closing marker with the same hash, and markers only count at the start of a line.
+fn sections(document: &str) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut at = 0;
+    while let Some(found) = document[at..].find(BEGIN) {
+        let start = at + found;
+        at = start + BEGIN.len();
+        if start > 0 && !document[..start].ends_with('\n') {
+            continue;
+        }
+        let Some(line_end) = document[at..].find('\n').map(|end| at + end) else {
+            break;
+        };
+        let header = &document[at..line_end];
+        let Some((sha256, path)) = header
+            .strip_suffix(" -->")
+            .and_then(|header| header.strip_prefix("sha256="))
+            .and_then(|header| header.split_once(" path="))
+        else {
+            continue;
+        };
+        let body = line_end + 1;
+        let closing = closing(sha256);
+        let end = document[body..]
+            .match_indices(&closing)
+            .map(|(end, _)| body + end)
+            .find(|&end| document[..end].ends_with('\n'));
+        let Some(end) = end else {
+            continue;
+        };
+        at = end + closing.len();
+        sections.push(Section {
+            path,
+            sha256,
+            range: start..at,
+        });
+    }
+    sections
+}
+
+/// How `update_document` changed the file sections
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateSummary {
+    /// Sections kept as they were
+    pub unchanged: usize,
+    /// Sections replaced because the file changed
+    pub changed: usize,
+    /// Sections of files new to the digest
+    pub added: usize,
+    /// Sections dropped with their files
+    pub removed: usize,
+}
+
+/// `fresh` with every section whose file is unchanged since `existing` taken from `existing`
+/// instead. Everything outside the sections (header, index, footer) comes from `fresh`.
+pub fn update_document(existing: &str, fresh: &str) -> (String, UpdateSummary) {
+    let previous: HashMap<&str, Section> = sections(existing)
+        .into_iter()
+        .map(|section| (section.path, section))
+        .collect();
+
+    let mut summary = UpdateSummary::default();
+    let mut document = String::with_capacity(fresh.len());
+    let mut copied = 0;
+    let mut seen = 0;
+    for section in sections(fresh) {
+        document.push_str(&fresh[copied..section.range.start]);
+        copied = section.range.end;
+        match previous.get(section.path) {
+            Some(old) if old.sha256 == section.sha256 => {
+                summary.unchanged += 1;
+                document.push_str(&existing[old.range.clone()]);
+            }
+            Some(_) => {
+                summary.changed += 1;
+                document.push_str(&fresh[section.range]);
+            }
+            None => {
+                summary.added += 1;
+                document.push_str(&fresh[section.range]);
+            }
+        }
+        seen += previous.contains_key(section.path) as usize;
+    }
+    document.push_str(&fresh[copied..]);
+    summary.removed = previous.len() - seen;
+    (document, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(header: &str, sections: &[(&str, &str, &str)]) -> String {
+        let mut document = format!("# {}\n\n", header);
+        for (path, sha256, body) in sections {
+            document.push_str(&mark_section(
+                path,
+                sha256,
+                &format!("### {}\n{}\n", path, body),
+            ));
+            document.push('\n');
+        }
+        document.push_str("footer\n");
+        document
+    }
+
+    #[test]
+    fn test_update_keeps_unchanged_sections() {
+        let existing = document(
+            "old header",
+            &[
+                ("a.rs", "aaa", "a, 3 days ago"),
+                ("b.rs", "bbb", "b v1"),
+                ("gone.rs", "ggg", "gone"),
+            ],
+        );
+        let fresh = document(
+            "new header",
+            &[
+                ("a.rs", "aaa", "a, 4 days ago"),
+                ("b.rs", "b2", "b v2"),
+                ("new.rs", "nnn", "new"),
+            ],
+        );
+        let (updated, summary) = update_document(&existing, &fresh);
+        assert_eq!(
+            updated,
+            document(
+                "new header",
+                &[
+                    ("a.rs", "aaa", "a, 3 days ago"),
+                    ("b.rs", "b2", "b v2"),
+                    ("new.rs", "nnn", "new"),
+                ],
+            )
+        );
+        assert_eq!(
+            summary,
+            UpdateSummary {
+                unchanged: 1,
+                changed: 1,
+                added: 1,
+                removed: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_update_without_markers_takes_fresh_document() {
+        let fresh = document("digest", &[("a.rs", "aaa", "a")]);
+        let (updated, summary) = update_document("# hand-written\n", &fresh);
+        assert_eq!(updated, fresh);
+        assert_eq!(summary.added, 1);
+    }
+
+    #[test]
+    fn test_markers_inside_content_are_not_sections() {
+        // A digest of a digest: the inner section lies wholly within the outer one
+        let inner = mark_section("inner.rs", "iii", "x\n");
+        let fresh = document("digest", &[("digest.md", "ddd", &inner)]);
+        let found: Vec<(&str, &str)> = sections(&fresh)
+            .iter()
+            .map(|section| (section.path, &fresh[section.range.clone()]))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(
+                "digest.md",
+                mark_section("digest.md", "ddd", &format!("### digest.md\n{}\n", inner)).as_str()
+            )]
+        );
+    }
+}
@@ -0,0 +1,264 @@
+// Filesystem access behind a trait, so file collection can run over trees that aren't on the
+// local disk, such as an in-memory project in a browser playground or a serverless function
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::Result;
+use crate::ignores::IgnoreSources;
+use crate::{collect_files_in, CollectOptions, Collection};
+
+/// What the collection pipeline needs to know about a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Metadata {
+    /// Size in bytes
+    pub len: u64,
+    /// Last modification time in seconds since the Unix epoch, when known
+    pub modified: Option<u64>,
+    pub is_dir: bool,
+    /// Whether the path itself is a symlink; the other fields describe its target
+    pub is_symlink: bool,
+}
+
+/// A file tree digest can collect from
+pub trait Vfs: Send + Sync {
+    /// Metadata of `path`, following symlinks
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Open the file at `path` for reading
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>>;
+
+    /// The paths of the entries directly inside the directory `dir`
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Where the symlink at `path` points
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a symlink", path.display()),
+        ))
+    }
+
+    /// The path with symlinks resolved, to spot files reached twice
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+}
+
+/// The local filesystem
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsFs;
+
+impl Vfs for OsFs {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let link = fs::symlink_metadata(path)?;
+        let is_symlink = link.file_type().is_symlink();
+        // A dangling symlink describes itself
+        let target = if is_symlink {
+            fs::metadata(path).unwrap_or(link)
+        } else {
+            link
+        };
+        Ok(Metadata {
+            len: target.len(),
+            modified: target
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs()),
+            is_dir: target.is_dir(),
+            is_symlink,
+        })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+/// A project held in memory as file paths, relative to the project root, and their contents.
+/// Directories are implied by the paths.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryProject {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryProject {
+    pub fn new() -> Self {
+        MemoryProject::default()
+    }
+
+    /// The project with the file at `path` added, replacing an earlier one
+    pub fn file(mut self, path: impl AsRef<Path>, content: impl Into<Vec<u8>>) -> Self {
+        self.insert(path, content);
+        self
+    }
+
+    /// Add the file at `path`, replacing an earlier one
+    pub fn insert(&mut self, path: impl AsRef<Path>, content: impl Into<Vec<u8>>) {
+        self.files.insert(normalize(path.as_ref()), content.into());
+    }
+
+    /// Collect the project's files as `collect_files` does for a directory on disk
+    pub fn collect(&self, ignores: &IgnoreSources, options: &CollectOptions) -> Result<Collection> {
+        collect_files_in(self, Path::new(""), ignores, options)
+    }
+}
+
+impl<P: AsRef<Path>, C: Into<Vec<u8>>> FromIterator<(P, C)> for MemoryProject {
+    fn from_iter<I: IntoIterator<Item = (P, C)>>(files: I) -> Self {
+        let mut project = MemoryProject::new();
+        for (path, content) in files {
+            project.insert(path, content);
+        }
+        project
+    }
+}
+
+/// `path` relative to the project root, without `.`, a leading `/`, or backslashes
+fn normalize(path: &Path) -> PathBuf {
+    let path = path.to_string_lossy().replace('\\', "/");
+    Path::new(&path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Vfs for MemoryProject {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let path = normalize(path);
+        if let Some(content) = self.files.get(&path) {
+            return Ok(Metadata {
+                len: content.len() as u64,
+                ..Metadata::default()
+            });
+        }
+        // Files under a path sort right after it
+        let is_dir = path.as_os_str().is_empty()
+            || self
+                .files
+                .range(path.clone()..)
+                .next()
+                .is_some_and(|(file, _)| file.starts_with(&path));
+        if is_dir {
+            Ok(Metadata {
+                is_dir: true,
+                ..Metadata::default()
+            })
+        } else {
+            Err(not_found(&path))
+        }
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        let path = normalize(path);
+        match self.files.get(&path) {
+            Some(content) => Ok(Box::new(Cursor::new(content.as_slice()))),
+            None => Err(not_found(&path)),
+        }
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let normalized = normalize(dir);
+        let mut entries: Vec<PathBuf> = Vec::new();
+        for (file, _) in self.files.range(normalized.clone()..) {
+            let Ok(relative) = file.strip_prefix(&normalized) else {
+                break;
+            };
+            if let Some(Component::Normal(name)) = relative.components().next() {
+                let entry = dir.join(name);
+                if entries.last() != Some(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+        if entries.is_empty() && !normalized.as_os_str().is_empty() {
+            return Err(not_found(&normalized));
+        }
+        Ok(entries)
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} is not in the project", path.display()),
+    )
+}
+
+/// Read a whole file from `vfs`
+pub fn read(vfs: &dyn Vfs, path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    vfs.open(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_os_fs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn f() {}").unwrap();
+
+        let metadata = OsFs.metadata(&root.join("src/lib.rs")).unwrap();
+        assert_eq!(metadata.len, 13);
+        assert!(!metadata.is_dir && !metadata.is_symlink);
+        assert!(metadata.modified.is_some());
+        assert!(OsFs.metadata(&root.join("src")).unwrap().is_dir);
+
+        assert_eq!(OsFs.read_dir(root).unwrap(), vec![root.join("src")]);
+        assert_eq!(
+            read(&OsFs, &root.join("src/lib.rs")).unwrap(),
+            b"pub fn f() {}"
+        );
+    }
+
+    #[test]
+    fn test_memory_project() {
+        let project = MemoryProject::new()
+            .file("src/main.rs", "fn main() {}")
+            .file("./src/util/mod.rs", "")
+            .file("README.md", "# Demo");
+
+        assert_eq!(
+            project.read_dir(Path::new("")).unwrap(),
+            vec![PathBuf::from("README.md"), PathBuf::from("src")]
+        );
+        assert_eq!(
+            project.read_dir(Path::new("src")).unwrap(),
+            vec![PathBuf::from("src/main.rs"), PathBuf::from("src/util")]
+        );
+        assert!(project.metadata(Path::new("src/util")).unwrap().is_dir);
+        assert_eq!(project.metadata(Path::new("README.md")).unwrap().len, 6);
+        // A path that only shares a string prefix with `src` is neither a file nor a directory
+        assert!(project.metadata(Path::new("sr")).is_err());
+        assert_eq!(
+            read(&project, Path::new("/src/main.rs")).unwrap(),
+            b"fn main() {}"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::Write;
+use tempfile::TempDir;
+
+use digest::archive::{
+    archive_kind, archive_stem, extract_archive, extract_archive_with_limits, ArchiveKind,
+    ExtractLimits,
+};
+use digest::error::DigestError;
+
+#[test]
+fn test_archive_kind_and_stem() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    for name in ["src.zip", "src-1.0.tar.gz", "src.TGZ", "src.tar", "src.rs"] {
+        File::create(temp_dir.path().join(name))?;
+    }
+
+    assert_eq!(
+        archive_kind(&temp_dir.path().join("src.zip")),
+        Some(ArchiveKind::Zip)
+    );
+    assert_eq!(
+        archive_kind(&temp_dir.path().join("src-1.0.tar.gz")),
+        Some(ArchiveKind::TarGz)
+    );
+    assert_eq!(
+        archive_kind(&temp_dir.path().join("src.TGZ")),
+        Some(ArchiveKind::TarGz)
+    );
+    assert_eq!(
+        archive_kind(&temp_dir.path().join("src.tar")),
+        Some(ArchiveKind::Tar)
+    );
+    assert_eq!(archive_kind(&temp_dir.path().join("src.rs")), None);
+    // Directories are never archives, whatever their name
+    fs::create_dir(temp_dir.path().join("dir.zip"))?;
+    assert_eq!(archive_kind(&temp_dir.path().join("dir.zip")), None);
+
+    assert_eq!(
+        archive_stem(&temp_dir.path().join("src-1.0.tar.gz")),
+        "src-1.0"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_tar_gz_with_single_top_level_dir() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let archive_path = temp_dir.path().join("proj-1.0.tar.gz");
+
+    let encoder = GzEncoder::new(File::create(&archive_path)?, Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for (path, content) in [
+        ("proj-1.0/src/main.rs", "fn main() {}"),
+        ("proj-1.0/README.md", "# proj"),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content.as_bytes())?;
+    }
+    builder.into_inner()?.finish()?;
+
+    let extracted = extract_archive(&archive_path, ArchiveKind::TarGz)?;
+    assert_eq!(extracted.name, "proj-1.0");
+    assert!(extracted.root.ends_with("proj-1.0"));
+    assert_eq!(
+        fs::read_to_string(extracted.root.join("src/main.rs"))?,
+        "fn main() {}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_zip_skips_unsafe_entries() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let archive_path = temp_dir.path().join("bundle.zip");
+
+    let mut writer = zip::ZipWriter::new(File::create(&archive_path)?);
+    let options = zip::write::SimpleFileOptions::default();
+    writer.start_file("a.rs", options)?;
+    writer.write_all(b"fn a() {}")?;
+    writer.start_file("lib/b.py", options)?;
+    writer.write_all(b"b = 1")?;
+    writer.start_file("../escape.rs", options)?;
+    writer.write_all(b"bad")?;
+    writer.finish()?;
+
+    let extracted = extract_archive(&archive_path, ArchiveKind::Zip)?;
+    assert_eq!(extracted.name, "bundle");
+    assert!(extracted.root.join("a.rs").is_file());
+    assert!(extracted.root.join("lib/b.py").is_file());
+    assert!(!extracted.root.parent().unwrap().join("escape.rs").exists());
+
+    Ok(())
+}
+
+/// A tar of regular files, plus a symlink and a hardlink to `/etc/passwd`
+fn tar_with_links(archive_path: &std::path::Path, files: &[(&str, &[u8])]) -> Result<()> {
+    let mut builder = tar::Builder::new(File::create(archive_path)?);
+    for (path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, *content)?;
+    }
+    for (path, kind) in [
+        ("passwd", tar::EntryType::Symlink),
+        ("hard", tar::EntryType::Link),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(kind);
+        header.set_size(0);
+        header.set_mode(0o777);
+        builder.append_link(&mut header, path, "/etc/passwd")?;
+    }
+    builder.into_inner()?.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_extract_tar_skips_links() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let archive_path = temp_dir.path().join("links.tar");
+    tar_with_links(
+        &archive_path,
+        &[("src/a.rs", b"fn a() {}"), ("README.md", b"# a")],
+    )?;
+
+    let extracted = extract_archive(&archive_path, ArchiveKind::Tar)?;
+    assert!(extracted.root.join("src/a.rs").is_file());
+    for link in ["passwd", "hard"] {
+        let path = extracted.root.join(link);
+        assert!(
+            fs::symlink_metadata(&path).is_err(),
+            "{} was extracted",
+            link
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_enforces_limits() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let big = vec![b'x'; 4096];
+
+    let tar_path = temp_dir.path().join("bomb.tar");
+    tar_with_links(&tar_path, &[("a.txt", &big), ("b.txt", &big)])?;
+    let zip_path = temp_dir.path().join("bomb.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&zip_path)?);
+    for name in ["a.txt", "b.txt"] {
+        writer.start_file(name, zip::write::SimpleFileOptions::default())?;
+        writer.write_all(&big)?;
+    }
+    writer.finish()?;
+
+    for (path, kind) in [(&tar_path, ArchiveKind::Tar), (&zip_path, ArchiveKind::Zip)] {
+        let too_few_bytes = ExtractLimits {
+            max_bytes: 6000,
+            ..ExtractLimits::default()
+        };
+        let err = extract_archive_with_limits(path, kind, too_few_bytes)
+            .err()
+            .expect("byte limit");
+        assert!(matches!(err, DigestError::ArchiveTooLarge { .. }));
+        assert!(err.to_string().contains("more than 6000 bytes"), "{}", err);
+
+        let too_few_entries = ExtractLimits {
+            max_entries: 1,
+            ..ExtractLimits::default()
+        };
+        let err = extract_archive_with_limits(path, kind, too_few_entries)
+            .err()
+            .expect("entry limit");
+        assert!(err.to_string().contains("more than 1 entries"), "{}", err);
+
+        assert!(extract_archive_with_limits(path, kind, ExtractLimits::default()).is_ok());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use std::fs;
+use tempfile::TempDir;
+
+use digest::dependencies::{summarize_dependencies, DependencyKind};
+
+#[test]
+fn test_cargo_dependencies_with_lockfile() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("Cargo.toml"),
+        r#"
+[package]
+name = "sample"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+anyhow = "1.0"
+local = { path = "../local" }
+
+[dev-dependencies]
+tempfile = "3.8"
+"#,
+    )?;
+    fs::write(
+        root.join("Cargo.lock"),
+        r#"
+[[package]]
+name = "sample"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "anyhow"
+version = "1.0.97"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+    )?;
+
+    let summaries = summarize_dependencies(root);
+    assert_eq!(summaries.len(), 1);
+
+    let cargo = &summaries[0];
+    assert_eq!(cargo.ecosystem, "Cargo");
+    assert_eq!(cargo.direct.len(), 4);
+
+    let serde = cargo.direct.iter().find(|d| d.name == "serde").unwrap();
+    assert_eq!(serde.version.as_deref(), Some("1.0"));
+    let local = cargo.direct.iter().find(|d| d.name == "local").unwrap();
+    assert_eq!(local.version.as_deref(), Some("path:../local"));
+    let tempfile = cargo.direct.iter().find(|d| d.name == "tempfile").unwrap();
+    assert_eq!(tempfile.kind, DependencyKind::Dev);
+
+    // The root package has no source and must not be counted
+    assert_eq!(cargo.lockfile.as_deref(), Some("Cargo.lock"));
+    assert_eq!(cargo.transitive_count, Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn test_npm_and_python_dependencies() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("package.json"),
+        r#"{"dependencies": {"react": "^18.2.0"}, "devDependencies": {"jest": "^29.0.0"}}"#,
+    )?;
+    fs::write(
+        root.join("package-lock.json"),
+        r#"{"lockfileVersion": 3, "packages": {"": {}, "node_modules/react": {}, "node_modules/loose-envify": {}, "node_modules/jest": {}}}"#,
+    )?;
+    fs::write(
+        root.join("requirements.txt"),
+        "# web\nflask>=2.0\nrequests[security]==2.31.0 ; python_version > \"3.8\"\n-r other.txt\n",
+    )?;
+
+    let summaries = summarize_dependencies(root);
+    assert_eq!(summaries.len(), 2);
+
+    let npm = summaries.iter().find(|s| s.ecosystem == "npm").unwrap();
+    assert_eq!(npm.direct.len(), 2);
+    assert_eq!(npm.transitive_count, Some(3));
+
+    let python = summaries.iter().find(|s| s.ecosystem == "Python").unwrap();
+    assert_eq!(python.manifest, "requirements.txt");
+    assert_eq!(python.direct.len(), 2);
+    assert_eq!(python.direct[0].name, "flask");
+    assert_eq!(python.direct[0].version.as_deref(), Some(">=2.0"));
+    assert_eq!(python.direct[1].name, "requests");
+    assert_eq!(python.direct[1].version.as_deref(), Some("==2.31.0"));
+    assert_eq!(python.transitive_count, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_go_dependencies_skip_indirect() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("go.mod"),
+        "module example.com/app\n\ngo 1.21\n\nrequire (\n\tgithub.com/spf13/cobra v1.8.0\n\tgolang.org/x/sys v0.15.0 // indirect\n)\n\nrequire github.com/pkg/errors v0.9.1\n",
+    )?;
+    fs::write(
+        root.join("go.sum"),
+        "github.com/spf13/cobra v1.8.0 h1:abc=\ngithub.com/spf13/cobra v1.8.0/go.mod h1:def=\ngolang.org/x/sys v0.15.0 h1:ghi=\ngolang.org/x/net v0.1.0/go.mod h1:jkl=\n",
+    )?;
+
+    let summaries = summarize_dependencies(root);
+    let go = &summaries[0];
+    let names: Vec<&str> = go.direct.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["github.com/spf13/cobra", "github.com/pkg/errors"]
+    );
+    assert_eq!(go.transitive_count, Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn test_no_manifests() {
+    let temp_dir = TempDir::new().unwrap();
+    assert!(summarize_dependencies(temp_dir.path()).is_empty());
+}
@@ -0,0 +1,62 @@
+use anyhow::Result;
+
+use digest::diff::{diff_digests, FileChange};
+
+/// Build a minimal JSON digest from (path, content) pairs
+fn digest_json(files: &[(&str, &str)]) -> String {
+    let files: Vec<serde_json::Value> = files
+        .iter()
+        .map(|(path, content)| {
+            serde_json::json!({ "path": path, "language": "Rust", "content": content })
+        })
+        .collect();
+    serde_json::json!({ "project_name": "sample", "files": files }).to_string()
+}
+
+#[test]
+fn test_diff_reports_added_removed_and_changed() -> Result<()> {
+    let old = digest_json(&[
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/old.rs", "pub fn old() {}\n"),
+        ("src/same.rs", "pub fn same() {}\n"),
+    ]);
+    let new = digest_json(&[
+        ("src/main.rs", "fn main() {\n    run();\n}\n"),
+        ("src/new.rs", "pub fn new() {}\n"),
+        ("src/same.rs", "pub fn same() {}\n"),
+    ]);
+
+    let diff = diff_digests(&old, &new)?;
+    assert_eq!(diff.unchanged, 1);
+
+    let changes: Vec<(&str, FileChange)> = diff
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.change))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            ("src/main.rs", FileChange::Changed),
+            ("src/new.rs", FileChange::Added),
+            ("src/old.rs", FileChange::Removed),
+        ]
+    );
+
+    let main = &diff.files[0];
+    assert!(main.token_delta() > 0);
+    assert_eq!((main.lines_added, main.lines_removed), (3, 1));
+
+    let removed = &diff.files[2];
+    assert_eq!(removed.new_tokens, 0);
+    assert_eq!(removed.token_delta(), -(removed.old_tokens as i64));
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_rejects_non_digest_json() {
+    let valid = digest_json(&[]);
+    assert!(diff_digests("{\"not\": \"a digest\"}", &valid).is_err());
+    assert!(diff_digests(&valid, "# Project Digest").is_err());
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use std::fs;
+use tempfile::TempDir;
+
+use digest::entry_points::{detect_entry_points, entry_point_ecosystem};
+
+#[test]
+fn test_entry_point_ecosystems() {
+    assert_eq!(entry_point_ecosystem("src/main.rs"), Some("Rust"));
+    assert_eq!(entry_point_ecosystem("src/bin/tool.rs"), Some("Rust"));
+    assert_eq!(entry_point_ecosystem("cmd/server/main.go"), Some("Go"));
+    assert_eq!(entry_point_ecosystem("manage.py"), Some("Python"));
+    assert_eq!(entry_point_ecosystem("src/index.ts"), Some("Node"));
+    assert_eq!(entry_point_ecosystem("project.godot"), Some("Godot"));
+
+    assert_eq!(entry_point_ecosystem("src/lib.rs"), None);
+    assert_eq!(entry_point_ecosystem("src/components/index.ts"), None);
+}
+
+#[test]
+fn test_detect_entry_points() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+
+    fs::create_dir_all(root.join("src/bin"))?;
+    fs::create_dir_all(root.join("node_modules/pkg"))?;
+    fs::create_dir_all(root.join("scripts"))?;
+    fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    fs::write(root.join("src/bin/tool.rs"), "fn main() {}")?;
+    fs::write(root.join("src/lib.rs"), "")?;
+    fs::write(root.join("node_modules/pkg/index.js"), "")?;
+    fs::write(root.join("scripts/cli.js"), "")?;
+    fs::write(
+        root.join("package.json"),
+        r#"{ "name": "app", "bin": { "app-cli": "./scripts/cli.js" } }"#,
+    )?;
+
+    let entry_points = detect_entry_points(root);
+    let found: Vec<(&str, &str)> = entry_points
+        .iter()
+        .map(|e| (e.path.as_str(), e.ecosystem))
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            ("scripts/cli.js", "Node"),
+            ("src/bin/tool.rs", "Rust"),
+            ("src/main.rs", "Rust"),
+        ]
+    );
+
+    Ok(())
+}
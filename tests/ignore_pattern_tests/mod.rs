@@ -6,14 +6,21 @@ use std::path::Path;
 use tempfile::TempDir;
 
 // Re-export the main module functions for testing
+use digest::error::DigestError;
+use digest::ignores::{IgnoreSource, IgnoreSources};
+use digest::lockfiles::LockfileMode;
+use digest::submodules::{read_submodules, SubmoduleMode};
+use digest::vfs::{MemoryProject, OsFs};
 use digest::{
-    check_for_digestignore, check_for_gitignore, collect_relevant_files, should_ignore, FileInfo,
+    check_for_digestignore, check_for_gitignore, collect_files, collect_files_in,
+    collect_relevant_files, collect_relevant_files_iter, invalid_ignore_lines, should_ignore,
+    CollectOptions, ExclusionReason, FileInfo, FilterCounts, SkippedKind,
 };
 
 mod pattern_generator;
 use pattern_generator::{
-    create_test_directory, get_common_test_cases, get_common_test_structure,
-    get_complex_test_cases, get_test_file_patterns, run_ignore_pattern_tests,
+    get_common_test_cases, get_common_test_structure, get_complex_test_cases,
+    get_test_file_patterns, run_ignore_pattern_tests,
 };
 
 /// Create a directory structure for testing ignore patterns
@@ -99,32 +106,88 @@ fn run_ignore_test(
         create_digestignore(temp_dir, patterns)?;
     }
 
-    // Build the ignore patterns set
-    let mut ignore_patterns = HashSet::new();
-
-    // Try to get patterns from .digestignore if it exists
-    if digestignore_path.exists() {
-        if let Ok(patterns) = check_for_digestignore(temp_dir) {
-            ignore_patterns.extend(patterns);
-        }
-    }
-
-    // Try to get patterns from .gitignore if it exists and respect_gitignore is true
-    if gitignore_path.exists() && respect_gitignore {
-        if let Ok(patterns) = check_for_gitignore(temp_dir) {
-            ignore_patterns.extend(patterns);
-        }
+    // Layer the ignore files, .gitignore only if respect_gitignore is true
+    let mut ignores = IgnoreSources::new().with(IgnoreSource::Builtin, [".git"]);
+    ignores.load_file(temp_dir, ".digestignore")?;
+    if respect_gitignore {
+        ignores.load_file(temp_dir, ".gitignore")?;
     }
 
     // Collect the files
-    collect_relevant_files(
+    Ok(collect_relevant_files(
         temp_dir,
-        &ignore_patterns,
+        &ignores,
         max_files,
         max_file_size,
         false, // not a Godot project
         respect_gitignore,
-    )
+    )?)
+}
+
+/// The binary's file selection from before it moved onto the library collector: no built-in
+/// exclusions beyond .git, directory patterns (`tests/` included) matching at any depth, and
+/// only code files kept
+#[test]
+fn test_collect_keeps_binary_selection() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    for path in [
+        "README.md",
+        "docs/guide.md",
+        "web/app.js",
+        "tests/cli.rs",
+        "src/tests/unit.rs",
+        "src/testing/helpers.rs",
+        "build/gen.rs",
+        "node_modules/dep/index.js",
+        "src/vendor/lib.rs",
+        "lib/vendor/lib.rs",
+        "logs/run.log",
+        "assets/logo.png",
+    ] {
+        fs::create_dir_all(root.join(path).parent().unwrap())?;
+        fs::write(root.join(path), "x")?;
+    }
+    let paths = |files: Vec<FileInfo>| -> Vec<String> {
+        let mut paths: Vec<String> = files.iter().map(|f| f.path.replace('\\', "/")).collect();
+        paths.sort();
+        paths
+    };
+
+    // Markdown, JavaScript, tests, build and node_modules are only left out when a pattern
+    // says so; logs and images aren't code
+    let files = run_ignore_test(root, None, None, 100, 10_000, true)?;
+    assert_eq!(
+        paths(files),
+        vec![
+            "README.md",
+            "build/gen.rs",
+            "docs/guide.md",
+            "lib/vendor/lib.rs",
+            "node_modules/dep/index.js",
+            "src/testing/helpers.rs",
+            "src/tests/unit.rs",
+            "src/vendor/lib.rs",
+            "tests/cli.rs",
+            "web/app.js",
+        ]
+    );
+
+    // A directory pattern matches at any depth, but only the whole directory name
+    let files = run_ignore_test(
+        root,
+        None,
+        Some(&["tests/", "vendor/", "node_modules/", "*.md"]),
+        100,
+        10_000,
+        true,
+    )?;
+    assert_eq!(
+        paths(files),
+        vec!["build/gen.rs", "src/testing/helpers.rs", "web/app.js"]
+    );
+
+    Ok(())
 }
 
 #[test]
@@ -452,35 +515,23 @@ fn test_directory_structure_generator() -> Result<()> {
         println!("\n--- Running test case {} ---", i);
 
         // Set up the ignore files
-        create_gitignore(root, &gitignore.iter().map(|s| *s).collect::<Vec<_>>())?;
-        create_digestignore(root, &digestignore.iter().map(|s| *s).collect::<Vec<_>>())?;
+        create_gitignore(root, gitignore)?;
+        create_digestignore(root, digestignore)?;
 
         println!("gitignore patterns: {:?}", gitignore);
         println!("digestignore patterns: {:?}", digestignore);
 
-        // Build the ignore patterns set
-        let mut ignore_patterns = HashSet::new();
+        // Layer the patterns from both files
+        let mut ignores = IgnoreSources::new().with(IgnoreSource::Builtin, [".git"]);
+        ignores.load_file(root, ".digestignore")?;
+        ignores.load_file(root, ".gitignore")?;
 
-        // Add patterns from both files
-        if let Ok(digestignore_patterns) = check_for_digestignore(root) {
-            println!(
-                "Loaded patterns from digestignore: {:?}",
-                digestignore_patterns
-            );
-            ignore_patterns.extend(digestignore_patterns);
-        }
-
-        if let Ok(gitignore_patterns) = check_for_gitignore(root) {
-            println!("Loaded patterns from gitignore: {:?}", gitignore_patterns);
-            ignore_patterns.extend(gitignore_patterns);
-        }
-
-        println!("Combined patterns: {:?}", ignore_patterns);
+        println!("Combined patterns: {:?}", ignores);
 
         // Check each expected included file
         for path in expected_included {
             let full_path = root.join(path);
-            let is_ignored = should_ignore(&full_path, &ignore_patterns);
+            let is_ignored = ignores.is_ignored(&full_path);
             println!(
                 "Testing path: {} - should NOT be ignored, actual: {}",
                 path, is_ignored
@@ -496,7 +547,7 @@ fn test_directory_structure_generator() -> Result<()> {
         // Check each expected excluded file
         for path in expected_excluded {
             let full_path = root.join(path);
-            let is_ignored = should_ignore(&full_path, &ignore_patterns);
+            let is_ignored = ignores.is_ignored(&full_path);
             println!(
                 "Testing path: {} - should be ignored, actual: {}",
                 path, is_ignored
@@ -529,3 +580,992 @@ fn test_programmatic_pattern_tests() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_collect_files_defaults_skip_ancestor_directories() -> Result<()> {
+    // A project checked out under build/ or target/ still has its files collected
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().join("build").join("target").join("app");
+    fs::create_dir_all(root.join("src"))?;
+    fs::create_dir_all(root.join("build"))?;
+    fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    fs::write(root.join("build/gen.rs"), "fn gen() {}")?;
+
+    let ignores = IgnoreSources::new().with(IgnoreSource::Defaults, ["build", "target", "env"]);
+    let collection = collect_files(&root, &ignores, &CollectOptions::default())?;
+    let paths: Vec<&str> = collection.files.iter().map(|f| f.path.as_str()).collect();
+
+    assert_eq!(paths, vec!["src/main.rs"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_negations_override_defaults() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("fixtures"))?;
+    fs::write(root.join("fixtures/input.rs"), "fn input() {}")?;
+    fs::write(root.join("main.rs"), "fn main() {}")?;
+    fs::write(root.join(".digestignore"), "!fixtures/\n")?;
+
+    let defaults = IgnoreSources::new().with(IgnoreSource::Defaults, ["fixtures/"]);
+    let collection = collect_files(root, &defaults, &CollectOptions::default())?;
+    assert_eq!(collection.files.len(), 1);
+
+    let mut ignores = defaults.clone();
+    ignores.load_file(root, ".digestignore")?;
+    let collection = collect_files(root, &ignores, &CollectOptions::default())?;
+    let mut paths: Vec<&str> = collection.files.iter().map(|f| f.path.as_str()).collect();
+    paths.sort();
+    assert_eq!(paths, vec!["fixtures/input.rs", "main.rs"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_counts_overflow() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    for i in 0..5 {
+        fs::write(temp_dir.path().join(format!("file{}.rs", i)), "fn f() {}")?;
+    }
+    fs::write(temp_dir.path().join("notes.txt"), "not code")?;
+
+    let options = CollectOptions {
+        max_files: 2,
+        max_file_size: 10_000,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(temp_dir.path(), &IgnoreSources::new(), &options)?;
+
+    assert_eq!(collection.files.len(), 2);
+    assert_eq!(collection.overflow, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_counts_overflow_in_memory() -> Result<()> {
+    let mut project: MemoryProject = (0..5)
+        .map(|i| (format!("file{}.rs", i), "fn f() {}"))
+        .collect();
+    project.insert("notes.txt", "not code");
+
+    let options = CollectOptions {
+        max_files: 2,
+        max_file_size: 10_000,
+        ..CollectOptions::default()
+    };
+    let collection = project.collect(&IgnoreSources::new(), &options)?;
+
+    assert_eq!(collection.files.len(), 2);
+    assert_eq!(collection.overflow, 3);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_collect_files_reports_unfollowed_symlinks() -> Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = TempDir::new()?;
+    fs::create_dir(temp_dir.path().join("src"))?;
+    fs::write(temp_dir.path().join("src/lib.rs"), "pub fn f() {}")?;
+    symlink("src", temp_dir.path().join("linked"))?;
+
+    let collection = collect_files(
+        temp_dir.path(),
+        &IgnoreSources::new(),
+        &CollectOptions::default(),
+    )?;
+
+    let paths: Vec<&str> = collection.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/lib.rs"]);
+    assert_eq!(collection.excluded.len(), 1);
+    assert_eq!(collection.excluded[0].path, "linked");
+    assert_eq!(collection.excluded[0].reason, ExclusionReason::Symlink);
+    assert_eq!(collection.excluded[0].target.as_deref(), Some("src"));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_collect_files_follows_symlinks_without_loops_or_duplicates() -> Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = TempDir::new()?;
+    fs::create_dir(temp_dir.path().join("src"))?;
+    fs::write(temp_dir.path().join("src/lib.rs"), "pub fn f() {}")?;
+    symlink("src", temp_dir.path().join("linked"))?;
+    symlink("..", temp_dir.path().join("src/parent"))?;
+
+    let options = CollectOptions {
+        follow_symlinks: true,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(temp_dir.path(), &IgnoreSources::new(), &options)?;
+
+    assert_eq!(collection.files.len(), 1);
+    assert!(collection
+        .excluded
+        .iter()
+        .any(|e| e.reason == ExclusionReason::SymlinkLoop));
+    assert!(collection
+        .excluded
+        .iter()
+        .any(|e| e.reason == ExclusionReason::Duplicate));
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_skips_hidden_except_well_known() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::create_dir_all(temp_dir.path().join(".github/workflows"))?;
+    fs::write(temp_dir.path().join(".github/workflows/ci.yml"), "on: push")?;
+    fs::create_dir(temp_dir.path().join(".venv"))?;
+    fs::write(temp_dir.path().join(".venv/site.py"), "import os")?;
+    fs::write(temp_dir.path().join("main.py"), "print(1)")?;
+
+    let collect = |include_hidden| -> Result<Vec<String>> {
+        let options = CollectOptions {
+            include_hidden,
+            ..CollectOptions::default()
+        };
+        let mut paths: Vec<String> =
+            collect_files(temp_dir.path(), &IgnoreSources::new(), &options)?
+                .files
+                .into_iter()
+                .map(|f| f.path.replace('\\', "/"))
+                .collect();
+        paths.sort();
+        Ok(paths)
+    };
+
+    assert_eq!(collect(false)?, vec![".github/workflows/ci.yml", "main.py"]);
+    assert_eq!(
+        collect(true)?,
+        vec![".github/workflows/ci.yml", ".venv/site.py", "main.py"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_dot_ignore_toggle() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::write(temp_dir.path().join(".ignore"), "generated.rs\n")?;
+    fs::write(temp_dir.path().join("generated.rs"), "// generated")?;
+    fs::write(temp_dir.path().join("lib.rs"), "pub fn f() {}")?;
+
+    let collect = |respect_dot_ignore| -> Result<usize> {
+        let options = CollectOptions {
+            respect_dot_ignore,
+            ..CollectOptions::default()
+        };
+        Ok(
+            collect_files(temp_dir.path(), &IgnoreSources::new(), &options)?
+                .files
+                .len(),
+        )
+    };
+
+    assert_eq!(collect(true)?, 1);
+    assert_eq!(collect(false)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_question_mark_and_character_class_patterns() {
+    let patterns: HashSet<String> = ["file?.rs", "*.py[co]", "log[0-9]/", "[A-Z]*.md"]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+
+    assert!(should_ignore(Path::new("src/file1.rs"), &patterns));
+    assert!(!should_ignore(Path::new("src/file10.rs"), &patterns));
+    assert!(should_ignore(Path::new("pkg/mod.pyc"), &patterns));
+    assert!(!should_ignore(Path::new("pkg/mod.py"), &patterns));
+    assert!(should_ignore(Path::new("log7/out.rs"), &patterns));
+    assert!(!should_ignore(Path::new("logs/out.rs"), &patterns));
+    assert!(should_ignore(Path::new("docs/README.md"), &patterns));
+    assert!(!should_ignore(Path::new("docs/guide.md"), &patterns));
+}
+
+#[test]
+fn test_ignore_file_escapes_and_trailing_spaces() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::write(
+        temp_dir.path().join(".digestignore"),
+        "# comment\n\\#notes.md\n\\!important.rs\nfoo\\ bar.rs\ntrailing.rs   \n",
+    )?;
+    let patterns = check_for_digestignore(temp_dir.path())?.unwrap();
+
+    assert!(patterns.contains("trailing.rs"));
+    assert!(!patterns.iter().any(|p| p.starts_with('#')));
+    assert!(should_ignore(Path::new("docs/#notes.md"), &patterns));
+    assert!(should_ignore(Path::new("src/!important.rs"), &patterns));
+    assert!(should_ignore(Path::new("src/foo bar.rs"), &patterns));
+    assert!(should_ignore(Path::new("src/trailing.rs"), &patterns));
+    assert!(!should_ignore(Path::new("src/foo.rs"), &patterns));
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_converts_notebooks() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let image = "A".repeat(4_000);
+    fs::write(
+        temp_dir.path().join("analysis.ipynb"),
+        format!(
+            r#"{{"cells": [{{"cell_type": "code", "source": ["x = 1"],
+               "outputs": [{{"data": {{"image/png": "{}"}}}}]}}]}}"#,
+            image
+        ),
+    )?;
+
+    // The raw notebook is over the limit, but its cells are not
+    let options = CollectOptions {
+        max_file_size: 1_000,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(temp_dir.path(), &IgnoreSources::new(), &options)?;
+
+    assert_eq!(collection.files.len(), 1);
+    assert_eq!(collection.files[0].content, "# %%\nx = 1\n");
+    assert_eq!(
+        collection.files[0].language.as_deref(),
+        Some("Jupyter Notebook")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_converts_notebooks_in_memory() -> Result<()> {
+    let image = "A".repeat(4_000);
+    let project = MemoryProject::new().file(
+        "analysis.ipynb",
+        format!(
+            r#"{{"cells": [{{"cell_type": "code", "source": ["x = 1"],
+               "outputs": [{{"data": {{"image/png": "{}"}}}}]}}]}}"#,
+            image
+        ),
+    );
+
+    // The raw notebook is over the limit, but its cells are not
+    let options = CollectOptions {
+        max_file_size: 1_000,
+        ..CollectOptions::default()
+    };
+    let collection = project.collect(&IgnoreSources::new(), &options)?;
+
+    assert_eq!(collection.files.len(), 1);
+    assert_eq!(collection.files[0].content, "# %%\nx = 1\n");
+    assert_eq!(
+        collection.files[0].language.as_deref(),
+        Some("Jupyter Notebook")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_tells_c_family_languages_apart() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("util.c"), "int add(int a, int b);")?;
+    fs::write(root.join("util.h"), "int add(int a, int b);")?;
+    fs::write(root.join("shape.h"), "namespace geo { class Shape {}; }")?;
+    fs::write(root.join("shape.cc"), "#include \"shape.h\"")?;
+    fs::write(root.join("View.h"), "@interface View : NSView\n@end")?;
+    fs::write(root.join("View.m"), "#import \"View.h\"")?;
+    fs::write(root.join("Bridge.mm"), "#import \"View.h\"")?;
+
+    let collection = collect_files(root, &IgnoreSources::new(), &CollectOptions::default())?;
+    let mut languages: Vec<(&str, &str)> = collection
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.language.as_deref().unwrap_or("")))
+        .collect();
+    languages.sort();
+
+    assert_eq!(
+        languages,
+        vec![
+            ("Bridge.mm", "Objective-C++"),
+            ("View.h", "Objective-C"),
+            ("View.m", "Objective-C"),
+            ("shape.cc", "C++"),
+            ("shape.h", "C++"),
+            ("util.c", "C"),
+            ("util.h", "C"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_tells_c_family_languages_apart_in_memory() -> Result<()> {
+    let project = MemoryProject::from_iter([
+        ("util.c", "int add(int a, int b);"),
+        ("util.h", "int add(int a, int b);"),
+        ("shape.h", "namespace geo { class Shape {}; }"),
+        ("shape.cc", "#include \"shape.h\""),
+        ("View.h", "@interface View : NSView\n@end"),
+        ("View.m", "#import \"View.h\""),
+        ("Bridge.mm", "#import \"View.h\""),
+    ]);
+
+    let collection = project.collect(&IgnoreSources::new(), &CollectOptions::default())?;
+    let mut languages: Vec<(&str, &str)> = collection
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.language.as_deref().unwrap_or("")))
+        .collect();
+    languages.sort();
+
+    assert_eq!(
+        languages,
+        vec![
+            ("Bridge.mm", "Objective-C++"),
+            ("View.h", "Objective-C"),
+            ("View.m", "Objective-C"),
+            ("shape.cc", "C++"),
+            ("shape.h", "C++"),
+            ("util.c", "C"),
+            ("util.h", "C"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_labels_m_files_by_content() -> Result<()> {
+    let project = MemoryProject::from_iter([
+        ("View.m", "#import \"View.h\"\n@implementation View\n@end\n"),
+        ("solve.m", "function x = solve(A, b)\n  x = A \\ b;\nend\n"),
+        ("plot_data.m", "% Plot the samples\nplot(t, y);\n"),
+        ("rules.m", ":- module rules.\n"),
+    ]);
+
+    let collection = project.collect(&IgnoreSources::new(), &CollectOptions::default())?;
+    let mut languages: Vec<(&str, &str)> = collection
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.language.as_deref().unwrap_or("")))
+        .collect();
+    languages.sort();
+
+    assert_eq!(
+        languages,
+        vec![
+            ("View.m", "Objective-C"),
+            ("plot_data.m", "MATLAB"),
+            ("rules.m", "Unknown"),
+            ("solve.m", "MATLAB"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_mentions_skipped_assets() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("main.rs"), "fn main() {}")?;
+    fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G'])?;
+    fs::write(root.join("data.bin"), [0u8; 16])?;
+    fs::write(root.join("notes.txt"), "not code, not binary")?;
+    fs::write(root.join("huge.rs"), "x".repeat(2_000))?;
+
+    let options = CollectOptions {
+        max_file_size: 1_000,
+        mention_skipped: true,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(root, &IgnoreSources::new(), &options)?;
+    let mut skipped: Vec<(&str, SkippedKind, u64)> = collection
+        .skipped
+        .iter()
+        .map(|s| (s.path.as_str(), s.kind, s.bytes))
+        .collect();
+    skipped.sort_by_key(|s| s.0);
+
+    assert_eq!(collection.files.len(), 1);
+    assert_eq!(
+        skipped,
+        vec![
+            ("data.bin", SkippedKind::Binary, 16),
+            ("huge.rs", SkippedKind::Oversized, 2_000),
+            ("logo.png", SkippedKind::Image, 4),
+        ]
+    );
+
+    // Nothing is recorded unless asked for
+    let options = CollectOptions {
+        max_file_size: 1_000,
+        ..CollectOptions::default()
+    };
+    assert!(collect_files(root, &IgnoreSources::new(), &options)?
+        .skipped
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_embeds_small_images() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("main.rs"), "fn main() {}")?;
+    fs::write(root.join("icon.png"), [0x89, b'P', b'N', b'G'])?;
+    fs::write(root.join("photo.jpg"), [0xffu8; 4096])?;
+
+    let options = CollectOptions {
+        embed_images: Some(1024),
+        mention_skipped: true,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(root, &IgnoreSources::new(), &options)?;
+
+    let embedded: Vec<&str> = collection.images.iter().map(|i| i.path.as_str()).collect();
+    assert_eq!(embedded, vec!["icon.png"]);
+    // Images too large to embed are still mentioned
+    assert_eq!(collection.skipped.len(), 1);
+    assert_eq!(collection.skipped[0].path, "photo.jpg");
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_includes_diagram_sources() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("arch.svg"), "<svg/>")?;
+    fs::write(root.join("flow.mmd"), "graph TD; A-->B")?;
+    fs::write(root.join("seq.puml"), "@startuml\n@enduml")?;
+    fs::write(root.join("deps.dot"), "digraph { a -> b }")?;
+
+    let collection = collect_files(root, &IgnoreSources::new(), &CollectOptions::default())?;
+    let mut languages: Vec<(&str, &str)> = collection
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.language.as_deref().unwrap_or("")))
+        .collect();
+    languages.sort();
+
+    assert_eq!(
+        languages,
+        vec![
+            ("arch.svg", "SVG"),
+            ("deps.dot", "Graphviz"),
+            ("flow.mmd", "Mermaid"),
+            ("seq.puml", "PlantUML"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_includes_diagram_sources_in_memory() -> Result<()> {
+    let project = MemoryProject::from_iter([
+        ("arch.svg", "<svg/>"),
+        ("flow.mmd", "graph TD; A-->B"),
+        ("seq.puml", "@startuml\n@enduml"),
+        ("deps.dot", "digraph { a -> b }"),
+    ]);
+
+    let collection = project.collect(&IgnoreSources::new(), &CollectOptions::default())?;
+    let mut languages: Vec<(&str, &str)> = collection
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.language.as_deref().unwrap_or("")))
+        .collect();
+    languages.sort();
+
+    assert_eq!(
+        languages,
+        vec![
+            ("arch.svg", "SVG"),
+            ("deps.dot", "Graphviz"),
+            ("flow.mmd", "Mermaid"),
+            ("seq.puml", "PlantUML"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_ignore_lines_report_line_and_position() {
+    let content = "# build output\ntarget/\nlog[9-0]\n\n!keep[\n";
+    let invalid: Vec<(usize, String, usize)> = invalid_ignore_lines(content)
+        .into_iter()
+        .map(|(line, pattern, err)| (line, pattern, err.position))
+        .collect();
+    assert_eq!(
+        invalid,
+        vec![(3, "log[9-0]".to_string(), 4), (5, "!keep[".to_string(), 6)]
+    );
+}
+
+#[test]
+fn test_collect_files_counts_what_each_stage_filtered() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("gen"))?;
+    fs::write(root.join("gen/a.rs"), "fn a() {}")?;
+    fs::write(root.join("gen/b.rs"), "fn b() {}")?;
+    fs::write(root.join("notes.txt"), "notes")?;
+    fs::write(root.join("big.rs"), "x".repeat(2048))?;
+    fs::write(root.join("latin1.rs"), b"// caf\xe9\n")?;
+    fs::write(root.join("main.rs"), "fn main() {}")?;
+
+    let ignores = IgnoreSources::from_iter(["gen/"]);
+    let options = CollectOptions {
+        max_file_size: 1024,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(root, &ignores, &options)?;
+
+    assert_eq!(collection.files.len(), 1);
+    assert_eq!(
+        collection.filtered,
+        FilterCounts {
+            ignored: 2,
+            not_code: 1,
+            oversized: 1,
+            unreadable: 1,
+            minified: 0,
+            undocumented: 0,
+        }
+    );
+    assert_eq!(digest::count_all_files(root), 6);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_sorted_walk_is_in_name_order() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    for name in ["c.rs", "a.rs", "d.rs", "b.rs"] {
+        fs::write(temp_dir.path().join(name), "fn f() {}")?;
+    }
+
+    let options = CollectOptions {
+        max_files: 2,
+        sort_paths: true,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(temp_dir.path(), &IgnoreSources::new(), &options)?;
+
+    let paths: Vec<&str> = collection.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["a.rs", "b.rs"]);
+    assert_eq!(collection.overflow, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_file_iterator_reads_lazily() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    for name in ["a.rs", "b.rs", "c.rs"] {
+        fs::write(temp_dir.path().join(name), "fn f() {}")?;
+    }
+    fs::write(temp_dir.path().join("z.txt"), "notes")?;
+
+    let patterns = IgnoreSources::new();
+    let options = CollectOptions {
+        sort_paths: true,
+        ..CollectOptions::default()
+    };
+    let mut walk = collect_relevant_files_iter(temp_dir.path(), &patterns, &options);
+    let first = walk.next().unwrap()?;
+    assert_eq!(first.path, "a.rs");
+
+    // Stopping here leaves the rest of the tree unwalked
+    let collection = walk.into_collection(vec![first]);
+    assert_eq!(collection.files.len(), 1);
+    assert_eq!(collection.filtered.not_code, 0);
+
+    // Walked to the end, it collects what collect_files does
+    let files = collect_relevant_files_iter(temp_dir.path(), &patterns, &options)
+        .collect::<Result<Vec<_>, _>>()?;
+    let collected = collect_files(temp_dir.path(), &patterns, &options)?;
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let collected_paths: Vec<&str> = collected.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["a.rs", "b.rs", "c.rs"]);
+    assert_eq!(paths, collected_paths);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_options_filter_and_transform() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("keep.rs"), "fn keep() {}")?;
+    fs::write(root.join("generated.rs"), "// @generated\nfn gen() {}")?;
+    fs::write(root.join("big.rs"), "fn big() {}".repeat(100))?;
+
+    let options = CollectOptions::default()
+        .filter(|_, metadata| metadata.len < 500)
+        .transform(|file| {
+            if file.content.starts_with("// @generated") {
+                return None;
+            }
+            let content = file.content.to_uppercase();
+            Some(FileInfo::new(file.path, file.language, content))
+        });
+    let collection = collect_files(root, &IgnoreSources::new(), &options)?;
+
+    assert_eq!(collection.files.len(), 1);
+    assert_eq!(collection.files[0].path, "keep.rs");
+    assert_eq!(collection.files[0].content, "FN KEEP() {}");
+    assert_eq!(collection.files[0].size, 12);
+    assert_eq!(collection.filtered.ignored, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_options_filter_and_transform_in_memory() -> Result<()> {
+    let project = MemoryProject::new()
+        .file("keep.rs", "fn keep() {}")
+        .file("generated.rs", "// @generated\nfn gen() {}")
+        .file("big.rs", "fn big() {}".repeat(100));
+
+    let options = CollectOptions::default()
+        .filter(|_, metadata| metadata.len < 500)
+        .transform(|file| {
+            if file.content.starts_with("// @generated") {
+                return None;
+            }
+            let content = file.content.to_uppercase();
+            Some(FileInfo::new(file.path, file.language, content))
+        });
+    let collection = project.collect(&IgnoreSources::new(), &options)?;
+
+    assert_eq!(collection.files.len(), 1);
+    assert_eq!(collection.files[0].path, "keep.rs");
+    assert_eq!(collection.files[0].content, "FN KEEP() {}");
+    assert_eq!(collection.files[0].size, 12);
+    assert_eq!(collection.filtered.ignored, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_options_pin_reads_past_max_files() -> Result<()> {
+    let project = MemoryProject::new()
+        .file("a.rs", "fn a() {}")
+        .file("b.rs", "fn b() {}")
+        .file("src/main.rs", "fn main() {}");
+
+    let options = CollectOptions {
+        max_files: 1,
+        ..CollectOptions::default()
+    }
+    .pin(|path| path == "src/main.rs");
+    let collection = project.collect(&IgnoreSources::new(), &options)?;
+
+    let paths: Vec<&str> = collection.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["a.rs", "src/main.rs"]);
+    assert_eq!(collection.overflow, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_in_vfs_matches_disk_walk() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src"))?;
+    fs::create_dir_all(root.join(".hidden"))?;
+    fs::create_dir_all(root.join("target"))?;
+    fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    fs::write(root.join("src/lib.rs"), "pub fn f() {}")?;
+    fs::write(root.join(".hidden/secret.rs"), "fn secret() {}")?;
+    fs::write(root.join("target/out.rs"), "fn out() {}")?;
+    fs::write(root.join("notes.txt"), "notes")?;
+
+    let patterns = IgnoreSources::from_iter(["target/"]);
+    let options = CollectOptions {
+        sort_paths: true,
+        ..CollectOptions::default()
+    };
+    let in_vfs = collect_files_in(&OsFs, root, &patterns, &options)?;
+    let on_disk = collect_files(root, &patterns, &options)?;
+
+    let paths: Vec<&str> = in_vfs.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/lib.rs", "src/main.rs"]);
+    let disk_paths: Vec<&str> = on_disk.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, disk_paths);
+    assert_eq!(in_vfs.files[0].content, on_disk.files[0].content);
+    assert_eq!(in_vfs.filtered, on_disk.filtered);
+
+    Ok(())
+}
+
+#[test]
+fn test_ignore_file_errors_can_be_matched() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    // A missing ignore file isn't an error
+    assert!(check_for_gitignore(root)?.is_none());
+
+    fs::write(root.join(".digestignore"), b"target/\n\xff\n")?;
+    let err = check_for_digestignore(root).unwrap_err();
+    assert!(matches!(err, DigestError::NotUtf8 { .. }));
+    assert_eq!(err.kind(), digest::exit::ErrorKind::Io);
+
+    Ok(())
+}
+
+#[test]
+fn test_gitignore_applies_once_through_the_walker() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join(".gitignore"), "*.rs\n!keep.rs\n")?;
+    fs::write(root.join("keep.rs"), "fn keep() {}")?;
+    fs::write(root.join("drop.rs"), "fn drop() {}")?;
+
+    let mut ignores = IgnoreSources::new();
+    ignores.load_file(root, ".gitignore")?;
+    let paths = |options: &CollectOptions| -> Result<Vec<String>> {
+        let mut paths: Vec<String> = collect_files(root, &ignores, options)?
+            .files
+            .into_iter()
+            .map(|file| file.path)
+            .collect();
+        paths.sort();
+        Ok(paths)
+    };
+
+    // The walker honors the re-include, which the loaded `*.rs` would otherwise override
+    assert_eq!(paths(&CollectOptions::default())?, vec!["keep.rs"]);
+    // Turning .gitignore off leaves no copy of its patterns behind
+    let options = CollectOptions {
+        respect_gitignore: false,
+        ..CollectOptions::default()
+    };
+    assert_eq!(paths(&options)?, vec!["drop.rs", "keep.rs"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_submodule_modes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(
+        root.join(".gitmodules"),
+        "[submodule \"core\"]\n\tpath = libs/core\n\turl = https://example.com/core.git\n",
+    )?;
+    fs::create_dir_all(root.join("libs/core/src"))?;
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(root.join("libs/core/src/lib.rs"), "pub fn core() {}")?;
+    fs::write(root.join("src/main.rs"), "fn main() {}")?;
+
+    let ignores = IgnoreSources::new();
+    let collect = |submodule_mode| -> Result<Vec<(String, Option<String>)>> {
+        let options = CollectOptions {
+            submodules: read_submodules(root),
+            submodule_mode,
+            sort_paths: true,
+            ..CollectOptions::default()
+        };
+        Ok(collect_files(root, &ignores, &options)?
+            .files
+            .into_iter()
+            .map(|file| (file.path.replace('\\', "/"), file.submodule))
+            .collect())
+    };
+
+    let core = (
+        "libs/core/src/lib.rs".to_string(),
+        Some("libs/core".to_string()),
+    );
+    let main = ("src/main.rs".to_string(), None);
+    assert_eq!(
+        collect(SubmoduleMode::Include)?,
+        vec![core.clone(), main.clone()]
+    );
+    assert_eq!(collect(SubmoduleMode::Exclude)?, vec![main]);
+    assert_eq!(collect(SubmoduleMode::Only)?, vec![core]);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_lockfile_modes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src"))?;
+    fs::create_dir_all(root.join("web"))?;
+    fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    fs::write(
+        root.join("Cargo.lock"),
+        "[[package]]\nname = \"serde\"\nsource = \"registry+https://example.com\"\n",
+    )?;
+    fs::write(
+        root.join("web/package-lock.json"),
+        r#"{"packages": {"": {}, "node_modules/a": {}}}"#,
+    )?;
+
+    let ignores = IgnoreSources::new();
+    let collect = |lockfiles| {
+        let options = CollectOptions {
+            lockfiles,
+            sort_paths: true,
+            ..CollectOptions::default()
+        };
+        collect_files(root, &ignores, &options)
+    };
+    let paths = |files: &[FileInfo]| -> Vec<String> {
+        files.iter().map(|f| f.path.replace('\\', "/")).collect()
+    };
+
+    // A .lock extension isn't code, but lockfiles are collected when asked for
+    let included = collect(LockfileMode::Include)?;
+    assert_eq!(
+        paths(&included.files),
+        vec!["Cargo.lock", "src/main.rs", "web/package-lock.json"]
+    );
+    assert!(included.lockfiles.is_empty());
+    assert_eq!(CollectOptions::default().lockfiles, LockfileMode::Exclude);
+
+    // Ignore patterns still apply to lockfiles that are asked for
+    let ignored = collect_files(
+        root,
+        &IgnoreSources::new().with(IgnoreSource::CommandLine, ["Cargo.lock"]),
+        &CollectOptions {
+            lockfiles: LockfileMode::Include,
+            sort_paths: true,
+            ..CollectOptions::default()
+        },
+    )?;
+    assert_eq!(
+        paths(&ignored.files),
+        vec!["src/main.rs", "web/package-lock.json"]
+    );
+
+    let excluded = collect(LockfileMode::Exclude)?;
+    assert_eq!(paths(&excluded.files), vec!["src/main.rs"]);
+    assert_eq!(excluded.filtered.ignored, 2);
+
+    let summarized = collect(LockfileMode::Summary)?;
+    assert_eq!(paths(&summarized.files), vec!["src/main.rs"]);
+    let summaries: Vec<(&str, &str, Option<usize>)> = summarized
+        .lockfiles
+        .iter()
+        .map(|l| (l.path.as_str(), l.ecosystem.as_str(), l.packages))
+        .collect();
+    assert_eq!(
+        summaries,
+        vec![
+            ("Cargo.lock", "Cargo", Some(1)),
+            ("web/package-lock.json", "npm", Some(1))
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_leaves_out_minified_assets() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("dist"))?;
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(
+        root.join("dist/bundle.js"),
+        format!("!function(){{{}}}();\n", "var a=1,b=2;".repeat(200)),
+    )?;
+    fs::write(
+        root.join("src/app.js"),
+        "function add(a, b) {\n    return a + b;\n}\n",
+    )?;
+
+    let ignores = IgnoreSources::new();
+    let collect = |include_minified| {
+        let options = CollectOptions {
+            include_minified,
+            mention_skipped: true,
+            sort_paths: true,
+            ..CollectOptions::default()
+        };
+        collect_files(root, &ignores, &options)
+    };
+
+    let collection = collect(false)?;
+    let paths: Vec<String> = collection
+        .files
+        .iter()
+        .map(|f| f.path.replace('\\', "/"))
+        .collect();
+    assert_eq!(paths, vec!["src/app.js"]);
+    assert_eq!(collection.filtered.minified, 1);
+    assert_eq!(collection.skipped.len(), 1);
+    assert_eq!(collection.skipped[0].kind, SkippedKind::Minified);
+
+    let collection = collect(true)?;
+    assert_eq!(collection.files.len(), 2);
+    assert_eq!(collection.filtered.minified, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_collect_files_docs_only() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("docs/adr"))?;
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(root.join("README.md"), "# Project\n")?;
+    fs::write(root.join("docs/guide.rst"), "Guide\n=====\n")?;
+    fs::write(root.join("docs/adr/0001-use-rust.md"), "# Use Rust\n")?;
+    fs::write(
+        root.join("src/lib.rs"),
+        "/// Adds one\npub fn inc(x: u32) -> u32 {\n    x + 1\n}\n",
+    )?;
+    fs::write(root.join("src/util.rs"), "fn helper() {}\n")?;
+    fs::write(root.join("notes.rst"), "Not under docs/\n")?;
+
+    let options = CollectOptions {
+        docs_only: true,
+        sort_paths: true,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(root, &IgnoreSources::new(), &options)?;
+    let files: Vec<(String, &str)> = collection
+        .files
+        .iter()
+        .map(|f| (f.path.replace('\\', "/"), f.content.as_str()))
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            ("README.md".to_string(), "# Project\n"),
+            ("docs/adr/0001-use-rust.md".to_string(), "# Use Rust\n"),
+            ("docs/guide.rst".to_string(), "Guide\n=====\n"),
+            (
+                "src/lib.rs".to_string(),
+                "/// Adds one\npub fn inc(x: u32) -> u32 {\n"
+            ),
+        ]
+    );
+    assert_eq!(collection.filtered.undocumented, 1);
+    assert_eq!(collection.filtered.not_code, 1);
+
+    Ok(())
+}
@@ -1,2 +1,8 @@
+mod archive_tests;
+mod dependency_tests;
+mod diff_tests;
+mod entry_point_tests;
+mod git_tests;
 mod ignore_pattern_tests;
 mod project_detection_tests;
+mod schema_tests;
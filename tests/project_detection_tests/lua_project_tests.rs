@@ -1,52 +1,10 @@
-use ignore::WalkBuilder;
+use digest::file_language;
+use digest::projects::is_lua_project;
 use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
 use tempfile::TempDir;
 
-// Test implementation of is_lua_project that matches the one in main.rs
-pub fn is_lua_project_impl(project_path: &Path) -> bool {
-    // Common Lua project files
-    let lua_files = ["init.lua", "main.lua", "conf.lua", "config.lua"];
-    for file in lua_files.iter() {
-        if project_path.join(file).exists() {
-            return true;
-        }
-    }
-
-    // Look for a concentration of Lua files in the project
-    let mut builder = WalkBuilder::new(project_path);
-    builder
-        .hidden(false)
-        .git_ignore(true) // Always respect .gitignore for detection
-        .max_depth(Some(3)); // Only check a few levels deep for performance
-
-    let walker = builder.build();
-
-    let mut lua_file_count = 0;
-    for result in walker {
-        if let Ok(entry) = result {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if let Some(ext_str) = ext.to_str() {
-                        if ext_str == "lua" {
-                            lua_file_count += 1;
-                            if lua_file_count >= 5 {
-                                // If we find at least 5 Lua files, consider it a Lua project
-                                return true;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    false
-}
-
 /// Create a temporary directory that looks like a Lua project
 fn create_lua_project() -> TempDir {
     let temp_dir = TempDir::new().unwrap();
@@ -129,7 +87,7 @@ fn test_lua_project_detection_with_init_lua() {
     // Test a project with init.lua (should be detected as Lua)
     let lua_project = create_lua_project();
     assert!(
-        is_lua_project_impl(lua_project.path()),
+        is_lua_project(lua_project.path()),
         "Should detect Lua project with init.lua"
     );
 }
@@ -139,7 +97,7 @@ fn test_non_lua_project_with_few_lua_files() {
     // Test a project with Lua files but not enough to be a Lua project
     let non_lua_project = create_non_lua_project_with_lua_files();
     assert!(
-        !is_lua_project_impl(non_lua_project.path()),
+        !is_lua_project(non_lua_project.path()),
         "Should not detect as Lua project when there are few Lua files"
     );
 }
@@ -182,7 +140,7 @@ fn test_lua_project_with_many_files() {
 
     // No init.lua or other main Lua file, but should still be detected due to number of .lua files
     assert!(
-        is_lua_project_impl(temp_path),
+        is_lua_project(temp_path),
         "Should detect as Lua project when there are many Lua files"
     );
 }
@@ -206,59 +164,22 @@ fn test_lua_file_extension_recognition() {
 
 #[test]
 fn test_lua_language_detection() {
-    // Test simple implementation of language detection similar to what's in the codebase
-    let lua_file_ext = "lua";
-    let lua_language = get_language_from_extension(lua_file_ext, false);
+    // The library's own extension-to-language mapping
+    let lua_language = file_language("lua", "-- Lua file", false);
 
     assert_eq!(
-        lua_language,
-        Some("Lua".to_string()),
+        lua_language, "Lua",
         "Should detect .lua files as Lua language"
     );
 
     // Test other languages for comparison
-    let rs_language = get_language_from_extension("rs", false);
+    let rs_language = file_language("rs", "fn main() {}", false);
     assert_eq!(
-        rs_language,
-        Some("Rust".to_string()),
+        rs_language, "Rust",
         "Should correctly detect other languages too"
     );
 }
 
-// Simple implementation of language detection for testing
-fn get_language_from_extension(ext: &str, is_godot_project: bool) -> Option<String> {
-    let lang = match ext {
-        "rs" => "Rust",
-        "js" => "JavaScript",
-        "ts" => "TypeScript",
-        "py" => "Python",
-        "java" => "Java",
-        "go" => "Go",
-        "c" | "cpp" | "h" | "hpp" => "C/C++",
-        "rb" => "Ruby",
-        "php" => "PHP",
-        "lua" => "Lua",
-        "cs" => {
-            if is_godot_project {
-                "GDScript C#"
-            } else {
-                "C#"
-            }
-        }
-        "html" => "HTML",
-        "css" => "CSS",
-        "json" => "JSON",
-        "md" => "Markdown",
-        "yml" | "yaml" => "YAML",
-        "toml" => "TOML",
-        "gd" => "GDScript",
-        "tscn" | "tres" => "Godot Scene",
-        "shader" => "Godot Shader",
-        _ => "Unknown",
-    };
-    Some(lang.to_string())
-}
-
 // Simple function to create Lua-specific ignore patterns for testing
 fn build_test_ignore_patterns(main_language: &Option<String>) -> HashSet<String> {
     // Common patterns to ignore across all languages
@@ -410,7 +331,8 @@ fn get_markdown_language_tag(language: &str) -> &str {
         "Rust" => "rust",
         "Java" => "java",
         "Go" => "go",
-        "C/C++" => "cpp",
+        "C" => "c",
+        "C++" => "cpp",
         "Ruby" => "ruby",
         "PHP" => "php",
         "Lua" => "lua",
@@ -0,0 +1,51 @@
+use std::fs;
+use tempfile::TempDir;
+
+use digest::ignores::IgnoreSources;
+use digest::projects::{detect_project_types, GODOT, LUA, TERRAFORM};
+use digest::{collect_files, CollectOptions};
+
+/// A Godot game with Lua modding scripts and Terraform for its backend
+fn create_mixed_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    fs::write(root.join("project.godot"), "config_version=5\n").unwrap();
+    fs::write(root.join("player.gd"), "extends Node2D\n").unwrap();
+    fs::write(root.join("theme.tres"), "[gd_resource]\n").unwrap();
+    fs::write(root.join("init.lua"), "return {}\n").unwrap();
+    fs::write(root.join("main.tf"), "terraform {}\n").unwrap();
+    fs::write(root.join("server.rs"), "fn main() {}\n").unwrap();
+
+    temp_dir
+}
+
+#[test]
+fn test_multiple_project_types_combine_rules() {
+    let project = create_mixed_project();
+
+    let project_types = detect_project_types(project.path());
+    let names: Vec<&str> = project_types.iter().map(|t| t.name).collect();
+    assert_eq!(names, vec![GODOT, LUA, TERRAFORM]);
+
+    let options = CollectOptions {
+        project_types,
+        ..CollectOptions::default()
+    };
+    let collection = collect_files(project.path(), &IgnoreSources::new(), &options).unwrap();
+    let mut paths: Vec<&str> = collection.files.iter().map(|f| f.path.as_str()).collect();
+    paths.sort();
+
+    // Godot resources sit next to the Lua, Terraform, and Rust sources
+    assert_eq!(
+        paths,
+        vec![
+            "init.lua",
+            "main.tf",
+            "player.gd",
+            "project.godot",
+            "server.rs",
+            "theme.tres"
+        ]
+    );
+}
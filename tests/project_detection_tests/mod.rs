@@ -1 +1,3 @@
 mod lua_project_tests;
+mod mixed_project_tests;
+mod unreal_project_tests;
@@ -0,0 +1,81 @@
+use std::fs;
+use tempfile::TempDir;
+
+use digest::ignores::IgnoreSources;
+use digest::projects::{detect_project_types, ProjectType};
+use digest::{collect_files, CollectOptions};
+
+/// Create a temporary directory that looks like an Unreal Engine project
+fn create_unreal_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    fs::create_dir_all(root.join("Source/Shooter")).unwrap();
+    fs::create_dir_all(root.join("Config")).unwrap();
+    fs::write(
+        root.join("Shooter.uproject"),
+        r#"{"EngineAssociation": "5.3"}"#,
+    )
+    .unwrap();
+    fs::write(
+        root.join("Source/Shooter/Shooter.cpp"),
+        "#include \"Shooter.h\"",
+    )
+    .unwrap();
+    fs::write(root.join("Source/Shooter/Shooter.h"), "#pragma once").unwrap();
+    fs::write(
+        root.join("Source/Shooter/Shooter.inl"),
+        "inline void F() {}",
+    )
+    .unwrap();
+    fs::write(
+        root.join("Source/Shooter/Shooter.Build.cs"),
+        "public class Shooter : ModuleRules {}",
+    )
+    .unwrap();
+    fs::write(root.join("Config/DefaultEngine.ini"), "[/Script/Engine]").unwrap();
+
+    temp_dir
+}
+
+fn collected_paths(root: &std::path::Path, project_types: Vec<ProjectType>) -> Vec<String> {
+    let options = CollectOptions {
+        project_types,
+        ..CollectOptions::default()
+    };
+    let mut paths: Vec<String> = collect_files(root, &IgnoreSources::new(), &options)
+        .unwrap()
+        .files
+        .into_iter()
+        .map(|f| f.path.replace('\\', "/"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_unreal_project_includes_config_and_descriptors() {
+    let project = create_unreal_project();
+
+    assert_eq!(
+        collected_paths(project.path(), detect_project_types(project.path())),
+        vec![
+            "Config/DefaultEngine.ini",
+            "Shooter.uproject",
+            "Source/Shooter/Shooter.Build.cs",
+            "Source/Shooter/Shooter.cpp",
+            "Source/Shooter/Shooter.h",
+            "Source/Shooter/Shooter.inl",
+        ]
+    );
+
+    // Outside Unreal projects, .ini and descriptor files are not treated as code
+    assert_eq!(
+        collected_paths(project.path(), Vec::new()),
+        vec![
+            "Source/Shooter/Shooter.Build.cs",
+            "Source/Shooter/Shooter.cpp",
+            "Source/Shooter/Shooter.h",
+        ]
+    );
+}
@@ -0,0 +1,211 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use digest::assets::EmbeddedImage;
+use digest::complexity::Complexity;
+use digest::dependencies::{Dependency, DependencyKind, DependencySummary};
+use digest::duplicates::{DuplicateBlock, Occurrence};
+use digest::entry_points::EntryPoint;
+use digest::git::{FileGitInfo, GitInfo};
+use digest::godot::{Autoload, GodotProject};
+use digest::lockfiles::LockfileSummary;
+use digest::pii::{PiiFinding, PiiKind};
+use digest::schema::{json_schema, SCHEMA_VERSION};
+use digest::stats::{LanguageStats, LineStats};
+use digest::submodules::Submodule;
+use digest::summary::{DigestSummary, GENERATED_BY, TOOL_VERSION};
+use digest::{
+    Digest, DigestPart, ExcludedFile, ExclusionReason, FileInfo, SkippedFile, SkippedKind,
+};
+
+/// A digest with every optional field filled in
+fn full_digest() -> Digest {
+    Digest {
+        schema_version: SCHEMA_VERSION,
+        generated_by: GENERATED_BY.to_string(),
+        digest_version: TOOL_VERSION.to_string(),
+        generated_at: Some("2024-05-01T12:30:00Z".to_string()),
+        project_name: "demo".to_string(),
+        part: Some(DigestPart { index: 1, total: 2 }),
+        instructions: Some("Review this".to_string()),
+        git: Some(GitInfo {
+            branch: Some("main".to_string()),
+            commit: "abc123".to_string(),
+            dirty: false,
+            remote_url: None,
+        }),
+        submodules: vec![Submodule {
+            name: "libs/core".to_string(),
+            path: "libs/core".to_string(),
+            url: Some("https://example.com/org/core.git".to_string()),
+        }],
+        entry_points: vec![EntryPoint {
+            path: "src/main.rs".to_string(),
+            ecosystem: "Rust",
+        }],
+        godot: Some(GodotProject {
+            name: Some("Dodge".to_string()),
+            main_scene: Some("main.tscn".to_string()),
+            autoloads: vec![Autoload {
+                name: "Globals".to_string(),
+                path: "globals.gd".to_string(),
+            }],
+            scripts: vec!["player.gd".to_string()],
+        }),
+        main_language: Some("Rust".to_string()),
+        language_breakdown: vec![LanguageStats {
+            language: "Rust".to_string(),
+            files: 1,
+            lines: 1,
+            bytes: 12,
+            percentage: 100.0,
+        }],
+        dependencies: vec![DependencySummary {
+            ecosystem: "Cargo".to_string(),
+            manifest: "Cargo.toml".to_string(),
+            direct: vec![Dependency {
+                name: "serde".to_string(),
+                version: Some("1.0".to_string()),
+                kind: DependencyKind::Normal,
+            }],
+            lockfile: Some("Cargo.lock".to_string()),
+            transitive_count: Some(3),
+        }],
+        excluded: vec![ExcludedFile {
+            path: "vendor".to_string(),
+            reason: ExclusionReason::Symlink,
+            target: Some("../shared/vendor".to_string()),
+        }],
+        skipped: vec![SkippedFile {
+            path: "assets/logo.png".to_string(),
+            kind: SkippedKind::Image,
+            bytes: 2048,
+        }],
+        images: vec![EmbeddedImage {
+            path: "docs/icon.png".to_string(),
+            media_type: "image/png",
+            bytes: 4,
+            data: "iVBORw==".to_string(),
+        }],
+        lockfiles: vec![LockfileSummary {
+            path: "Cargo.lock".to_string(),
+            ecosystem: "Cargo".to_string(),
+            packages: Some(12),
+            lines: 140,
+            sha256: "ab".repeat(32),
+        }],
+        duplicates: vec![DuplicateBlock {
+            lines: 8,
+            occurrences: vec![
+                Occurrence {
+                    path: "src/a.rs".to_string(),
+                    start_line: 10,
+                    end_line: 19,
+                },
+                Occurrence {
+                    path: "src/b.rs".to_string(),
+                    start_line: 3,
+                    end_line: 12,
+                },
+            ],
+        }],
+        pii: vec![PiiFinding {
+            path: "fixtures/users.csv".to_string(),
+            line: 2,
+            kind: PiiKind::Email,
+            redacted: true,
+        }],
+        files: vec![FileInfo {
+            modified: Some(1_700_000_000),
+            stats: Some(LineStats {
+                code: 1,
+                comments: 0,
+                blanks: 0,
+            }),
+            git: Some(FileGitInfo {
+                commit: "abc123".to_string(),
+                author: "Dev".to_string(),
+                date: "2024-01-01T00:00:00+00:00".to_string(),
+                age_days: 3,
+            }),
+            url: Some("https://github.com/org/demo/blob/abc123/src/main.rs".to_string()),
+            submodule: Some("libs/core".to_string()),
+            complexity: Some(Complexity {
+                cyclomatic: 1,
+                max_nesting: 1,
+            }),
+            ..FileInfo::new(
+                "src/main.rs".to_string(),
+                Some("Rust".to_string()),
+                "fn main() {}".to_string(),
+            )
+        }],
+        summary: Some(DigestSummary {
+            files_included: 1,
+            files_excluded: BTreeMap::from([
+                ("ignored", 4),
+                ("not_code", 3),
+                ("oversized", 1),
+                ("unreadable", 1),
+                ("minified", 1),
+                ("content_filter", 2),
+                ("max_files", 5),
+                ("symlink", 1),
+                ("symlink_loop", 1),
+                ("duplicate", 1),
+            ]),
+            total_lines: 1,
+            total_tokens: 3,
+            generation_ms: Some(12),
+            tool_version: "0.1.0",
+        }),
+    }
+}
+
+/// Check that every field in `value` is described by `schema`, recursing into objects and arrays
+fn assert_described(value: &Value, schema: &Value, at: &str) {
+    match value {
+        Value::Object(fields) => {
+            let properties = schema["properties"]
+                .as_object()
+                .unwrap_or_else(|| panic!("{} has no properties in the schema", at));
+            for (key, field) in fields {
+                let field_schema = properties
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{}.{} is missing from the schema", at, key));
+                assert_described(field, field_schema, &format!("{}.{}", at, key));
+            }
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let required = required.as_str().unwrap();
+                assert!(
+                    fields.contains_key(required),
+                    "{}.{} is required by the schema but not serialized",
+                    at,
+                    required
+                );
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                assert_described(item, &schema["items"], &format!("{}[{}]", at, i));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_schema_describes_every_field() {
+    let value = serde_json::to_value(full_digest()).unwrap();
+    assert_described(&value, &json_schema(), "digest");
+}
+
+#[test]
+fn test_schema_version_is_serialized() {
+    let value = serde_json::to_value(full_digest()).unwrap();
+    assert_eq!(value["schema_version"], SCHEMA_VERSION);
+    assert_eq!(
+        json_schema()["properties"]["schema_version"]["const"],
+        SCHEMA_VERSION
+    );
+}